@@ -32,6 +32,7 @@
 //! * [`Call counts`](#call-counts)
 //! * [`Sequences`](#sequences)
 //! * [`Checkpoints`](#checkpoints)
+//! * [`Mock builders`](#mock-builders)
 //! * [`Reference arguments`](#reference-arguments)
 //! * [`Reference return values`](#reference-return-values)
 //! * [`impl Trait`](#impl-trait)
@@ -69,12 +70,47 @@
 //! assert_eq!(5, call_with_four(&mock));
 //! ```
 //!
+//! Mocks may be declared inside of a function, too, which is handy for small
+//! test fixtures.  However, Mockall puts the generated expectation types into
+//! modules, and a module can't see its enclosing function's local items.  So
+//! any types used by the mocked methods' signatures must be declared outside
+//! of the function.
+//!
+//! If a mocked method's name collides with one of the helper methods that
+//! Mockall generates, like a method named `checkpoint`, or one named
+//! `expect_foo` alongside one named `foo`, then the mocked method keeps its
+//! name.  The helper method gets a `mockall_` prefix instead, like
+//! `mockall_checkpoint` or `mockall_expect_foo`.
+//!
+//! Likewise, if two mocked traits have methods of the same name, then the
+//! helper methods for each are qualified by the name of the trait, like
+//! `expect_Read_read` and `expect_Validate_read`.  Inherent methods, and trait
+//! methods whose names are unique, always get the short form, like
+//! `expect_read`.  [`checkpoint`](#checkpoints) still covers all of them.
+//!
 //! ## Static Return values
 //!
 //! Every expectation must have an associated return value (though when the
 //! **nightly** feature is enabled expectations will automatically return the
 //! default values of their return types, if their return types implement
-//! `Default`.).  For methods that return a `static` value, the macros will
+//! `Default`.).  The exception is methods that return nothing, or `()`.
+//! Their expectations needn't set a return value, even without **nightly**:
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Writer {
+//!     fn flush(&mut self);
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockWriter::new();
+//! mock.expect_flush().times(1);
+//! mock.flush();
+//! # }
+//! ```
+//!
+//! For methods that return a `static` value, the macros will
 //! generate an `Expectation` struct like
 //! [`this`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html).
 //! There are two ways to set such an expectation's return value: with a
@@ -145,6 +181,36 @@
 //! # }
 //! ```
 //!
+//! Methods that return a `Result` have a few shortcuts:
+//! [`return_ok`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.return_ok),
+//! [`return_err`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.return_err),
+//! and
+//! [`return_ok_default`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.return_ok_default).
+//! Likewise, methods that return an `Option` have
+//! [`return_some`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.return_some)
+//! and
+//! [`return_none`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.return_none).
+//! Like `return_const`, the values must be `Clone`.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn foo(&self, x: u32) -> Result<u32, String>;
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! mock.expect_foo()
+//!     .with(predicate::eq(0))
+//!     .return_err("zero".to_owned());
+//! mock.expect_foo()
+//!     .return_ok(42);
+//! assert_eq!(Err("zero".to_owned()), mock.foo(0));
+//! assert_eq!(Ok(42), mock.foo(1));
+//! # }
+//! ```
+//!
 //! Mock objects are always `Send`.  If you need to use a return type that
 //! isn't, you can use the
 //! [`returning_st`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.returning_st)
@@ -175,6 +241,35 @@
 //! # }
 //! ```
 //!
+//! A return function may call other methods of the same mock object, if it can
+//! get a reference to it, like through an `Arc`.  It may even call the same
+//! method again, so long as the nested call matches a different expectation.
+//! But an expectation can't call itself from its own return function, because
+//! that function is still running.  Mockall will panic if it tries.
+//!
+//! ```
+//! # use mockall::*;
+//! # use std::sync::{Arc, Mutex, Weak};
+//! #[automock]
+//! trait Foo {
+//!     fn outer(&self, x: u32) -> u32;
+//!     fn inner(&self, x: u32) -> u32;
+//! }
+//!
+//! # fn main() {
+//! let slot = Arc::new(Mutex::new(Weak::<MockFoo>::new()));
+//! let slot2 = slot.clone();
+//! let mut mock = MockFoo::new();
+//! mock.expect_outer()
+//!     .returning(move |x| slot2.lock().unwrap().upgrade().unwrap().inner(x));
+//! mock.expect_inner()
+//!     .returning(|x| x + 1);
+//! let mock = Arc::new(mock);
+//! *slot.lock().unwrap() = Arc::downgrade(&mock);
+//! assert_eq!(5, mock.outer(4));
+//! # }
+//! ```
+//!
 //! ## Matching arguments
 //!
 //! Optionally, expectations may have argument matchers set.  A matcher will
@@ -199,6 +294,9 @@
 //! ```
 //!
 //! See [`predicates`] for a list of Mockall's builtin predicate functions.
+//! Reusable custom matchers can be written by implementing the [`Matcher`]
+//! trait.  And arguments like enums are easily matched with patterns, using
+//! the [`matching!`] macro.
 //! For convenience,
 //! [`withf`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.withf)
 //! is a shorthand for setting the commonly used
@@ -222,6 +320,50 @@
 //! # }
 //! ```
 //!
+//! `with` and `withf` may be combined on one expectation.  Then a call must
+//! satisfy both.  The per-argument predicates are evaluated first.
+//!
+//! ```
+//! # use mockall::*;
+//! # use mockall::predicate::*;
+//! #[automock]
+//! trait Foo {
+//!     fn send(&self, topic: u32, payload: &str);
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! mock.expect_send()
+//!     .with(eq(1), always())
+//!     .withf(|_, payload| payload.len() < 1024)
+//!     .return_const(());
+//!
+//! mock.send(1, "hello");
+//! # }
+//! ```
+//!
+//! Every method with arguments also gets an `expect_*_with` method, which
+//! takes one matcher per argument.  It's shorthand for `expect_*().with(...)`.
+//! It isn't generated if its name would collide with another method's
+//! `expect_*` method.
+//!
+//! ```
+//! # use mockall::*;
+//! # use mockall::predicate::*;
+//! #[automock]
+//! trait Foo {
+//!     fn send(&self, topic: u32, payload: &str);
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! mock.expect_send_with(eq(1), always())
+//!     .return_const(());
+//!
+//! mock.send(1, "hello");
+//! # }
+//! ```
+//!
 //! ### Matching multiple calls
 //!
 //! Matchers can also be used to discriminate between different invocations of
@@ -270,12 +412,55 @@
 //!     .return_const(None);
 //! ```
 //!
+//! Expectations shared by many tests can be collected into a [`Fixture`].  Its
+//! expectations have lower priority than the test's own, regardless of the
+//! order they were set in, so a test can override any part of it.
+//!
+//! For harnesses that treat any panic as a crash, like some fuzzers, the
+//! [`fallible`] function makes unmatched calls return a default value instead,
+//! and saves their errors for [`take_last_error`].  A harness that drives a
+//! method's `Expectations` itself can call their `try_call` method, which
+//! returns a [`CallError`] instead of panicking.
+//!
+//! ### Trait object arguments
+//!
+//! Arguments like `&dyn Command` can be matched by their concrete types with
+//! [`is_type`](predicate/fn.is_type.html) and
+//! [`downcast_matches`](predicate/fn.downcast_matches.html).  Those predicates
+//! only work if the trait has [`AsAny`](predicate/trait.AsAny.html) as a
+//! supertrait.  Otherwise, they'll fail to compile.
+//!
+//! ```
+//! # use mockall::*;
+//! # use mockall::predicate::*;
+//! pub trait Command: AsAny {}
+//! pub struct Move { distance: u32 }
+//! impl Command for Move {}
+//!
+//! #[automock]
+//! trait Robot {
+//!     fn execute(&self, cmd: &dyn Command);
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockRobot::new();
+//! mock.expect_execute()
+//!     .with(downcast_matches::<Move, _>(|m| m.distance == 7))
+//!     .return_const(());
+//! mock.execute(&Move{distance: 7});
+//! # }
+//! ```
+//!
 //! ## Call counts
 //!
 //! By default, every expectation is allowed to be called an unlimited number of
 //! times.  But Mockall can optionally verify that an expectation was called a
 //! fixed number of times, or any number of times within a given range.
 //!
+//! Such an expectation is always satisfied, even if it is never called.  To
+//! make that intent explicit, use
+//! [`times_any`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.times_any).
+//!
 //! ```should_panic
 //! # use mockall::*;
 //! # use mockall::predicate::*;
@@ -297,6 +482,140 @@
 //! [`never`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.never) and
 //! [`times`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.times).
 //!
+//! An expectation that's set up just in case, like in a shared fixture, can be
+//! marked
+//! [`optional`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.optional).
+//! Then it's satisfied even if it's never called, but its maximum call count
+//! still applies.  In a [`Sequence`], an optional expectation may be skipped.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn foo(&self, x: u32) -> u32;
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! mock.expect_foo()
+//!     .times(1)
+//!     .optional()
+//!     .return_const(42u32);
+//! mock.checkpoint();  // Ok, even though foo was never called
+//! # }
+//! ```
+//!
+//! To limit the total number of calls to several expectations, possibly of
+//! different mocks, enroll them in a [`CallBudget`].
+//!
+//! ### Successive return values
+//!
+//! A single expectation can return a different value on each call with
+//! [`returning_seq`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.returning_seq).
+//! The values are returned in order, and the expectation must be called
+//! exactly once for each value.  For return types that aren't `Clone`,
+//! [`returning_fn_seq`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.returning_fn_seq)
+//! does the same with a list of `FnOnce` closures.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn fetch(&self) -> Result<u32, String>;
+//! }
+//!
+//! let mut mock = MockFoo::new();
+//! mock.expect_fetch()
+//!     .returning_seq(vec![Err("Timeout".to_owned()), Ok(42)]);
+//! assert!(mock.fetch().is_err());
+//! assert_eq!(Ok(42), mock.fetch());
+//! ```
+//!
+//! ### Builder methods
+//!
+//! Methods that consume `self` and return `Self`, like a builder's, can return
+//! the mock object itself with `returning_self`.  Its expectations move along
+//! with it, so a whole chain of calls can be mocked at once.  Each call still
+//! counts against the expectation's `times`.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Builder {
+//!     fn name(self, name: &str) -> Self;
+//!     fn build(self) -> String;
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockBuilder::new();
+//! mock.expect_name()
+//!     .returning_self();
+//! mock.expect_build()
+//!     .return_once(|| "widget".to_owned());
+//! assert_eq!("widget", mock.name("foo").build());
+//! # }
+//! ```
+//!
+//! The expectations of any method that consumes `self` are set the same way as
+//! other methods', before the call moves the mock.  `return_once` suits their
+//! outputs, which often aren't `Clone`.  A mock verifies its expectations when
+//! it's dropped, so an expectation of `times(1)` still fails if the consuming
+//! method is never called.
+//!
+//! ### Methods that never return
+//!
+//! A method that returns `!` can't return anything, so its expectations must
+//! panic instead, with `panics_with`.  The payload is the same as `panic!`'s,
+//! and the call still counts toward the expectation's `times`.
+//!
+//! ```
+//! # use mockall::*;
+//! # use std::panic;
+//! #[automock]
+//! trait Process {
+//!     fn abort(&self, code: i32) -> !;
+//! }
+//!
+//! let mut mock = MockProcess::new();
+//! mock.expect_abort()
+//!     .times(1)
+//!     .panics_with("aborted");
+//! let r = panic::catch_unwind(panic::AssertUnwindSafe(|| mock.abort(1)));
+//! assert_eq!("aborted", *r.unwrap_err().downcast::<&str>().unwrap());
+//! mock.checkpoint();
+//! ```
+//!
+//! ### Reconfiguring expectations
+//!
+//! The `&mut Expectation` returned by an `expect_*` method borrows the mock
+//! object, so only one may be held at a time.  To change an expectation after
+//! setting others, keep an [`ExpectationRef`] to it instead, from its
+//! `stable_ref` method.  Each one borrows the mock only while in use.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn foo(&self, x: u32) -> u32;
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! let one = mock.expect_foo()
+//!     .with(predicate::eq(1))
+//!     .return_const(1u32)
+//!     .stable_ref();
+//! let two = mock.expect_foo()
+//!     .with(predicate::eq(2))
+//!     .return_const(2u32)
+//!     .stable_ref();
+//! one.get(&mut mock).return_const(10u32);
+//! two.get(&mut mock).return_const(20u32);
+//! assert_eq!(10, mock.foo(1));
+//! assert_eq!(20, mock.foo(2));
+//! # }
+//! ```
+//!
 //! ## Sequences
 //!
 //! By default expectations may be matched in any order.  But it's possible to
@@ -311,32 +630,40 @@
 //! }
 //!
 //! # fn main() {
-//! let mut seq = Sequence::new();
+//! let seq = Sequence::new();
 //!
 //! let mut mock1 = MockFoo::new();
 //! mock1.expect_foo()
 //!     .times(1)
-//!     .in_sequence(&mut seq)
+//!     .in_sequence(&seq)
 //!     .returning(|| ());
 //!
 //! let mut mock2 = MockFoo::new();
 //! mock2.expect_foo()
 //!     .times(1)
-//!     .in_sequence(&mut seq)
+//!     .in_sequence(&seq)
 //!     .returning(|| ());
 //!
 //! mock2.foo();    // Panics!  mock1.foo should've been called first.
 //! # }
 //! ```
 //!
+//! A `Sequence` may be cloned and sent to other threads.  Every clone refers to
+//! the same sequence.
+//!
+//! A `Sequence` is a total order.  For a partial order, take an expectation's
+//! [`ExpectationHandle`] and require other expectations to happen `after` it.
+//!
 //! ## Checkpoints
 //!
 //! Sometimes its useful to validate all expectations mid-test, throw them away,
 //! and add new ones.  That's what checkpoints do.  Every mock object has a
 //! `checkpoint` method.  When called, it will immediately validate all methods'
 //! expectations.  So any expectations that haven't satisfied their call count
-//! will panic.  Afterwards, those expectations will be cleared so you can add
-//! new expectations and keep testing.
+//! will panic.  The panic message lists every unsatisfied expectation, not just
+//! the first, along with where each was set, like
+//! `(set at tests/checkout.rs:142:10)`.  Afterwards, those expectations will be
+//! cleared so you can add new expectations and keep testing.
 //!
 //! ```should_panic
 //! # use mockall::*;
@@ -373,6 +700,147 @@
 //! # }
 //! ```
 //!
+//! Each mocked method also gets its own checkpoint method, named like
+//! `checkpoint_foo`.  It validates and clears only that method's expectations,
+//! leaving the others alone.  For static methods, it validates the method's
+//! global expectations, just like the context object's `checkpoint` method.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn connect(&self);
+//!     fn send(&self, x: u32);
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! mock.expect_connect()
+//!     .times(1)
+//!     .returning(|| ());
+//! mock.expect_send()
+//!     .returning(|_| ());
+//!
+//! mock.connect();
+//! mock.checkpoint_connect();   // Only verifies connect
+//! mock.send(42);
+//! # }
+//! ```
+//!
+//! To see the state of a mock object's expectations without validating or
+//! clearing them, use its `report` method.  It returns one line per
+//! expectation, with its call count constraint, how many times it's been
+//! called, and whether that's enough.  A failed `checkpoint` includes the same
+//! report after its list of failures.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn foo(&self, x: u32);
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! mock.expect_foo()
+//!     .times(2)
+//!     .return_const(());
+//! mock.foo(1);
+//! let report = mock.report();
+//! assert!(report.starts_with("MockFoo::foo: Expectation(<anything>) (set at "));
+//! assert!(report.ends_with(": exactly 2 times, called 1 time, unsatisfied"));
+//! # mock.foo(2);
+//! # }
+//! ```
+//!
+//! To just count them, use `expectation_count`, which covers all of the mock
+//! object's non-static methods, or `expectation_count_foo` for a single
+//! method.  For a static method, `expectation_count_foo` is an associated
+//! function, and its `Context` object also has `len` and `is_empty` methods.
+//! None of them validate or clear anything.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn foo(&self, x: u32);
+//!     fn bar(&self);
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFoo::new();
+//! assert_eq!(0, mock.expectation_count());
+//! mock.expect_foo().return_const(());
+//! mock.expect_foo().return_const(());
+//! mock.expect_bar().return_const(());
+//! assert_eq!(3, mock.expectation_count());
+//! assert_eq!(2, mock.expectation_count_foo());
+//! # }
+//! ```
+//!
+//! To check every mock at once, create them with `new_registered` instead of
+//! `new`, and call [`verify_all`] at the end of the test.  It reports the
+//! unsatisfied expectations of all of the current thread's registered mocks
+//! together.
+//!
+//! To see the calls themselves, use the mock's `record` method.  It returns a
+//! [`Recording`] of every later call to the mock's non-static methods, with
+//! their arguments and return values, suitable for snapshot tests.
+//!
+//! ## Mock builders
+//!
+//! Every mock struct also has a `builder` method, which can set all of the
+//! mock object's expectations as part of a single expression.  That's handy
+//! for table-driven tests.  The builder has one method for each mocked method,
+//! which takes a closure to configure a new expectation.  For a static method,
+//! the closure gets the method's `Context` object instead.  Then `build`
+//! returns the mock object.  If any static methods' expectations were set, use
+//! `build_with_contexts` instead.  It also returns the `Context` objects, which
+//! keep those expectations alive.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Foo {
+//!     fn foo(&self, x: u32) -> u32;
+//!     fn bar(&self) -> u32;
+//! }
+//!
+//! # fn main() {
+//! let mock = MockFoo::builder()
+//!     .foo(|e| { e.returning(|x| x + 1); })
+//!     .bar(|e| { e.return_const(42u32); })
+//!     .build();
+//! assert_eq!(5, mock.foo(4));
+//! assert_eq!(42, mock.bar());
+//! # }
+//! ```
+//!
+//! For simpler cases, `with_expectations` creates a mock object and passes it
+//! to a closure that sets its expectations.  That avoids a `mut` binding, and
+//! works well in struct literals.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Db {
+//!     fn get(&self, key: u32) -> Option<u32>;
+//! }
+//!
+//! struct Service {
+//!     db: MockDb
+//! }
+//!
+//! # fn main() {
+//! let service = Service {
+//!     db: MockDb::with_expectations(|m| {
+//!         m.expect_get().return_const(None);
+//!     })
+//! };
+//! assert_eq!(None, service.db.get(1));
+//! # }
+//! ```
+//!
 //! ## Reference arguments
 //!
 //! Mockall can mock methods with reference arguments, too.  There's one catch:
@@ -395,6 +863,27 @@
 //! assert_eq!(6, mock.foo(&5));
 //! ```
 //!
+//! Likewise, the `returning` closure receives reference arguments exactly as
+//! the mocked method did.  They're never cloned, so they needn't even be
+//! `Clone`.
+//!
+//! ```
+//! # use mockall::*;
+//! struct Haystack(String);    // Not Clone
+//!
+//! #[automock]
+//! trait Finder {
+//!     fn find(&self, haystack: &Haystack, needle: &str) -> Option<usize>;
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockFinder::new();
+//! mock.expect_find()
+//!     .returning(|haystack, needle| haystack.0.find(needle));
+//! assert_eq!(Some(2), mock.find(&Haystack("abcdef".to_owned()), "cd"));
+//! # }
+//! ```
+//!
 //! ## Reference return values
 //!
 //! Mockall can also use reference return values.  There is one restriction: the
@@ -536,6 +1025,38 @@
 //! # }
 //! ```
 //!
+//! Static methods and module functions that return `impl Trait` work too,
+//! which is handy for factory methods.  However, the mock versions of those
+//! functions actually return `Box<dyn Trait>`, because their expectations must
+//! have a nameable type.  That's usually transparent to the caller.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Connection {
+//!     fn send(&self, x: u32) -> u32;
+//! }
+//! struct Pool {}
+//! #[automock]
+//! impl Pool {
+//!     fn build() -> impl Connection {
+//!         // ...
+//!         # MockConnection::new()
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let ctx = MockPool::build_context();
+//! ctx.expect()
+//!     .returning(|| {
+//!         let mut conn = MockConnection::new();
+//!         conn.expect_send().returning(|x| x + 1);
+//!         Box::new(conn)
+//!     });
+//! assert_eq!(5, MockPool::build().send(4));
+//! # }
+//! ```
+//!
 //! However, `impl Trait` isn't *exactly* equivalent to `Box<dyn Trait>` but
 //! with fewer allocations.  There are some things the former can do but the
 //! latter can't.  For one thing, you can't build a trait object out of a
@@ -572,7 +1093,41 @@
 //! those would be to refactor them to return named (but possibly opaque) types
 //! instead.
 //!
-//! See Also [`impl-trait-for-returning-complex-types-with-ease.html`](https://rust-lang-nursery.github.io/edition-guide/rust-2018/trait-system/impl-trait-for-returning-complex-types-with-ease)
+//! Methods that take `impl Trait` arguments work the same way.  The
+//! expectation sees each such argument as a `Box<dyn Trait>`, so it may ignore
+//! the argument or consume it.  The argument needn't be `'static`, and the same
+//! limitations on the trait apply.  For example, `impl Into<String>` can't be
+//! mocked, because `Into` can't be made into a trait object.  Such methods
+//! should take a concrete type instead.
+//!
+//! ```compile_fail
+//! # use mockall::*;
+//! #[automock]
+//! trait Named {
+//!     fn rename(&mut self, name: impl Into<String>);
+//! }
+//! ```
+//!
+//! ```
+//! # use mockall::*;
+//! # use std::io::{self, Cursor, Read};
+//! #[automock]
+//! trait Loader {
+//!     fn load(&mut self, src: impl Read) -> io::Result<usize>;
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockLoader::new();
+//! mock.expect_load()
+//!     .returning(|mut src| {
+//!         let mut s = String::new();
+//!         src.read_to_string(&mut s)
+//!     });
+//! assert_eq!(5, mock.load(Cursor::new("Hello")).unwrap());
+//! # }
+//! ```
+//!
+//! See Also [`impl-trait-for-returning-complex-types-with-ease.html`](https://rust-lang-nursery.github.io/edition-guide/rust-2018/trait-system/impl-trait-for-returning-complex-types-with-ease)
 //!
 //! ## Mocking structs
 //!
@@ -623,7 +1178,50 @@
 //! # fn main() {}
 //! ```
 //! For structs with more than one `impl` block, see [`mock!`]
-//! instead.
+//! instead.  A struct's `const fn`s may be mocked too, but their mock versions
+//! won't be `const`, so they can't be used in constant expressions.
+//! ```compile_fail
+//! # use mockall::*;
+//! pub struct Calc {}
+//! #[automock]
+//! impl Calc {
+//!     pub const fn zero() -> u32 {
+//!         0
+//!     }
+//! }
+//! const ZERO: u32 = MockCalc::zero();
+//! # fn main() {}
+//! ```
+//!
+//! `#[automock]` also works on a trait implemented for a reference to a
+//! struct.  The mock struct then implements the trait for `&MockX`, so the
+//! mock object can be passed by reference to code that is generic over the
+//! trait.  Methods that take `&mut self` will receive a `&mut &MockX`, so they
+//! can't return mutable references.
+//! ```
+//! # use mockall::*;
+//! pub trait Visitor {
+//!     fn visit(&self, x: u32) -> u32;
+//! }
+//! pub struct Analyzer {}
+//! #[automock]
+//! impl Visitor for &Analyzer {
+//!     fn visit(&self, x: u32) -> u32 {
+//!         // ...
+//!         # unimplemented!()
+//!     }
+//! }
+//!
+//! fn walk<V: Visitor>(v: V) -> u32 {
+//!     v.visit(1)
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockAnalyzer::new();
+//! mock.expect_visit().returning(|x| x + 1);
+//! assert_eq!(2, walk(&mock));
+//! # }
+//! ```
 //!
 //! ## Generic methods
 //!
@@ -651,6 +1249,30 @@
 //! assert_eq!(-5, mock.foo(5i8));
 //! ```
 //!
+//! When setting expectations for each instantiation is too verbose, a
+//! generic method that takes `self` by reference may also have an
+//! `expect_*_any` method, as long as its arguments and return type are owned
+//! `'static` values.
+//! It creates a [`DynExpectation`], which applies to any instantiation that
+//! has no expectations of its own.  It sees the arguments as `&dyn Any`.
+//!
+//! ```
+//! # use mockall::*;
+//! # use std::any::Any;
+//! #[automock]
+//! trait Foo {
+//!     fn foo<T: 'static>(&self, t: T) -> i32;
+//! }
+//!
+//! let mut mock = MockFoo::new();
+//! mock.expect_foo_any()
+//!     .withf(|args: &[&dyn Any]| !args[0].is::<String>())
+//!     .return_const(42i32);
+//!
+//! assert_eq!(42, mock.foo(5i16));
+//! assert_eq!(42, mock.foo(5.0f32));
+//! ```
+//!
 //! ## Methods with generic lifetimes
 //!
 //! A method with a lifetime parameter is technically a generic method, but
@@ -685,8 +1307,7 @@
 //!
 //! Mocking generic structs and generic traits is not a problem.  The mock
 //! struct will be generic, too.  The same restrictions apply as with mocking
-//! generic methods: each generic parameter must be `'static`, and generic
-//! lifetime parameters are not allowed.
+//! generic methods: each generic type parameter must be `'static`.
 //!
 //! ```
 //! # use mockall::*;
@@ -703,6 +1324,28 @@
 //! # }
 //! ```
 //!
+//! Traits may have lifetime parameters, too, like a parser over borrowed
+//! input.  A method may use the trait's lifetime in its arguments, or in its
+//! return type, but not in both.  Nor may it return a reference with that
+//! lifetime.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! trait Parser<'a> {
+//!     fn parse(&self, input: &'a str) -> usize;
+//! }
+//!
+//! # fn main() {
+//! let mut mock = MockParser::new();
+//! mock.expect_parse()
+//!     .withf(|input| input.starts_with('x'))
+//!     .returning(|input| input.len());
+//! let input = String::from("xyz");
+//! assert_eq!(3, mock.parse(&input));
+//! # }
+//! ```
+//!
 //! ## Associated types
 //!
 //! Traits with associated types can be mocked too.  Unlike generic traits, the
@@ -725,6 +1368,26 @@
 //! assert_eq!(4, mock.foo(4));
 //! ```
 //!
+//! A specified type may also refer to other associated types, even through
+//! their own associated types, like `type Error = Self::Conn::Error;`.  If
+//! `Conn` has a single trait bound, like `type Conn: HasError;`, that becomes
+//! `<PgConn as HasError>::Error`.
+//!
+//! Each specified type is checked against the trait's bounds on the associated
+//! type, so a type that doesn't satisfy them is reported at the attribute.
+//! Types and bounds that refer to the trait's generic parameters can't be
+//! checked there, though.
+//!
+//! ```compile_fail
+//! # use mockall::*;
+//! # use std::rc::Rc;
+//! #[automock(type Shared=Rc<u32>;)]
+//! pub trait A {
+//!     type Shared: Send;
+//!     fn foo(&self) -> Self::Shared;
+//! }
+//! ```
+//!
 //! ## Multiple and inherited traits
 //!
 //! Creating a mock struct that implements multiple traits, whether inherited or
@@ -806,6 +1469,10 @@
 //! assert_eq!(99, MockA::foo());
 //! ```
 //!
+//! The `ExpectationGuard` returned by `expect` locks the method's expectations
+//! only while one of its own methods runs.  So it may be held while the method
+//! is called, and it, like the `Context`, may be sent to another thread.
+//!
 //! A common pattern is mocking a trait with a constructor method.  In this case,
 //! you can easily set the mock constructor method to return a mock object.
 //!
@@ -838,6 +1505,81 @@
 //! # }
 //! ```
 //!
+//! But that configures a new mock on every call.  To return one mock that the
+//! test configured beforehand, use `return_once_mock`, which also expects
+//! exactly one call.  Or use the mock's `expect_<method>_returning` helper,
+//! which creates the `Context` and its expectation at once.  Either way, the
+//! test can still `checkpoint` that mock after the code under test receives
+//! it.
+//!
+//! ```
+//! # use mockall::*;
+//! # struct Foo{}
+//! # #[automock]
+//! # impl Foo {
+//! #     fn from_i32(x: i32) -> Self { unimplemented!() }
+//! #     fn foo(&self) -> i32 { unimplemented!() }
+//! # }
+//! # fn main() {
+//! let mut mock = MockFoo::default();
+//! mock.expect_foo()
+//!     .return_const(42);
+//! let _ctx = MockFoo::expect_from_i32_returning(mock);
+//! let foo = MockFoo::from_i32(7);
+//! assert_eq!(42, foo.foo());
+//! # }
+//! ```
+//!
+//! Constructors that wrap `Self`, like `fn open(path: &str) -> io::Result<Self>`
+//! or `fn find(id: u32) -> Option<Self>`, work the same way.  Their
+//! expectations return the wrapped mock type, like `io::Result<MockFoo>`.  So
+//! do constructors that return `Box<Self>`, `Rc<Self>`, or `Arc<Self>`.  And a
+//! constructor that returns a trait object like `Arc<dyn Foo>` can return a
+//! preconfigured mock object, coerced to the trait object type.
+//!
+//! Static methods bounded by `where Self: Sized` don't stop a trait from being
+//! object safe, and neither do they stop its mock.  Whenever `#[automock]` can
+//! tell that a trait is object safe, it checks at compile time that the mock
+//! can be used as a trait object, too, like `Box<dyn Foo>`.
+//!
+//! A static method's return function runs after its expectations have been
+//! unlocked, so it may call other static methods or mocked functions, or even
+//! the same one.  However, an expectation's own return function can't call
+//! that same expectation again, because the return function is still running.
+//! Mockall will panic if it tries.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! pub trait A {
+//!     fn foo(x: u32) -> u32;
+//!     fn bar(x: u32) -> u32;
+//! }
+//!
+//! let foo_ctx = MockA::foo_context();
+//! foo_ctx.expect().returning(|x| MockA::bar(x) + 1);
+//! let bar_ctx = MockA::bar_context();
+//! bar_ctx.expect().returning(|x| x * 2);
+//! assert_eq!(7, MockA::foo(3));
+//! ```
+//!
+//! Static methods and functions that return a `'static` reference, like
+//! `&'static str`, can return data computed at runtime with `return_static`.
+//! It takes an owned value, like a `String` or a `Vec`, and keeps it alive for
+//! the rest of the process.
+//!
+//! ```
+//! # use mockall::*;
+//! #[automock]
+//! pub trait A {
+//!     fn name() -> &'static str;
+//! }
+//!
+//! let ctx = MockA::name_context();
+//! ctx.expect().return_static(format!("{}-{}", "foo", 42));
+//! assert_eq!("foo-42", MockA::name());
+//! ```
+//!
 //! ### Generic static methods
 //!
 //! Mocking static methods of generic structs or traits, whether or not the
@@ -903,6 +1645,50 @@
 //! # }
 //! ```
 //!
+//! To checkpoint all of a mock struct's static methods at once, use its
+//! `checkpoint_statics` associated function.
+//!
+//! ```should_panic
+//! # use mockall::*;
+//! #[automock]
+//! pub trait A {
+//!     fn foo() -> u32;
+//!     fn bar() -> u32;
+//! }
+//!
+//! let foo_ctx = MockA::foo_context();
+//! foo_ctx.expect().return_const(1u32);
+//! let bar_ctx = MockA::bar_context();
+//! bar_ctx.expect().times(1).return_const(2u32);
+//! MockA::checkpoint_statics();    // Panics!
+//! ```
+//!
+//! And to checkpoint everything at once, both a mock object and its struct's
+//! static methods, use `checkpoint_all`.
+//!
+//! ```should_panic
+//! # use mockall::*;
+//! #[automock]
+//! pub trait A {
+//!     fn foo() -> u32;
+//!     fn bar(&self) -> u32;
+//! }
+//!
+//! let ctx = MockA::foo_context();
+//! ctx.expect().times(1).return_const(1u32);
+//! let mut mock = MockA::new();
+//! mock.expect_bar().times(1).return_const(2u32);
+//! MockA::checkpoint_all(&mut mock);    // Panics, reporting both methods
+//! ```
+//!
+//! A context that drops while its test is panicking leaves its expectations
+//! behind, since checking them then would only cause a double panic.  If the
+//! panic is caught, and the process goes on to use the same static methods,
+//! then clear those expectations first, without verifying them.
+//! `reset_statics` does that for all of a mock struct's static methods, a
+//! mocked module's `reset` function does it for all of its functions, and
+//! [`reset`] does it for every static method and mocked function.
+//!
 //! One more thing: Mockall normally creates a zero-argument `new` method for
 //! every mock struct.  But it *won't* do that when mocking a struct that
 //! already has a method named `new`.
@@ -954,6 +1740,11 @@
 //! # fn main() {}
 //! ```
 //!
+//! If the extern block has a `#[cfg]` attribute, like
+//! `#[cfg(target_os = "linux")]`, then the whole mock module gets it too.  A
+//! `#[cfg]` attribute on an individual foreign function applies to that
+//! function's mock.
+//!
 //! ## Modules
 //!
 //! In addition to mocking foreign functions, Mockall can also derive mocks for
@@ -1001,6 +1792,27 @@
 //! # fn main() {}
 //! ```
 //!
+//! `use` items inside of the module are copied into the mock module, so
+//! re-exports keep working.  If the module re-exports one of its own functions
+//! under a different name, like `pub use self::bar as baz;`, then the mock
+//! module will also have a `baz_context` method.
+//!
+//! Other items like type aliases, constants, statics, and data type
+//! definitions (structs, enums, and unions, along with their attributes) are
+//! copied into the mock module too.  However, `impl` blocks are not.  A
+//! function's `#[cfg]` attributes carry over to its mock, so functions that
+//! are defined differently on different platforms can still be mocked.  Statics lose any `#[no_mangle]` or `#[export_name]`
+//! attributes, so that they don't clash with the originals at link time.
+//!
+//! Mockall can also mock modules whose contents are in another file.  Because
+//! a procedural macro can't see the contents of such a module, you must tell
+//! Mockall where its source file is, relative to the crate root.
+//!
+//! ```ignore
+//! #[automock(path = "src/inner.rs")]
+//! mod inner;
+//! ```
+//!
 //! ## Crate features
 //!
 //! Mockall has a **nightly** feature.  Currently this feature has three
@@ -1028,6 +1840,24 @@
 //! assert!(mock.foo().is_empty());
 //! ```
 //!
+//! The **tracing** feature instruments mocks with the
+//! [`tracing`](https://docs.rs/tracing) crate.  Each expectation gets a
+//! `mockall::expect` span when it's set, with `mock`, `method`, and `location`
+//! fields.  Each call to a mocked method emits a `mockall::call` event at the
+//! `TRACE` level, with `mock`, `method`, and `matched` fields.  If the call
+//! matched an expectation, the event also has an `expectation` field
+//! describing it, and is emitted within that expectation's span.  Without the
+//! feature, mocks behave exactly the same, but emit nothing.
+//!
+//! To see the code that Mockall generates, for example to diagnose a compile
+//! error within it, set the `MOCKALL_DEBUG` environment variable while
+//! building.  Each mock's code will be saved to
+//! `mockall/<crate name>/<mock name>.rs` within the target directory, formatted
+//! by `rustfmt` if it's available.  Mocks of the same name in one crate get
+//! numbered, like `MockFoo-2.rs`.  The target directory is assumed to be
+//! `target` within the workspace's root, unless the crate has a build script.
+//! If it's somewhere else, set `CARGO_TARGET_DIR` too.
+//!
 //! ## Examples
 //!
 //! For additional examples of Mockall in action, including detailed
@@ -1048,13 +1878,23 @@
 use downcast::*;
 use std::{
     any,
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashSet},
+    fmt,
     marker::PhantomData,
-    ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo,
+    ops::{Deref, DerefMut, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo,
           RangeToInclusive},
     sync::{
         Arc,
-        atomic::{AtomicUsize, Ordering}
+        LockResult,
+        Mutex,
+        MutexGuard,
+        PoisonError,
+        Weak,
+        atomic::{AtomicBool, AtomicUsize, Ordering}
     },
+    panic::Location,
+    thread,
 };
 
 #[doc(hidden)]
@@ -1062,17 +1902,104 @@ pub use downcast::{Any, Downcast};
 #[doc(hidden)]
 pub use fragile::Fragile;
 
-/// For mocking static methods
+/// Mockall no longer uses `lazy_static` for mocking static methods.  This
+/// re-export is kept only for compatibility with code that used it, and will be
+/// removed in the next release.
 #[doc(hidden)]
 pub use lazy_static::lazy_static;
 
 pub use predicates::{
     boolean::PredicateBooleanExt,
     prelude::{
-        Predicate, PredicateBoxExt, PredicateFileContentExt, PredicateStrExt,
-        predicate
+        Predicate, PredicateBoxExt, PredicateFileContentExt, PredicateStrExt
     }
 };
+
+pub mod predicate;
+
+/// Matches a mock method's argument.
+///
+/// [`with`](https://docs.rs/mockall_examples/latest/mockall_examples/__mock_Foo_Foo/foo/struct.Expectation.html#method.with)
+/// accepts anything that implements `Matcher` for the argument's type.  Every
+/// [`Predicate`] already does, so most tests never need to implement it
+/// themselves.  But a custom `Matcher` can be handy for checks that are reused
+/// across many expectations, especially because it controls how it's described
+/// in panic messages.
+///
+/// # Examples
+///
+/// ```should_panic
+/// # use mockall::*;
+/// /// Matches a `Vec` of a given length
+/// struct HasLen(usize);
+///
+/// impl<T> Matcher<Vec<T>> for HasLen {
+///     fn matches(&self, arg: &Vec<T>) -> bool {
+///         arg.len() == self.0
+///     }
+///
+///     fn describe(&self) -> String {
+///         format!("a Vec of length {}", self.0)
+///     }
+/// }
+///
+/// #[automock]
+/// trait Foo {
+///     fn foo(&self, v: Vec<u32>);
+/// }
+///
+/// let mut mock = MockFoo::new();
+/// mock.expect_foo()
+///     .with(HasLen(2))
+///     .return_const(());
+/// mock.foo(vec![1, 2]);
+/// mock.foo(vec![1, 2, 3]);    // Panics, mentioning "a Vec of length 2"
+/// ```
+pub trait Matcher<T: ?Sized> {
+    /// Does `arg` satisfy this matcher?
+    fn matches(&self, arg: &T) -> bool;
+
+    /// Describe what this matcher expects, for panic messages.
+    fn describe(&self) -> String;
+
+    /// If this matcher rejects `arg`, explain why.  The default implementation
+    /// just repeats [`describe`](#tymethod.describe).
+    fn describe_mismatch(&self, arg: &T) -> Option<String> {
+        if self.matches(arg) {
+            None
+        } else {
+            Some(format!("`{}` failed", self.describe()))
+        }
+    }
+}
+
+impl<T, P> Matcher<T> for P
+    where T: ?Sized,
+          P: Predicate<T> + ?Sized
+{
+    fn matches(&self, arg: &T) -> bool {
+        self.eval(arg)
+    }
+
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+
+    /// Describe the predicate, along with whatever values it reports about
+    /// the argument, like its actual value.
+    fn describe_mismatch(&self, arg: &T) -> Option<String> {
+        self.find_case(false, arg).map(|case| {
+            let products = case.products()
+                .map(|p| format!("{}: {}", p.name(), p.value()))
+                .collect::<Vec<_>>();
+            if products.is_empty() {
+                format!("`{}` failed", self)
+            } else {
+                format!("`{}` failed for {}", self, products.join(", "))
+            }
+        })
+    }
+}
 #[doc(hidden)]
 pub use predicates_tree::CaseTreeExt;
 
@@ -1123,6 +2050,113 @@ pub use predicates_tree::CaseTreeExt;
 /// }
 /// ```
 ///
+/// Extra derive macros can be applied to the mock struct with a `derive`
+/// metaitem.  `Default` may not be listed, because every mock struct already
+/// implements it.
+/// ```
+/// # use mockall_derive::*;
+/// #[automock(derive(Clone, Debug))]
+/// trait Marker {}
+///
+/// let mock = MockMarker::new();
+/// println!("{:?}", mock.clone());
+/// ```
+///
+/// A `trait` metaitem on a struct's inherent `impl` block extracts a trait from
+/// its methods, and implements that trait for both the struct and its mock.
+/// Code that is generic over the trait can then use either one.  Methods that
+/// are `async` or return `impl Trait` can't be put into the extracted trait.
+/// ```
+/// # use mockall_derive::*;
+/// struct Client {}
+/// #[automock(trait = ClientApi)]
+/// impl Client {
+///     fn get(&self, key: u32) -> u32 {
+///         // ...
+///         # unimplemented!()
+///     }
+/// }
+///
+/// fn lookup<C: ClientApi>(client: &C) -> u32 {
+///     client.get(42)
+/// }
+///
+/// let mut mock = MockClient::new();
+/// mock.expect_get().return_const(7u32);
+/// assert_eq!(7, lookup(&mock));
+/// ```
+///
+/// A `ref_impls` metaitem implements the mocked trait for `&MockFoo` and
+/// `&mut MockFoo` too, so the mock can be passed by reference to code that
+/// takes the trait by value, and still be used afterwards.  `&MockFoo` only
+/// gets it if all of the trait's methods take `&self`, and `&mut MockFoo` only
+/// if they all take `&self` or `&mut self`.  Methods that mention `Self`
+/// prevent both.  With [`mock`], use a `#[ref_impls]` attribute on the mock
+/// struct instead.
+/// ```
+/// # use mockall_derive::*;
+/// #[automock(ref_impls)]
+/// trait Foo {
+///     fn foo(&self) -> u32;
+/// }
+///
+/// fn call_foo<F: Foo>(f: F) -> u32 {
+///     f.foo()
+/// }
+///
+/// let mut mock = MockFoo::new();
+/// mock.expect_foo().return_const(7u32);
+/// assert_eq!(7, call_foo(&mock));
+/// mock.checkpoint();
+/// ```
+///
+/// A `methods` metaitem mocks only the listed methods, for when a test only
+/// cares about a few of them.  The others get no expectations.  If called, they
+/// use their default implementations if they have one, or else panic.
+/// ```
+/// # use mockall_derive::*;
+/// #[automock(methods(get))]
+/// trait Store {
+///     fn get(&self, key: u32) -> u32;
+///     fn put(&mut self, key: u32, value: u32);
+///     fn size(&self) -> usize {
+///         0
+///     }
+/// }
+///
+/// let mut mock = MockStore::new();
+/// mock.expect_get().return_const(7u32);
+/// assert_eq!(7, mock.get(1));
+/// assert_eq!(0, mock.size());
+/// ```
+///
+/// An `inner` metaitem names a real type to fall back on.  A mock created with
+/// `new_with_inner` forwards every call that matches no expectation to the
+/// real object that it wraps, instead of panicking.  Static methods have no
+/// object, so they always fall back on the real type's.  Only methods whose
+/// signatures don't mention `Self`, and that don't take `self` by value, are
+/// forwarded.  Checkpoints only verify the explicit expectations.
+/// ```
+/// # use mockall_derive::*;
+/// struct RealStore {}
+/// #[automock(inner = RealStore)]
+/// trait Store {
+///     fn get(&self, key: u32) -> u32;
+/// }
+/// impl Store for RealStore {
+///     fn get(&self, key: u32) -> u32 {
+///         key * 10
+///     }
+/// }
+///
+/// let mut mock = MockStore::new_with_inner(RealStore{});
+/// mock.expect_get()
+///     .with(mockall::predicate::eq(1))
+///     .return_const(5u32);
+/// assert_eq!(5, mock.get(1));
+/// assert_eq!(20, mock.get(2));
+/// ```
+///
 /// Finally, `#[automock]` can also mock foreign functions.  This requires
 /// another metaitem to specify the mock module name.
 ///
@@ -1206,23 +2240,9 @@ pub use mockall_derive::automock;
 /// # fn main() {}
 /// ```
 /// Associated types can easily be mocked by specifying a concrete type in the
-/// `mock!{}` invocation.  But be careful not to reference the associated type
-/// in the signatures of any of the trait's methods; repeat the concrete type
-/// instead.  For example, do:
-/// ```
-/// # use mockall_derive::mock;
-/// mock!{
-///     MyIter {}
-///     trait Iterator {
-///         type Item=u32;
-///
-///         fn next(&mut self) -> Option<u32>;
-///     }
-/// }
-/// # fn main() {}
+/// `mock!{}` invocation.  The trait's methods may refer to the associated type
+/// by a qualified path, or just repeat the concrete type.
 /// ```
-/// *not*
-/// ```compile_fail
 /// # use mockall_derive::mock;
 /// mock!{
 ///     MyIter {}
@@ -1237,7 +2257,22 @@ pub use mockall_derive::automock;
 pub use mockall_derive::mock;
 
 #[doc(hidden)]
-pub trait AnyExpectations : Any + Send + Sync {}
+pub trait AnyExpectations : Any + Send + Sync {
+    /// Clear all expectations, returning a description of each one that
+    /// wasn't satisfied.
+    fn checkpoint(&mut self) -> Vec<String>;
+
+    /// Summarize the state of every expectation, one per line.
+    fn report(&self) -> Vec<String>;
+
+    /// The number of current expectations.
+    fn len(&self) -> usize;
+
+    /// Are there no current expectations?
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 downcast!(dyn AnyExpectations);
 
 #[doc(hidden)]
@@ -1284,52 +2319,153 @@ pub struct DefaultReturner<O>(PhantomData<O>);
     }
 }
 
-// Though it's not entirely correct, we treat usize::max_value() as
-// approximately infinity.
-#[derive(Debug)]
+/// Implemented by the output types of methods that return a `Result`.  Used to
+/// bound the `return_ok` and `return_ok_default` helpers.
 #[doc(hidden)]
-pub struct TimesRange(Range<usize>);
+pub trait ReturnOk<T> {
+    fn from_ok(ok: T) -> Self;
+}
 
-impl Default for TimesRange {
-    fn default() -> TimesRange {
-        // By default, allow any number of calls
-        TimesRange(0..usize::max_value())
+impl<T, E> ReturnOk<T> for Result<T, E> {
+    fn from_ok(ok: T) -> Self {
+        Ok(ok)
     }
 }
 
-impl From<usize> for TimesRange {
-    fn from(n: usize) -> TimesRange {
-        TimesRange(n..(n+1))
-    }
+/// Implemented by the output types of methods that return a `Result`.  Used to
+/// bound the `return_err` helper.
+#[doc(hidden)]
+pub trait ReturnErr<E> {
+    fn from_err(err: E) -> Self;
 }
 
-impl From<Range<usize>> for TimesRange {
-    fn from(r: Range<usize>) -> TimesRange {
-        assert!(r.end > r.start, "Backwards range");
-        TimesRange(r)
+impl<T, E> ReturnErr<E> for Result<T, E> {
+    fn from_err(err: E) -> Self {
+        Err(err)
     }
 }
 
-impl From<RangeFrom<usize>> for TimesRange {
-    fn from(r: RangeFrom<usize>) -> TimesRange {
-        TimesRange(r.start..usize::max_value())
-    }
+/// Implemented by the output types of methods that return an `Option`.  Used
+/// to bound the `return_some` and `return_none` helpers.
+#[doc(hidden)]
+pub trait ReturnOption<T> {
+    fn from_some(some: T) -> Self;
+    fn none() -> Self;
 }
 
-impl From<RangeFull> for TimesRange {
-    fn from(_: RangeFull) -> TimesRange {
-        TimesRange(0..usize::max_value())
+impl<T> ReturnOption<T> for Option<T> {
+    fn from_some(some: T) -> Self {
+        Some(some)
     }
-}
 
-impl From<RangeInclusive<usize>> for TimesRange {
-    fn from(r: RangeInclusive<usize>) -> TimesRange {
-        assert!(r.end() >= r.start(), "Backwards range");
-        TimesRange(*r.start()..*r.end() + 1)
+    fn none() -> Self {
+        None
     }
 }
 
-impl From<RangeTo<usize>> for TimesRange {
+/// A `'static` reference to a value owned by Mockall, for the `return_static`
+/// helper of functions that return `&'static T`.
+#[doc(hidden)]
+pub struct StaticRef<T: ?Sized + 'static>(&'static T);
+
+impl<T: ?Sized> StaticRef<T> {
+    pub fn get(&self) -> &'static T {
+        self.0
+    }
+}
+
+/// Implemented by owned values that can be stored for the rest of the process,
+/// like `String` for `&'static str`.  Used to bound the `return_static` helper.
+#[doc(hidden)]
+pub trait IntoStaticRef<T: ?Sized> {
+    fn into_static_ref(self) -> StaticRef<T>;
+}
+
+impl<O, T> IntoStaticRef<T> for O
+    where O: Into<Box<T>>,
+          T: ?Sized + Send + Sync + 'static
+{
+    fn into_static_ref(self) -> StaticRef<T> {
+        // Values are never removed, and statics are never dropped, so each
+        // one lives until the process exits.  Unlike a leaked Box, they remain
+        // reachable, so leak checkers won't complain.
+        static STATIC_VALUES: Mutex<Vec<Box<dyn any::Any + Send>>> =
+            Mutex::new(Vec::new());
+        let r: &'static T = Box::leak(self.into());
+        STATIC_VALUES.lock().unwrap().push(Box::new(r));
+        StaticRef(r)
+    }
+}
+
+// Though it's not entirely correct, we treat usize::max_value() as
+// approximately infinity.
+#[derive(Debug)]
+#[doc(hidden)]
+pub struct TimesRange(Range<usize>);
+
+impl Default for TimesRange {
+    fn default() -> TimesRange {
+        // By default, allow any number of calls
+        TimesRange(0..usize::max_value())
+    }
+}
+
+impl fmt::Display for TimesRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (min, max) = (self.0.start, self.0.end - 1);
+        if max == usize::max_value() - 1 {
+            if min == 0 {
+                write!(f, "any number of times")
+            } else {
+                write!(f, "at least {}", Plural(min, "time"))
+            }
+        } else if min == max {
+            if min == 0 {
+                write!(f, "never")
+            } else {
+                write!(f, "exactly {}", Plural(min, "time"))
+            }
+        } else if min == 0 {
+            write!(f, "at most {}", Plural(max, "time"))
+        } else {
+            write!(f, "{} to {} times", min, max)
+        }
+    }
+}
+
+impl From<usize> for TimesRange {
+    fn from(n: usize) -> TimesRange {
+        TimesRange(n..(n+1))
+    }
+}
+
+impl From<Range<usize>> for TimesRange {
+    fn from(r: Range<usize>) -> TimesRange {
+        assert!(r.end > r.start, "Backwards range");
+        TimesRange(r)
+    }
+}
+
+impl From<RangeFrom<usize>> for TimesRange {
+    fn from(r: RangeFrom<usize>) -> TimesRange {
+        TimesRange(r.start..usize::max_value())
+    }
+}
+
+impl From<RangeFull> for TimesRange {
+    fn from(_: RangeFull) -> TimesRange {
+        TimesRange(0..usize::max_value())
+    }
+}
+
+impl From<RangeInclusive<usize>> for TimesRange {
+    fn from(r: RangeInclusive<usize>) -> TimesRange {
+        assert!(r.end() >= r.start(), "Backwards range");
+        TimesRange(*r.start()..*r.end() + 1)
+    }
+}
+
+impl From<RangeTo<usize>> for TimesRange {
     fn from(r: RangeTo<usize>) -> TimesRange {
         TimesRange(0..r.end)
     }
@@ -1341,22 +2477,46 @@ impl From<RangeToInclusive<usize>> for TimesRange {
     }
 }
 
+/// An expectation's call count constraint, and how many times it's been
+/// called.  Its state may be shared with a [`Registration`], so it's all
+/// atomic.
 #[derive(Debug, Default)]
 #[doc(hidden)]
-pub struct Times{
+pub struct Times(Arc<TimesInner>);
+
+#[derive(Debug)]
+struct TimesInner {
     /// How many times has the expectation already been called?
     count: AtomicUsize,
-    range: TimesRange
+    /// The start and end of the allowed range of call counts, as in
+    /// `TimesRange`
+    start: AtomicUsize,
+    end: AtomicUsize,
+    /// Is the expectation exempt from its minimum call count?
+    optional: AtomicBool
+}
+
+impl Default for TimesInner {
+    fn default() -> Self {
+        let range = TimesRange::default().0;
+        TimesInner {
+            count: AtomicUsize::new(0),
+            start: AtomicUsize::new(range.start),
+            end: AtomicUsize::new(range.end),
+            optional: AtomicBool::new(false)
+        }
+    }
 }
 
 impl Times {
     pub fn call(&self) -> Result<(), String> {
-        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
-        if count >= self.range.0.end {
-            if self.range.0.end == 1 {
+        let count = self.0.count.fetch_add(1, Ordering::Relaxed) + 1;
+        let end = self.end();
+        if count >= end {
+            if end == 1 {
                 Err("should not have been called".to_owned())
             } else {
-                Err(format!("called more than {} times", self.range.0.end - 1))
+                Err(format!("called more than {} times", end - 1))
             }
         } else {
             Ok(())
@@ -1364,60 +2524,1022 @@ impl Times {
     }
 
     pub fn any(&mut self) {
-        self.range.0 = 0..usize::max_value();
+        self.set_range(0..usize::max_value());
     }
 
     /// Has this expectation already been called the maximum allowed number of
     /// times?
     pub fn is_done(&self) -> bool {
-        self.count.load(Ordering::Relaxed) >= self.range.0.end - 1
+        self.count() >= self.end() - 1
     }
 
     /// Is it required that this expectation be called an exact number of times,
     /// or may it be satisfied by a range of call counts?
     pub fn is_exact(&self) -> bool {
-        (self.range.0.end - self.range.0.start) == 1
+        (self.end() - self.minimum()) == 1
+    }
+
+    /// Has this expectation already been called the minimum required number of
+    /// times?
+    pub fn is_satisfied(&self) -> bool {
+        self.is_optional() || self.count() >= self.minimum()
+    }
+
+    /// Is this expectation exempt from its minimum call count?
+    pub fn is_optional(&self) -> bool {
+        self.0.optional.load(Ordering::Relaxed)
+    }
+
+    /// Exempt this expectation from its minimum call count, but not its
+    /// maximum.
+    pub fn optional(&mut self) {
+        self.0.optional.store(true, Ordering::Relaxed);
+    }
+
+    /// The minimum number of times that this expectation must be called
+    pub fn minimum(&self) -> usize {
+        self.0.start.load(Ordering::Relaxed)
+    }
+
+    /// The maximum number of times that this expectation may be called
+    pub fn maximum(&self) -> usize {
+        self.end() - 1
+    }
+
+    /// How many times has this expectation already been called?
+    pub fn count(&self) -> usize {
+        self.0.count.load(Ordering::Relaxed)
+    }
+
+    /// Summarize this expectation's call count constraint, how many times it
+    /// has been called, and whether that's enough, like "exactly 2 times,
+    /// called 1 time, unsatisfied".
+    pub fn report(&self) -> String {
+        let status = if self.is_satisfied() {"satisfied"} else {"unsatisfied"};
+        let optional = if self.is_optional() {" (optional)"} else {""};
+        format!("{}{}, called {}, {}",
+                TimesRange(self.minimum()..self.end()), optional,
+                Plural(self.count(), "time"), status)
+    }
+
+    /// Explain why an expectation that has been called the maximum allowed
+    /// number of times rejected a call.
+    pub fn exhausted(&self) -> String {
+        format!("already called {}/{} times", self.count(), self.maximum())
+    }
+
+    // https://github.com/rust-lang/rust-clippy/issues/3307
+    #[allow(clippy::range_plus_one)]
+    pub fn n(&mut self, n: usize) {
+        self.set_range(n..(n+1));
+    }
+
+    pub fn never(&mut self) {
+        self.set_range(0..1);
+    }
+
+    pub fn range(&mut self, range: Range<usize>) {
+        assert!(range.end > range.start, "Backwards range");
+        self.set_range(range);
+    }
+
+    pub fn times<T: Into<TimesRange>>(&mut self, t: T) {
+        self.set_range(t.into().0);
+    }
+
+    /// Another reference to the same state, for a [`Registration`].
+    pub fn share(&self) -> Times {
+        Times(self.0.clone())
+    }
+
+    fn end(&self) -> usize {
+        self.0.end.load(Ordering::Relaxed)
+    }
+
+    fn set_range(&self, range: Range<usize>) {
+        self.0.start.store(range.start, Ordering::Relaxed);
+        self.0.end.store(range.end, Ordering::Relaxed);
+    }
+}
+
+/// A count of something, like "1 time" or "3 times"
+struct Plural(usize, &'static str);
+
+impl fmt::Display for Plural {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}{}", self.0, self.1, if self.0 == 1 {""} else {"s"})
+    }
+}
+
+/// Collects the unsatisfied expectations from one or more checkpoints, and
+/// reports them all together rather than stopping at the first.  Used by the
+/// generated checkpoint methods.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct Checkpoints {
+    failures: Vec<String>,
+    /// Every checked expectation's state, as of just before the checkpoint
+    report: Vec<String>
+}
+
+impl Checkpoints {
+    /// Record the failures from a single checkpoint, if any
+    pub fn add(&mut self, failures: Vec<String>) {
+        self.failures.extend(failures);
+    }
+
+    /// Record the state of the expectations that are about to be checked, to
+    /// be included in the panic message if any of them fail.
+    pub fn report(&mut self, rows: Vec<String>) {
+        self.report.extend(rows);
+    }
+
+    /// Panic with all of the recorded failures, if there were any
+    pub fn finish(self) {
+        let msg = match self.failures.len() {
+            0 => return,
+            1 => self.failures[0].clone(),
+            n => format!("{} expectations were not satisfied:\n- {}", n,
+                         self.failures.join("\n- "))
+        };
+        if self.report.is_empty() {
+            panic!("{}", msg);
+        }
+        panic!("{}\nAll expectations:\n{}", msg, self.report.join("\n"));
+    }
+}
+
+impl From<Vec<String>> for Checkpoints {
+    fn from(failures: Vec<String>) -> Self {
+        Checkpoints { failures, report: Vec::new() }
+    }
+}
+
+/// Where an expectation was set, if known.  It displays as a suffix for the
+/// expectation's error messages.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SetLocation(Option<&'static Location<'static>>);
+
+impl SetLocation {
+    /// The location of the caller, or of its caller if that is also
+    /// `#[track_caller]`, and so on.
+    #[track_caller]
+    pub fn caller() -> Self {
+        SetLocation(Some(Location::caller()))
+    }
+}
+
+impl fmt::Display for SetLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(l) = self.0 {
+            write!(f, " (set at {})", l)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Instruments one expectation, when the `tracing` feature is enabled.  Its
+/// span is created when the expectation is set, and each call that the
+/// expectation matches emits a `mockall::call` event within that span.
+/// Without the feature, it does nothing.
+#[doc(hidden)]
+#[cfg_attr(not(feature = "tracing"), derive(Default))]
+pub struct TraceSpan {
+    /// A span holds a reference to its subscriber, which needn't be
+    /// `RefUnwindSafe`.  But a span is only used to record events, so a panic
+    /// can't leave it in an inconsistent state, and it mustn't keep mocks from
+    /// being `UnwindSafe`.
+    #[cfg(feature = "tracing")]
+    span: std::panic::AssertUnwindSafe<tracing::Span>
+}
+
+impl TraceSpan {
+    #[allow(unused_variables)]
+    pub fn new(mock: &'static str, method: &'static str, location: SetLocation)
+        -> Self
+    {
+        TraceSpan {
+            #[cfg(feature = "tracing")]
+            span: std::panic::AssertUnwindSafe(
+                tracing::trace_span!(target: "mockall::expect",
+                    "expectation", mock, method,
+                    location = %location.0.map(ToString::to_string)
+                        .unwrap_or_default()))
+        }
+    }
+
+    /// Trace a call that this expectation matched.  `desc` describes the
+    /// expectation, and is only evaluated if the event is enabled.
+    #[allow(unused_variables)]
+    pub fn call<F>(&self, mock: &'static str, method: &'static str, desc: F)
+        where F: FnOnce() -> String
+    {
+        #[cfg(feature = "tracing")]
+        self.span.in_scope(|| {
+            tracing::trace!(target: "mockall::call", mock, method,
+                matched = true, expectation = %desc())
+        });
+    }
+}
+
+// tracing::Span doesn't implement Default
+#[cfg(feature = "tracing")]
+impl Default for TraceSpan {
+    fn default() -> Self {
+        TraceSpan {
+            span: std::panic::AssertUnwindSafe(tracing::Span::none())
+        }
+    }
+}
+
+/// Trace a call that matched no expectation, when the `tracing` feature is
+/// enabled.
+#[allow(unused_variables)]
+fn trace_unmatched(mock: &'static str, method: &'static str) {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(target: "mockall::call", mock, method, matched = false);
+}
+
+thread_local! {
+    /// The expectations rejected by this thread's last unmatched call
+    static REJECTED: RefCell<Vec<String>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Record the expectations that were considered but rejected by a call that
+/// matched none of them.
+#[doc(hidden)]
+pub fn reject(candidates: Vec<String>) {
+    REJECTED.with(|r| *r.borrow_mut() = candidates);
+}
+
+/// A generic method's instantiation that had no expectations, and the ones
+/// that did
+struct Unregistered {
+    ident: &'static str,
+    type_name: &'static str,
+    registered: Vec<&'static str>
+}
+
+thread_local! {
+    /// The instantiation looked up by this thread's last call of a generic
+    /// method, if it had no expectations
+    static UNREGISTERED: RefCell<Option<Unregistered>> =
+        const { RefCell::new(None) };
+}
+
+/// Note whether a call of generic method `ident` found any expectations for
+/// its generic parameters.  If not, then the panic message will say which
+/// parameters do have expectations.
+#[doc(hidden)]
+pub fn note_lookup<V>(ident: &'static str, key: &Key, store: &BTreeMap<Key, V>)
+{
+    let unregistered = if store.contains_key(key) {
+        None
+    } else {
+        Some(Unregistered {
+            ident,
+            type_name: key.type_name(),
+            registered: store.keys().map(Key::type_name).collect()
+        })
+    };
+    UNREGISTERED.with(|u| *u.borrow_mut() = unregistered);
+}
+
+thread_local! {
+    /// Whether calls on this thread that match no expectation are fallible
+    static FALLIBLE: Cell<bool> = const { Cell::new(false) };
+    /// The error from this thread's last fallible call
+    static LAST_ERROR: RefCell<Option<CallError>> =
+        const { RefCell::new(None) };
+}
+
+/// A call to a mocked method that matched none of its expectations.
+///
+/// Normally such a call panics with this error's message.  But while a
+/// [`FallibleGuard`] exists, it's saved for [`take_last_error`] instead.  And
+/// a method's `Expectations` can be called directly with `try_call`, which
+/// returns it.
+#[derive(Clone, Debug)]
+pub struct CallError {
+    msg: String,
+    rejected: Vec<String>
+}
+
+impl CallError {
+    fn new(mock: &str, method: &str) -> Self {
+        Self::with_ident(format!("{}::{}", mock, method), method)
+    }
+
+    /// Describe a call of the method named `ident`, like `MockFoo::foo`, that
+    /// matched none of its expectations.
+    #[doc(hidden)]
+    pub fn unmatched(ident: &str) -> Self {
+        Self::with_ident(ident.to_owned(), ident)
+    }
+
+    fn with_ident(ident: String, method: &str) -> Self {
+        let rejected = REJECTED.with(|r| r.take());
+        // Expectations for any generic parameters may still have been
+        // candidates
+        let unregistered = UNREGISTERED.with(|u| u.take())
+            .filter(|u| u.ident == ident || u.ident == method)
+            .filter(|_| rejected.is_empty());
+        let msg = if let Some(u) = unregistered {
+            let registered = if u.registered.is_empty() {
+                String::from("none")
+            } else {
+                u.registered.iter()
+                    .map(|tn| format!("<{}>", tn))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            format!("{}::<{}>: no expectation registered for this \
+                    instantiation (registered: {})",
+                    ident, u.type_name, registered)
+        } else {
+            format!("{}: No matching expectation found", ident)
+        };
+        CallError { msg, rejected }
+    }
+
+    /// Describe each of the expectations that the call rejected, and why.
+    pub fn rejected(&self) -> &[String] {
+        &self.rejected
+    }
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)?;
+        let n = self.rejected.len();
+        if n > 0 {
+            write!(f, ".  {} expectation{}, none matched:", n,
+                   if n == 1 {""} else {"s"})?;
+            for (i, r) in self.rejected.iter().enumerate() {
+                write!(f, "\n#{} {}", i + 1, r)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// Handle a call that matched no expectation.  Normally, panic with a message
+/// listing the expectations that it rejected.  But if this thread's calls are
+/// fallible, and `fallback` can supply a return value, then save the error and
+/// return that value instead.
+#[doc(hidden)]
+#[track_caller]
+pub fn no_match<O, F>(mock: &'static str, method: &'static str, fallback: F)
+    -> O
+    where F: FnOnce() -> Option<O>
+{
+    trace_unmatched(mock, method);
+    let e = CallError::new(mock, method);
+    if FALLIBLE.with(Cell::get) {
+        if let Some(o) = fallback() {
+            LAST_ERROR.with(|l| *l.borrow_mut() = Some(e));
+            return o;
+        }
+    }
+    panic!("{}", e);
+}
+
+/// Handle an expectation of a method that returns `!`, which returned anyway.
+/// Such expectations must panic instead, usually with `panics_with`.
+#[doc(hidden)]
+#[track_caller]
+pub fn never_returned(mock: &'static str, method: &'static str) -> ! {
+    panic!("{}::{}: Expectation returned from a method that never returns.  Use panics_with instead.",
+           mock, method);
+}
+
+/// Makes calls on the current thread that match no expectation fallible.
+/// Created by [`fallible`].  When dropped, it restores the previous behavior.
+#[must_use = "calls are only fallible while the guard exists"]
+pub struct FallibleGuard(bool);
+
+impl Drop for FallibleGuard {
+    fn drop(&mut self) {
+        FALLIBLE.with(|f| f.set(self.0));
+    }
+}
+
+/// Make calls on the current thread that match no expectation fallible, for
+/// as long as the returned guard exists.
+///
+/// Normally, such a call panics.  But that's inconvenient for harnesses that
+/// treat any panic as a crash, like some fuzzers.  While the guard exists,
+/// such a call instead returns the default value of the mocked method's
+/// return type, and saves a [`CallError`] for [`take_last_error`].
+///
+/// Only calls that match no expectation are affected.  If the return type has
+/// no default value, like a reference or a type that doesn't implement
+/// `Default`, then the call still panics.  So do calls that match an
+/// expectation but violate its call count or sequence.  The return type must
+/// be concrete for its default value to be found, unless the "nightly" feature
+/// is enabled.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::*;
+/// #[automock]
+/// trait Foo {
+///     fn foo(&self, x: u32) -> u32;
+/// }
+///
+/// # fn main() {
+/// let mut mock = MockFoo::new();
+/// mock.expect_foo()
+///     .with(predicate::eq(1))
+///     .return_const(42u32);
+/// let _guard = mockall::fallible();
+/// assert_eq!(42, mock.foo(1));
+/// assert!(mockall::take_last_error().is_none());
+/// assert_eq!(0, mock.foo(2));
+/// let e = mockall::take_last_error().unwrap();
+/// assert!(e.to_string().starts_with("MockFoo::foo: No matching expectation"));
+/// # }
+/// ```
+pub fn fallible() -> FallibleGuard {
+    FallibleGuard(FALLIBLE.with(|f| f.replace(true)))
+}
+
+/// Take the error saved by the current thread's last fallible call, if any.
+/// See [`fallible`].
+pub fn take_last_error() -> Option<CallError> {
+    LAST_ERROR.with(|l| l.borrow_mut().take())
+}
+
+/// Supplies the default value for a fallible call's return type, if it has
+/// one.  Call it like `(&&Fallback::<O>(PhantomData)).fallback()`, with both
+/// [`FallbackDefault`] and [`FallbackNone`] in scope.  Method resolution will
+/// prefer the former when `O: Default`.
+#[doc(hidden)]
+pub struct Fallback<O>(pub PhantomData<O>);
+
+#[doc(hidden)]
+pub trait FallbackDefault<O> {
+    fn fallback(&self) -> Option<O>;
+}
+
+impl<O: Default> FallbackDefault<O> for &Fallback<O> {
+    fn fallback(&self) -> Option<O> {
+        Some(O::default())
+    }
+}
+
+#[doc(hidden)]
+pub trait FallbackNone<O> {
+    fn fallback(&self) -> Option<O>;
+}
+
+impl<O> FallbackNone<O> for Fallback<O> {
+    fn fallback(&self) -> Option<O> {
+        DefaultReturner::<O>::maybe_return_default()
+    }
+}
+
+/// Formats a recorded argument or return value.  Call it like
+/// `(&&ArgPrinter(&x)).debug_string()`, with both [`ViaDebug`] and
+/// [`ViaNothing`] in scope.  Method resolution will prefer the former when the
+/// value implements `Debug`.
+#[doc(hidden)]
+pub struct ArgPrinter<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait ViaDebug {
+    fn debug_string(&self) -> String;
+}
+
+impl<'a, T: fmt::Debug + ?Sized> ViaDebug for &ArgPrinter<'a, T> {
+    fn debug_string(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaNothing {
+    fn debug_string(&self) -> String;
+}
+
+impl<'a, T: ?Sized> ViaNothing for ArgPrinter<'a, T> {
+    fn debug_string(&self) -> String {
+        String::from("?")
+    }
+}
+
+/// One call recorded by a [`Recording`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordedCall {
+    method: String,
+    args: Vec<String>,
+    returned: Option<String>
+}
+
+impl RecordedCall {
+    /// The mock struct and method, like `MockFoo::foo`.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Each argument, formatted with `Debug`.  Arguments that don't implement
+    /// `Debug` are shown as `?`.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// The value that the call returned, formatted like the arguments.
+    /// `None` if the call panicked instead.
+    pub fn returned(&self) -> Option<&str> {
+        self.returned.as_deref()
+    }
+}
+
+impl fmt::Display for RecordedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.method, self.args.join(", "))?;
+        match &self.returned {
+            Some(r) => write!(f, " -> {}", r),
+            None => write!(f, " panicked")
+        }
+    }
+}
+
+/// A record of every call to a mock object's non-static methods, in order.
+///
+/// Started by the mock's `record` method.  Useful for characterization tests:
+/// run the code under test against a mock with permissive expectations, then
+/// compare the recording's [`Display`](fmt::Display) or
+/// [`to_json`](#method.to_json) output to a snapshot.  The mock keeps
+/// recording for as long as it lives, or until `record` is called again.
+///
+/// # Examples
+/// ```
+/// # use mockall::*;
+/// #[automock]
+/// trait Foo {
+///     fn foo(&self, x: u32, name: &str) -> u32;
+/// }
+///
+/// # fn main() {
+/// let mut mock = MockFoo::new();
+/// mock.expect_foo().return_const(0u32);
+/// let recording = mock.record();
+/// mock.foo(1, "one");
+/// mock.foo(2, "two");
+/// assert_eq!(recording.to_string(),
+///     "MockFoo::foo(1, \"one\") -> 0\nMockFoo::foo(2, \"two\") -> 0");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Recording(Arc<Mutex<Vec<RecordedCall>>>);
+
+impl Recording {
+    /// The calls recorded so far.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Format the calls recorded so far as a JSON array, one call per line.
+    /// Each call is an object with `method`, `args`, and `returned` members.
+    /// `returned` is `null` if the call panicked.
+    pub fn to_json(&self) -> String {
+        let calls = self.0.lock().unwrap();
+        if calls.is_empty() {
+            return String::from("[]");
+        }
+        let rows = calls.iter().map(|c| {
+            let args = c.args.iter()
+                .map(|a| json_string(a))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let returned = c.returned.as_deref()
+                .map(json_string)
+                .unwrap_or_else(|| String::from("null"));
+            format!("  {{\"method\": {}, \"args\": [{}], \"returned\": {}}}",
+                    json_string(&c.method), args, returned)
+        }).collect::<Vec<_>>();
+        format!("[\n{}\n]", rows.join(",\n"))
+    }
+
+    /// Record the start of a call.
+    #[doc(hidden)]
+    pub fn start(&self, method: &str, args: Vec<String>) -> PendingRecord {
+        let mut calls = self.0.lock().unwrap();
+        calls.push(RecordedCall {
+            method: method.to_owned(),
+            args,
+            returned: None
+        });
+        PendingRecord{recording: self.clone(), i: calls.len() - 1}
+    }
+}
+
+impl fmt::Debug for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.lock().unwrap().iter()).finish()
+    }
+}
+
+impl fmt::Display for Recording {
+    /// One call per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in self.0.lock().unwrap().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+/// A call that has been recorded, but hasn't returned yet.  Recording the
+/// start of each call separately keeps nested calls in order.
+#[doc(hidden)]
+pub struct PendingRecord {
+    recording: Recording,
+    i: usize
+}
+
+impl PendingRecord {
+    /// Record the call's return value.
+    pub fn finish(self, returned: String) {
+        self.recording.0.lock().unwrap()[self.i].returned = Some(returned);
+    }
+}
+
+/// Quote and escape a string for JSON.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            },
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A [`DynExpectation`]'s matcher, over the type-erased arguments
+type AnyMatcher = Box<dyn Fn(&[&dyn any::Any]) -> bool + Send>;
+
+/// A [`DynExpectation`]'s return function, over the type-erased arguments
+type AnyReturner =
+    Box<dyn FnMut(&[&dyn any::Any]) -> Box<dyn any::Any> + Send>;
+
+/// An expectation for a generic method that applies to every instantiation of
+/// the method's generic parameters.
+///
+/// Its matcher and return function see the method's arguments as `&dyn Any`,
+/// one per argument, and may downcast them as they see fit.  The return
+/// function's result gets downcast to the method's actual return type.  These
+/// are created by methods like `expect_foo_any`, which Mockall generates for
+/// generic methods that take `self` by reference and whose arguments and
+/// return type are all owned `'static` values.
+///
+/// They're a fallback: Mockall only uses them for instantiations that have no
+/// expectations of their own.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::*;
+/// # use std::any::Any;
+/// #[automock]
+/// trait Foo {
+///     fn foo<T: 'static>(&self, t: T) -> T;
+/// }
+///
+/// let mut mock = MockFoo::new();
+/// mock.expect_foo_any()
+///     .withf(|args: &[&dyn Any]| args[0].downcast_ref::<u32>().is_none())
+///     .returning(|args: &[&dyn Any]| {
+///         if let Some(s) = args[0].downcast_ref::<String>() {
+///             Box::new(s.to_uppercase())
+///         } else {
+///             Box::new(args[0].downcast_ref::<i16>().unwrap() + 1)
+///         }
+///     });
+/// assert_eq!("HELLO", mock.foo(String::from("hello")));
+/// assert_eq!(6, mock.foo(5i16));
+/// ```
+pub struct DynExpectation {
+    name: &'static str,
+    matcher: Mutex<Option<AnyMatcher>>,
+    rfunc: Mutex<Option<AnyReturner>>,
+    times: Times,
+    location: SetLocation
+}
+
+impl DynExpectation {
+    /// Return a constant value from the expectation.  It must be the method's
+    /// actual return type for every instantiation that uses it.
+    pub fn return_const<T>(&mut self, c: T) -> &mut Self
+        where T: Clone + Send + 'static
+    {
+        self.returning(move |_| Box::new(c.clone()))
+    }
+
+    /// Supply a closure that the expectation will use to create its return
+    /// value.  The value it returns must be the method's actual return type
+    /// for every instantiation that uses it.
+    pub fn returning<F>(&mut self, f: F) -> &mut Self
+        where F: FnMut(&[&dyn any::Any]) -> Box<dyn any::Any> + Send + 'static
+    {
+        *self.rfunc.lock().unwrap() = Some(Box::new(f));
+        self
+    }
+
+    /// Expect this expectation to be called exactly once.
+    pub fn once(&mut self) -> &mut Self {
+        self.times(1)
+    }
+
+    /// Forbid this expectation from ever being called.
+    pub fn never(&mut self) -> &mut Self {
+        self.times.never();
+        self
+    }
+
+    /// Exempt this expectation from its minimum call count, but not its
+    /// maximum.
+    pub fn optional(&mut self) -> &mut Self {
+        self.times.optional();
+        self
+    }
+
+    /// Expect this expectation to be called any number of times contained
+    /// with the given range.
+    pub fn times<T: Into<TimesRange>>(&mut self, t: T) -> &mut Self {
+        self.times.times(t);
+        self
+    }
+
+    /// Set a matching function for this expectation.  It receives each of
+    /// the method's arguments as a `&dyn Any`.
+    pub fn withf<F>(&mut self, f: F) -> &mut Self
+        where F: Fn(&[&dyn any::Any]) -> bool + Send + 'static
+    {
+        *self.matcher.lock().unwrap() = Some(Box::new(f));
+        self
+    }
+
+    fn matches(&self, args: &[&dyn any::Any]) -> bool {
+        self.matcher.lock().unwrap().as_ref().map_or(true, |f| f(args))
+    }
+
+    fn describe(&self) -> String {
+        let matcher = if self.matcher.lock().unwrap().is_some() {
+            "<function>"
+        } else {
+            "<anything>"
+        };
+        format!("Expectation({} for any type){}", matcher, self.location)
+    }
+
+    #[track_caller]
+    fn call<O: 'static>(&self, args: &[&dyn any::Any]) -> O {
+        if let Err(m) = self.times.call() {
+            self.fail(&m);
+        }
+        let mut guard = self.rfunc.lock().unwrap();
+        let r = match guard.as_mut() {
+            Some(f) => f(args).downcast::<O>().map(|o| *o).map_err(|_| {
+                format!("returned a value that isn't a {}",
+                        any::type_name::<O>())
+            }),
+            None => DefaultReturner::<O>::return_default()
+                .map_err(String::from)
+        };
+        match r {
+            Ok(o) => o,
+            Err(m) => self.fail(&m)
+        }
+    }
+
+    #[track_caller]
+    fn fail(&self, msg: &dyn fmt::Display) -> ! {
+        panic!("{}: {} {}", self.name, self.describe(), msg)
+    }
+
+    fn report(&self) -> String {
+        format!("{}: {}: {}", self.name, self.describe(), self.times.report())
+    }
+
+    /// If this expectation hasn't been called often enough, then describe
+    /// the problem, and forget about it so that it won't be reported again on
+    /// drop.
+    fn unsatisfied(&mut self) -> Option<String> {
+        if self.times.is_satisfied() {
+            return None;
+        }
+        let msg = format!("{}: {} called fewer than {} times", self.name,
+                          self.describe(), self.times.minimum());
+        self.times.times(..);
+        Some(msg)
+    }
+}
+
+impl Drop for DynExpectation {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            if let Some(msg) = self.unsatisfied() {
+                panic!("{}", msg);
+            }
+        }
+    }
+}
+
+/// A generic method's collection of [`DynExpectation`]s.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct DynExpectations(Vec<DynExpectation>);
+
+impl DynExpectations {
+    /// Simulate calling the real method, using the first expectation with
+    /// matching arguments.
+    #[track_caller]
+    pub fn call<O: 'static>(&self, args: &[&dyn any::Any]) -> Option<O> {
+        let n = self.0.len();
+        let e = self.0.iter()
+            .find(|e| e.matches(args) && (!e.times.is_done() || n == 1));
+        if e.is_none() {
+            reject(self.0.iter().map(|e| {
+                let why = if e.matches(args) {
+                    e.times.exhausted()
+                } else {
+                    String::from("rejected by the `withf` function")
+                };
+                format!("{}: {}", e.describe(), why)
+            }).collect());
+        }
+        // Not `e.map`, because a closure would lose the caller's location
+        Some(e?.call(args))
+    }
+
+    /// Would a call with these arguments match any current expectation?
+    pub fn has_match(&self, args: &[&dyn any::Any]) -> bool {
+        let n = self.0.len();
+        self.0.iter().any(|e| e.matches(args) && (!e.times.is_done() || n == 1))
+    }
+
+    /// Clear all current expectations, returning a description of each one
+    /// that wasn't satisfied.
+    pub fn checkpoint(&mut self) -> Vec<String> {
+        self.0.drain(..)
+            .filter_map(|mut e| e.unsatisfied())
+            .collect()
+    }
+
+    /// Summarize the state of every expectation, one per line.
+    pub fn report(&self) -> Vec<String> {
+        self.0.iter().map(DynExpectation::report).collect()
+    }
+
+    /// The number of current expectations.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no current expectations?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Create a new expectation for the method named `name`.
+    #[track_caller]
+    pub fn expect(&mut self, name: &'static str) -> &mut DynExpectation {
+        self.0.push(DynExpectation {
+            name,
+            matcher: Mutex::new(None),
+            rfunc: Mutex::new(None),
+            times: Times::default(),
+            location: SetLocation::caller()
+        });
+        self.0.last_mut().unwrap()
+    }
+
+    pub const fn new() -> Self {
+        DynExpectations(Vec::new())
     }
+}
+
+/// A `Mutex` for an expectation's return function.  Unlike a plain `Mutex`, it
+/// detects when the return function tries to call its own expectation, which
+/// would otherwise deadlock.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct CallMutex<T> {
+    inner: Mutex<T>,
+    owner: Mutex<Option<thread::ThreadId>>,
+}
 
-    /// Has this expectation already been called the minimum required number of
-    /// times?
-    pub fn is_satisfied(&self) -> bool {
-        self.count.load(Ordering::Relaxed) >= self.range.0.start
+impl<T> CallMutex<T> {
+    pub fn new(t: T) -> Self {
+        CallMutex {
+            inner: Mutex::new(t),
+            owner: Mutex::new(None)
+        }
     }
 
-    /// The minimum number of times that this expectation must be called
-    pub fn minimum(&self) -> usize {
-        self.range.0.start
+    /// Lock the mutex for configuration
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        Ok(self.inner.lock().unwrap_or_else(PoisonError::into_inner))
     }
 
-    // https://github.com/rust-lang/rust-clippy/issues/3307
-    #[allow(clippy::range_plus_one)]
-    pub fn n(&mut self, n: usize) {
-        self.range.0 = n..(n+1);
+    /// Lock the mutex in order to call the return function.  Return `None` if
+    /// the current thread already holds it.
+    pub fn lock_for_call(&self) -> Option<CallGuard<'_, T>> {
+        let me = thread::current().id();
+        if *self.owner.lock().unwrap() == Some(me) {
+            return None;
+        }
+        // A return function that panics, like one set by `panics_with`,
+        // poisons the mutex.  But it leaves the return function intact.
+        let guard = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        *self.owner.lock().unwrap() = Some(me);
+        Some(CallGuard{owner: &self.owner, guard})
     }
+}
 
-    pub fn never(&mut self) {
-        self.range.0 = 0..1;
+/// Returned by [`CallMutex::lock_for_call`]
+#[doc(hidden)]
+pub struct CallGuard<'a, T> {
+    owner: &'a Mutex<Option<thread::ThreadId>>,
+    guard: MutexGuard<'a, T>
+}
+
+impl<'a, T> Deref for CallGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
     }
+}
 
-    pub fn range(&mut self, range: Range<usize>) {
-        assert!(range.end > range.start, "Backwards range");
-        self.range.0 = range;
+impl<'a, T> DerefMut for CallGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
     }
+}
 
-    pub fn times<T: Into<TimesRange>>(&mut self, t: T) {
-        self.range = t.into();
+impl<'a, T> Drop for CallGuard<'a, T> {
+    fn drop(&mut self) {
+        // The owner's lock can only be poisoned by a panic while it's held,
+        // and nothing that can panic happens then.
+        if let Ok(mut owner) = self.owner.lock() {
+            *owner = None;
+        }
     }
 }
 
-/// Non-generic keys to `GenericExpectation` internal storage
+/// Non-generic keys to `GenericExpectation` internal storage.  They remember
+/// the name of the generic parameters' types, for error messages.
 #[doc(hidden)]
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct Key(any::TypeId);
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Key(any::TypeId, &'static str);
 
 impl Key {
     pub fn new<T: 'static>() -> Self {
-        Key(any::TypeId::of::<T>())
+        Key(any::TypeId::of::<T>(), any::type_name::<T>())
+    }
+
+    /// The generic parameters' types, like `u32` or `u32, i16`.  `T` must be
+    /// a tuple of them.
+    pub fn type_name(&self) -> &'static str {
+        let tn = self.1;
+        tn.strip_prefix('(')
+            .and_then(|tn| tn.strip_suffix(')'))
+            .map(|tn| tn.strip_suffix(',').unwrap_or(tn))
+            .unwrap_or(tn)
+    }
+
+    /// Name this key's generic parameters in each message about method
+    /// `ident`, so "MockFoo::foo: ..." becomes "MockFoo::foo::<u32>: ...".
+    pub fn annotate(&self, ident: &str, msgs: Vec<String>) -> Vec<String> {
+        let params = self.type_name();
+        msgs.into_iter()
+            .map(|msg| match msg.strip_prefix(ident) {
+                Some(rest) if rest.starts_with(':') =>
+                    format!("{}::<{}>{}", ident, params, rest),
+                _ => msg
+            }).collect()
     }
 }
 
@@ -1429,32 +3551,53 @@ pub struct SeqHandle {
 
 impl SeqHandle {
     /// Tell the Sequence that this expectation has been fully satisfied
+    #[track_caller]
     pub fn satisfy(&self) {
         self.inner.satisfy(self.seq);
     }
 
     /// Verify that this handle was called in the correct order
+    #[track_caller]
     pub fn verify(&self) {
         self.inner.verify(self.seq);
     }
+
+    /// Allow the sequence to skip this handle's expectation
+    pub fn optional(&self) {
+        self.inner.optional.lock().unwrap().insert(self.seq);
+    }
 }
 
 #[derive(Default)]
 struct SeqInner {
+    /// The slot that the next expectation added to the sequence will get
+    next_seq: AtomicUsize,
     satisfaction_level: AtomicUsize,
+    /// The calls that may be skipped
+    optional: Mutex<HashSet<usize>>,
 }
 
 impl SeqInner {
+    /// May the sequence skip from the call identified by `from` to the one
+    /// identified by `to`?
+    fn may_skip(&self, from: usize, to: usize) -> bool {
+        let optional = self.optional.lock().unwrap();
+        from <= to && (from..to).all(|seq| optional.contains(&seq))
+    }
+
     /// Record the call identified by `seq` as fully satisfied.
+    #[track_caller]
     fn satisfy(&self, seq: usize) {
-        let old_sl = self.satisfaction_level.fetch_add(1, Ordering::Relaxed);
-        assert_eq!(old_sl, seq, "Method sequence violation.  Was an already-satisfied method called another time?");
+        let old_sl = self.satisfaction_level.fetch_max(seq + 1,
+                                                       Ordering::AcqRel);
+        assert!(self.may_skip(old_sl, seq), "Method sequence violation.  Was an already-satisfied method called another time?");
     }
 
     /// Verify that the call identified by `seq` was called in the correct order
+    #[track_caller]
     fn verify(&self, seq: usize) {
-        assert_eq!(seq, self.satisfaction_level.load(Ordering::Relaxed),
-            "Method sequence violation")
+        let sl = self.satisfaction_level.load(Ordering::Acquire);
+        assert!(self.may_skip(sl, seq), "Method sequence violation")
     }
 }
 
@@ -1462,6 +3605,7 @@ impl SeqInner {
 ///
 /// Each expectation must expect to be called a fixed number of times.  Once
 /// satisfied, the next expectation in the sequence will expect to be called.
+/// But an expectation marked `optional` may be skipped.
 ///
 /// # Examples
 /// ```
@@ -1471,7 +3615,7 @@ impl SeqInner {
 ///     fn foo(&self);
 ///     fn bar(&self) -> u32;
 /// }
-/// let mut seq = Sequence::new();
+/// let seq = Sequence::new();
 ///
 /// let mut mock0 = MockFoo::new();
 /// let mut mock1 = MockFoo::new();
@@ -1479,17 +3623,20 @@ impl SeqInner {
 /// mock0.expect_foo()
 ///     .times(1)
 ///     .returning(|| ())
-///     .in_sequence(&mut seq);
+///     .in_sequence(&seq);
 ///
 /// mock1.expect_bar()
 ///     .times(1)
 ///     .returning(|| 42)
-///     .in_sequence(&mut seq);
+///     .in_sequence(&seq);
 ///
 /// mock0.foo();
 /// mock1.bar();
 /// ```
 ///
+/// Clones of a `Sequence` all refer to the same sequence, so expectations
+/// set and called on different threads may be ordered by one sequence.
+///
 /// It is an error to add an expectation to a `Sequence` if its call count is
 /// unspecified.
 /// ```should_panic(expected = "with an exact call count")
@@ -1498,17 +3645,16 @@ impl SeqInner {
 /// trait Foo {
 ///     fn foo(&self);
 /// }
-/// let mut seq = Sequence::new();
+/// let seq = Sequence::new();
 ///
 /// let mut mock = MockFoo::new();
 /// mock.expect_foo()
 ///     .returning(|| ())
-///     .in_sequence(&mut seq);  // panics!
+///     .in_sequence(&seq);  // panics!
 /// ```
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Sequence {
     inner: Arc<SeqInner>,
-    next_seq: usize,
 }
 
 impl Sequence {
@@ -1519,9 +3665,680 @@ impl Sequence {
     /// Not for public consumption, but it must be public so the generated code
     /// can call it.
     #[doc(hidden)]
-    pub fn next_handle(&mut self) -> SeqHandle {
-        let handle = SeqHandle{inner: self.inner.clone(), seq: self.next_seq};
-        self.next_seq += 1;
-        handle
+    pub fn next_handle(&self) -> SeqHandle {
+        let seq = self.inner.next_seq.fetch_add(1, Ordering::AcqRel);
+        SeqHandle{inner: self.inner.clone(), seq}
+    }
+}
+
+/// A reference to an expectation, which other expectations may be ordered
+/// after.
+///
+/// Obtained from an expectation's `handle` method, and used by other
+/// expectations' `after` methods.  Unlike a [`Sequence`], this orders only
+/// the pairs of expectations that matter.  An expectation that must happen
+/// after another one panics if it's called before the other has been
+/// satisfied, and called at least once.  The two expectations may belong to
+/// different mocks, or to static methods.
+///
+/// # Examples
+/// ```
+/// # use mockall::*;
+/// #[automock]
+/// trait File {
+///     fn open(&self);
+///     fn read(&self) -> u32;
+///     fn stat(&self) -> u64;
+/// }
+///
+/// # fn main() {
+/// let mut mock = MockFile::new();
+/// let opened = mock.expect_open()
+///     .times(1)
+///     .return_const(())
+///     .handle();
+/// mock.expect_read()
+///     .after(&opened)
+///     .return_const(42u32);
+/// // stat isn't ordered relative to the others
+/// mock.expect_stat()
+///     .return_const(0u64);
+/// mock.stat();
+/// mock.open();
+/// mock.read();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ExpectationHandle(Arc<HandleInner>);
+
+struct HandleInner {
+    /// The method's name and where the expectation was set
+    name: String,
+    /// Has the expectation been satisfied, and called at least once?
+    satisfied: AtomicBool,
+    /// The expectations that this one must happen after
+    after: Mutex<Vec<ExpectationHandle>>
+}
+
+impl ExpectationHandle {
+    #[doc(hidden)]
+    pub fn new(name: String) -> Self {
+        ExpectationHandle(Arc::new(HandleInner {
+            name,
+            satisfied: AtomicBool::new(false),
+            after: Mutex::new(Vec::new())
+        }))
+    }
+
+    /// Require this handle's expectation to happen after `other`'s.
+    #[doc(hidden)]
+    pub fn after(&self, other: &ExpectationHandle) {
+        self.0.after.lock().unwrap().push(other.clone());
+    }
+
+    /// Record that this handle's expectation has been satisfied.
+    #[doc(hidden)]
+    pub fn satisfy(&self) {
+        self.0.satisfied.store(true, Ordering::Relaxed);
+    }
+
+    /// Verify that every expectation that this one must happen after has
+    /// been satisfied.
+    #[doc(hidden)]
+    pub fn verify(&self) -> Result<(), String> {
+        let after = self.0.after.lock().unwrap().clone();
+        for other in after.iter() {
+            if other.0.satisfied.load(Ordering::Relaxed) {
+                continue;
+            }
+            let mut visited = HashSet::new();
+            if let Some(mut cycle) = other.path_to(self, &mut visited) {
+                cycle.insert(0, self.0.name.clone());
+                return Err(format!(
+                    "can never be called, because of a happens-before cycle: \
+                    {}", cycle.join(" after ")));
+            }
+            return Err(format!("called before {}, which must happen first",
+                               other.0.name));
+        }
+        Ok(())
+    }
+
+    /// Find a chain of unsatisfied expectations, each of which must happen
+    /// after the next, from this one to `target`.  Return their names.
+    fn path_to(&self, target: &ExpectationHandle,
+               visited: &mut HashSet<*const HandleInner>)
+        -> Option<Vec<String>>
+    {
+        if Arc::ptr_eq(&self.0, &target.0) {
+            return Some(vec![self.0.name.clone()]);
+        }
+        if !visited.insert(Arc::as_ptr(&self.0)) {
+            return None;
+        }
+        let after = self.0.after.lock().unwrap().clone();
+        after.iter()
+            .filter(|h| !h.0.satisfied.load(Ordering::Relaxed))
+            .find_map(|h| h.path_to(target, visited))
+            .map(|mut path| {
+                path.insert(0, self.0.name.clone());
+                path
+            })
+    }
+}
+
+/// A reference to one of a mock object's expectations, that doesn't borrow the
+/// mock object.
+///
+/// Obtained from an expectation's `stable_ref` method.  Unlike the `&mut`
+/// reference returned by an `expect_*` method, a test may hold several of
+/// these at once, store them alongside the mock, and use them to reconfigure
+/// their expectations after setting others.
+///
+/// # Examples
+/// ```
+/// # use mockall::*;
+/// #[automock]
+/// trait Foo {
+///     fn foo(&self, x: u32) -> u32;
+/// }
+///
+/// # fn main() {
+/// let mut mock = MockFoo::new();
+/// let one = mock.expect_foo()
+///     .with(predicate::eq(1))
+///     .return_const(10u32)
+///     .stable_ref();
+/// let two = mock.expect_foo()
+///     .with(predicate::eq(2))
+///     .return_const(20u32)
+///     .stable_ref();
+/// one.get(&mut mock).return_const(11u32);
+/// two.get(&mut mock).times(1);
+/// assert_eq!(11, mock.foo(1));
+/// assert_eq!(20, mock.foo(2));
+/// # }
+/// ```
+pub struct ExpectationRef<E> {
+    id: usize,
+    _e: PhantomData<fn() -> E>
+}
+
+impl<E> ExpectationRef<E> {
+    #[doc(hidden)]
+    pub fn new(id: usize) -> Self {
+        ExpectationRef{id, _e: PhantomData}
+    }
+
+    /// Borrow the expectation from the mock object that owns it.
+    ///
+    /// # Panics
+    ///
+    /// If the expectation no longer exists, because it was cleared by a
+    /// checkpoint, or if `mock` is a different mock object.
+    pub fn get<'a, M>(&self, mock: &'a mut M) -> &'a mut E
+        where M: ExpectationStore<E>
+    {
+        mock.expectation_by_id(self.id)
+            .expect("No such expectation.  It was cleared by a checkpoint, or \
+                    it belongs to a different mock object")
+    }
+}
+
+impl<E> Clone for ExpectationRef<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for ExpectationRef<E> {}
+
+impl<E> fmt::Debug for ExpectationRef<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ExpectationRef").field(&self.id).finish()
+    }
+}
+
+/// Implemented by mock objects for each of their methods' expectation types,
+/// so an [`ExpectationRef`] can find its expectation.
+#[doc(hidden)]
+pub trait ExpectationStore<E> {
+    fn expectation_by_id(&mut self, id: usize) -> Option<&mut E>;
+}
+
+/// A new id for an expectation, unique within the process.
+#[doc(hidden)]
+pub fn next_expectation_id() -> usize {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A call count shared by several expectations, possibly of different mocks.
+///
+/// Each expectation enrolled with `in_budget` adds its weight to the budget's
+/// count whenever it's called.  A call that would exceed the budget's maximum
+/// panics.  The budget's minimum is verified by [`verify`](#method.verify),
+/// and by the checkpoint of every mock with an enrolled expectation, including
+/// the implicit checkpoint when the mock is dropped.  Once a budget has failed
+/// its minimum, it won't be reported again.
+///
+/// # Examples
+///
+/// Exactly one of `commit` or `rollback` must be called.
+/// ```
+/// # use mockall::*;
+/// #[automock]
+/// trait Transaction {
+///     fn commit(&self);
+///     fn rollback(&self);
+/// }
+///
+/// # fn main() {
+/// let budget = CallBudget::new(1);
+/// let mut mock = MockTransaction::new();
+/// mock.expect_commit()
+///     .in_budget(&budget, 1)
+///     .return_const(());
+/// mock.expect_rollback()
+///     .in_budget(&budget, 1)
+///     .return_const(());
+/// mock.rollback();
+/// budget.verify();
+/// # }
+/// ```
+pub struct CallBudget {
+    inner: Arc<BudgetInner>
+}
+
+impl CallBudget {
+    /// Create a new budget, allowing a total weight of calls within the given
+    /// range, like the argument to an expectation's `times` method.
+    #[track_caller]
+    pub fn new<T: Into<TimesRange>>(t: T) -> Self {
+        let inner = BudgetInner {
+            count: AtomicUsize::new(0),
+            range: t.into(),
+            reported: AtomicBool::new(false),
+            location: SetLocation::caller()
+        };
+        CallBudget { inner: Arc::new(inner) }
+    }
+
+    /// Panic if the budget's enrolled expectations haven't yet been called
+    /// the minimum number of times.
+    pub fn verify(&self) {
+        if let Some(msg) = self.inner.unsatisfied() {
+            panic!("{}", msg);
+        }
+    }
+
+    /// Not for public consumption, but it must be public so the generated code
+    /// can call it.
+    #[doc(hidden)]
+    pub fn handle(&self, weight: usize) -> BudgetHandle {
+        BudgetHandle { inner: self.inner.clone(), weight }
+    }
+}
+
+struct BudgetInner {
+    /// The total weight of the calls so far
+    count: AtomicUsize,
+    range: TimesRange,
+    /// Has the budget's failure already been reported?
+    reported: AtomicBool,
+    location: SetLocation
+}
+
+impl BudgetInner {
+    fn unsatisfied(&self) -> Option<String> {
+        let min = self.range.0.start;
+        if self.count.load(Ordering::Relaxed) >= min {
+            return None;
+        }
+        self.reported.store(true, Ordering::Relaxed);
+        Some(format!("{} called fewer than {} times", self, min))
+    }
+}
+
+impl fmt::Display for BudgetInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CallBudget({}){}", self.range, self.location)
+    }
+}
+
+/// An expectation's enrollment in a [`CallBudget`].
+#[doc(hidden)]
+pub struct BudgetHandle {
+    inner: Arc<BudgetInner>,
+    weight: usize
+}
+
+impl BudgetHandle {
+    /// Add this expectation's weight to the budget.  Fail if that exceeds it.
+    pub fn call(&self) -> Result<(), String> {
+        let count = self.inner.count.fetch_add(self.weight, Ordering::Relaxed)
+            + self.weight;
+        if count >= self.inner.range.0.end {
+            Err(format!("exceeded {}", self.inner))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Describe the budget's failure, if it hasn't been called often enough
+    /// and hasn't already been reported.
+    pub fn unsatisfied(&self) -> Option<String> {
+        if self.inner.reported.load(Ordering::Relaxed) {
+            None
+        } else {
+            self.inner.unsatisfied()
+        }
+    }
+}
+
+thread_local! {
+    /// The handles of the expectations created by each [`Fixture`] step
+    /// currently being installed on this thread, innermost last.
+    static INSTALLING: RefCell<Vec<Vec<ExpectationHandle>>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Is a [`Fixture`] being installed on this thread?  If so, new expectations
+/// belong to it.
+#[doc(hidden)]
+pub fn installing_fixture() -> bool {
+    INSTALLING.with(|i| !i.borrow().is_empty())
+}
+
+/// Record an expectation created by the [`Fixture`] step being installed.
+#[doc(hidden)]
+pub fn fixture_member(handle: ExpectationHandle) {
+    INSTALLING.with(|i| {
+        if let Some(step) = i.borrow_mut().last_mut() {
+            step.push(handle);
+        }
+    });
+}
+
+/// Ends the installation of a [`Fixture`] step, even if it panics.
+struct InstallGuard;
+
+impl InstallGuard {
+    fn new() -> Self {
+        INSTALLING.with(|i| i.borrow_mut().push(Vec::new()));
+        InstallGuard
+    }
+
+    /// The handles of the expectations that the step created.
+    fn finish(self) -> Vec<ExpectationHandle> {
+        INSTALLING.with(|i| {
+            i.borrow_mut().last_mut().map(std::mem::take).unwrap_or_default()
+        })
+    }
+}
+
+impl Drop for InstallGuard {
+    fn drop(&mut self) {
+        INSTALLING.with(|i| i.borrow_mut().pop());
+    }
+}
+
+/// A [`Fixture`] step's closure, which sets expectations on a mock
+type Step<M> = Arc<dyn Fn(&mut M) + Send + Sync>;
+
+/// A reusable, named set of expectations, like a standard "happy path" setup
+/// shared by many tests.
+///
+/// Each step is a closure that sets expectations on a mock.  Nothing happens
+/// until the fixture is [`install`](#method.install)ed on a mock.  Before
+/// then, its steps can be listed by name, and individual steps can be
+/// removed.
+///
+/// The expectations created by a fixture have lower priority than the test's
+/// own.  A call is only matched against the fixture's expectations if none of
+/// the test's own expectations for that method match its arguments.  So a test
+/// can override part of a fixture just by setting expectations of its own,
+/// even ones that are already done, like those with `never()` or a tighter
+/// `times`.
+///
+/// # Examples
+/// ```
+/// # use mockall::*;
+/// #[automock]
+/// trait Store {
+///     fn get(&self, key: u32) -> Option<u32>;
+///     fn put(&self, key: u32, value: u32);
+/// }
+///
+/// # fn main() {
+/// let happy_path = Fixture::new()
+///     .expect("get", |m: &mut MockStore| {
+///         m.expect_get().return_const(Some(1));
+///     })
+///     .expect("put", |m: &mut MockStore| {
+///         m.expect_put().return_const(());
+///     });
+///
+/// let mut mock = MockStore::new();
+/// happy_path.install(&mut mock);
+/// // Override the fixture for one key
+/// mock.expect_get()
+///     .with(predicate::eq(5))
+///     .return_const(None);
+/// assert_eq!(None, mock.get(5));
+/// assert_eq!(Some(1), mock.get(6));
+///
+/// // Forbid a method the fixture allows
+/// mock.expect_put().never();
+/// # }
+/// ```
+pub struct Fixture<M> {
+    steps: Vec<(String, Step<M>)>
+}
+
+impl<M> Fixture<M> {
+    /// Create an empty fixture.
+    pub fn new() -> Self {
+        Fixture { steps: Vec::new() }
+    }
+
+    /// Add a named step, which will set expectations on the mock when the
+    /// fixture is installed.  Steps are installed in the order they were
+    /// added.
+    pub fn expect<F>(mut self, name: &str, step: F) -> Self
+        where F: Fn(&mut M) + Send + Sync + 'static
+    {
+        self.steps.push((name.to_owned(), Arc::new(step)));
+        self
+    }
+
+    /// Remove every step with the given name.
+    pub fn without(mut self, name: &str) -> Self {
+        self.steps.retain(|(n, _)| n != name);
+        self
+    }
+
+    /// The names of this fixture's steps, in order.
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.steps.iter().map(|(n, _)| n.as_str())
+    }
+
+    /// Set this fixture's expectations on a mock.  Returns handles to the
+    /// expectations that each step created, which the test's own
+    /// expectations may be ordered [`after`](ExpectationHandle).
+    pub fn install(&self, mock: &mut M) -> FixtureHandles {
+        let mut handles = Vec::new();
+        for (name, step) in self.steps.iter() {
+            let guard = InstallGuard::new();
+            step(mock);
+            handles.push((name.clone(), guard.finish()));
+        }
+        FixtureHandles(handles)
+    }
+}
+
+impl<M> Clone for Fixture<M> {
+    fn clone(&self) -> Self {
+        Fixture { steps: self.steps.clone() }
+    }
+}
+
+impl<M> Default for Fixture<M> {
+    fn default() -> Self {
+        Fixture::new()
+    }
+}
+
+impl<M> fmt::Debug for Fixture<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Handles to the expectations created by an installed [`Fixture`], by step
+/// name.
+pub struct FixtureHandles(Vec<(String, Vec<ExpectationHandle>)>);
+
+impl FixtureHandles {
+    /// The handles of the expectations created by the named step, in the
+    /// order they were created.  Empty if there's no such step.
+    pub fn get(&self, name: &str) -> &[ExpectationHandle] {
+        self.0.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, h)| h.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+thread_local! {
+    /// The expectations of this thread's registered mocks
+    static REGISTRY: RefCell<Vec<Weak<Registration>>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// An expectation of a registered mock, which [`verify_all`] will check.
+///
+/// The expectation owns it, and the registry only has a weak reference, so
+/// registration doesn't keep expectations alive.
+#[doc(hidden)]
+pub struct Registration {
+    /// The method's name, like `MockFoo::foo`
+    ident: &'static str,
+    /// The expectation's matcher, formatted
+    desc: Mutex<String>,
+    location: SetLocation,
+    /// Shared with the expectation
+    times: Times,
+    /// Has the expectation's failure already been reported?
+    reported: AtomicBool
+}
+
+impl Registration {
+    /// Register an expectation with this thread's registry.
+    pub fn new(ident: &'static str, location: SetLocation, times: Times)
+        -> Arc<Self>
+    {
+        let r = Arc::new(Registration {
+            ident,
+            desc: Mutex::new(String::new()),
+            location,
+            times,
+            reported: AtomicBool::new(false)
+        });
+        REGISTRY.with(|reg| reg.borrow_mut().push(Arc::downgrade(&r)));
+        r
+    }
+
+    /// Update the description of the expectation's matcher.
+    pub fn describe(&self, desc: String) {
+        *self.desc.lock().unwrap() = desc;
+    }
+
+    fn report(&self) -> String {
+        format!("{}: Expectation({}){}: {}", self.ident,
+                self.desc.lock().unwrap(), self.location, self.times.report())
+    }
+
+    /// Has the expectation's failure already been reported by
+    /// [`verify_all`]?
+    pub fn is_reported(&self) -> bool {
+        self.reported.load(Ordering::Relaxed)
+    }
+
+    /// If the expectation hasn't been called often enough, then describe the
+    /// problem, and remember that so that it won't be reported again.  The
+    /// expectation itself is unchanged.
+    fn unsatisfied(&self) -> Option<String> {
+        if self.is_reported() || self.times.is_satisfied() {
+            return None;
+        }
+        self.reported.store(true, Ordering::Relaxed);
+        Some(format!("{}: Expectation({}) called fewer than {} times{}",
+                     self.ident, self.desc.lock().unwrap(),
+                     self.times.minimum(), self.location))
+    }
+}
+
+/// Verify the expectations of every registered mock on this thread that's
+/// still alive.
+///
+/// Mocks are registered by creating them with their `new_registered`
+/// constructor instead of `new`.  Every registered expectation that hasn't
+/// been called often enough is reported in a single panic, like by a
+/// checkpoint.  Unlike a checkpoint, the expectations aren't cleared, but
+/// they won't be reported again, either here or when their mocks drop.
+///
+/// The registry is per-thread, so tests running in parallel won't verify each
+/// other's mocks.  And it doesn't keep mocks alive.
+///
+/// # Examples
+/// ```should_panic(expected = "2 expectations were not satisfied")
+/// # use mockall::*;
+/// #[automock]
+/// trait Foo {
+///     fn foo(&self);
+/// }
+///
+/// # fn main() {
+/// let mut mock1 = MockFoo::new_registered();
+/// mock1.expect_foo().times(1).return_const(());
+/// let mut mock2 = MockFoo::new_registered();
+/// mock2.expect_foo().times(1).return_const(());
+/// // Unregistered mocks aren't verified
+/// let mut mock3 = MockFoo::new();
+/// mock3.expect_foo().return_const(());
+/// verify_all();   // Panics!  Neither mock1.foo nor mock2.foo was called.
+/// # }
+/// ```
+pub fn verify_all() {
+    let registered = REGISTRY.with(|reg| {
+        let mut reg = reg.borrow_mut();
+        reg.retain(|r| r.strong_count() > 0);
+        reg.iter().filter_map(Weak::upgrade).collect::<Vec<_>>()
+    });
+    let mut cps = Checkpoints::default();
+    cps.report(registered.iter().map(|r| r.report()).collect());
+    cps.add(registered.iter().filter_map(|r| r.unsatisfied()).collect());
+    cps.finish();
+}
+
+/// The `reset` functions of every static method and mocked function that has
+/// had any expectations.
+static RESETS: Mutex<Vec<fn()>> = Mutex::new(Vec::new());
+
+/// Register a static method's `reset` function, for [`reset`] to call.  Each
+/// method registers its own once, when its first expectation is set.
+#[doc(hidden)]
+pub fn register_reset(reset: fn()) {
+    RESETS.lock().unwrap_or_else(PoisonError::into_inner).push(reset);
+}
+
+/// Lock a static method's expectations.  A panic while they were locked, like
+/// from a call that its expectation didn't allow, poisons the lock.  But the
+/// expectations are still intact, and [`reset`] can clear them.
+#[doc(hidden)]
+pub fn lock_static<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Clear the expectations of every static method and mocked function, without
+/// verifying them.
+///
+/// Those expectations are global, so a test that panics before its `Context`
+/// objects drop, or before checkpointing them, can leave them behind for the
+/// next test in the same process.  Calling `reset` at the start of a test
+/// discards them.  It also discards any other thread's, so tests that use it
+/// mustn't run concurrently with other tests of static methods.  To clear
+/// just one mock's, use its `reset_statics` method, or a mocked module's
+/// `reset` function.
+///
+/// # Examples
+/// ```
+/// # use mockall::*;
+/// # use std::panic;
+/// #[automock]
+/// trait Foo {
+///     fn foo() -> u32;
+/// }
+///
+/// # fn main() {
+/// let r = panic::catch_unwind(|| {
+///     let ctx = MockFoo::foo_context();
+///     ctx.expect().times(1).return_const(42u32);
+///     panic!("The test failed before calling foo");
+/// });
+/// assert!(r.is_err());
+/// // The expectation survived the panic, and would still be checked
+/// reset();
+/// let ctx = MockFoo::foo_context();
+/// ctx.expect().return_const(7u32);
+/// assert_eq!(7, MockFoo::foo());
+/// # }
+/// ```
+pub fn reset() {
+    // Copy the functions, in case dropping an expectation registers another
+    let resets = RESETS.lock().unwrap_or_else(PoisonError::into_inner).clone();
+    for reset in resets {
+        reset();
     }
 }
@@ -0,0 +1,758 @@
+// vim: tw=80
+//! Predicate factories for matching a mock method's arguments.
+//!
+//! Everything in the `predicates` crate's `predicate` module is re-exported
+//! here, along with some predicates that are specific to Mockall.  So are the
+//! `Predicate` trait and its extension traits, which provide combinators like
+//! `and`, `or`, `not`, and `trim`.  They come from the same version of
+//! `predicates` that Mockall uses, so `use mockall::predicate::*` is all that
+//! a test needs in order to build any matcher that `with` accepts.
+//!
+//! # Examples
+//!
+//! ```
+//! # use mockall::automock;
+//! use mockall::predicate::*;
+//!
+//! #[automock]
+//! trait Foo {
+//!     fn foo(&self, name: &str, x: u32, y: f64);
+//! }
+//!
+//! let mut mock = MockFoo::new();
+//! mock.expect_foo()
+//!     .with(str::starts_with("foo").trim(),
+//!           gt(1).and(lt(5)),
+//!           float::is_close(0.3))
+//!     .return_const(());
+//! mock.foo(" food", 2, 0.1 + 0.2);
+//! ```
+
+use std::{
+    any::{self, Any},
+    borrow::Borrow,
+    fmt,
+    marker::PhantomData
+};
+
+use predicates::reflection::{Case, PredicateReflection, Product};
+
+pub use predicates::prelude::predicate::*;
+pub use super::{
+    Matcher, Predicate, PredicateBooleanExt, PredicateBoxExt, PredicateStrExt
+};
+
+/// Upcast a value to `&dyn Any`.
+///
+/// This trait is implemented for every `'static` type.  Make it a supertrait
+/// of any trait whose trait objects should be matched with [`is_type`] or
+/// [`downcast_matches`].
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::predicate::*;
+/// pub trait Command: AsAny {}
+/// ```
+pub trait AsAny {
+    /// Return `self` as a `&dyn Any`, which can be downcast to the concrete
+    /// type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl AsAny for dyn Any {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl AsAny for dyn Any + Send {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl AsAny for dyn Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Predicate that checks whether a trait object's concrete type is `T`.
+///
+/// This is created by the [`is_type`] function.
+pub struct IsTypePredicate<T>(PhantomData<fn() -> T>);
+
+impl<T, U> Predicate<U> for IsTypePredicate<T>
+    where T: 'static,
+          U: AsAny + ?Sized
+{
+    fn eval(&self, variable: &U) -> bool {
+        variable.as_any().is::<T>()
+    }
+}
+
+impl<T> PredicateReflection for IsTypePredicate<T> {}
+
+impl<T> fmt::Display for IsTypePredicate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "is_type::<{}>()", any::type_name::<T>())
+    }
+}
+
+/// Create a predicate that is true when a trait object's concrete type is `T`.
+///
+/// The trait must have [`AsAny`] as a supertrait.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::*;
+/// # use mockall::predicate::*;
+/// pub trait Command: AsAny {}
+/// struct Stop;
+/// impl Command for Stop {}
+/// struct Go;
+/// impl Command for Go {}
+///
+/// let predicate_fn = is_type::<Stop>();
+/// assert!(predicate_fn.eval(&Stop as &dyn Command));
+/// assert!(!predicate_fn.eval(&Go as &dyn Command));
+/// ```
+pub fn is_type<T: 'static>() -> IsTypePredicate<T> {
+    IsTypePredicate(PhantomData)
+}
+
+/// Predicate that downcasts a trait object to `T`, then evaluates a function on
+/// it.
+///
+/// This is created by the [`downcast_matches`] function.
+pub struct DowncastPredicate<T, F> {
+    f: F,
+    _t: PhantomData<fn() -> T>
+}
+
+impl<T, U, F> Predicate<U> for DowncastPredicate<T, F>
+    where T: 'static,
+          U: AsAny + ?Sized,
+          F: Fn(&T) -> bool
+{
+    fn eval(&self, variable: &U) -> bool {
+        variable.as_any().downcast_ref::<T>().map_or(false, &self.f)
+    }
+}
+
+impl<T, F> PredicateReflection for DowncastPredicate<T, F> {}
+
+impl<T, F> fmt::Display for DowncastPredicate<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "downcast_matches::<{}>(fn)", any::type_name::<T>())
+    }
+}
+
+/// Create a predicate that is true when a trait object's concrete type is `T`
+/// and `f` returns true for it.
+///
+/// The trait must have [`AsAny`] as a supertrait.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::*;
+/// # use mockall::predicate::*;
+/// pub trait Command: AsAny {}
+/// struct Move { distance: u32 }
+/// impl Command for Move {}
+///
+/// let predicate_fn = downcast_matches::<Move, _>(|m| m.distance == 7);
+/// assert!(predicate_fn.eval(&Move{ distance: 7 } as &dyn Command));
+/// assert!(!predicate_fn.eval(&Move{ distance: 8 } as &dyn Command));
+/// ```
+pub fn downcast_matches<T, F>(f: F) -> DowncastPredicate<T, F>
+    where T: 'static,
+          F: Fn(&T) -> bool
+{
+    DowncastPredicate{f, _t: PhantomData}
+}
+
+/// Equality between a predicate's constant and a mock method's argument.
+///
+/// Besides the usual `T == T`, a constant may be a reference to a value like
+/// the argument, and string constants match both `str` and `String`
+/// arguments, whether they're owned or borrowed.  This is what lets [`eq`] and
+/// [`ne`] compare a `String` argument to a string literal.
+pub trait EqArg<A: ?Sized> {
+    /// Is this constant equal to `arg`?
+    fn eq_arg(&self, arg: &A) -> bool;
+}
+
+impl<T: PartialEq> EqArg<T> for T {
+    fn eq_arg(&self, arg: &T) -> bool {
+        self == arg
+    }
+}
+
+impl<T: PartialEq + ?Sized> EqArg<T> for &T {
+    fn eq_arg(&self, arg: &T) -> bool {
+        *self == arg
+    }
+}
+
+impl EqArg<String> for &str {
+    fn eq_arg(&self, arg: &String) -> bool {
+        self == arg
+    }
+}
+
+impl EqArg<str> for String {
+    fn eq_arg(&self, arg: &str) -> bool {
+        self == arg
+    }
+}
+
+/// Predicate that checks whether the argument equals a constant.
+///
+/// This is created by the [`eq`] function.
+pub struct EqPredicate<T>(T);
+
+impl<T, A> Predicate<A> for EqPredicate<T>
+    where T: EqArg<A> + fmt::Debug,
+          A: ?Sized
+{
+    fn eval(&self, variable: &A) -> bool {
+        self.0.eq_arg(variable)
+    }
+}
+
+impl<T: fmt::Debug> PredicateReflection for EqPredicate<T> {}
+
+impl<T: fmt::Debug> fmt::Display for EqPredicate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "var == {:?}", self.0)
+    }
+}
+
+/// Create a predicate that is true when the argument equals `constant`.
+///
+/// Unlike the `predicates` crate's version, it compares string arguments to
+/// string constants regardless of whether either is a `String` or a `&str`.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::predicate::*;
+/// let predicate_fn = eq("foo");
+/// assert!(predicate_fn.eval("foo"));
+/// assert!(predicate_fn.eval(&String::from("foo")));
+/// assert!(!predicate_fn.eval(&String::from("bar")));
+/// ```
+pub fn eq<T: fmt::Debug>(constant: T) -> EqPredicate<T> {
+    EqPredicate(constant)
+}
+
+/// Predicate that checks whether the argument differs from a constant.
+///
+/// This is created by the [`ne`] function.
+pub struct NePredicate<T>(T);
+
+impl<T, A> Predicate<A> for NePredicate<T>
+    where T: EqArg<A> + fmt::Debug,
+          A: ?Sized
+{
+    fn eval(&self, variable: &A) -> bool {
+        !self.0.eq_arg(variable)
+    }
+}
+
+impl<T: fmt::Debug> PredicateReflection for NePredicate<T> {}
+
+impl<T: fmt::Debug> fmt::Display for NePredicate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "var != {:?}", self.0)
+    }
+}
+
+/// Create a predicate that is true when the argument does not equal
+/// `constant`.
+///
+/// Like [`eq`], it works with both `String` and `&str` arguments and
+/// constants.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::predicate::*;
+/// let predicate_fn = ne(String::from("foo"));
+/// assert!(predicate_fn.eval("bar"));
+/// assert!(!predicate_fn.eval("foo"));
+/// ```
+pub fn ne<T: fmt::Debug>(constant: T) -> NePredicate<T> {
+    NePredicate(constant)
+}
+
+/// `str` predicate factories.
+///
+/// Everything in the `predicates` crate's `predicate::str` module is
+/// re-exported here.  But the most common ones are wrapped so that they work
+/// with `String` arguments, too, not just `str`.
+pub mod str {
+    use super::*;
+
+    pub use predicates::prelude::predicate::str::*;
+
+    /// Predicate that applies a `str` predicate to any argument that can be
+    /// borrowed as a `str`, like `String` or `&str`.
+    ///
+    /// This is created by functions like [`starts_with`] and [`contains`].
+    pub struct StrPredicate<P>(P);
+
+    impl<P, A> Predicate<A> for StrPredicate<P>
+        where P: Predicate<::std::primitive::str>,
+              A: Borrow<::std::primitive::str> + ?Sized
+    {
+        fn eval(&self, variable: &A) -> bool {
+            self.0.eval(variable.borrow())
+        }
+    }
+
+    impl<P: fmt::Display> PredicateReflection for StrPredicate<P> {}
+
+    impl<P: fmt::Display> fmt::Display for StrPredicate<P> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.fmt(f)
+        }
+    }
+
+    impl StrPredicate<predicates::str::ContainsPredicate> {
+        /// Require a specific number of matches, like
+        /// `predicates::str::ContainsPredicate::count`.
+        pub fn count(self, count: usize)
+            -> StrPredicate<predicates::str::MatchesPredicate>
+        {
+            StrPredicate(self.0.count(count))
+        }
+    }
+
+    /// Create a predicate that is true when the argument is an empty string.
+    pub fn is_empty() -> StrPredicate<predicates::str::IsEmptyPredicate> {
+        StrPredicate(predicates::str::is_empty())
+    }
+
+    /// Create a predicate that is true when the argument starts with
+    /// `pattern`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mockall::predicate::*;
+    /// let predicate_fn = str::starts_with("Hello");
+    /// assert!(predicate_fn.eval("Hello, World"));
+    /// assert!(predicate_fn.eval(&String::from("Hello, World")));
+    /// ```
+    pub fn starts_with<P: Into<String>>(pattern: P)
+        -> StrPredicate<predicates::str::StartsWithPredicate>
+    {
+        StrPredicate(predicates::str::starts_with(pattern))
+    }
+
+    /// Create a predicate that is true when the argument ends with `pattern`.
+    pub fn ends_with<P: Into<String>>(pattern: P)
+        -> StrPredicate<predicates::str::EndsWithPredicate>
+    {
+        StrPredicate(predicates::str::ends_with(pattern))
+    }
+
+    /// Create a predicate that is true when the argument contains `pattern`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mockall::predicate::*;
+    /// let predicate_fn = str::contains("o, W");
+    /// assert!(predicate_fn.eval("Hello, World"));
+    /// assert!(predicate_fn.eval(&String::from("Hello, World")));
+    /// ```
+    pub fn contains<P: Into<String>>(pattern: P)
+        -> StrPredicate<predicates::str::ContainsPredicate>
+    {
+        StrPredicate(predicates::str::contains(pattern))
+    }
+
+    /// Create a predicate that is true when the argument matches the regular
+    /// expression `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// If `pattern` is not a valid regular expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mockall::predicate::*;
+    /// let predicate_fn = str::matches_regex(r"^\d+$");
+    /// assert!(predicate_fn.eval("12345"));
+    /// assert!(!predicate_fn.eval(&String::from("123a5")));
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn matches_regex<S: AsRef<::std::primitive::str>>(pattern: S)
+        -> StrPredicate<predicates::str::RegexPredicate>
+    {
+        let pattern = pattern.as_ref();
+        match predicates::str::is_match(pattern) {
+            Ok(p) => StrPredicate(p),
+            Err(e) => panic!("Invalid regex {:?}: {}", pattern, e)
+        }
+    }
+}
+
+/// Copy the products of `case`, typically a child's, into a new case for
+/// `pred`.
+fn adopt_case<'a>(pred: &'a dyn PredicateReflection, result: bool,
+                  case: Case<'_>) -> Case<'a>
+{
+    case.products().fold(Case::new(Some(pred), result), |acc, product| {
+        acc.add_product(Product::new(product.name().to_owned(),
+                                     product.value().to_string()))
+    })
+}
+
+/// Predicate that applies a function to the argument, then evaluates another
+/// predicate on the result.
+///
+/// This is created by the [`map`] function.
+pub struct MapPredicate<F, P, B> {
+    f: F,
+    p: P,
+    _b: PhantomData<fn() -> B>
+}
+
+impl<A, B, F, P> Predicate<A> for MapPredicate<F, P, B>
+    where A: ?Sized,
+          F: Fn(&A) -> B,
+          P: Predicate<B>
+{
+    fn eval(&self, variable: &A) -> bool {
+        self.p.eval(&(self.f)(variable))
+    }
+
+    fn find_case<'a>(&'a self, expected: bool, variable: &A)
+        -> Option<Case<'a>>
+    {
+        self.p.find_case(expected, &(self.f)(variable))
+            .map(|case| adopt_case(self, expected, case))
+    }
+}
+
+impl<F, P: fmt::Display, B> PredicateReflection for MapPredicate<F, P, B> {}
+
+impl<F, P: fmt::Display, B> fmt::Display for MapPredicate<F, P, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "map(fn, {})", self.p)
+    }
+}
+
+/// Create a predicate that applies `f` to the argument, and then evaluates
+/// `p` on the result.
+///
+/// This is useful to match just part of an argument, like one field of a
+/// tuple.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::predicate::*;
+/// let predicate_fn = map(|x: &(u32, f64)| x.1, float::epsilon_eq(0.3, 1e-9));
+/// assert!(predicate_fn.eval(&(5, 0.1 + 0.2)));
+/// assert!(!predicate_fn.eval(&(5, 0.4)));
+/// ```
+pub fn map<A, B, F, P>(f: F, p: P) -> MapPredicate<F, P, B>
+    where A: ?Sized,
+          F: Fn(&A) -> B,
+          P: Predicate<B>
+{
+    MapPredicate{f, p, _b: PhantomData}
+}
+
+/// Predicate that matches an `Option` that is `Some` and whose contents
+/// satisfy another predicate.
+///
+/// This is created by the [`some`] function.
+pub struct SomePredicate<P>(P);
+
+impl<T, P: Predicate<T>> Predicate<Option<T>> for SomePredicate<P> {
+    fn eval(&self, variable: &Option<T>) -> bool {
+        variable.as_ref().map_or(false, |t| self.0.eval(t))
+    }
+
+    fn find_case<'a>(&'a self, expected: bool, variable: &Option<T>)
+        -> Option<Case<'a>>
+    {
+        match variable {
+            Some(t) => self.0.find_case(expected, t)
+                .map(|case| adopt_case(self, expected, case)),
+            None if !expected => Some(Case::new(Some(self), expected)
+                .add_product(Product::new("var", "None"))),
+            None => None
+        }
+    }
+}
+
+impl<P: fmt::Display> PredicateReflection for SomePredicate<P> {}
+
+impl<P: fmt::Display> fmt::Display for SomePredicate<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Some({})", self.0)
+    }
+}
+
+/// Create a predicate that is true when the argument is `Some` and its
+/// contents satisfy `p`.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::predicate::*;
+/// let predicate_fn = some(float::epsilon_eq(0.3, 1e-9));
+/// assert!(predicate_fn.eval(&Some(0.1 + 0.2)));
+/// assert!(!predicate_fn.eval(&None));
+/// ```
+pub fn some<P>(p: P) -> SomePredicate<P> {
+    SomePredicate(p)
+}
+
+/// Predicate that matches the argument against a pattern.
+///
+/// This is created by the [`matching!`](../macro.matching.html) macro.
+pub struct PatternPredicate<F> {
+    f: F,
+    pattern: &'static str
+}
+
+impl<T, F> Predicate<T> for PatternPredicate<F>
+    where F: Fn(&T) -> bool
+{
+    fn eval(&self, variable: &T) -> bool {
+        (self.f)(variable)
+    }
+}
+
+impl<F> PredicateReflection for PatternPredicate<F> {}
+
+impl<F> fmt::Display for PatternPredicate<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "matching!({})", self.pattern)
+    }
+}
+
+#[doc(hidden)]
+pub fn pattern<T, F>(f: F, pattern: &'static str) -> PatternPredicate<F>
+    where F: Fn(&T) -> bool
+{
+    PatternPredicate{f, pattern}
+}
+
+/// Create a predicate that is true when the argument matches a pattern.
+///
+/// The pattern may include a guard.  The argument is matched by reference, so
+/// bindings in the pattern are references, too.  Use one `matching!` per
+/// argument to match several arguments.
+///
+/// # Examples
+///
+/// ```
+/// # use mockall::*;
+/// #[derive(Debug)]
+/// pub enum Event {
+///     KeyPress { code: u32, shift: bool },
+///     Click(i32, i32)
+/// }
+///
+/// #[automock]
+/// trait Handler {
+///     fn handle(&self, event: Event, repeat: u32) -> bool;
+/// }
+///
+/// # fn main() {
+/// let mut mock = MockHandler::new();
+/// mock.expect_handle()
+///     .with(matching!(Event::KeyPress { code: 13, .. }),
+///           matching!(x if *x > 3))
+///     .return_const(true);
+/// assert!(mock.handle(Event::KeyPress { code: 13, shift: false }, 4));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! matching {
+    ($($pattern:tt)+) => {
+        $crate::predicate::pattern(
+            |__mockall_arg| match __mockall_arg {
+                $($pattern)+ => true,
+                _ => false
+            },
+            stringify!($($pattern)+)
+        )
+    }
+}
+
+/// Floating point predicate factories.
+///
+/// Everything in the `predicates` crate's `predicate::float` module is
+/// re-exported here, along with approximate equality predicates that work with
+/// both `f32` and `f64`.
+pub mod float {
+    use super::*;
+
+    pub use predicates::prelude::predicate::float::*;
+
+    /// A floating point type that can be compared approximately.
+    ///
+    /// This is implemented for `f32` and `f64`.
+    pub trait Float: Copy + fmt::Debug + PartialOrd {
+        /// The absolute value of the difference between `self` and `other`
+        fn abs_diff(self, other: Self) -> Self;
+
+        /// How many representable values lie between `self` and `other`.
+        /// `None` if either is NaN.
+        fn ulps_diff(self, other: Self) -> Option<u64>;
+    }
+
+    macro_rules! float_impl {
+        ($t:ty, $i:ty) => {
+            impl Float for $t {
+                fn abs_diff(self, other: Self) -> Self {
+                    (self - other).abs()
+                }
+
+                fn ulps_diff(self, other: Self) -> Option<u64> {
+                    if self.is_nan() || other.is_nan() {
+                        return None;
+                    }
+                    // Map the bits onto integers that sort the same way as
+                    // the floats do, with both zeros at 0.
+                    let ordered = |x: $t| {
+                        let bits = x.to_bits() as $i;
+                        if bits < 0 {
+                            i128::from(<$i>::min_value()) - i128::from(bits)
+                        } else {
+                            i128::from(bits)
+                        }
+                    };
+                    let d = (ordered(self) - ordered(other)).abs();
+                    Some(d as u64)
+                }
+            }
+        }
+    }
+
+    float_impl!(f32, i32);
+    float_impl!(f64, i64);
+
+    /// Predicate that checks whether the argument is within a fixed distance
+    /// of the expected value.
+    ///
+    /// This is created by the [`epsilon_eq`] function.
+    pub struct EpsilonEqPredicate<T> {
+        value: T,
+        epsilon: T
+    }
+
+    impl<T: Float> Predicate<T> for EpsilonEqPredicate<T> {
+        fn eval(&self, variable: &T) -> bool {
+            variable.abs_diff(self.value) <= self.epsilon
+        }
+
+        fn find_case<'a>(&'a self, expected: bool, variable: &T)
+            -> Option<Case<'a>>
+        {
+            if self.eval(variable) != expected {
+                return None;
+            }
+            Some(Case::new(Some(self), expected)
+                .add_product(Product::new("var", format!("{:?}", variable)))
+                .add_product(Product::new("difference",
+                    format!("{:?}", variable.abs_diff(self.value)))))
+        }
+    }
+
+    impl<T: fmt::Debug> PredicateReflection for EpsilonEqPredicate<T> {}
+
+    impl<T: fmt::Debug> fmt::Display for EpsilonEqPredicate<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "var ~= {:?} (within {:?})", self.value, self.epsilon)
+        }
+    }
+
+    /// Create a predicate that is true when the argument differs from `value`
+    /// by no more than `epsilon`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mockall::predicate::*;
+    /// let predicate_fn = float::epsilon_eq(0.3, 1e-9);
+    /// assert!(predicate_fn.eval(&(0.1 + 0.2)));
+    /// assert!(!predicate_fn.eval(&0.31));
+    /// ```
+    pub fn epsilon_eq<T: Float>(value: T, epsilon: T)
+        -> EpsilonEqPredicate<T>
+    {
+        EpsilonEqPredicate{value, epsilon}
+    }
+
+    /// Predicate that checks whether the argument is within a fixed number of
+    /// representable values of the expected value.
+    ///
+    /// This is created by the [`ulps_eq`] function.
+    pub struct UlpsEqPredicate<T> {
+        value: T,
+        ulps: u64
+    }
+
+    impl<T: Float> Predicate<T> for UlpsEqPredicate<T> {
+        fn eval(&self, variable: &T) -> bool {
+            variable.ulps_diff(self.value).map_or(false, |d| d <= self.ulps)
+        }
+
+        fn find_case<'a>(&'a self, expected: bool, variable: &T)
+            -> Option<Case<'a>>
+        {
+            if self.eval(variable) != expected {
+                return None;
+            }
+            let diff = variable.ulps_diff(self.value)
+                .map_or_else(|| String::from("NaN"), |d| d.to_string());
+            Some(Case::new(Some(self), expected)
+                .add_product(Product::new("var", format!("{:?}", variable)))
+                .add_product(Product::new("ulps", diff)))
+        }
+    }
+
+    impl<T: fmt::Debug> PredicateReflection for UlpsEqPredicate<T> {}
+
+    impl<T: fmt::Debug> fmt::Display for UlpsEqPredicate<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "var ~= {:?} (within {} ulps)", self.value, self.ulps)
+        }
+    }
+
+    /// Create a predicate that is true when there are no more than `ulps`
+    /// representable floating point values between the argument and `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mockall::predicate::*;
+    /// let predicate_fn = float::ulps_eq(0.3f32, 2);
+    /// assert!(predicate_fn.eval(&(0.1f32 + 0.2f32)));
+    /// assert!(!predicate_fn.eval(&0.31f32));
+    /// ```
+    pub fn ulps_eq<T: Float>(value: T, ulps: u64) -> UlpsEqPredicate<T> {
+        UlpsEqPredicate{value, ulps}
+    }
+}
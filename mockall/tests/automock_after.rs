@@ -0,0 +1,175 @@
+// vim: tw=80
+//! An expectation may be required to happen after another one
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait File {
+    fn open(&self);
+    fn read(&self) -> u32;
+    fn close(&self);
+    fn stat(&self) -> u64;
+    fn sopen();
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn ok() {
+    let mut mock = MockFile::new();
+    let opened = mock.expect_open()
+        .times(1)
+        .return_const(())
+        .handle();
+    mock.expect_read()
+        .after(&opened)
+        .return_const(42u32);
+    mock.expect_stat()
+        .return_const(0u64);
+    mock.stat();
+    mock.open();
+    assert_eq!(42, mock.read());
+    assert_eq!(42, mock.read());
+}
+
+#[test]
+fn too_early() {
+    let mut mock = MockFile::new();
+    let opened = mock.expect_open()
+        .times(1)
+        .return_const(())
+        .handle();
+    mock.expect_read()
+        .after(&opened)
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.read();});
+    assert!(msg.starts_with(
+        "MockFile::read: Expectation(<anything>) (set at "), "{}", msg);
+    assert!(msg.contains("): called before MockFile::open (set at "),
+        "{}", msg);
+    assert!(msg.ends_with("), which must happen first"), "{}", msg);
+    mock.open();
+    mock.read();
+}
+
+/// The other expectation must be satisfied, not just called
+#[test]
+fn not_yet_satisfied() {
+    let mut mock = MockFile::new();
+    let opened = mock.expect_open()
+        .times(2)
+        .return_const(())
+        .handle();
+    mock.expect_read()
+        .after(&opened)
+        .return_const(42u32);
+    mock.open();
+    let msg = panic_msg(|| {mock.read();});
+    assert!(msg.contains("called before MockFile::open"), "{}", msg);
+    mock.open();
+    mock.read();
+}
+
+/// An expectation that is satisfied without being called hasn't happened yet
+#[test]
+fn never_called() {
+    let mut mock = MockFile::new();
+    let opened = mock.expect_open()
+        .return_const(())
+        .handle();
+    mock.expect_read()
+        .after(&opened)
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.read();});
+    assert!(msg.contains("called before MockFile::open"), "{}", msg);
+}
+
+#[test]
+fn several() {
+    let mut mock = MockFile::new();
+    let opened = mock.expect_open()
+        .return_const(())
+        .handle();
+    let read = mock.expect_read()
+        .return_const(42u32)
+        .handle();
+    mock.expect_close()
+        .after(&opened)
+        .after(&read)
+        .return_const(());
+    mock.open();
+    let msg = panic_msg(|| mock.close());
+    assert!(msg.contains("called before MockFile::read"), "{}", msg);
+    mock.read();
+    mock.close();
+}
+
+#[test]
+fn different_mocks() {
+    let mut m0 = MockFile::new();
+    let mut m1 = MockFile::new();
+    let opened = m0.expect_open()
+        .return_const(())
+        .handle();
+    m1.expect_read()
+        .after(&opened)
+        .return_const(42u32);
+    let msg = panic_msg(|| {m1.read();});
+    assert!(msg.contains("called before MockFile::open"), "{}", msg);
+    m0.open();
+    m1.read();
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFile::sopen_context();
+    let opened = ctx.expect()
+        .return_const(())
+        .handle();
+    let mut mock = MockFile::new();
+    mock.expect_read()
+        .after(&opened)
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.read();});
+    assert!(msg.contains("called before MockFile::sopen"), "{}", msg);
+    MockFile::sopen();
+    mock.read();
+}
+
+/// A cycle is reported at the first call that can't proceed
+#[test]
+fn cycle() {
+    let mut m0 = MockFile::new();
+    let mut m1 = MockFile::new();
+    let mut m2 = MockFile::new();
+    let read = m0.expect_read()
+        .return_const(42u32);
+    let hread = read.handle();
+    let opened = m1.expect_open()
+        .after(&hread)
+        .return_const(())
+        .handle();
+    let closed = m2.expect_close()
+        .after(&opened)
+        .return_const(())
+        .handle();
+    read.after(&closed);
+    // Unrelated expectations are unaffected
+    m0.expect_stat()
+        .return_const(0u64);
+    m0.stat();
+    let msg = panic_msg(|| {m1.open();});
+    assert!(msg.starts_with(
+        "MockFile::open: Expectation(<anything>) (set at "), "{}", msg);
+    assert!(msg.contains("): can never be called, because of a happens-before \
+        cycle: MockFile::open (set at "), "{}", msg);
+    assert!(msg.contains(") after MockFile::read (set at "), "{}", msg);
+    assert!(msg.contains(") after MockFile::close (set at "), "{}", msg);
+}
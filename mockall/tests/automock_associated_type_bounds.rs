@@ -0,0 +1,36 @@
+// vim: tw=80
+//! Substituted associated types that satisfy the trait's bounds on them
+#![deny(warnings)]
+
+use mockall::*;
+use std::fmt::Debug;
+
+#[automock(type Item = u32; type Iter = std::vec::IntoIter<u32>;)]
+pub trait Container {
+    type Item: Clone + Debug + Send + 'static;
+    type Iter: Iterator<Item = Self::Item>;
+    fn items(&self) -> Self::Iter;
+}
+
+/// Types that use the trait's generic parameters aren't checked, but others
+/// are
+#[automock(type Output = Vec<T>; type Count = u32;)]
+pub trait Convert<T: 'static> {
+    type Output: AsRef<[T]> + Default;
+    type Count: From<u8> + PartialEq<Self::Count>;
+    fn convert(&self, t: T) -> Self::Output;
+}
+
+#[test]
+fn satisfied() {
+    let mut mock = MockContainer::new();
+    mock.expect_items().returning(|| vec![1, 2].into_iter());
+    assert_eq!(vec![1, 2], mock.items().collect::<Vec<_>>());
+}
+
+#[test]
+fn generic() {
+    let mut mock = MockConvert::<u8>::new();
+    mock.expect_convert().returning(|t| vec![t]);
+    assert_eq!(vec![5], mock.convert(5));
+}
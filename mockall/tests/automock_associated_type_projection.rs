@@ -0,0 +1,83 @@
+// vim: tw=80
+//! Associated types that are used by other associated types' bounds and
+//! definitions, or projected through in method signatures
+#![deny(warnings)]
+
+use mockall::*;
+use std::ops::Deref;
+
+pub struct PgConn(u32);
+
+impl Deref for PgConn {
+    type Target = u32;
+
+    fn deref(&self) -> &u32 {
+        &self.0
+    }
+}
+
+#[automock(type Conn = PgConn; type Inner = u32; type Batch = Vec<Self::Inner>;)]
+pub trait Repo {
+    type Conn: Deref<Target = Self::Inner>;
+    type Inner;
+    type Batch: IntoIterator<Item = Self::Inner>;
+    fn conn(&self) -> Self::Conn;
+    fn target(&self) -> <Self::Conn as Deref>::Target;
+    fn batch(&self, x: &<Self::Conn as Deref>::Target) -> Self::Batch;
+}
+
+#[test]
+fn returning_a_projection() {
+    let mut mock = MockRepo::new();
+    mock.expect_target().return_const(42u32);
+    assert_eq!(42u32, mock.target());
+}
+
+#[test]
+fn defined_by_another_associated_type() {
+    let mut mock = MockRepo::new();
+    mock.expect_batch()
+        .withf(|x| *x == 5)
+        .returning(|x| vec![*x, *x]);
+    assert_eq!(vec![5, 5], mock.batch(&5));
+}
+
+#[test]
+fn bounded_by_another_associated_type() {
+    let mut mock = MockRepo::new();
+    mock.expect_conn().returning(|| PgConn(7));
+    assert_eq!(7, *mock.conn());
+}
+
+#[automock(type Conn = PgConn; type Inner = u32; type Target = Self::Conn::Target;)]
+pub trait Pool {
+    type Conn: Deref<Target = Self::Inner>;
+    type Inner;
+    type Target;
+    fn target(&self) -> Self::Target;
+}
+
+#[automock(type Conn = T; type Target = Self::Conn::Target;)]
+pub trait Wrapper<T: Deref + 'static> {
+    type Conn;
+    type Target: ?Sized;
+    fn conn(&self) -> Self::Conn;
+    fn target(&self, x: &Self::Target) -> bool;
+}
+
+/// A three-segment projection chain, through another associated type
+#[test]
+fn projection_chain() {
+    let mut mock = MockPool::new();
+    mock.expect_target().return_const(8u32);
+    assert_eq!(8u32, mock.target());
+}
+
+#[test]
+fn projection_chain_through_type_param() {
+    let mut mock = MockWrapper::<PgConn>::new();
+    mock.expect_conn().returning(|| PgConn(9));
+    mock.expect_target().withf(|x| *x == 10).return_const(true);
+    assert_eq!(9, *mock.conn());
+    assert!(mock.target(&10));
+}
@@ -0,0 +1,70 @@
+// vim: tw=80
+//! Associated types may be nested within other types' generic arguments
+#![deny(warnings)]
+
+use mockall::*;
+use std::collections::HashMap;
+
+#[automock(type T = u32; type E = String;)]
+pub trait Store {
+    type T;
+    type E;
+    fn all(&self) -> Vec<Self::T>;
+    fn first(&self) -> Option<Self::T>;
+    fn load(&self, key: &str) -> Result<Self::T, Self::E>;
+    fn index(&self) -> HashMap<String, Self::T>;
+    fn sparse(&self, items: Vec<Option<Self::T>>) -> usize;
+    fn iter(&self) -> Box<dyn Iterator<Item = Self::T>>;
+}
+
+#[test]
+fn vec() {
+    let mut mock = MockStore::new();
+    mock.expect_all().returning(|| vec![1, 2]);
+    assert_eq!(vec![1, 2], mock.all());
+}
+
+#[test]
+fn option() {
+    let mut mock = MockStore::new();
+    mock.expect_first().return_const(Some(3));
+    assert_eq!(Some(3), mock.first());
+}
+
+#[test]
+fn result() {
+    let mut mock = MockStore::new();
+    mock.expect_load()
+        .with(predicate::eq("a"))
+        .returning(|_| Ok(4));
+    mock.expect_load()
+        .with(predicate::eq("b"))
+        .returning(|k| Err(format!("no {}", k)));
+    assert_eq!(Ok(4), mock.load("a"));
+    assert_eq!(Err(String::from("no b")), mock.load("b"));
+}
+
+#[test]
+fn hash_map() {
+    let mut mock = MockStore::new();
+    mock.expect_index()
+        .returning(|| vec![(String::from("x"), 5)].into_iter().collect());
+    assert_eq!(Some(&5), mock.index().get("x"));
+}
+
+/// Doubly nested
+#[test]
+fn vec_of_options() {
+    let mut mock = MockStore::new();
+    mock.expect_sparse()
+        .withf(|items| items == &[Some(6), None])
+        .returning(|items| items.iter().flatten().count());
+    assert_eq!(1, mock.sparse(vec![Some(6), None]));
+}
+
+#[test]
+fn binding() {
+    let mut mock = MockStore::new();
+    mock.expect_iter().returning(|| Box::new(7..9));
+    assert_eq!(vec![7, 8], mock.iter().collect::<Vec<_>>());
+}
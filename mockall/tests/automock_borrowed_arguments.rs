@@ -0,0 +1,65 @@
+// vim: tw=80
+//! The returning closure and matchers receive reference arguments exactly as
+//! the mocked method did: by reference, without any cloning.
+
+use mockall::*;
+
+/// Deliberately neither `Clone` nor `Copy`
+pub struct Haystack(String);
+
+#[automock]
+trait Finder {
+    fn find(&self, haystack: &Haystack, needle: &str) -> Option<usize>;
+    fn find_str(&self, haystack: &str, needle: &str) -> Option<usize>;
+    fn sfind(haystack: &Haystack, needle: &str) -> Option<usize>;
+}
+
+#[test]
+fn returning() {
+    let mut mock = MockFinder::new();
+    mock.expect_find()
+        .returning(|haystack, needle| haystack.0.find(needle));
+    let h = Haystack("abcdef".to_owned());
+    assert_eq!(Some(2), mock.find(&h, "cd"));
+    assert_eq!(None, mock.find(&h, "xy"));
+}
+
+#[test]
+fn returning_str() {
+    let mut mock = MockFinder::new();
+    mock.expect_find_str()
+        .returning(|haystack: &str, needle: &str| haystack.find(needle));
+    assert_eq!(Some(1), mock.find_str("abc", "bc"));
+}
+
+#[test]
+fn withf() {
+    let mut mock = MockFinder::new();
+    mock.expect_find()
+        .withf(|haystack: &Haystack, needle: &str| {
+            haystack.0.len() > needle.len()
+        }).returning(|haystack, needle| haystack.0.find(needle));
+    mock.expect_find()
+        .return_const(None);
+    let h = Haystack("abc".to_owned());
+    assert_eq!(Some(0), mock.find(&h, "a"));
+    assert_eq!(None, mock.find(&h, "abcd"));
+}
+
+#[test]
+fn with() {
+    let mut mock = MockFinder::new();
+    mock.expect_find_str()
+        .with(predicate::str::starts_with("abc"), predicate::eq("c"))
+        .returning(|haystack, needle| haystack.find(needle));
+    assert_eq!(Some(2), mock.find_str("abcdef", "c"));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFinder::sfind_context();
+    ctx.expect()
+        .returning(|haystack, needle| haystack.0.find(needle));
+    let h = Haystack("abc".to_owned());
+    assert_eq!(Some(1), MockFinder::sfind(&h, "b"));
+}
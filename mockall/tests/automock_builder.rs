@@ -0,0 +1,67 @@
+// vim: tw=80
+//! Setting a mock's expectations with its builder
+
+use mockall::*;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar(&self) -> String;
+    fn baz<T: 'static>(&self, t: T) -> u32;
+}
+
+pub struct Bean {}
+
+#[automock]
+impl Bean {
+    pub fn get(&self) -> u32 {
+        0
+    }
+    pub fn create(_x: u32) -> u32 {
+        0
+    }
+    // We must have a separate static method for every test
+    pub fn open(_x: u32) -> u32 {
+        0
+    }
+}
+
+#[test]
+fn build() {
+    let mock = MockFoo::builder()
+        .foo(|e| {
+            e.with(predicate::eq(4))
+                .returning(|x| x + 1);
+        }).bar(|e| {
+            e.return_const("bar".to_owned());
+        }).baz(|e: &mut __mock_Foo_Foo::baz::Expectation<i16>| {
+            e.return_const(6u32);
+        }).build();
+    assert_eq!(5, mock.foo(4));
+    assert_eq!("bar", mock.bar());
+    assert_eq!(6, mock.baz(1i16));
+}
+
+#[test]
+fn build_with_contexts() {
+    let (mock, _contexts) = MockBean::builder()
+        .get(|e| {
+            e.return_const(1u32);
+        }).create(|ctx| {
+            ctx.expect()
+                .returning(|x| x + 2);
+        }).build_with_contexts();
+    assert_eq!(1, mock.get());
+    assert_eq!(3, MockBean::create(1));
+}
+
+#[test]
+#[should_panic(expected =
+    "MockBeanBuilder: static methods' expectations were set, so use build_with_contexts instead of build")]
+fn build_with_statics() {
+    MockBean::builder()
+        .open(|ctx| {
+            ctx.expect()
+                .return_const(0u32);
+        }).build();
+}
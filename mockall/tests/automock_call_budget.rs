@@ -0,0 +1,225 @@
+// vim: tw=80
+//! Expectations enrolled in a CallBudget share its call count
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Transaction {
+    fn commit(&self);
+    fn rollback(&self);
+}
+
+#[automock]
+pub trait Logger {
+    fn debug(&self, msg: &str);
+    fn info(&self, msg: &str);
+    fn warn(&self, msg: &str);
+    fn log<T: 'static>(&self, t: T);
+    fn slog(msg: &str);
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+fn logger(budget: &CallBudget) -> MockLogger {
+    let mut mock = MockLogger::new();
+    mock.expect_debug()
+        .in_budget(budget, 1)
+        .return_const(());
+    mock.expect_info()
+        .in_budget(budget, 1)
+        .return_const(());
+    mock.expect_warn()
+        .in_budget(budget, 1)
+        .return_const(());
+    mock
+}
+
+/// Exactly one of commit or rollback must be called, and the two expectations
+/// may belong to different mocks
+mod exclusive {
+    use super::*;
+
+    fn transactions(budget: &CallBudget)
+        -> (MockTransaction, MockTransaction)
+    {
+        let mut t0 = MockTransaction::new();
+        t0.expect_commit()
+            .in_budget(budget, 1)
+            .return_const(());
+        let mut t1 = MockTransaction::new();
+        t1.expect_rollback()
+            .in_budget(budget, 1)
+            .return_const(());
+        (t0, t1)
+    }
+
+    #[test]
+    fn commit() {
+        let budget = CallBudget::new(1);
+        let (t0, t1) = transactions(&budget);
+        t0.commit();
+        budget.verify();
+        drop(t1);
+    }
+
+    #[test]
+    fn rollback() {
+        let budget = CallBudget::new(1);
+        let (t0, t1) = transactions(&budget);
+        t1.rollback();
+        budget.verify();
+        drop(t0);
+    }
+
+    #[test]
+    fn both() {
+        let budget = CallBudget::new(1);
+        let (t0, t1) = transactions(&budget);
+        t0.commit();
+        let msg = panic_msg(|| t1.rollback());
+        assert!(msg.starts_with(
+            "MockTransaction::rollback: Expectation(<anything>) (set at "),
+            "{}", msg);
+        assert!(msg.contains(": exceeded CallBudget(exactly 1 time) (set at "),
+            "{}", msg);
+    }
+
+    #[test]
+    fn neither() {
+        let budget = CallBudget::new(1);
+        let (mut t0, t1) = transactions(&budget);
+        let msg = panic_msg(|| t0.checkpoint());
+        let line = msg.lines().next().unwrap();
+        assert!(line.starts_with("CallBudget(exactly 1 time) (set at "),
+            "{}", msg);
+        assert!(line.ends_with(") called fewer than 1 times"), "{}", msg);
+        // The failure is only reported once
+        drop(t0);
+        drop(t1);
+    }
+
+    #[test]
+    fn neither_verify() {
+        let budget = CallBudget::new(1);
+        let (_t0, _t1) = transactions(&budget);
+        let msg = panic_msg(|| budget.verify());
+        assert!(msg.ends_with(") called fewer than 1 times"), "{}", msg);
+    }
+
+    /// An unsatisfied budget is reported when an enrolled mock is dropped
+    #[test]
+    #[should_panic(expected = "called fewer than 1 times")]
+    fn neither_drop() {
+        let budget = CallBudget::new(1);
+        let _ = transactions(&budget);
+    }
+}
+
+#[test]
+fn maximum() {
+    let budget = CallBudget::new(..=3);
+    let mock = logger(&budget);
+    mock.debug("a");
+    mock.info("b");
+    mock.warn("c");
+    let msg = panic_msg(|| mock.debug("d"));
+    assert!(msg.contains(": exceeded CallBudget(at most 3 times) (set at "),
+        "{}", msg);
+}
+
+#[test]
+fn weight() {
+    let budget = CallBudget::new(..=10);
+    let mut mock = MockLogger::new();
+    mock.expect_debug()
+        .in_budget(&budget, 1)
+        .return_const(());
+    mock.expect_warn()
+        .in_budget(&budget, 5)
+        .return_const(());
+    mock.warn("a");
+    for _ in 0..5 {
+        mock.debug("b");
+    }
+    let msg = panic_msg(|| mock.debug("c"));
+    assert!(msg.contains("exceeded CallBudget(at most 10 times)"), "{}", msg);
+}
+
+/// An expectation's own call count still applies
+#[test]
+fn with_times() {
+    let budget = CallBudget::new(..);
+    let mut mock = MockLogger::new();
+    mock.expect_debug()
+        .times(1)
+        .in_budget(&budget, 1)
+        .return_const(());
+    let msg = panic_msg(|| mock.checkpoint());
+    assert!(msg.contains("MockLogger::debug: Expectation(<anything>) \
+        called fewer than 1 times"), "{}", msg);
+}
+
+#[test]
+fn several_budgets() {
+    let b0 = CallBudget::new(2);
+    let b1 = CallBudget::new(1..);
+    let mut mock = MockLogger::new();
+    mock.expect_debug()
+        .in_budget(&b0, 1)
+        .in_budget(&b1, 1)
+        .return_const(());
+    mock.expect_info()
+        .in_budget(&b0, 1)
+        .return_const(());
+    mock.info("a");
+    mock.debug("b");
+    b0.verify();
+    b1.verify();
+}
+
+/// Both the expectation and its budget are reported together
+#[test]
+fn checkpoint_reports_all() {
+    let budget = CallBudget::new(1);
+    let mut mock = MockLogger::new();
+    mock.expect_debug()
+        .times(1)
+        .in_budget(&budget, 1)
+        .return_const(());
+    let msg = panic_msg(|| mock.checkpoint());
+    assert!(msg.starts_with("2 expectations were not satisfied:"), "{}", msg);
+}
+
+#[test]
+fn generic_method() {
+    let budget = CallBudget::new(1);
+    let mut mock = MockLogger::new();
+    mock.expect_log::<u32>()
+        .in_budget(&budget, 1)
+        .return_const(());
+    mock.expect_log::<i16>()
+        .in_budget(&budget, 1)
+        .return_const(());
+    mock.log(1u32);
+    let msg = panic_msg(|| mock.log(1i16));
+    assert!(msg.contains("exceeded CallBudget(exactly 1 time)"), "{}", msg);
+}
+
+#[test]
+fn static_method() {
+    let budget = CallBudget::new(1);
+    let ctx = MockLogger::slog_context();
+    ctx.expect()
+        .in_budget(&budget, 1)
+        .return_const(());
+    MockLogger::slog("a");
+    budget.verify();
+    ctx.checkpoint();
+}
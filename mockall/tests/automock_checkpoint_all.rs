@@ -0,0 +1,71 @@
+// vim: tw=80
+//! checkpoint_all validates both a mock object's expectations and its static
+//! methods' expectations, and reports all failures together.
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait A {
+    fn build() -> u32;
+    fn foo(&self) -> u32;
+    fn bar(&self) -> u32;
+}
+
+#[automock]
+pub trait B {
+    fn build() -> u32;
+    fn foo(&self) -> u32;
+}
+
+#[automock]
+pub trait C {
+    fn foo(&self) -> u32;
+    fn bar(&self) -> u32;
+}
+
+#[automock]
+pub trait Empty {}
+
+#[test]
+fn satisfied() {
+    let ctx = MockA::build_context();
+    ctx.expect().times(1).return_const(1u32);
+    let mut mock = MockA::new();
+    mock.expect_foo().times(1).return_const(2u32);
+    assert_eq!(1, MockA::build());
+    assert_eq!(2, mock.foo());
+    MockA::checkpoint_all(&mut mock);
+}
+
+/// Static expectations are validated, too
+#[test]
+#[should_panic(expected = "MockB::build: Expectation(<anything>) called fewer than 1 times")]
+fn statics() {
+    let ctx = MockB::build_context();
+    ctx.expect().times(1).return_const(1u32);
+    let mut mock = MockB::new();
+    mock.checkpoint_all();
+}
+
+/// All failures are reported, not just the first
+#[test]
+fn aggregated() {
+    let mut mock = MockC::new();
+    mock.expect_foo().times(1).return_const(1u32);
+    mock.expect_bar().times(1).return_const(2u32);
+    let msg = std::panic::catch_unwind(move || mock.checkpoint_all())
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap();
+    assert!(msg.starts_with("2 expectations were not satisfied"), "{}", msg);
+    assert!(msg.contains("MockC::foo"), "{}", msg);
+    assert!(msg.contains("MockC::bar"), "{}", msg);
+}
+
+/// With nothing to checkpoint at all
+#[test]
+fn empty() {
+    let mut mock = MockEmpty::new();
+    mock.checkpoint_all();
+}
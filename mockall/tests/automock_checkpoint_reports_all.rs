@@ -0,0 +1,162 @@
+// vim: tw=80
+//! A checkpoint reports every unsatisfied expectation, not just the first
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar(&self) -> u32;
+    fn baz<T: 'static>(&self, t: T);
+    fn sfoo() -> u32;
+}
+
+#[automock]
+pub mod m {
+    pub fn foo(_x: u32) -> u32 {0}
+    pub fn bar() -> u32 {0}
+}
+
+#[automock(mod mock_ffi;)]
+extern "C" {
+    pub fn ffoo(x: u32) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+/// The lines of a checkpoint's panic message that list its failures, without
+/// the report of all expectations that follows them
+fn failures(msg: &str) -> Vec<&str> {
+    msg.split("\nAll expectations:\n").next().unwrap().lines().collect()
+}
+
+/// With only one failure, the message is unchanged
+#[test]
+#[should_panic(expected =
+    "MockFoo::foo: Expectation(<anything>) called fewer than 1 times")]
+fn one() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(0u32);
+    mock.expect_bar().times(..).return_const(0u32);
+    mock.checkpoint();
+}
+
+/// Every expectation of a single method is listed, in the order they were
+/// created.
+#[test]
+fn one_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1))
+        .times(1)
+        .return_const(0u32);
+    mock.expect_foo()
+        .with(predicate::eq(2))
+        .times(2)
+        .return_const(0u32);
+    let msg = panic_msg(|| mock.checkpoint_foo());
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{}", msg);
+    assert_eq!(lines[0], "2 expectations were not satisfied:");
+    assert!(lines[1].starts_with(
+        "- MockFoo::foo: Expectation(var == 1) called fewer than 1 times"));
+    assert!(lines[2].starts_with(
+        "- MockFoo::foo: Expectation(var == 2) called fewer than 2 times"));
+}
+
+/// The aggregate checkpoint lists the failures of every method, in declaration
+/// order.
+#[test]
+fn all_methods() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar().times(1).return_const(0u32);
+    mock.expect_foo().times(1).return_const(0u32);
+    mock.expect_baz::<u32>().times(1).return_const(());
+    mock.expect_baz::<i16>().times(1).return_const(());
+    let msg = panic_msg(|| mock.checkpoint());
+    let lines = failures(&msg);
+    assert_eq!(lines.len(), 5, "{}", msg);
+    assert_eq!(lines[0], "4 expectations were not satisfied:");
+    assert!(lines[1].starts_with("- MockFoo::foo:"), "{}", msg);
+    assert!(lines[2].starts_with("- MockFoo::bar:"), "{}", msg);
+    assert!(lines[3].starts_with("- MockFoo::baz:"), "{}", msg);
+    assert!(lines[4].starts_with("- MockFoo::baz:"), "{}", msg);
+}
+
+/// Every instantiation of a generic method is checked, even ones that were
+/// never called, and failures name the instantiation's types
+#[test]
+fn generic_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_baz::<u32>().times(1).return_const(());
+    mock.expect_baz::<i16>().times(1).return_const(());
+    mock.baz(5i16);
+    let msg = panic_msg(|| mock.checkpoint());
+    assert!(msg.starts_with(
+        "MockFoo::baz::<u32>: Expectation(<anything>) called fewer than 1 times"
+        ), "{}", msg);
+}
+
+/// Satisfied expectations aren't listed, and all of them are cleared anyway
+#[test]
+fn cleared() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(0u32);
+    mock.expect_bar().times(1).return_const(0u32);
+    mock.expect_bar().times(1).return_const(0u32);
+    mock.bar();
+    let msg = panic_msg(|| mock.checkpoint());
+    let lines = failures(&msg);
+    assert_eq!(lines.len(), 3, "{}", msg);
+    assert!(lines[1].starts_with("- MockFoo::foo:"), "{}", msg);
+    assert!(lines[2].starts_with("- MockFoo::bar:"), "{}", msg);
+    mock.checkpoint();
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect().times(1).return_const(0u32);
+    ctx.expect().times(3).return_const(0u32);
+    let msg = panic_msg(|| ctx.checkpoint());
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{}", msg);
+    assert!(lines[1].starts_with(
+        "- MockFoo::sfoo: Expectation(<anything>) called fewer than 1 times"));
+    assert!(lines[2].starts_with(
+        "- MockFoo::sfoo: Expectation(<anything>) called fewer than 3 times"));
+}
+
+/// A module's checkpoint names the module and function of each failure
+#[test]
+fn module() {
+    let foo_ctx = mock_m::foo_context();
+    let bar_ctx = mock_m::bar_context();
+    foo_ctx.expect().times(1).return_const(0u32);
+    bar_ctx.expect().times(1).return_const(0u32);
+    let msg = panic_msg(mock_m::checkpoint);
+    let lines = failures(&msg);
+    assert_eq!(lines.len(), 3, "{}", msg);
+    assert!(lines[1].starts_with(
+        "- mock_m::foo: Expectation(<anything>) called fewer than 1 times"),
+        "{}", msg);
+    assert!(lines[2].starts_with(
+        "- mock_m::bar: Expectation(<anything>) called fewer than 1 times"),
+        "{}", msg);
+}
+
+#[test]
+#[should_panic(expected =
+    "mock_ffi::ffoo: Expectation(<anything>) called fewer than 1 times")]
+fn foreign_module() {
+    let ctx = mock_ffi::ffoo_context();
+    ctx.expect().times(1).return_const(0u32);
+    mock_ffi::checkpoint();
+}
@@ -0,0 +1,84 @@
+// vim: tw=80
+//! checkpoint_statics validates static methods' expectations, which instance
+//! checkpoints leave alone.
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait A {
+    fn build() -> u32;
+    fn bar(&self) -> i32;
+}
+
+#[automock]
+pub trait B {
+    fn build() -> u32;
+}
+
+mock! {
+    pub C {
+        fn make() -> u32;
+        fn statics(&self) -> u32;
+    }
+    trait B {
+        fn build() -> u32;
+    }
+}
+
+mock! {
+    pub D {
+        fn make() -> u32;
+    }
+    trait B {
+        fn build() -> u32;
+    }
+}
+
+/// An instance checkpoint doesn't checkpoint static methods
+#[test]
+fn instance_checkpoint() {
+    let ctx = MockA::build_context();
+    ctx.expect().times(1).return_const(4u32);
+    let mut mock = MockA::new();
+    mock.expect_bar().return_const(5);
+    mock.checkpoint();
+    assert_eq!(4, MockA::build());
+    MockA::checkpoint_statics();
+}
+
+#[test]
+#[should_panic(expected = "called fewer than 1 times")]
+fn unsatisfied() {
+    let ctx = MockB::build_context();
+    ctx.expect().times(1).return_const(4u32);
+    MockB::checkpoint_statics();
+}
+
+/// Inherent and trait static methods are both checkpointed.  And since `MockC`
+/// has a method named `statics`, its `checkpoint_statics` gets a prefix.
+#[test]
+fn both() {
+    let make_ctx = MockC::make_context();
+    make_ctx.expect().times(1).return_const(1u32);
+    let build_ctx = MockC::build_context();
+    build_ctx.expect().times(1).return_const(2u32);
+    assert_eq!(1, MockC::make());
+    assert_eq!(2, <MockC as B>::build());
+    MockC::mockall_checkpoint_statics();
+
+    let mut mock = MockC::new();
+    mock.expect_statics().times(1).return_const(3u32);
+    assert_eq!(3, mock.statics());
+    mock.checkpoint_statics();
+}
+
+#[test]
+#[should_panic(expected = "called fewer than 1 times")]
+fn trait_unsatisfied() {
+    let make_ctx = MockD::make_context();
+    make_ctx.expect().return_const(1u32);
+    let build_ctx = MockD::build_context();
+    build_ctx.expect().times(1).return_const(2u32);
+    MockD::checkpoint_statics();
+}
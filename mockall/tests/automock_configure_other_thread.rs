@@ -0,0 +1,75 @@
+// vim: tw=80
+//! Expectations may be set on one thread, and the mock used and checkpointed on
+//! others
+#![deny(warnings)]
+
+use mockall::*;
+use std::thread;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar() -> u32;
+    fn baz<T: 'static>(t: T) -> u32;
+    fn bean() -> u32;
+}
+
+type Setup = Box<dyn FnOnce(&mut MockFoo) + Send>;
+
+#[test]
+fn three_threads() {
+    let (mock, ctx) = thread::spawn(|| {
+        let mut seq = Sequence::new();
+        let setup: Setup = Box::new(move |mock| {
+            mock.expect_foo()
+                .with(predicate::eq(1))
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_const(10u32);
+            mock.expect_foo()
+                .with(predicate::eq(2))
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_const(20u32);
+        });
+        let mut mock = MockFoo::new();
+        setup(&mut mock);
+        let ctx = MockFoo::bar_context();
+        ctx.expect().times(1).return_const(5u32);
+        (mock, ctx)
+    }).join().unwrap();
+    let (mut mock, ctx) = thread::spawn(move || {
+        assert_eq!(10, mock.foo(1));
+        assert_eq!(20, mock.foo(2));
+        assert_eq!(5, MockFoo::bar());
+        (mock, ctx)
+    }).join().unwrap();
+    thread::spawn(move || {
+        mock.checkpoint();
+        ctx.checkpoint();
+    }).join().unwrap();
+}
+
+/// A static method's ExpectationGuard may be sent to another thread
+#[test]
+fn send_guard() {
+    let ctx = MockFoo::baz_context();
+    let mut guard = ctx.expect::<u16>();
+    thread::spawn(move || {
+        guard.times(1).return_const(3u32);
+    }).join().unwrap();
+    assert_eq!(3, MockFoo::baz(0u16));
+    ctx.checkpoint();
+}
+
+/// An ExpectationGuard doesn't lock the method's expectations between calls,
+/// so the method may be called while one is held.
+#[test]
+fn hold_guard() {
+    let ctx = MockFoo::bean_context();
+    let mut guard = ctx.expect();
+    guard.return_const(1u32);
+    assert_eq!(1, MockFoo::bean());
+    guard.return_const(2u32);
+    assert_eq!(2, MockFoo::bean());
+}
@@ -0,0 +1,34 @@
+// vim: tw=80
+//! const fns are mocked as ordinary functions, since the mock functions must
+//! lock their expectations.
+
+use mockall::*;
+
+pub struct Calc {}
+
+#[automock]
+#[allow(unused)]
+impl Calc {
+    pub const fn zero() -> u32 {
+        0
+    }
+    pub const fn double(&self, x: u32) -> u32 {
+        2 * x
+    }
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockCalc::zero_context();
+    ctx.expect()
+        .return_const(42u32);
+    assert_eq!(42, MockCalc::zero());
+}
+
+#[test]
+fn method() {
+    let mut mock = MockCalc::new();
+    mock.expect_double()
+        .returning(|x| x + 1);
+    assert_eq!(5, mock.double(4));
+}
@@ -0,0 +1,73 @@
+// vim: tw=80
+//! A generic mock's constructor has separate expectations for each
+//! instantiation of the mock's generic parameters
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Factory<T: 'static> {
+    fn new() -> Self;
+    fn get(&self) -> T;
+}
+
+mock! {
+    pub Maker<T: 'static> {
+        fn new() -> Self;
+        fn get(&self) -> T;
+    }
+}
+
+#[test]
+fn automock() {
+    let ctx_u32 = MockFactory::<u32>::new_context();
+    let ctx_i16 = MockFactory::<i16>::new_context();
+    ctx_u32.expect().times(1).returning(|| {
+        let mut mock = MockFactory::default();
+        mock.expect_get().return_const(1u32);
+        mock
+    });
+    ctx_i16.expect().times(2).returning(|| {
+        let mut mock = MockFactory::default();
+        mock.expect_get().return_const(-1i16);
+        mock
+    });
+    let a = MockFactory::<i16>::new();
+    let b = MockFactory::<u32>::new();
+    let c = MockFactory::<i16>::new();
+    assert_eq!(-1, a.get());
+    assert_eq!(1, b.get());
+    assert_eq!(-1, c.get());
+    ctx_u32.checkpoint();
+    ctx_i16.checkpoint();
+}
+
+#[test]
+fn mock() {
+    let ctx_u32 = MockMaker::<u32>::new_context();
+    ctx_u32.expect().returning(|| {
+        let mut mock = MockMaker::default();
+        mock.expect_get().return_const(2u32);
+        mock
+    });
+    let ctx_u64 = MockMaker::<u64>::new_context();
+    ctx_u64.expect().returning(|| {
+        let mut mock = MockMaker::default();
+        mock.expect_get().return_const(3u64);
+        mock
+    });
+    assert_eq!(3, MockMaker::<u64>::new().get());
+    assert_eq!(2, MockMaker::<u32>::new().get());
+}
+
+/// Checkpointing one instantiation's constructor leaves the others alone
+#[test]
+fn checkpoint() {
+    let ctx_u8 = MockMaker::<u8>::new_context();
+    let ctx_i8 = MockMaker::<i8>::new_context();
+    ctx_u8.expect().returning(MockMaker::default);
+    ctx_i8.expect().times(1).returning(MockMaker::default);
+    ctx_u8.checkpoint();
+    let _ = MockMaker::<i8>::new();
+    ctx_i8.checkpoint();
+}
@@ -0,0 +1,101 @@
+// vim: tw=80
+//! A constructor's expectation can return a single mock that the test
+//! configured beforehand, and the test can still check that mock afterwards
+#![deny(warnings)]
+
+use mockall::*;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+#[automock]
+pub trait Conn {
+    fn connect(addr: u32) -> Self;
+    fn get(&self, key: u32) -> u32;
+}
+
+#[automock]
+pub trait Pool {
+    fn open() -> Self;
+}
+
+/// The code under test, which constructs its own connection
+pub struct Client<C: Conn> {
+    conn: Arc<Mutex<C>>
+}
+
+impl<C: Conn> Client<C> {
+    pub fn new(addr: u32) -> Self {
+        Client{conn: Arc::new(Mutex::new(C::connect(addr)))}
+    }
+
+    pub fn get(&self, key: u32) -> u32 {
+        self.conn.lock().unwrap().get(key)
+    }
+
+    pub fn conn(&self) -> Arc<Mutex<C>> {
+        self.conn.clone()
+    }
+}
+
+static CONNECT_MTX: Mutex<()> = Mutex::new(());
+
+/// Serialize the tests that use `connect`, whose expectations are global
+fn lock_connect() -> MutexGuard<'static, ()> {
+    CONNECT_MTX.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[test]
+fn return_once_mock() {
+    let _m = lock_connect();
+    let mut mock = MockConn::default();
+    mock.expect_get()
+        .with(predicate::eq(1))
+        .times(1)
+        .return_const(10u32);
+    let ctx = MockConn::connect_context();
+    ctx.expect()
+        .with(predicate::eq(80))
+        .return_once_mock(mock);
+
+    let client = Client::<MockConn>::new(80);
+    assert_eq!(10, client.get(1));
+    client.conn().lock().unwrap().checkpoint();
+    ctx.checkpoint();
+}
+
+#[test]
+fn expect_returning() {
+    let _m = lock_connect();
+    let mut mock = MockConn::default();
+    mock.expect_get()
+        .return_const(20u32);
+    let _ctx = MockConn::expect_connect_returning(mock);
+
+    let client = Client::<MockConn>::new(80);
+    assert_eq!(20, client.get(2));
+}
+
+/// Checkpointing the mock that the code under test received checks the
+/// expectations that the test set before it was constructed
+#[test]
+#[should_panic(expected =
+    "MockConn::get: Expectation(<anything>) called fewer than 2 times")]
+fn checkpoint_returned_mock() {
+    let _m = lock_connect();
+    let mut mock = MockConn::default();
+    mock.expect_get()
+        .times(2)
+        .return_const(30u32);
+    let _ctx = MockConn::expect_connect_returning(mock);
+
+    let client = Client::<MockConn>::new(80);
+    assert_eq!(30, client.get(3));
+    client.conn().lock().unwrap().checkpoint();
+}
+
+/// The constructor is expected to be called once
+#[test]
+#[should_panic(expected =
+    "MockPool::open: Expectation(<anything>) called fewer than 1 times")]
+fn never_constructed() {
+    let _ctx = MockPool::expect_open_returning(MockPool::default());
+}
@@ -0,0 +1,104 @@
+// vim: tw=80
+//! Users can write their own argument matchers
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+/// Matches any string that has the same "shape" as the expected one: the same
+/// sequence of letters, digits, and punctuation, ignoring which ones.
+pub struct Shape(&'static str);
+
+impl Shape {
+    fn shape(s: &str) -> String {
+        s.chars().map(|c| if c.is_ascii_alphabetic() {
+            'a'
+        } else if c.is_ascii_digit() {
+            '0'
+        } else {
+            c
+        }).collect()
+    }
+}
+
+impl Matcher<str> for Shape {
+    fn matches(&self, arg: &str) -> bool {
+        Shape::shape(arg) == Shape::shape(self.0)
+    }
+
+    fn describe(&self) -> String {
+        format!("shaped like {:?}", self.0)
+    }
+}
+
+/// Matches even numbers, explaining itself in detail
+pub struct Even;
+
+impl Matcher<u32> for Even {
+    fn matches(&self, arg: &u32) -> bool {
+        arg & 1 == 0
+    }
+
+    fn describe(&self) -> String {
+        String::from("an even number")
+    }
+
+    fn describe_mismatch(&self, arg: &u32) -> Option<String> {
+        if self.matches(arg) {
+            None
+        } else {
+            Some(format!("{} is odd", arg))
+        }
+    }
+}
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, name: &str, x: u32) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn matches() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(Shape("ab-12"), Even)
+        .return_const(1u32);
+    assert_eq!(1, mock.foo("xy-99", 4));
+}
+
+/// Custom matchers can be mixed with predicates
+#[test]
+fn mixed() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(Shape("ab-12"), predicate::gt(3))
+        .return_const(1u32);
+    assert_eq!(1, mock.foo("xy-99", 4));
+}
+
+#[test]
+fn no_match() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(Shape("ab-12"), Even)
+        .return_const(1u32);
+    let msg = panic_msg(|| {mock.foo("xy_99", 4);});
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2, "{}", msg);
+    assert!(lines[1].starts_with(
+        "#1 Expectation(shaped like \"ab-12\", an even number) (set at "),
+        "{}", msg);
+    assert!(lines[1].ends_with(
+        ": rejected by the `with` predicates: `shaped like \"ab-12\"` failed"),
+        "{}", msg);
+    let msg = panic_msg(|| {mock.foo("xy-99", 5);});
+    assert!(msg.ends_with(": rejected by the `with` predicates: 5 is odd"),
+        "{}", msg);
+}
@@ -0,0 +1,122 @@
+// vim: tw=80
+//! Generated code must compile silently in crates that deny warnings, naming
+//! lints, or documentation lints.
+#![deny(warnings, missing_docs, nonstandard_style)]
+#![deny(clippy::all)]
+
+use mockall::*;
+
+/// A trait whose mock includes a sub-structure named `MockA_A`
+#[automock]
+pub trait A {
+    /// A method
+    fn foo(&self, x: u32) -> u32;
+    /// A static method
+    fn bar() -> u32;
+}
+
+/// A struct with a trait impl
+pub struct S {}
+#[automock]
+impl A for S {
+    fn foo(&self, x: u32) -> u32 {x}
+    fn bar() -> u32 {0}
+}
+
+/// A generic struct with an inherent impl
+pub struct G<T: 'static> {
+    _t: std::marker::PhantomData<T>
+}
+#[automock]
+impl<T: 'static> G<T> {
+    /// A method
+    pub fn baz(&self, _t: T) -> i16 {0}
+}
+
+/// A trait with every kind of return value
+#[automock]
+pub trait Returns {
+    /// Returns nothing
+    fn unit(&self, x: u32);
+    /// Returns a reference
+    fn by_ref(&self) -> &u32;
+    /// Returns a mutable reference
+    fn by_mut(&mut self) -> &mut u32;
+    /// Returns an `impl Trait`
+    fn by_impl(&self) -> impl Iterator<Item=u32>;
+    /// Returns a Result
+    fn res(&self) -> Result<u32, String>;
+    /// Takes a reference argument
+    fn by_ref_arg(&self, x: &u32) -> u32;
+}
+
+/// A generic trait with generic methods
+#[automock]
+pub trait Generic<T: 'static> {
+    /// A generic method
+    fn gen<Q: 'static>(&self, q: Q) -> T;
+    /// A generic static method
+    fn gen_static<Q: 'static>(q: Q) -> u32;
+}
+
+/// A trait with an associated type
+#[automock(type Item=u32;)]
+pub trait Assoc {
+    /// The associated type
+    type Item;
+    /// A method
+    fn item(&self) -> Self::Item;
+}
+
+mock! {
+    pub Multi {
+        fn f(&self) -> u32;
+    }
+    trait A {
+        fn foo(&self, x: u32) -> u32;
+        fn bar() -> u32;
+    }
+    trait Assoc {
+        type Item = i64;
+        fn item(&self) -> i64;
+    }
+}
+
+/// A module of free functions
+#[automock]
+pub mod m {
+    /// A function
+    pub fn f(x: u32) -> u32 {x}
+}
+
+#[automock(mod mock_ffi;)]
+extern "C" {
+    // The real function is never called by the test
+    #[allow(dead_code)]
+    fn ffi(x: u32) -> u32;
+}
+
+#[test]
+fn usable() {
+    let mut mock = MockA::new();
+    mock.expect_foo().return_const(4u32);
+    assert_eq!(4, mock.foo(1));
+
+    let mut multi = MockMulti::new();
+    multi.expect_f().return_const(5u32);
+    assert_eq!(5, multi.f());
+
+    let mut returns = MockReturns::new();
+    returns.expect_by_ref().return_const(6u32);
+    returns.expect_by_mut().return_var(7u32);
+    assert_eq!(6, *returns.by_ref());
+    assert_eq!(7, *returns.by_mut());
+
+    let mut generic = MockGeneric::<u64>::new();
+    generic.expect_gen::<i8>().return_const(8u64);
+    assert_eq!(8, generic.gen(0i8));
+
+    let ctx = mock_ffi::ffi_context();
+    ctx.expect().returning(|x| x + 1);
+    assert_eq!(10, unsafe { mock_ffi::ffi(9) });
+}
@@ -0,0 +1,107 @@
+// vim: tw=80
+//! Mocking deprecated methods doesn't warn, but setting expectations on them
+//! does
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Foo {
+    #[deprecated(since = "0.1.0", note = "Use bar instead")]
+    fn foo(&self, x: u32) -> u32;
+    #[deprecated(note = "Use bar instead")]
+    fn baz<T: 'static>(&self, t: T) -> u32;
+    #[deprecated]
+    fn bean() -> u32;
+    fn bar(&self, x: u32) -> u32;
+}
+
+pub struct RealBaz {}
+
+#[automock(inner = RealBaz)]
+pub trait Baz {
+    #[deprecated]
+    fn baz(&self) -> u32;
+}
+
+#[allow(deprecated)]
+impl Baz for RealBaz {
+    fn baz(&self) -> u32 {
+        10
+    }
+}
+
+pub struct A{}
+#[automock]
+impl A {
+    #[deprecated(since = "0.1.0", note = "Use bar instead")]
+    pub fn foo(&self, _x: u32) -> u32 {0}
+    #[deprecated]
+    pub fn bean() -> u32 {0}
+}
+
+#[automock]
+pub mod m {
+    #[deprecated]
+    pub fn foo(_x: u32) -> u32 {0}
+}
+
+mock! {
+    pub Bar {
+        #[deprecated]
+        fn foo(&self) -> u32;
+    }
+    trait Clone {
+        #[deprecated]
+        fn clone(&self) -> Self;
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn trait_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().returning(|x| x + 1);
+    mock.expect_baz::<u16>().return_const(2u32);
+    assert_eq!(5, mock.foo(4));
+    assert_eq!(2, mock.baz(0u16));
+    let ctx = MockFoo::bean_context();
+    ctx.expect().return_const(3u32);
+    assert_eq!(3, MockFoo::bean());
+}
+
+#[test]
+#[allow(deprecated)]
+fn inner() {
+    let mock = MockBaz::new_with_inner(RealBaz{});
+    assert_eq!(10, mock.baz());
+}
+
+#[test]
+#[allow(deprecated)]
+fn inherent_method() {
+    let mut mock = MockA::new();
+    mock.expect_foo().return_const(6u32);
+    assert_eq!(6, mock.foo(0));
+    let ctx = MockA::bean_context();
+    ctx.expect().return_const(7u32);
+    assert_eq!(7, MockA::bean());
+}
+
+#[test]
+#[allow(deprecated)]
+fn module_function() {
+    let ctx = mock_m::foo_context();
+    ctx.expect().return_const(8u32);
+    assert_eq!(8, mock_m::foo(0));
+}
+
+#[test]
+#[allow(deprecated)]
+fn mock_macro() {
+    let mut mock = MockBar::new();
+    mock.expect_foo().return_const(9u32);
+    mock.expect_clone().returning(MockBar::new);
+    assert_eq!(9, mock.foo());
+    let _ = mock.clone();
+}
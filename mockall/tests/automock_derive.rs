@@ -0,0 +1,18 @@
+// vim: tw=80
+//! Extra derive macros can be applied to the mock struct
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock(derive(Clone, Debug))]
+pub trait Marker {}
+
+fn take<M: Marker + Clone + std::fmt::Debug>(m: M) -> String {
+    format!("{:?}", m.clone())
+}
+
+#[test]
+fn derived() {
+    let mock = MockMarker::new();
+    assert!(take(mock).starts_with("MockMarker"));
+}
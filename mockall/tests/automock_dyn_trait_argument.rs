@@ -0,0 +1,89 @@
+// vim: tw=80
+//! Methods with trait object arguments, matched by their concrete types
+
+use mockall::*;
+use mockall::predicate::*;
+
+pub trait Command: AsAny {
+    fn id(&self) -> u32;
+}
+
+pub struct Stop {
+    id: u32
+}
+impl Command for Stop {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+pub struct Go;
+impl Command for Go {
+    fn id(&self) -> u32 {
+        0
+    }
+}
+
+#[automock]
+pub trait Executor {
+    fn execute(&self, cmd: &dyn Command) -> bool;
+    fn execute_both(&self, first: &dyn Command, second: &dyn Command) -> u32;
+}
+
+#[test]
+fn is_type_() {
+    let mut mock = MockExecutor::new();
+    mock.expect_execute()
+        .with(is_type::<Go>())
+        .return_const(true);
+    mock.expect_execute()
+        .return_const(false);
+    assert!(mock.execute(&Go));
+    assert!(!mock.execute(&Stop{id: 1}));
+}
+
+#[test]
+fn downcast_matches_() {
+    let mut mock = MockExecutor::new();
+    mock.expect_execute()
+        .with(downcast_matches::<Stop, _>(|s| s.id == 7))
+        .return_const(true);
+    assert!(mock.execute(&Stop{id: 7}));
+}
+
+#[test]
+#[should_panic(expected = "No matching expectation found")]
+fn downcast_matches_wrong_type() {
+    let mut mock = MockExecutor::new();
+    mock.expect_execute()
+        .with(downcast_matches::<Stop, _>(|_| true))
+        .return_const(true);
+    mock.execute(&Go);
+}
+
+#[test]
+fn multiple_args() {
+    let mut mock = MockExecutor::new();
+    mock.expect_execute_both()
+        .with(is_type::<Go>(), downcast_matches::<Stop, _>(|s| s.id == 2))
+        .return_const(42u32);
+    assert_eq!(42, mock.execute_both(&Go, &Stop{id: 2}));
+}
+
+#[test]
+fn withf() {
+    let mut mock = MockExecutor::new();
+    mock.expect_execute()
+        .withf(|cmd| cmd.id() == 9)
+        .return_const(true);
+    assert!(mock.execute(&Stop{id: 9}));
+}
+
+#[test]
+fn returning() {
+    let mut mock = MockExecutor::new();
+    mock.expect_execute()
+        .returning(|cmd| cmd.id() > 5);
+    assert!(mock.execute(&Stop{id: 9}));
+    assert!(!mock.execute(&Go));
+}
@@ -0,0 +1,27 @@
+// vim: tw=80
+//! A trait with no methods still gets a complete mock struct
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Marker {}
+
+#[automock]
+pub trait GenericMarker<T: 'static> {}
+
+fn take<M: Marker>(_m: M) {}
+
+#[test]
+fn usable_generically() {
+    let mut mock = MockMarker::new();
+    mock.checkpoint();
+    take(mock);
+    take(MockMarker::default());
+}
+
+#[test]
+fn generic() {
+    let mut mock = MockGenericMarker::<u32>::new();
+    mock.checkpoint();
+}
@@ -0,0 +1,57 @@
+// vim: tw=80
+//! automocking an enum's inherent impl and a trait impl for an enum
+
+use mockall::*;
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Ok,
+    Failed
+}
+
+#[allow(unused)]
+pub enum Command {
+    Start,
+    Stop
+}
+
+#[allow(unused)]
+#[automock]
+impl Command {
+    pub fn run(&self) -> Status {
+        Status::Ok
+    }
+}
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[allow(unused)]
+pub enum Shape {
+    Circle,
+    Square
+}
+
+#[automock]
+impl Describe for Shape {
+    fn describe(&self) -> String {
+        String::from("shape")
+    }
+}
+
+#[test]
+fn inherent_impl() {
+    let mut mock = MockCommand::new();
+    mock.expect_run()
+        .returning(|| Status::Failed);
+    assert_eq!(Status::Failed, mock.run());
+}
+
+#[test]
+fn trait_impl() {
+    let mut mock = MockShape::new();
+    mock.expect_describe()
+        .return_const("circle".to_owned());
+    assert_eq!("circle", mock.describe());
+}
@@ -0,0 +1,85 @@
+// vim: tw=80
+//! Matchers may be passed directly to an expect_*_with method, one per argument
+#![deny(warnings)]
+
+use mockall::*;
+use mockall::predicate::*;
+
+#[automock]
+pub trait Foo {
+    fn get(&self, key: u32) -> Option<u32>;
+    fn put(&mut self, key: u32, value: &str);
+    fn generic<T: PartialEq + Send + 'static>(&self, t: T) -> u32;
+    fn name(&self) -> &str;
+    fn slot(&mut self, i: usize) -> &mut u32;
+}
+
+#[automock]
+pub trait Bar {
+    fn bar(&self, x: u32) -> u32;
+    // A method whose expect_ method has the same name as bar's shorthand
+    fn bar_with(&self, x: u32) -> u32;
+}
+
+#[test]
+fn one_arg() {
+    let mut mock = MockFoo::new();
+    mock.expect_get_with(eq(5)).return_const(Some(50));
+    mock.expect_get().return_const(None);
+    assert_eq!(Some(50), mock.get(5));
+    assert_eq!(None, mock.get(6));
+}
+
+/// Matchers are positional
+#[test]
+fn two_args() {
+    let mut mock = MockFoo::new();
+    mock.expect_put_with(eq(1), eq("one"))
+        .times(1)
+        .return_const(());
+    mock.put(1, "one");
+}
+
+#[test]
+fn generic() {
+    let mut mock = MockFoo::new();
+    mock.expect_generic_with(eq(5u16)).return_const(1u32);
+    mock.expect_generic_with::<u16, _>(always()).return_const(2u32);
+    assert_eq!(1, mock.generic(5u16));
+    assert_eq!(2, mock.generic(6u16));
+}
+
+#[test]
+fn closure() {
+    let mut mock = MockFoo::new();
+    mock.expect_get_with(function(|k: &u32| k % 2 == 0))
+        .return_const(Some(0));
+    assert_eq!(Some(0), mock.get(4));
+}
+
+#[test]
+fn ref_mut_return() {
+    let mut mock = MockFoo::new();
+    mock.expect_slot_with(eq(0)).return_var(7);
+    assert_eq!(7, *mock.slot(0));
+}
+
+/// Methods without arguments don't get a shorthand, but the zero-argument
+/// expect_ methods still work
+#[test]
+fn no_args() {
+    let mut mock = MockFoo::new();
+    mock.expect_name().return_const("foo".to_owned());
+    assert_eq!("foo", mock.name());
+}
+
+/// If the shorthand would collide with another method's expect_ method, it
+/// isn't generated
+#[test]
+fn collision() {
+    let mut mock = MockBar::new();
+    mock.expect_bar_with().return_const(42u32);
+    mock.expect_bar().with(eq(1)).return_const(1u32);
+    assert_eq!(42, mock.bar_with(0));
+    assert_eq!(1, mock.bar(1));
+}
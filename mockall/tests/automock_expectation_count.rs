@@ -0,0 +1,103 @@
+// vim: tw=80
+//! A mock's expectations can be counted without validating or clearing them
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar<T: 'static>(&self, t: T) -> u32;
+    fn baz() -> u32;
+    fn bean<T: 'static>(t: T) -> u32;
+}
+
+#[automock]
+pub mod m {
+    pub fn qux() -> u32 {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn empty() {
+    let mock = MockFoo::new();
+    assert_eq!(0, mock.expectation_count());
+    assert_eq!(0, mock.expectation_count_foo());
+    assert_eq!(0, mock.expectation_count_bar());
+}
+
+#[test]
+fn per_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().return_const(1u32);
+    mock.expect_foo().return_const(2u32);
+    mock.expect_bar::<u16>().return_const(3u32);
+    assert_eq!(2, mock.expectation_count_foo());
+    assert_eq!(1, mock.expectation_count_bar());
+    assert_eq!(3, mock.expectation_count());
+}
+
+/// A generic method's expectations are counted for all sets of generic
+/// parameters
+#[test]
+fn generic_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar::<u16>().return_const(1u32);
+    mock.expect_bar::<i64>().return_const(2u32);
+    mock.expect_bar::<i64>().return_const(3u32);
+    assert_eq!(3, mock.expectation_count_bar());
+}
+
+/// Counting doesn't validate or remove anything
+#[test]
+fn unsatisfied() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(1u32);
+    assert_eq!(1, mock.expectation_count());
+    assert_eq!(1, mock.expectation_count());
+    assert_eq!(1, mock.foo(0));
+}
+
+/// Checkpointing clears the expectations
+#[test]
+fn checkpoint() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().return_const(1u32);
+    mock.checkpoint();
+    assert_eq!(0, mock.expectation_count());
+}
+
+/// Static methods aren't counted by the mock object, but by their own
+/// functions and contexts
+#[test]
+fn static_method() {
+    let ctx = MockFoo::baz_context();
+    assert!(ctx.is_empty());
+    ctx.expect().return_const(1u32);
+    ctx.expect().return_const(2u32);
+    assert_eq!(2, ctx.len());
+    assert_eq!(2, MockFoo::expectation_count_baz());
+    assert_eq!(0, MockFoo::new().expectation_count());
+    ctx.checkpoint();
+    assert_eq!(0, MockFoo::expectation_count_baz());
+}
+
+#[test]
+fn generic_static_method() {
+    let ctx = MockFoo::bean_context();
+    ctx.expect::<u16>().return_const(1u32);
+    ctx.expect::<i64>().return_const(2u32);
+    assert_eq!(2, ctx.len());
+    assert_eq!(2, MockFoo::expectation_count_bean());
+    ctx.checkpoint();
+}
+
+#[test]
+fn module() {
+    let ctx = mock_m::qux_context();
+    ctx.expect().return_const(1u32);
+    assert_eq!(1, ctx.len());
+    assert!(!ctx.is_empty());
+    ctx.checkpoint();
+}
@@ -0,0 +1,134 @@
+// vim: tw=80
+//! Panic messages say where the offending expectations were set
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar<T: 'static>(&self, t: T) -> u32;
+    fn baz(&self) -> &u32;
+    fn bean(&mut self) -> &mut u32;
+    fn sfoo(x: u32) -> u32;
+}
+
+const FILE: &str = file!();
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+/// The location of the line before the caller's
+#[track_caller]
+fn prev_line() -> String {
+    let l = panic::Location::caller();
+    format!("{}:{}:", l.file(), l.line() - 1)
+}
+
+#[test]
+fn called_too_few_times() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(0u32);
+    let loc = prev_line();
+    let msg = panic_msg(|| mock.checkpoint());
+    assert!(msg.contains(&format!("called fewer than 1 times (set at {}", loc)),
+        "{}", msg);
+}
+
+#[test]
+fn called_too_many_times() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(0u32);
+    let loc = prev_line();
+    mock.foo(1);
+    let msg = panic_msg(|| {mock.foo(2);});
+    assert!(msg.contains(&format!("called more than 1 times (set at {}", loc)),
+        "{}", msg);
+}
+
+#[test]
+fn no_return_value() {
+    let mut mock = MockFoo::new();
+    mock.expect_baz().times(..);
+    let loc = prev_line();
+    let msg = panic_msg(|| {mock.baz();});
+    assert!(msg.contains(&format!("(set at {}", loc)), "{}", msg);
+}
+
+/// A call that matches no expectation lists all of the ones it rejected
+#[test]
+fn no_match() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().with(predicate::eq(1)).return_const(0u32);
+    let loc1 = prev_line();
+    mock.expect_foo().with(predicate::eq(2)).return_const(0u32);
+    let loc2 = prev_line();
+    let msg = panic_msg(|| {mock.foo(3);});
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{}", msg);
+    assert_eq!(lines[0], "MockFoo::foo: No matching expectation found.  \
+        2 expectations, none matched:");
+    assert!(lines[1].starts_with(
+        &format!("#1 Expectation(var == 1) (set at {}", loc1)), "{}", msg);
+    assert!(lines[2].starts_with(
+        &format!("#2 Expectation(var == 2) (set at {}", loc2)), "{}", msg);
+}
+
+#[test]
+fn no_match_generic() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar::<u32>()
+        .with(predicate::eq(1))
+        .return_const(0u32);
+    let msg = panic_msg(|| {mock.bar(3u32);});
+    assert!(msg.contains(
+        "1 expectation, none matched:\n#1 Expectation(var == 1) (set at "),
+        "{}", msg);
+    // Expectations for other types aren't candidates
+    let msg = panic_msg(|| {mock.bar(3i16);});
+    assert_eq!(msg, "MockFoo::bar::<i16>: no expectation registered for this \
+        instantiation (registered: <u32>)");
+}
+
+#[test]
+fn no_match_ref_mut() {
+    let mut mock = MockFoo::new();
+    mock.expect_bean()
+        .times(1)
+        .return_var(0u32);
+    mock.bean();
+    let msg = panic_msg(|| {
+        let mut mock2 = MockFoo::new();
+        mock2.bean();
+    });
+    assert_eq!(msg, "MockFoo::bean: No matching expectation found");
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect()
+        .with(predicate::eq(1))
+        .return_const(0u32);
+    let msg = panic_msg(|| {MockFoo::sfoo(2);});
+    assert!(msg.contains(&format!(
+        "none matched:\n#1 Expectation(var == 1) (set at {}", FILE)),
+        "{}", msg);
+    ctx.checkpoint();
+}
+
+#[test]
+fn builder() {
+    let msg = panic_msg(|| {
+        let mut mock = MockFoo::builder()
+            .foo(|e| {e.times(1).return_const(0u32);})
+            .build();
+        mock.checkpoint();
+    });
+    assert!(msg.contains(&format!("(set at {}", FILE)), "{}", msg);
+}
@@ -0,0 +1,58 @@
+// vim: tw=80
+//! Extracting a trait from a struct's inherent impl, so that code can be
+//! generic over the struct and its mock
+
+use mockall::*;
+
+pub struct Client {
+    base: u32
+}
+
+#[automock(trait = ClientApi)]
+impl Client {
+    pub fn new(base: u32) -> Self {
+        Client{base}
+    }
+    pub fn get(&self, x: u32) -> u32 {
+        self.base + x
+    }
+    pub fn set(&mut self, mut base: u32) {
+        base += 0;
+        self.base = base;
+    }
+    pub fn name(&self) -> String {
+        String::from("real")
+    }
+}
+
+fn get_twice<C: ClientApi>(client: &C, x: u32) -> u32 {
+    client.get(x) + client.get(x)
+}
+
+#[test]
+fn mock() {
+    let mut mock = MockClient::default();
+    mock.expect_get()
+        .returning(|x| x + 1);
+    mock.expect_set()
+        .with(predicate::eq(5))
+        .return_const(());
+    ClientApi::set(&mut mock, 5);
+    assert_eq!(10, get_twice(&mock, 4));
+}
+
+#[test]
+fn real() {
+    let mut client = <Client as ClientApi>::new(1);
+    ClientApi::set(&mut client, 2);
+    assert_eq!(12, get_twice(&client, 4));
+    assert_eq!("real", ClientApi::name(&client));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockClient::new_context();
+    ctx.expect()
+        .returning(|_| MockClient::default());
+    let _mock = <MockClient as ClientApi>::new(1);
+}
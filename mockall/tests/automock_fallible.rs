@@ -0,0 +1,234 @@
+// vim: tw=80
+//! While a FallibleGuard exists, calls that match no expectation return a
+//! default value instead of panicking, and save their errors
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+pub struct NoDefault(u32);
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar(&self) -> &str;
+    fn baz(&self) -> NoDefault;
+    fn bean(&self, x: u32);
+    fn bang<T: 'static>(&self, t: T) -> i16;
+    fn sfoo(x: u32) -> u32;
+}
+
+#[automock]
+pub trait Bar {
+    fn bar(&mut self, x: u32) -> &mut u32;
+}
+
+#[automock]
+pub mod m {
+    pub fn mfoo(_x: u32) -> u64 { unimplemented!() }
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+/// By default, an unmatched call still panics, and saves no error
+#[test]
+fn panics_by_default() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1))
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.foo(2);});
+    assert!(msg.starts_with("MockFoo::foo: No matching expectation found"),
+        "{}", msg);
+    assert!(take_last_error().is_none());
+}
+
+#[test]
+fn fallible() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1))
+        .return_const(42u32);
+    let _guard = mockall::fallible();
+    assert_eq!(0, mock.foo(2));
+    let e = take_last_error().unwrap();
+    let msg = e.to_string();
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "MockFoo::foo: No matching expectation found.  \
+        1 expectation, none matched:");
+    assert!(lines[1].starts_with("#1 Expectation(var == 1) (set at "),
+        "{}", msg);
+    assert_eq!(e.rejected().len(), 1);
+    assert!(e.rejected()[0].ends_with("`var == 1` failed"), "{}", msg);
+    // The error can only be taken once
+    assert!(take_last_error().is_none());
+    // Matching calls work as usual, and don't save errors
+    assert_eq!(42, mock.foo(1));
+    assert!(take_last_error().is_none());
+}
+
+/// Only the last error is saved
+#[test]
+fn last_error() {
+    let mock = MockFoo::new();
+    let _guard = mockall::fallible();
+    mock.foo(1);
+    mock.bean(2);
+    let e = take_last_error().unwrap();
+    assert_eq!(e.to_string(), "MockFoo::bean: No matching expectation found");
+    assert!(e.rejected().is_empty());
+}
+
+#[test]
+fn guard_restores() {
+    let mock = MockFoo::new();
+    {
+        let _guard = mockall::fallible();
+        mock.foo(1);
+    }
+    assert!(take_last_error().is_some());
+    let msg = panic_msg(|| {mock.foo(1);});
+    assert_eq!(msg, "MockFoo::foo: No matching expectation found");
+}
+
+#[test]
+fn nested_guards() {
+    let mock = MockFoo::new();
+    let _outer = mockall::fallible();
+    {
+        let _inner = mockall::fallible();
+    }
+    assert_eq!(0, mock.foo(1));
+    assert!(take_last_error().is_some());
+}
+
+/// The default value is that of the method's declared return type, not the
+/// expectation's
+#[test]
+fn deref_return() {
+    let mock = MockFoo::new();
+    let _guard = mockall::fallible();
+    assert_eq!("", mock.bar());
+    assert!(take_last_error().is_some());
+}
+
+#[test]
+fn unit_return() {
+    let mock = MockFoo::new();
+    let _guard = mockall::fallible();
+    mock.bean(5);
+    assert!(take_last_error().is_some());
+}
+
+/// A call whose return type has no default value still panics
+#[test]
+fn no_default() {
+    let mock = MockFoo::new();
+    let _guard = mockall::fallible();
+    let msg = panic_msg(|| {mock.baz().0;});
+    assert_eq!(msg, "MockFoo::baz: No matching expectation found");
+    assert!(take_last_error().is_none());
+}
+
+/// So does a call that matches an expectation but is called too many times
+#[test]
+fn called_too_many_times() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(42u32);
+    let _guard = mockall::fallible();
+    mock.foo(1);
+    let msg = panic_msg(|| {mock.foo(1);});
+    assert!(msg.contains("called more than 1 times"), "{}", msg);
+}
+
+#[test]
+fn generic_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_bang::<u32>().return_const(7i16);
+    let _guard = mockall::fallible();
+    assert_eq!(0, mock.bang(1i8));
+    assert!(take_last_error().is_some());
+    assert_eq!(7, mock.bang(1u32));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect()
+        .with(predicate::eq(1))
+        .return_const(42u32);
+    let _guard = mockall::fallible();
+    assert_eq!(0, MockFoo::sfoo(2));
+    assert!(take_last_error().is_some());
+    ctx.checkpoint();
+}
+
+#[test]
+fn module_function() {
+    let ctx = mock_m::mfoo_context();
+    ctx.expect()
+        .with(predicate::eq(1))
+        .return_const(42u64);
+    let _guard = mockall::fallible();
+    assert_eq!(0, mock_m::mfoo(2));
+    let e = take_last_error().unwrap();
+    assert!(e.to_string().starts_with("mock_m::mfoo: No matching expectation"),
+        "{}", e);
+}
+
+/// A property-testing harness can check properties without crashing, and
+/// count the cases that called the mock unexpectedly
+#[test]
+fn property_style() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .withf(|x| x % 2 == 0)
+        .returning(|x| x / 2);
+    let _guard = mockall::fallible();
+    let failures = (0..10u32).filter(|&x| {
+        let _ = mock.foo(x);
+        take_last_error().is_some()
+    }).count();
+    assert_eq!(failures, 5);
+}
+
+/// A method's `Expectations` can be called directly without panicking
+#[test]
+fn try_call() {
+    let mut e = __mock_Foo_Foo::foo::Expectations::default();
+    e.expect()
+        .with(predicate::eq(1))
+        .return_const(42u32);
+    assert_eq!(42, e.try_call(1).unwrap());
+    let err = e.try_call(2).unwrap_err();
+    assert!(err.to_string()
+        .starts_with("MockFoo::foo: No matching expectation found"), "{}", err);
+    assert_eq!(1, err.rejected().len());
+    assert!(take_last_error().is_none());
+}
+
+#[test]
+fn try_call_ref() {
+    let mut e = __mock_Foo_Foo::bar::Expectations::default();
+    assert!(e.try_call().is_err());
+    e.expect().return_const("abc".to_owned());
+    assert_eq!("abc", e.try_call().unwrap());
+}
+
+#[test]
+fn try_call_mut() {
+    let mut e = __mock_Bar_Bar::bar::Expectations::default();
+    e.expect()
+        .with(predicate::eq(1))
+        .return_var(5u32);
+    *e.try_call_mut(1).unwrap() += 1;
+    assert_eq!(6, *e.try_call_mut(1).unwrap());
+    let err = e.try_call_mut(2).unwrap_err();
+    assert!(err.to_string()
+        .starts_with("MockBar::bar: No matching expectation found"), "{}", err);
+}
@@ -0,0 +1,82 @@
+// vim: tw=80
+//! Constructors that return `Self` wrapped in a `Result` or `Option`
+#![deny(warnings)]
+
+use mockall::*;
+use std::io;
+
+#[automock]
+pub trait File {
+    fn open(path: &str) -> io::Result<Self> where Self: Sized;
+    fn find(id: u32) -> Option<Self> where Self: Sized;
+    fn size(&self) -> u64;
+}
+
+pub struct Dir {}
+#[automock]
+#[allow(dead_code)]
+impl Dir {
+    pub fn open(_path: &str) -> io::Result<Self> { Ok(Dir{}) }
+    pub fn find(_id: u32) -> Option<Self> { None }
+    pub fn entries(&self) -> usize { 0 }
+}
+
+mock! {
+    pub Socket {
+        fn connect(port: u16) -> Result<Self, String>;
+    }
+}
+
+#[test]
+fn result_ok() {
+    let ctx = MockFile::open_context();
+    ctx.expect()
+        .withf(|path| path == "/etc/passwd")
+        .returning(|_| {
+            let mut mock = MockFile::new();
+            mock.expect_size().return_const(99u64);
+            Ok(mock)
+        });
+    let file = MockFile::open("/etc/passwd").unwrap();
+    assert_eq!(99, file.size());
+}
+
+#[test]
+fn result_err() {
+    let ctx = MockDir::open_context();
+    ctx.expect()
+        .returning(|_| Err(io::Error::new(io::ErrorKind::NotFound, "nope")));
+    let e = MockDir::open("/nonexistent").err().unwrap();
+    assert_eq!(io::ErrorKind::NotFound, e.kind());
+}
+
+#[test]
+fn option_some() {
+    let ctx = MockDir::find_context();
+    ctx.expect()
+        .returning(|_| {
+            let mut mock = MockDir::new();
+            mock.expect_entries().return_const(3usize);
+            Some(mock)
+        });
+    assert_eq!(3, MockDir::find(1).unwrap().entries());
+}
+
+#[test]
+fn option_none() {
+    let ctx = MockFile::find_context();
+    ctx.expect().returning(|_| None);
+    assert!(MockFile::find(1).is_none());
+}
+
+#[test]
+fn mock_macro() {
+    let ctx = MockSocket::connect_context();
+    ctx.expect()
+        .with(predicate::eq(80))
+        .returning(|_| Ok(MockSocket::new()));
+    ctx.expect()
+        .returning(|port| Err(format!("port {} is closed", port)));
+    assert!(MockSocket::connect(80).is_ok());
+    assert_eq!("port 81 is closed", MockSocket::connect(81).err().unwrap());
+}
@@ -0,0 +1,158 @@
+// vim: tw=80
+//! A Fixture is a reusable set of expectations, with lower priority than the
+//! test's own
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Store {
+    fn get(&self, key: u32) -> Option<u32>;
+    fn put(&self, key: u32, value: u32);
+    fn name(&self) -> &str;
+    fn slot(&mut self) -> &mut u32;
+    fn lookup<T: 'static>(&self, t: T) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+fn happy_path() -> Fixture<MockStore> {
+    Fixture::new()
+        .expect("get", |m: &mut MockStore| {
+            m.expect_get().return_const(Some(1));
+        })
+        .expect("put", |m: &mut MockStore| {
+            m.expect_put().return_const(());
+        })
+        .expect("name", |m: &mut MockStore| {
+            m.expect_name().return_const("fixture".to_owned());
+        })
+        .expect("slot", |m: &mut MockStore| {
+            m.expect_slot().return_var(1);
+        })
+        .expect("lookup", |m: &mut MockStore| {
+            m.expect_lookup::<u32>().return_const(1u32);
+        })
+}
+
+#[test]
+fn install() {
+    let mut mock = MockStore::new();
+    happy_path().install(&mut mock);
+    assert_eq!(Some(1), mock.get(5));
+    mock.put(5, 6);
+    assert_eq!("fixture", mock.name());
+    assert_eq!(1, *mock.slot());
+    assert_eq!(1, mock.lookup(2u32));
+}
+
+#[test]
+fn names() {
+    let f = happy_path();
+    assert_eq!(f.names().collect::<Vec<_>>(),
+               ["get", "put", "name", "slot", "lookup"]);
+    assert_eq!(format!("{:?}", f.without("put")),
+               r#"["get", "name", "slot", "lookup"]"#);
+}
+
+/// The test's own expectations take priority, even if set afterwards
+#[test]
+fn override_() {
+    let mut mock = MockStore::new();
+    happy_path().install(&mut mock);
+    mock.expect_get()
+        .with(predicate::eq(5))
+        .return_const(None);
+    mock.expect_name().return_const("test".to_owned());
+    mock.expect_slot().return_var(2);
+    mock.expect_lookup::<u32>()
+        .with(predicate::eq(5))
+        .return_const(2u32);
+    assert_eq!(None, mock.get(5));
+    assert_eq!(Some(1), mock.get(6));
+    assert_eq!("test", mock.name());
+    assert_eq!(2, *mock.slot());
+    assert_eq!(2, mock.lookup(5u32));
+    assert_eq!(1, mock.lookup(6u32));
+}
+
+/// A test's own expectation shadows the fixture's even once it's done
+#[test]
+fn never() {
+    let mut mock = MockStore::new();
+    happy_path().install(&mut mock);
+    mock.expect_put()
+        .with(predicate::eq(5), predicate::always())
+        .never();
+    mock.put(6, 0);
+    let msg = panic_msg(|| mock.put(5, 0));
+    assert!(msg.contains("should not have been called"), "{}", msg);
+}
+
+#[test]
+fn tighten_times() {
+    let mut mock = MockStore::new();
+    happy_path().install(&mut mock);
+    mock.expect_get()
+        .times(1)
+        .return_const(Some(1));
+    mock.get(0);
+    let msg = panic_msg(|| {mock.get(0);});
+    assert!(msg.contains("called more than 1 times"), "{}", msg);
+}
+
+#[test]
+fn without() {
+    let mut mock = MockStore::new();
+    happy_path().without("get").install(&mut mock);
+    let msg = panic_msg(|| {mock.get(0);});
+    assert!(msg.starts_with("MockStore::get: No matching expectation found"),
+        "{}", msg);
+}
+
+/// The test's own expectations may be ordered after the fixture's
+#[test]
+fn handles() {
+    let mut mock = MockStore::new();
+    let handles = Fixture::new()
+        .expect("open", |m: &mut MockStore| {
+            m.expect_put()
+                .times(1)
+                .return_const(());
+        })
+        .install(&mut mock);
+    assert_eq!(handles.get("open").len(), 1);
+    assert!(handles.get("close").is_empty());
+    mock.expect_get()
+        .after(&handles.get("open")[0])
+        .return_const(Some(1));
+    let msg = panic_msg(|| {mock.get(0);});
+    assert!(msg.contains("called before MockStore::put (set at "), "{}", msg);
+    mock.put(0, 0);
+    mock.get(0);
+}
+
+/// Expectations set after installation don't belong to the fixture, even if
+/// a step panicked
+#[test]
+fn step_panics() {
+    let mut mock = MockStore::new();
+    let f = Fixture::new()
+        .expect("get", |m: &mut MockStore| {
+            m.expect_get().return_const(Some(1));
+            panic!("oops");
+        });
+    panic::catch_unwind(panic::AssertUnwindSafe(|| {f.install(&mut mock);}))
+        .unwrap_err();
+    mock.expect_get()
+        .with(predicate::eq(5))
+        .never();
+    let msg = panic_msg(|| {mock.get(5);});
+    assert!(msg.contains("should not have been called"), "{}", msg);
+}
@@ -0,0 +1,100 @@
+// vim: tw=80
+//! Floating point arguments can be matched approximately
+#![deny(warnings)]
+
+use mockall::*;
+use mockall::predicate::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn f64(&self, x: f64) -> u32;
+    fn f32(&self, x: f32) -> u32;
+    fn tuple(&self, x: (u32, f64)) -> u32;
+    fn option(&self, x: Option<f64>) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn epsilon_eq() {
+    let mut mock = MockFoo::new();
+    mock.expect_f64()
+        .with(float::epsilon_eq(0.3, 1e-9))
+        .return_const(1u32);
+    mock.expect_f32()
+        .with(float::epsilon_eq(0.3f32, 1e-6))
+        .return_const(2u32);
+    assert_eq!(1, mock.f64(0.1 + 0.2));
+    assert_eq!(2, mock.f32(0.1 + 0.2));
+}
+
+#[test]
+fn ulps_eq() {
+    let mut mock = MockFoo::new();
+    mock.expect_f64()
+        .with(float::ulps_eq(0.3, 4))
+        .return_const(1u32);
+    mock.expect_f32()
+        .with(float::ulps_eq(-0.0f32, 0))
+        .return_const(2u32);
+    assert_eq!(1, mock.f64(0.1 + 0.2));
+    assert_eq!(2, mock.f32(0.0));
+}
+
+#[test]
+fn nan() {
+    let mut mock = MockFoo::new();
+    mock.expect_f64()
+        .with(float::ulps_eq(0.0, u64::max_value())
+              .or(float::epsilon_eq(0.0, std::f64::INFINITY)))
+        .return_const(1u32);
+    panic_msg(|| {mock.f64(std::f64::NAN);});
+}
+
+#[test]
+fn tuple() {
+    let mut mock = MockFoo::new();
+    mock.expect_tuple()
+        .with(map(|x: &(u32, f64)| x.1, float::epsilon_eq(0.3, 1e-9)))
+        .return_const(1u32);
+    assert_eq!(1, mock.tuple((5, 0.1 + 0.2)));
+}
+
+#[test]
+fn option() {
+    let mut mock = MockFoo::new();
+    mock.expect_option()
+        .with(some(float::epsilon_eq(0.3, 1e-9)))
+        .return_const(1u32);
+    assert_eq!(1, mock.option(Some(0.1 + 0.2)));
+    let msg = panic_msg(|| {mock.option(None);});
+    assert!(msg.ends_with(
+        "`Some(var ~= 0.3 (within 1e-9))` failed for var: None"), "{}", msg);
+}
+
+/// The panic message shows the actual value, the expected value, and the
+/// tolerance.
+#[test]
+fn rejection() {
+    let mut mock = MockFoo::new();
+    mock.expect_f64()
+        .with(float::epsilon_eq(0.3, 0.01))
+        .return_const(1u32);
+    mock.expect_tuple()
+        .with(map(|x: &(u32, f64)| x.1, float::ulps_eq(0.3, 2)))
+        .return_const(1u32);
+    let msg = panic_msg(|| {mock.f64(0.5);});
+    assert!(msg.ends_with(concat!("rejected by the `with` predicates: ",
+        "`var ~= 0.3 (within 0.01)` failed for var: 0.5, difference: 0.2")),
+        "{}", msg);
+    let msg = panic_msg(|| {mock.tuple((1, 0.5));});
+    assert!(msg.contains(
+        "`map(fn, var ~= 0.3 (within 2 ulps))` failed for var: 0.5, ulps: "),
+        "{}", msg);
+}
@@ -1,13 +1,42 @@
 // vim: tw=80
-//! A non-generic struct can have a generic constructor method
+//! A constructor that is itself generic.  A non-generic struct can have a
+//! generic constructor method.
+#![deny(warnings)]
 
 use mockall::*;
 
+pub trait Config {
+    fn port(&self) -> u16;
+}
+
+pub struct Tcp(u16);
+impl Config for Tcp {
+    fn port(&self) -> u16 { self.0 }
+}
+
+pub struct Unix;
+impl Config for Unix {
+    fn port(&self) -> u16 { 0 }
+}
+
 #[automock]
 trait Foo {
     fn build<T: 'static>(t: T) -> Self;
 }
 
+#[automock]
+pub trait Server {
+    fn new<C: Config + 'static>(cfg: C) -> Self where Self: Sized;
+    fn port(&self) -> u16;
+}
+
+pub struct Client {}
+#[automock]
+#[allow(dead_code)]
+impl Client {
+    pub fn new<C: Config + 'static>(_cfg: C) -> Self { Client{} }
+}
+
 #[test]
 fn returning_once() {
     let ctx = MockFoo::build_context();
@@ -16,3 +45,33 @@ fn returning_once() {
 
     let _mock: MockFoo = MockFoo::build::<i16>(-1);
 }
+
+#[test]
+fn trait_constructor() {
+    let ctx = MockServer::new_context();
+    ctx.expect::<Tcp>()
+        .withf(|cfg| cfg.port() == 80)
+        .returning(|cfg| {
+            let port = cfg.port();
+            let mut mock = MockServer::default();
+            mock.expect_port().return_const(port);
+            mock
+        });
+    let server = <MockServer as Server>::new(Tcp(80));
+    assert_eq!(80, server.port());
+}
+
+/// Each instantiation of the constructor has its own expectations
+#[test]
+fn distinct_instantiations() {
+    let ctx = MockClient::new_context();
+    ctx.expect::<Tcp>()
+        .times(1)
+        .returning(|_| MockClient::default());
+    ctx.expect::<Unix>()
+        .times(2)
+        .returning(|_| MockClient::default());
+    MockClient::new(Tcp(443));
+    MockClient::new(Unix);
+    MockClient::new(Unix);
+}
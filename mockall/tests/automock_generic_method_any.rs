@@ -0,0 +1,116 @@
+// vim: tw=80
+//! Generic methods can have expectations that apply to any instantiation of
+//! their generic parameters.
+#![deny(warnings)]
+
+use mockall::*;
+use std::{any::Any, panic};
+
+#[automock]
+pub trait Foo {
+    fn foo<T: 'static>(&self, t: T, x: &u32) -> u32;
+    fn bar<T: 'static>(&mut self, t: T);
+    fn baz<T: 'static>(&self, t: T) -> T;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn return_const() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo_any().return_const(42u32);
+    assert_eq!(42, mock.foo(1i16, &2));
+    assert_eq!(42, mock.foo("abc", &2));
+}
+
+/// The matcher can downcast the arguments
+#[test]
+fn withf() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo_any()
+        .withf(|args: &[&dyn Any]| {
+            args[0].downcast_ref::<i16>().map_or(true, |t| *t > 0) &&
+                args[1].downcast_ref::<u32>() == Some(&2)
+        }).return_const(1u32);
+    mock.expect_foo_any().return_const(2u32);
+    assert_eq!(1, mock.foo(5i16, &2));
+    assert_eq!(2, mock.foo(-5i16, &2));
+    assert_eq!(1, mock.foo(1.5f32, &2));
+    assert_eq!(2, mock.foo(1.5f32, &3));
+}
+
+#[test]
+fn returning() {
+    let mut mock = MockFoo::new();
+    mock.expect_baz_any()
+        .returning(|args: &[&dyn Any]| {
+            if let Some(s) = args[0].downcast_ref::<String>() {
+                Box::new(format!("{}!", s))
+            } else {
+                Box::new(*args[0].downcast_ref::<u8>().unwrap() * 2)
+            }
+        });
+    assert_eq!("hello!", mock.baz(String::from("hello")));
+    assert_eq!(6, mock.baz(3u8));
+}
+
+/// Instantiations with their own expectations don't use the `_any` ones
+#[test]
+fn fallback() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo::<u16>().return_const(1u32);
+    mock.expect_foo_any().return_const(2u32);
+    assert_eq!(1, mock.foo(0u16, &0));
+    assert_eq!(2, mock.foo(0u64, &0));
+}
+
+#[test]
+fn unit() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar_any().times(2).return_const(());
+    mock.bar(5u32);
+    mock.bar("abc");
+    mock.checkpoint();
+}
+
+#[test]
+fn times() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar_any().times(2).return_const(());
+    mock.bar(5u32);
+    let msg = panic_msg(|| mock.checkpoint());
+    assert!(msg.starts_with(
+        "MockFoo::bar: Expectation(<anything> for any type) (set at "),
+        "{}", msg);
+    assert!(msg.lines().next().unwrap().ends_with("called fewer than 2 times"),
+        "{}", msg);
+}
+
+#[test]
+fn wrong_return_type() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo_any().return_const(42u64);
+    let msg = panic_msg(|| {mock.foo(1i16, &2);});
+    assert!(msg.ends_with("returned a value that isn't a u32"), "{}", msg);
+}
+
+#[test]
+fn no_match() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo_any()
+        .withf(|args: &[&dyn Any]| args[0].is::<u32>())
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.foo(1i16, &2);});
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "MockFoo::foo: No matching expectation found.  \
+        1 expectation, none matched:");
+    assert!(lines[1].starts_with(
+        "#1 Expectation(<function> for any type) (set at "), "{}", msg);
+    assert!(lines[1].ends_with(": rejected by the `withf` function"),
+        "{}", msg);
+}
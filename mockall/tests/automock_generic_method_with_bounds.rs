@@ -20,3 +20,27 @@ fn withf() {
 
     mock.foo(X(42u32));
 }
+
+trait Message {}
+impl Message for u32 {}
+
+#[automock]
+trait Bus {
+    fn send<M: Message + Send + 'static>(&self, m: M);
+    fn post<M>(&self, m: M) where M: Message + Send + 'static;
+}
+
+/// expect_* has the same bounds as the method, so helpers generic over those
+/// bounds can call it.
+fn expect_both<M: Message + Send + 'static>(mock: &mut MockBus) {
+    mock.expect_send::<M>().times(1).return_const(());
+    mock.expect_post::<M>().times(1).return_const(());
+}
+
+#[test]
+fn generic_helper() {
+    let mut mock = MockBus::new();
+    expect_both::<u32>(&mut mock);
+    mock.send(1u32);
+    mock.post(2u32);
+}
@@ -0,0 +1,53 @@
+// vim: tw=80
+//! generic methods with default type parameters, including ones that are only
+//! used in the return type
+#![allow(invalid_type_param_default)]
+
+use mockall::*;
+
+#[automock]
+trait Foo {
+    fn convert<T: From<u32> + 'static = u64>(&self, x: u32) -> T;
+}
+
+#[test]
+fn turbofish() {
+    let mut mock = MockFoo::new();
+    mock.expect_convert::<u64>()
+        .returning(u64::from);
+    assert_eq!(4u64, mock.convert::<u64>(4));
+}
+
+#[test]
+fn inferred() {
+    let mut mock = MockFoo::new();
+    mock.expect_convert::<u64>()
+        .returning(u64::from);
+    let r: u64 = mock.convert(4);
+    assert_eq!(4, r);
+}
+
+/// Instantiations that differ only in their return type must have distinct
+/// expectations
+#[test]
+fn distinct_instantiations() {
+    let mut mock = MockFoo::new();
+    mock.expect_convert::<u64>()
+        .returning(|x| u64::from(x) * 2);
+    mock.expect_convert::<u32>()
+        .returning(|x| x + 1);
+    let r: u64 = mock.convert(4);
+    assert_eq!(8, r);
+    assert_eq!(5u32, mock.convert::<u32>(4));
+    assert_eq!(8u64, mock.convert::<u64>(4));
+}
+
+#[test]
+#[should_panic(expected =
+    "MockFoo::convert::<u32>: no expectation registered for this")]
+fn uninstantiated() {
+    let mut mock = MockFoo::new();
+    mock.expect_convert::<u64>()
+        .returning(u64::from);
+    let _: u32 = mock.convert(4);
+}
@@ -0,0 +1,69 @@
+// vim: tw=80
+//! A call of a generic method whose generic parameters have no expectations
+//! names them, and the ones that do have expectations.
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Codec {
+    fn decode<F: 'static, E: 'static>(&self, f: F, e: E) -> u32;
+    fn encode<T: 'static>(&self, t: T) -> u32;
+    fn name(&self) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn unregistered() {
+    let mut mock = MockCodec::new();
+    mock.expect_decode::<u32, i16>().return_const(0u32);
+    let msg = panic_msg(|| {mock.decode(1u32, 2u64);});
+    assert_eq!(msg, "MockCodec::decode::<u32, u64>: no expectation registered \
+        for this instantiation (registered: <u32, i16>)");
+}
+
+#[test]
+fn none_registered() {
+    let mock = MockCodec::new();
+    let msg = panic_msg(|| {mock.encode(1u8);});
+    assert_eq!(msg, "MockCodec::encode::<u8>: no expectation registered for \
+        this instantiation (registered: none)");
+}
+
+/// When the instantiation has expectations, the message lists them instead
+#[test]
+fn registered() {
+    let mut mock = MockCodec::new();
+    mock.expect_encode::<u8>()
+        .with(predicate::eq(2))
+        .return_const(0u32);
+    let msg = panic_msg(|| {mock.encode(1u8);});
+    assert!(msg.starts_with("MockCodec::encode: No matching expectation \
+        found.  1 expectation, none matched:"), "{}", msg);
+}
+
+/// A generic method's lookup doesn't affect the message for another method
+#[test]
+fn other_method() {
+    let mock = MockCodec::new();
+    let _ = panic_msg(|| {mock.encode(1u8);});
+    let msg = panic_msg(|| {mock.name();});
+    assert_eq!(msg, "MockCodec::name: No matching expectation found");
+}
+
+#[test]
+fn fallible() {
+    let mock = MockCodec::new();
+    let _guard = mockall::fallible();
+    assert_eq!(0, mock.encode(1i64));
+    let e = mockall::take_last_error().unwrap();
+    assert_eq!(e.to_string(), "MockCodec::encode::<i64>: no expectation \
+        registered for this instantiation (registered: none)");
+}
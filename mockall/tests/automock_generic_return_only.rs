@@ -0,0 +1,90 @@
+// vim: tw=80
+//! A method may be generic only in its return type.  Its expectations for
+//! different types are kept separately.
+#![deny(warnings)]
+
+use mockall::*;
+
+pub trait Decode: Sized {
+    fn decode(s: &str) -> Option<Self>;
+}
+
+#[automock]
+pub trait Store {
+    fn get<T: Decode + 'static>(&self, key: &str) -> T;
+    fn get_or<T>(&self, key: &str, default: u32) -> T where T: 'static;
+}
+
+pub trait Cache {
+    fn fetch<T: 'static>(&self, key: &str) -> T;
+}
+
+mock! {
+    pub Client {}
+    trait Cache {
+        fn fetch<T: 'static>(&self, key: &str) -> T;
+    }
+}
+
+impl Decode for u32 {
+    fn decode(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+}
+
+impl Decode for String {
+    fn decode(s: &str) -> Option<Self> {
+        Some(s.to_owned())
+    }
+}
+
+#[test]
+fn two_types() {
+    let mut mock = MockStore::new();
+    mock.expect_get::<u32>()
+        .with(predicate::eq("n"))
+        .return_const(42u32);
+    mock.expect_get::<String>()
+        .returning(|k| k.to_uppercase());
+    assert_eq!(42u32, mock.get::<u32>("n"));
+    assert_eq!("ABC", mock.get::<String>("abc"));
+}
+
+/// The type may be inferred from the call site
+#[test]
+fn inferred() {
+    let mut mock = MockStore::new();
+    mock.expect_get::<u32>().return_const(1u32);
+    mock.expect_get::<String>().return_const("one".to_owned());
+    let n: u32 = mock.get("k");
+    let s: String = mock.get("k");
+    assert_eq!((1, "one"), (n, s.as_str()));
+}
+
+#[test]
+fn where_clause() {
+    let mut mock = MockStore::new();
+    mock.expect_get_or::<u16>().returning(|_, d| d as u16);
+    mock.expect_get_or::<i64>().returning(|_, d| -i64::from(d));
+    assert_eq!(5u16, mock.get_or::<u16>("k", 5));
+    assert_eq!(-5i64, mock.get_or::<i64>("k", 5));
+}
+
+#[test]
+fn trait_impl() {
+    let mut mock = MockClient::new();
+    mock.expect_fetch::<u32>().return_const(1u32);
+    mock.expect_fetch::<String>().return_const("one".to_owned());
+    assert_eq!(1u32, mock.fetch::<u32>("k"));
+    assert_eq!("one", mock.fetch::<String>("k"));
+}
+
+/// Expectations for one type don't match calls for another
+#[test]
+#[should_panic(expected = "MockStore::get::<alloc::string::String>: \
+    no expectation registered for this instantiation (registered: <u32>)")]
+fn wrong_type() {
+    let mut mock = MockStore::new();
+    mock.expect_get::<u32>().return_const(1u32);
+    let _: String = mock.get("k");
+}
@@ -0,0 +1,106 @@
+// vim: tw=80
+//! A generic struct with generic methods whose signatures mention the struct's
+//! generic parameters, including through associated types.
+
+use mockall::*;
+
+pub trait Store {
+    type Value;
+}
+
+pub trait Key<T: Store> {
+    fn key(&self) -> u32;
+}
+
+impl<T: Store> Key<T> for u32 {
+    fn key(&self) -> u32 {
+        *self
+    }
+}
+
+pub struct StringStore;
+impl Store for StringStore {
+    type Value = String;
+}
+
+#[allow(unused)]
+pub struct Cache<T: Store>(T);
+
+#[automock]
+#[allow(unused)]
+impl<T: Store + 'static> Cache<T> where T::Value: Clone + Send + Sync {
+    pub fn get<K: Key<T> + 'static>(&self, k: K) -> Option<T::Value> {
+        None
+    }
+    pub fn put<K: Key<T> + 'static>(&self, k: K, v: T::Value) -> bool {
+        false
+    }
+    pub fn peek<K: 'static>(&self) -> &T::Value {
+        unimplemented!()
+    }
+    pub fn peek_mut<K: 'static>(&mut self) -> &mut T::Value {
+        unimplemented!()
+    }
+    pub fn build<K: Key<T> + 'static>(k: K) -> Option<T::Value> {
+        None
+    }
+}
+
+fn key<K: Key<StringStore>>(k: &K) -> u32 {
+    k.key()
+}
+
+#[test]
+fn returning() {
+    let mut mock = MockCache::<StringStore>::new();
+    mock.expect_get::<u32>()
+        .returning(|k| Some(format!("{}", key(&k))));
+    assert_eq!(Some("5".to_owned()), mock.get(5u32));
+}
+
+#[test]
+fn withf() {
+    let mut mock = MockCache::<StringStore>::new();
+    mock.expect_put::<u32>()
+        .withf(|k, v| key(k) == 1 && v == "x")
+        .return_const(true);
+    assert!(mock.put(1u32, "x".to_owned()));
+}
+
+#[test]
+fn return_reference() {
+    let mut mock = MockCache::<StringStore>::new();
+    mock.expect_peek::<u8>()
+        .return_const("x".to_owned());
+    assert_eq!("x", mock.peek::<u8>());
+}
+
+#[test]
+fn return_mutable_reference() {
+    let mut mock = MockCache::<StringStore>::new();
+    mock.expect_peek_mut::<u8>()
+        .return_var("x".to_owned());
+    mock.peek_mut::<u8>().push('y');
+    assert_eq!("xy", mock.peek_mut::<u8>());
+}
+
+/// Instantiations of a method with different generic parameters have
+/// independent expectations, even if they share the mock's generic parameters.
+#[test]
+fn distinct_instantiations() {
+    let mut mock = MockCache::<StringStore>::new();
+    mock.expect_peek::<u8>()
+        .return_const("u8".to_owned());
+    mock.expect_peek::<i8>()
+        .return_const("i8".to_owned());
+    assert_eq!("i8", mock.peek::<i8>());
+    assert_eq!("u8", mock.peek::<u8>());
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockCache::<StringStore>::build_context();
+    ctx.expect::<u32>()
+        .returning(|k| Some(format!("{}", key(&k))));
+    assert_eq!(Some("3".to_owned()), MockCache::<StringStore>::build(3u32));
+}
@@ -0,0 +1,86 @@
+// vim: tw=80
+//! Arguments of type `impl Trait` are passed to the expectation as boxed trait
+//! objects
+#![deny(warnings)]
+
+use mockall::*;
+use std::io::{self, Cursor, Read};
+
+#[automock]
+pub trait Loader {
+    fn load(&mut self, src: impl Read) -> io::Result<usize>;
+    fn sum(&self, it: impl Iterator<Item=u32>) -> u32;
+    fn stat(src: impl Read + 'static) -> u64;
+}
+
+pub struct Sink {}
+
+#[automock]
+impl Sink {
+    pub fn drain(&self, _src: impl Read + Send) -> usize {
+        unimplemented!()
+    }
+}
+
+/// The expectation may consume the argument
+#[test]
+fn returning() {
+    let mut mock = MockLoader::new();
+    mock.expect_load()
+        .returning(|mut src| {
+            let mut s = String::new();
+            src.read_to_string(&mut s)
+        });
+    let src = Cursor::new(b"abcdef".to_vec());
+    assert_eq!(6, mock.load(src).unwrap());
+}
+
+/// Or ignore it
+#[test]
+fn ignored() {
+    let mut mock = MockLoader::new();
+    mock.expect_sum()
+        .times(2)
+        .return_const(42u32);
+    assert_eq!(42, mock.sum(0..10));
+    assert_eq!(42, mock.sum(vec![1, 2].into_iter()));
+    mock.checkpoint();
+}
+
+/// The argument needn't be 'static
+#[test]
+fn borrowed() {
+    let mut mock = MockLoader::new();
+    mock.expect_sum()
+        .returning(|it| it.sum());
+    let v = vec![1u32, 2, 3];
+    assert_eq!(6, mock.sum(v.iter().cloned()));
+}
+
+#[test]
+fn withf() {
+    let mut mock = MockLoader::new();
+    mock.expect_sum()
+        .withf(|it| it.size_hint().0 > 5)
+        .return_const(1u32);
+    mock.expect_sum()
+        .return_const(2u32);
+    assert_eq!(1, mock.sum(0..10));
+    assert_eq!(2, mock.sum(0..1));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockLoader::stat_context();
+    ctx.expect()
+        .returning(|src| src.bytes().count() as u64);
+    assert_eq!(3, MockLoader::stat(Cursor::new(vec![0u8; 3])));
+}
+
+#[test]
+fn inherent() {
+    let mut mock = MockSink::new();
+    mock.expect_drain()
+        .returning(|src| src.bytes().count());
+    assert_eq!(2, mock.drain(&b"ab"[..]));
+}
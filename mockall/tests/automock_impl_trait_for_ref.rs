@@ -0,0 +1,87 @@
+// vim: tw=80
+//! A trait implemented for a reference to a struct
+//!
+//! The mock struct implements the trait for `&MockX` too, so it can be passed
+//! to code that's generic over the trait.  Methods that take `&mut self`
+//! receive a `&mut &MockX`, so they can be mocked as long as they don't return
+//! a mutable reference.
+
+use mockall::*;
+
+trait Visitor {
+    fn visit(&self, x: u32) -> u32;
+    fn reset(&mut self) -> bool;
+    fn name(&self) -> &str;
+}
+
+#[allow(unused)]
+struct Analyzer {}
+
+#[automock]
+impl Visitor for &Analyzer {
+    fn visit(&self, x: u32) -> u32 {
+        x
+    }
+    fn reset(&mut self) -> bool {
+        true
+    }
+    fn name(&self) -> &str {
+        "Analyzer"
+    }
+}
+
+#[automock]
+impl Visitor for &'_ Analyzer2 {
+    fn visit(&self, x: u32) -> u32 {
+        x
+    }
+    fn reset(&mut self) -> bool {
+        true
+    }
+    fn name(&self) -> &str {
+        "Analyzer2"
+    }
+}
+
+#[allow(unused)]
+struct Analyzer2 {}
+
+fn walk<V: Visitor>(mut v: V) -> u32 {
+    v.reset();
+    v.visit(1) + v.visit(2)
+}
+
+#[test]
+fn generic_over_trait() {
+    let mut mock = MockAnalyzer::new();
+    mock.expect_reset()
+        .return_const(true);
+    mock.expect_visit()
+        .returning(|x| x * 10);
+    assert_eq!(30, walk(&mock));
+}
+
+#[test]
+fn mut_self() {
+    let mut mock = MockAnalyzer::new();
+    mock.expect_reset()
+        .return_const(false);
+    let mut r = &mock;
+    assert!(!r.reset());
+}
+
+#[test]
+fn return_reference() {
+    let mut mock = MockAnalyzer::new();
+    mock.expect_name()
+        .return_const("mock".to_owned());
+    assert_eq!("mock", (&mock).name());
+}
+
+#[test]
+fn elided_lifetime() {
+    let mut mock = MockAnalyzer2::new();
+    mock.expect_visit()
+        .returning(|x| x + 1);
+    assert_eq!(5, (&mock).visit(4));
+}
@@ -0,0 +1,39 @@
+// vim: tw=80
+//! Impl blocks that contain associated constants but no methods
+#![deny(warnings)]
+
+use mockall::*;
+
+pub struct Limits {}
+
+#[automock]
+impl Limits {
+    pub const MAX: u32 = 42;
+}
+
+pub trait Named {
+    const NAME: &'static str;
+}
+
+pub struct Widget {}
+
+#[automock]
+impl Named for Widget {
+    const NAME: &'static str = "widget";
+}
+
+#[test]
+fn inherent_const() {
+    let mut mock = MockLimits::new();
+    mock.checkpoint();
+    assert_eq!(42, MockLimits::MAX);
+}
+
+#[test]
+fn trait_const() {
+    fn name<N: Named>(_n: &N) -> &'static str {
+        N::NAME
+    }
+    let mock = MockWidget::new();
+    assert_eq!("widget", name(&mock));
+}
@@ -0,0 +1,62 @@
+// vim: tw=80
+//! Mocks can be declared inside of a function body
+#![deny(warnings)]
+
+use mockall::*;
+
+/// Types used in the mocked signatures must be visible from the enclosing
+/// module, not just from the function.
+#[derive(Debug, PartialEq)]
+pub struct Outer(u32);
+
+#[test]
+fn automock_trait() {
+    #[automock]
+    trait Foo {
+        fn foo(&self, x: u32) -> Outer;
+        fn bar(x: u32) -> u32;
+        fn baz<T: 'static>(&self, t: T) -> u32;
+    }
+
+    let mut mock = MockFoo::new();
+    mock.expect_foo().returning(|x| Outer(x + 1));
+    mock.expect_baz::<i16>().return_const(6u32);
+    assert_eq!(Outer(5), mock.foo(4));
+    assert_eq!(6, mock.baz(0i16));
+
+    let ctx = MockFoo::bar_context();
+    ctx.expect().returning(|x| x * 2);
+    assert_eq!(8, MockFoo::bar(4));
+}
+
+#[test]
+fn automock_struct() {
+    #[allow(dead_code)]
+    struct Bar {}
+    #[automock]
+    #[allow(dead_code)]
+    impl Bar {
+        pub fn bar(&self) -> u32 { 0 }
+    }
+
+    let mut mock = MockBar::new();
+    mock.expect_bar().return_const(3u32);
+    assert_eq!(3, mock.bar());
+}
+
+#[test]
+fn mock_macro() {
+    trait Baz {
+        fn baz(&self) -> u32;
+    }
+    mock!{
+        Baz {}
+        trait Baz {
+            fn baz(&self) -> u32;
+        }
+    }
+
+    let mut mock = MockBaz::new();
+    mock.expect_baz().return_const(7u32);
+    assert_eq!(7, mock.baz());
+}
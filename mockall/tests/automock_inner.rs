@@ -0,0 +1,126 @@
+// vim: tw=80
+//! A mock with an inner object forwards calls that match no expectation to it
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+pub struct RealStore {
+    size: usize,
+}
+
+#[automock(inner = RealStore)]
+pub trait Store {
+    fn get(&self, key: u32) -> Option<u32>;
+    fn name(&self, prefix: &str) -> String;
+    fn grow(&mut self, by: usize) -> usize;
+    fn convert<T: From<u32> + 'static>(&self, x: u32) -> T;
+    fn version() -> u32;
+}
+
+impl Store for RealStore {
+    fn get(&self, key: u32) -> Option<u32> {
+        Some(key * 10)
+    }
+    fn name(&self, prefix: &str) -> String {
+        format!("{}real", prefix)
+    }
+    fn grow(&mut self, by: usize) -> usize {
+        self.size += by;
+        self.size
+    }
+    fn convert<T: From<u32> + 'static>(&self, x: u32) -> T {
+        T::from(x + 1)
+    }
+    fn version() -> u32 {
+        1
+    }
+}
+
+pub struct Real {}
+
+#[automock(inner = Real)]
+impl Real {
+    pub fn double(&self, x: u32) -> u32 {
+        2 * x
+    }
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn overridden() {
+    let mut mock = MockStore::new_with_inner(RealStore{size: 0});
+    mock.expect_get()
+        .with(predicate::eq(1))
+        .return_const(Some(5));
+    assert_eq!(Some(5), mock.get(1));
+    mock.checkpoint();
+}
+
+#[test]
+fn passthrough() {
+    let mut mock = MockStore::new_with_inner(RealStore{size: 0});
+    assert_eq!(Some(20), mock.get(2));
+    assert_eq!("a-real", mock.name("a-"));
+    assert_eq!(3, mock.grow(3));
+    assert_eq!(5, mock.grow(2));
+    assert_eq!(8u64, mock.convert::<u64>(7));
+}
+
+/// Calls that don't match any expectation go to the inner object
+#[test]
+fn unmatched() {
+    let mut mock = MockStore::new_with_inner(RealStore{size: 0});
+    mock.expect_get()
+        .with(predicate::eq(1))
+        .times(1)
+        .return_const(None);
+    assert_eq!(Some(20), mock.get(2));
+    assert_eq!(None, mock.get(1));
+    // A saturated expectation no longer matches, once there are others
+    mock.expect_get()
+        .with(predicate::eq(3))
+        .times(1)
+        .return_const(None);
+    assert_eq!(Some(10), mock.get(1));
+    assert_eq!(None, mock.get(3));
+    mock.checkpoint();
+}
+
+#[test]
+fn generic_method() {
+    let mut mock = MockStore::new_with_inner(RealStore{size: 0});
+    mock.expect_convert::<u64>().return_const(0u64);
+    assert_eq!(0u64, mock.convert::<u64>(7));
+    assert_eq!(8u32, mock.convert::<u32>(7));
+}
+
+#[test]
+fn inherent() {
+    let mut mock = MockReal::new_with_inner(Real{});
+    assert_eq!(6, mock.double(3));
+    mock.expect_double().return_const(0u32);
+    assert_eq!(0, mock.double(3));
+}
+
+#[test]
+fn no_inner() {
+    let mock = MockStore::new();
+    let msg = panic_msg(|| {mock.get(1);});
+    assert_eq!(msg, "MockStore::get: No matching expectation found");
+}
+
+/// Static methods have no object to forward to, so they use the inner type's
+#[test]
+fn static_method() {
+    assert_eq!(1, MockStore::version());
+    let ctx = MockStore::version_context();
+    ctx.expect().return_const(2u32);
+    assert_eq!(2, MockStore::version());
+}
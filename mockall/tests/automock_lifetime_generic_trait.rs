@@ -0,0 +1,55 @@
+// vim: tw=80
+//! A trait with a lifetime parameter, like a parser over borrowed input
+#![deny(warnings)]
+
+use mockall::*;
+
+#[derive(Debug, PartialEq)]
+pub struct Ast<'a> {
+    pub token: &'a str
+}
+
+#[automock]
+pub trait Parser<'a> {
+    fn count(&self, input: &'a str) -> usize;
+    fn matches(&self, input: &'a str, pattern: &'a str) -> bool;
+    fn last(&self) -> Ast<'a>;
+    fn reset(&mut self, x: u32);
+}
+
+fn count_with<'a, P: Parser<'a>>(parser: &P, input: &'a str) -> usize {
+    parser.count(input)
+}
+
+/// Arguments that use the trait's lifetime can be matched and used by returning
+/// closures, whatever the caller's lifetime
+#[test]
+fn borrowed_args() {
+    let mut mock = MockParser::new();
+    mock.expect_count()
+        .withf(|input| input.starts_with('a'))
+        .returning(|input| input.len());
+    mock.expect_matches()
+        .with(predicate::eq("foo"), predicate::always())
+        .return_const(true);
+    let input = String::from("abc");
+    assert_eq!(3, count_with(&mock, &input));
+    let pattern = String::from("f.*");
+    assert!(mock.matches("foo", &pattern));
+}
+
+/// Return values may use the trait's lifetime, if the arguments don't
+#[test]
+fn borrowed_return() {
+    let mut mock = MockParser::new();
+    mock.expect_last().returning(|| Ast{token: "foo"});
+    assert_eq!(Ast{token: "foo"}, mock.last());
+}
+
+/// Methods that don't use the trait's lifetime at all
+#[test]
+fn unused() {
+    let mut mock = MockParser::new();
+    mock.expect_reset().with(predicate::eq(4)).return_const(());
+    mock.reset(4);
+}
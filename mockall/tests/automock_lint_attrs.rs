@@ -0,0 +1,44 @@
+// vim: tw=80
+//! A method's lint attributes apply to everything generated from it, even
+//! when wrapped in `cfg_attr`
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Foo {
+    #[allow(non_snake_case)]
+    fn foo(&self, Foo_Arg: u32) -> u32;
+    #[cfg_attr(all(), allow(non_snake_case))]
+    fn bar(&self, Bar_Arg: u32) -> u32;
+    #[cfg_attr(all(), allow(non_snake_case, clippy::too_many_arguments))]
+    fn baz(&self, a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8, Baz_Arg: u8)
+        -> u32;
+}
+
+pub struct A{}
+#[automock]
+impl A {
+    #[cfg_attr(all(), must_use, inline, allow(non_snake_case))]
+    pub fn foo(&self, Foo_Arg: u32) -> u32 {Foo_Arg}
+}
+
+#[test]
+fn allow() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().returning(|x| x + 1);
+    assert_eq!(5, mock.foo(4));
+}
+
+#[test]
+fn cfg_attr() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar_with(predicate::eq(1)).return_const(2u32);
+    mock.expect_baz().return_const(3u32);
+    assert_eq!(2, mock.bar(1));
+    assert_eq!(3, mock.baz(0, 0, 0, 0, 0, 0, 0, 0));
+
+    let mut mock = MockA::new();
+    mock.expect_foo().return_const(4u32);
+    assert_eq!(4, mock.foo(0));
+}
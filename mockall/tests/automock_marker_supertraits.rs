@@ -0,0 +1,90 @@
+// vim: tw=80
+//! Traits may be bounded by `Sized`, `Send`, `Sync`, or `'static`, either as
+//! supertraits or with a `where Self:` clause
+#![deny(warnings)]
+
+use mockall::*;
+use std::rc::Rc;
+
+#[automock]
+pub trait Codec: Sized {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(data: &[u8]) -> Self;
+}
+
+#[automock]
+pub trait Shared: Send + Sync + 'static {
+    fn get(&self) -> u32;
+}
+
+#[automock]
+pub trait Generic<T: 'static>: Sized + Send + Sync {
+    fn get(&self) -> T;
+}
+
+#[automock]
+pub trait Where<T: 'static>: Sized where Self: Send + Sync {
+    fn get(&self) -> T;
+    fn make() -> T;
+}
+
+mock! {
+    pub Both<T: 'static> {}
+    trait Where<T: 'static>: Sized where Self: Send + Sync {
+        fn get(&self) -> T;
+        fn make() -> T;
+    }
+}
+
+fn is_send_sync<T: Send + Sync>() {}
+
+fn shared_get<S: Shared>(s: S) -> u32 {
+    std::thread::spawn(move || s.get()).join().unwrap()
+}
+
+#[test]
+fn sized() {
+    let ctx = MockCodec::decode_context();
+    ctx.expect().returning(|data| {
+        let mut mock = MockCodec::new();
+        mock.expect_encode().return_const(data.to_vec());
+        mock
+    });
+    let mock = MockCodec::decode(&[1, 2, 3]);
+    assert_eq!(vec![1, 2, 3], mock.encode());
+}
+
+#[test]
+fn send_sync_static() {
+    let mut mock = MockShared::new();
+    mock.expect_get().return_const(42u32);
+    assert_eq!(42, shared_get(mock));
+}
+
+/// The mock is Send and Sync even when its generic parameters aren't
+#[test]
+fn generic() {
+    is_send_sync::<MockGeneric<Rc<u32>>>();
+    let mut mock = MockGeneric::<Rc<u32>>::new();
+    mock.expect_get().returning(|| Rc::new(5));
+    assert_eq!(5, *mock.get());
+}
+
+#[test]
+fn where_clause() {
+    is_send_sync::<MockWhere<Rc<u32>>>();
+    let mut mock = MockWhere::<u32>::new();
+    mock.expect_get().return_const(6u32);
+    assert_eq!(6, mock.get());
+    let ctx = MockWhere::<u32>::make_context();
+    ctx.expect().return_const(7u32);
+    assert_eq!(7, MockWhere::<u32>::make());
+}
+
+#[test]
+fn mock_macro() {
+    is_send_sync::<MockBoth<Rc<u32>>>();
+    let mut mock = MockBoth::<u32>::new();
+    mock.expect_get().return_const(8u32);
+    assert_eq!(8, mock.get());
+}
@@ -0,0 +1,74 @@
+// vim: tw=80
+//! Arguments can be matched with patterns
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[derive(Debug)]
+pub enum Event {
+    KeyPress { code: u32, shift: bool },
+    Click(i32, i32)
+}
+
+#[automock]
+pub trait Handler {
+    fn handle(&self, event: Event, repeat: u32) -> u32;
+    fn click(&self, event: &Event) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn one_per_argument() {
+    let mut mock = MockHandler::new();
+    mock.expect_handle()
+        .with(matching!(Event::KeyPress { code: 13, .. }),
+              matching!(x if *x > 3))
+        .return_const(1u32);
+    mock.expect_handle()
+        .with(matching!(Event::Click(_, y) if *y < 0), matching!(_))
+        .return_const(2u32);
+    mock.expect_handle()
+        .with(matching!(Event::KeyPress { .. } | Event::Click(..)),
+              matching!(0..=3))
+        .return_const(3u32);
+    assert_eq!(1, mock.handle(Event::KeyPress { code: 13, shift: true }, 4));
+    assert_eq!(2, mock.handle(Event::Click(5, -1), 9));
+    assert_eq!(3, mock.handle(Event::KeyPress { code: 13, shift: true }, 3));
+    assert_eq!(3, mock.handle(Event::Click(5, 1), 0));
+}
+
+#[test]
+fn reference_argument() {
+    let mut mock = MockHandler::new();
+    mock.expect_click()
+        .with(matching!(Event::Click(x, _) if *x == 7))
+        .return_const(1u32);
+    assert_eq!(1, mock.click(&Event::Click(7, 0)));
+}
+
+/// The pattern is shown in panic messages
+#[test]
+fn no_match() {
+    let mut mock = MockHandler::new();
+    mock.expect_handle()
+        .with(matching!(Event::Click(..)), matching!(x if *x > 3))
+        .return_const(1u32);
+    let msg = panic_msg(|| {
+        mock.handle(Event::KeyPress { code: 1, shift: false }, 4);
+    });
+    // stringify!'s spacing varies between compiler versions
+    let msg = msg.replace(' ', "");
+    assert!(msg.contains(
+        "Expectation(matching!(Event::Click(..)),matching!(xif*x>3))"),
+        "{}", msg);
+    assert!(msg.ends_with(
+        "rejectedbythe`with`predicates:`matching!(Event::Click(..))`failed"),
+        "{}", msg);
+}
@@ -0,0 +1,54 @@
+// vim: tw=80
+//! Mocked module and extern functions may take borrowed arguments, just like
+//! methods.  They're matched by the data they point to.
+#![deny(warnings)]
+
+use mockall::*;
+use std::path::Path;
+
+#[automock]
+pub mod fs {
+    use std::path::Path;
+    pub fn read(_path: &Path) -> Vec<u8> {
+        unimplemented!()
+    }
+    pub fn write(_name: &str, _data: &[u8]) -> usize {
+        unimplemented!()
+    }
+}
+
+#[automock(mod mock_ffi;)]
+extern "Rust" {
+    #[allow(unused)]
+    fn checksum(data: &[u8]) -> u32;
+}
+
+#[test]
+fn path() {
+    let ctx = mock_fs::read_context();
+    ctx.expect()
+        .withf(|p| p == Path::new("/etc/hosts"))
+        .returning(|_| vec![1, 2]);
+    let path = String::from("/etc/hosts");
+    assert_eq!(vec![1, 2], mock_fs::read(Path::new(&path)));
+}
+
+#[test]
+fn str_and_slice() {
+    let ctx = mock_fs::write_context();
+    ctx.expect()
+        .with(predicate::eq("log"), predicate::function(|d: &[u8]| d.len() < 4))
+        .returning(|n, d| n.len() + d.len());
+    let data = vec![0u8, 1, 2];
+    assert_eq!(6, mock_fs::write(&String::from("log"), &data));
+}
+
+#[test]
+fn extern_slice() {
+    let ctx = mock_ffi::checksum_context();
+    ctx.expect()
+        .withf(|d| d.starts_with(b"ab"))
+        .returning(|d| d.len() as u32);
+    let data = b"abc".to_vec();
+    assert_eq!(3, unsafe{ mock_ffi::checksum(&data) });
+}
@@ -0,0 +1,62 @@
+// vim: tw=80
+//! A mocked module's generic functions keep separate expectations for each
+//! instantiation
+#![deny(warnings)]
+
+use mockall::*;
+use std::{net::IpAddr, panic, str::FromStr};
+
+#[automock]
+pub mod m {
+    use std::str::FromStr;
+    pub fn parse<T: FromStr + 'static>(s: &str) -> Option<T> {
+        s.parse().ok()
+    }
+    // Each test needs its own function, because expectations are global
+    pub fn parse2<T: FromStr + 'static>(s: &str) -> Option<T> {
+        s.parse().ok()
+    }
+    pub fn parse3<T: FromStr + 'static>(s: &str) -> Option<T> {
+        s.parse().ok()
+    }
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn two_types() {
+    let ctx = mock_m::parse_context();
+    ctx.expect::<u32>().returning(|s| Some(s.len() as u32));
+    ctx.expect::<IpAddr>().returning(|s| IpAddr::from_str(s).ok());
+    assert_eq!(Some(3u32), mock_m::parse::<u32>("abc"));
+    assert_eq!(Some(IpAddr::from([127, 0, 0, 1])),
+               mock_m::parse::<IpAddr>("127.0.0.1"));
+    ctx.checkpoint();
+}
+
+/// Expectations for one type don't apply to another
+#[test]
+fn other_type() {
+    let ctx = mock_m::parse2_context();
+    ctx.expect::<u32>().returning(|_| Some(0));
+    let msg = panic_msg(|| {mock_m::parse2::<IpAddr>("127.0.0.1");});
+    assert!(msg.contains("no expectation registered for this instantiation \
+        (registered: <u32>)"), "{}", msg);
+}
+
+/// Checkpointing verifies every instantiation
+#[test]
+fn checkpoint() {
+    let ctx = mock_m::parse3_context();
+    ctx.expect::<u32>().times(1).returning(|_| Some(0));
+    ctx.expect::<IpAddr>().times(1).returning(|_| None);
+    mock_m::parse3::<IpAddr>("");
+    let msg = panic_msg(|| ctx.checkpoint());
+    assert!(msg.starts_with("mock_m::parse3::<u32>: Expectation(<anything>) \
+        called fewer than 1 times"), "{}", msg);
+}
@@ -0,0 +1,27 @@
+// vim: tw=80
+//! Mocking an out-of-line module, by specifying its source file
+
+// mocking modules requires the proc_macro_hygiene feature in the _consumer_
+// code
+#![cfg_attr(feature = "nightly", feature(proc_macro_hygiene))]
+
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "nightly")] {
+        use mockall::*;
+
+        #[automock(path = "tests/automock_module_path/foo.rs")]
+        #[path = "automock_module_path/foo.rs"]
+        #[allow(unused)]
+        mod foo;
+
+        #[test]
+        fn returning() {
+            let ctx = mock_foo::bar_context();
+            ctx.expect()
+                .returning(|x| i64::from(x) + 1);
+            assert_eq!(5, mock_foo::bar(4));
+        }
+    }
+}
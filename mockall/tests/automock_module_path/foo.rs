@@ -0,0 +1,4 @@
+// vim: tw=80
+//! Contents of an out-of-line module, for automock_module_path.rs
+
+pub fn bar(_x: u32) -> i64 {unimplemented!()}
@@ -0,0 +1,65 @@
+// vim: tw=80
+//! A mocked function's return function may call other mocked functions, or
+//! even itself, without deadlocking.
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+#[allow(dead_code)]
+pub mod foo {
+    pub fn a(_x: u32) -> u32 { unimplemented!() }
+    pub fn b(_x: u32) -> u32 { unimplemented!() }
+    pub fn c(_x: u32) -> u32 { unimplemented!() }
+    pub fn d(_x: u32) -> u32 { unimplemented!() }
+}
+
+#[automock]
+pub trait Builder {
+    fn build(x: u32) -> u32;
+    fn finish(x: u32) -> u32;
+}
+
+#[test]
+fn calls_another() {
+    let a_ctx = mock_foo::a_context();
+    a_ctx.expect().returning(|x| mock_foo::b(x) + 1);
+    let b_ctx = mock_foo::b_context();
+    b_ctx.expect().returning(|x| x * 2);
+    assert_eq!(7, mock_foo::a(3));
+}
+
+#[test]
+fn calls_itself() {
+    let ctx = mock_foo::c_context();
+    ctx.expect()
+        .with(predicate::eq(0))
+        .times(2)
+        .return_const(0u32);
+    ctx.expect()
+        .with(predicate::eq(1))
+        .times(2)
+        .returning(|x| mock_foo::c(x - 1) + 10);
+    assert_eq!(10, mock_foo::c(1));
+    assert_eq!(10, mock_foo::c(1));
+}
+
+/// Calling the very same expectation from its own return function can't work,
+/// because the return function is already running.  But it panics rather than
+/// deadlocking.
+#[test]
+#[should_panic(expected = "d: Expectation(<anything>) called from its own return function")]
+fn calls_same_expectation() {
+    let ctx = mock_foo::d_context();
+    ctx.expect().returning(|x| if x > 0 { mock_foo::d(x - 1) } else { 0 });
+    mock_foo::d(1);
+}
+
+#[test]
+fn static_methods() {
+    let build_ctx = MockBuilder::build_context();
+    build_ctx.expect().returning(|x| MockBuilder::finish(x) + 1);
+    let finish_ctx = MockBuilder::finish_context();
+    finish_ctx.expect().returning(|x| x * 3);
+    assert_eq!(7, MockBuilder::build(2));
+}
@@ -0,0 +1,56 @@
+// vim: tw=80
+//! `use` items inside of a mocked module should be reproduced in the mock
+//! module, including re-exports of the module's own functions.
+
+// mocking modules requires the proc_macro_hygiene feature in the _consumer_
+// code
+#![cfg_attr(feature = "nightly", feature(proc_macro_hygiene))]
+
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "nightly")] {
+        mod helpers {
+            pub fn double(x: u32) -> u32 { 2 * x }
+        }
+
+        mod m {
+            use mockall::*;
+
+            #[automock]
+            #[allow(unused)]
+            mod foo {
+                // Re-export of an item outside of the module
+                pub use super::super::helpers::double;
+                // Re-exports of the module's own functions
+                pub use self::parse_impl as parse;
+                pub use self::{bar as baz, parse_impl as parse2};
+                pub fn parse_impl(_x: &str) -> u32 {unimplemented!()}
+                pub fn bar(_x: u32) -> u32 {unimplemented!()}
+            }
+
+            #[test]
+            fn external_reexport() {
+                assert_eq!(4, mock_foo::double(2));
+            }
+
+            #[test]
+            fn renamed_reexport() {
+                let ctx = mock_foo::parse_context();
+                ctx.expect()
+                    .returning(|x| x.len() as u32);
+                assert_eq!(5, mock_foo::parse("hello"));
+                assert_eq!(5, mock_foo::parse_impl("hello"));
+            }
+
+            #[test]
+            fn grouped_reexport() {
+                let ctx = mock_foo::baz_context();
+                ctx.expect()
+                    .returning(|x| x + 1);
+                assert_eq!(5, mock_foo::baz(4));
+                assert_eq!(5, mock_foo::bar(4));
+            }
+        }
+    }
+}
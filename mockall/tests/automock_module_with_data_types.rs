@@ -0,0 +1,53 @@
+// vim: tw=80
+//! Mocking a module that defines data types alongside its functions
+
+// mocking modules requires the proc_macro_hygiene feature in the _consumer_
+// code
+#![cfg_attr(feature = "nightly", feature(proc_macro_hygiene))]
+
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "nightly")] {
+        mod m {
+            use mockall::*;
+
+            #[automock]
+            #[allow(unused)]
+            pub mod ffi {
+                #[derive(Clone, Copy, Debug, PartialEq)]
+                #[repr(C)]
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32
+                }
+
+                #[derive(Clone, Copy, Debug, PartialEq)]
+                pub enum Quadrant {
+                    First,
+                    Second,
+                    Third,
+                    Fourth
+                }
+
+                pub fn quadrant(_p: Point) -> Quadrant {unimplemented!()}
+                pub fn origin(_q: Quadrant) -> Point {unimplemented!()}
+            }
+
+            #[test]
+            fn data_types() {
+                let qctx = mock_ffi::quadrant_context();
+                qctx.expect()
+                    .with(predicate::eq(mock_ffi::Point{x: 1, y: -1}))
+                    .return_const(mock_ffi::Quadrant::Fourth);
+                let octx = mock_ffi::origin_context();
+                octx.expect()
+                    .return_const(mock_ffi::Point{x: 0, y: 0});
+                assert_eq!(mock_ffi::Quadrant::Fourth,
+                    mock_ffi::quadrant(mock_ffi::Point{x: 1, y: -1}));
+                assert_eq!(mock_ffi::Point{x: 0, y: 0},
+                    mock_ffi::origin(mock_ffi::Quadrant::First));
+            }
+        }
+    }
+}
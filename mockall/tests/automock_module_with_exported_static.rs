@@ -0,0 +1,51 @@
+// vim: tw=80
+//! Mocking a module that exports a static symbol.  The mock module's copy of
+//! the static must not export the same symbol.
+
+// mocking modules requires the proc_macro_hygiene feature in the _consumer_
+// code
+#![cfg_attr(feature = "nightly", feature(proc_macro_hygiene))]
+
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "nightly")] {
+        mod m {
+            use mockall::*;
+            use std::sync::atomic::{AtomicBool, Ordering};
+
+            #[automock]
+            pub mod foo {
+                use std::sync::atomic::AtomicBool;
+
+                #[no_mangle]
+                pub static MOCKALL_TEST_FLAG: AtomicBool =
+                    AtomicBool::new(false);
+
+                #[export_name = "mockall_test_exported"]
+                pub static EXPORTED: u32 = 42;
+
+                pub fn bar(x: u32) -> u32 {
+                    x
+                }
+            }
+
+            #[test]
+            fn statics() {
+                mock_foo::MOCKALL_TEST_FLAG.store(true, Ordering::Relaxed);
+                assert!(!foo::MOCKALL_TEST_FLAG.load(Ordering::Relaxed));
+                assert_eq!(foo::EXPORTED, mock_foo::EXPORTED);
+                let _: &AtomicBool = &mock_foo::MOCKALL_TEST_FLAG;
+            }
+
+            #[test]
+            fn function() {
+                let ctx = mock_foo::bar_context();
+                ctx.expect()
+                    .returning(|x| x + 1);
+                assert_eq!(5, mock_foo::bar(4));
+                assert_eq!(4, foo::bar(4));
+            }
+        }
+    }
+}
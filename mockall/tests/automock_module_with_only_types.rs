@@ -0,0 +1,31 @@
+// vim: tw=80
+//! Mocking a module that contains no functions
+
+// mocking modules requires the proc_macro_hygiene feature in the _consumer_
+// code
+#![cfg_attr(feature = "nightly", feature(proc_macro_hygiene))]
+#![deny(warnings)]
+
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "nightly")] {
+        mod m {
+            use mockall::*;
+
+            #[automock]
+            #[allow(unused)]
+            pub mod foo {
+                pub type Id = u32;
+                pub const ZERO: Id = 0;
+            }
+
+            #[test]
+            fn types_and_consts() {
+                let id: mock_foo::Id = mock_foo::ZERO;
+                assert_eq!(0, id);
+                mock_foo::checkpoint();
+            }
+        }
+    }
+}
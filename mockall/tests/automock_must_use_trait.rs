@@ -0,0 +1,25 @@
+// vim: tw=80
+//! must_use on a trait or its methods doesn't make the mock warn, nor its
+//! expect_* methods
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+#[must_use]
+pub trait Foo {
+    #[must_use = "foo is pure"]
+    fn foo(&self, x: u32) -> u32;
+    #[must_use]
+    fn bar() -> u32;
+}
+
+#[test]
+fn returning() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().returning(|x| x + 1);
+    assert_eq!(5, mock.foo(4));
+    let ctx = MockFoo::bar_context();
+    ctx.expect().return_const(6u32);
+    assert_eq!(6, MockFoo::bar());
+}
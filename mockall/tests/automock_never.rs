@@ -0,0 +1,89 @@
+// vim: tw=80
+//! Methods and functions that return `!` may be mocked.  Their expectations
+//! must panic, and still count toward their call counts.
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn abort(&self, x: u32) -> !;
+    fn exit(code: i32) -> !;
+}
+
+#[automock]
+pub mod m {
+    pub fn die(_x: u32) -> ! {
+        unimplemented!()
+    }
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    let payload = panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err();
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => payload.downcast::<&str>().unwrap().to_string()
+    }
+}
+
+#[test]
+fn panics_with() {
+    let mut mock = MockFoo::new();
+    mock.expect_abort()
+        .with(predicate::eq(1))
+        .times(1)
+        .panics_with("boom");
+    assert_eq!("boom", panic_msg(|| mock.abort(1)));
+    mock.checkpoint();
+}
+
+/// The panic doesn't stop the expectation from being used again
+#[test]
+fn times() {
+    let mut mock = MockFoo::new();
+    mock.expect_abort()
+        .times(2)
+        .panics_with(String::from("boom"));
+    assert_eq!("boom", panic_msg(|| mock.abort(1)));
+    assert_eq!("boom", panic_msg(|| mock.abort(2)));
+    let msg = panic_msg(|| mock.abort(3));
+    assert!(msg.contains("called more than 2 times"), "{}", msg);
+}
+
+#[test]
+#[should_panic(expected = "MockFoo::abort: Expectation(<anything>) called fewer than 1 times")]
+fn too_few() {
+    let mut mock = MockFoo::new();
+    mock.expect_abort().times(1).panics_with("boom");
+}
+
+/// An expectation without `panics_with` returns, which the method can't do
+#[test]
+fn returned() {
+    let mut mock = MockFoo::new();
+    mock.expect_abort().returning(|_| ());
+    let msg = panic_msg(|| mock.abort(1));
+    assert!(msg.starts_with("MockFoo::abort: Expectation returned from a method that never returns"),
+            "{}", msg);
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::exit_context();
+    ctx.expect()
+        .with(predicate::eq(3))
+        .times(1)
+        .panics_with("exit 3");
+    assert_eq!("exit 3", panic_msg(|| MockFoo::exit(3)));
+    ctx.checkpoint();
+}
+
+#[test]
+fn function() {
+    let ctx = mock_m::die_context();
+    ctx.expect().times(1).panics_with("dead");
+    assert_eq!("dead", panic_msg(|| mock_m::die(0)));
+    ctx.checkpoint();
+}
@@ -0,0 +1,63 @@
+// vim: tw=80
+//! A method named `new` that isn't a constructor is mocked like any other
+//! method.
+#![deny(warnings)]
+#![allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
+
+use mockall::*;
+
+#[derive(Debug, PartialEq)]
+pub struct Child(u32);
+pub struct Cfg(u32);
+#[derive(Debug, PartialEq)]
+pub struct E;
+
+/// A factory-of-children method named `new`
+#[automock]
+pub trait Factory {
+    fn new(&self) -> Child;
+}
+
+pub struct Parent {}
+#[automock]
+#[allow(dead_code)]
+impl Parent {
+    pub fn new(&self) -> Child { Child(0) }
+}
+
+/// A fallible constructor named `new`
+pub struct Fallible {}
+#[automock]
+#[allow(dead_code)]
+impl Fallible {
+    pub fn new(_cfg: Cfg) -> Result<Self, E> { Ok(Fallible{}) }
+}
+
+/// The trait's `new` does not replace the mock's generated constructor.
+#[test]
+fn trait_method() {
+    let mut mock = MockFactory::new();
+    mock.expect_new().returning(|| Child(3));
+    assert_eq!(Child(3), mock.new());
+}
+
+/// The mock struct's own `new` method can't be generated, because it would
+/// collide.  But `default` still works.
+#[test]
+fn inherent_method() {
+    let mut mock = MockParent::default();
+    mock.expect_new().returning(|| Child(4));
+    assert_eq!(Child(4), mock.new());
+}
+
+#[test]
+fn fallible_constructor() {
+    let ctx = MockFallible::new_context();
+    ctx.expect()
+        .withf(|cfg| cfg.0 == 5)
+        .returning(|_| Ok(MockFallible::default()));
+    ctx.expect()
+        .returning(|_| Err(E));
+    assert!(MockFallible::new(Cfg(5)).is_ok());
+    assert_eq!(Some(E), MockFallible::new(Cfg(6)).err());
+}
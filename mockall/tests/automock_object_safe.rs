@@ -0,0 +1,72 @@
+// vim: tw=80
+//! A mock of an object-safe trait may be used as a trait object
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Service {
+    fn get(&self, x: u32) -> u32;
+    fn set(&mut self, x: u32);
+    fn new() -> Self where Self: Sized;
+    fn convert<T: From<u32> + 'static>(&self, x: u32) -> T where Self: Sized;
+    fn into_inner(self) -> u32 where Self: Sized;
+}
+
+#[automock(type Item = u32;)]
+pub trait Source {
+    type Item;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+
+#[automock]
+pub trait Store<T: 'static>: Send + Sync {
+    fn load(&self) -> T;
+}
+
+fn get(service: &dyn Service, x: u32) -> u32 {
+    service.get(x)
+}
+
+#[test]
+fn boxed() {
+    let mut mock = MockService::default();
+    mock.expect_get()
+        .with(predicate::eq(1))
+        .return_const(10u32);
+    mock.expect_set()
+        .with(predicate::eq(2))
+        .times(1)
+        .return_const(());
+    let mut service: Box<dyn Service> = Box::new(mock);
+    service.set(2);
+    assert_eq!(10, get(&*service, 1));
+}
+
+/// Methods that require `Self: Sized` may still be used on the mock itself
+#[test]
+fn sized_methods() {
+    let ctx = MockService::new_context();
+    ctx.expect().returning(MockService::default);
+    let mut mock = MockService::new();
+    mock.expect_convert::<u64>().return_const(5u64);
+    mock.expect_into_inner().return_const(6u32);
+    assert_eq!(5u64, mock.convert::<u64>(0));
+    assert_eq!(6, mock.into_inner());
+}
+
+#[test]
+fn associated_type() {
+    let mut mock = MockSource::new();
+    mock.expect_next().return_const(Some(7u32));
+    let mut source: Box<dyn Source<Item = u32>> = Box::new(mock);
+    assert_eq!(Some(7), source.next());
+}
+
+#[test]
+fn generic_trait() {
+    let mut mock = MockStore::<u32>::new();
+    mock.expect_load().return_const(8u32);
+    let store: Box<dyn Store<u32>> = Box::new(mock);
+    assert_eq!(8, store.load());
+}
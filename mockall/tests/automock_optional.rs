@@ -0,0 +1,201 @@
+// vim: tw=80
+//! An optional expectation is exempt from its minimum call count
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar(&self) -> u32;
+    fn baz<T: 'static>(&self, t: T) -> u32;
+    fn sfoo() -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn unused() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(2)
+        .optional()
+        .return_const(42u32);
+    mock.checkpoint();
+}
+
+/// The order of `times` and `optional` doesn't matter
+#[test]
+fn times_after_optional() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .optional()
+        .times(2)
+        .return_const(42u32);
+    mock.checkpoint();
+}
+
+#[test]
+fn matcher() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1))
+        .optional()
+        .return_const(42u32);
+    mock.expect_foo()
+        .return_const(0u32);
+    assert_eq!(42, mock.foo(1));
+    assert_eq!(0, mock.foo(2));
+}
+
+#[test]
+fn count_cap() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(1)
+        .optional()
+        .return_const(42u32);
+    assert_eq!(42, mock.foo(1));
+    let msg = panic_msg(|| {mock.foo(1);});
+    assert!(msg.contains("called more than 1 times"), "{}", msg);
+}
+
+#[test]
+fn report() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(1)
+        .optional()
+        .return_const(42u32);
+    let report = mock.report();
+    assert!(report.ends_with(
+        ": exactly 1 time (optional), called 0 times, satisfied"),
+        "{}", report);
+}
+
+#[test]
+fn generic_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_baz::<u32>()
+        .times(1)
+        .optional()
+        .return_const(42u32);
+    mock.checkpoint();
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect()
+        .times(1)
+        .optional()
+        .return_const(42u32);
+    ctx.checkpoint();
+}
+
+#[test]
+fn any_type() {
+    let mut mock = MockFoo::new();
+    mock.expect_baz_any()
+        .times(1)
+        .optional()
+        .return_const(42u32);
+    mock.checkpoint();
+}
+
+mod sequence {
+    use super::*;
+
+    /// An optional expectation may be skipped without blocking its successors
+    #[test]
+    fn skipped() {
+        let mut seq = Sequence::new();
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .times(1)
+            .optional()
+            .in_sequence(&mut seq)
+            .return_const(42u32);
+        mock.expect_bar()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(0u32);
+        mock.bar();
+    }
+
+    /// `optional` may also come after `in_sequence`
+    #[test]
+    fn optional_after_in_sequence() {
+        let mut seq = Sequence::new();
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .times(1)
+            .in_sequence(&mut seq)
+            .optional()
+            .return_const(42u32);
+        mock.expect_bar()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(0u32);
+        mock.bar();
+    }
+
+    #[test]
+    fn used() {
+        let mut seq = Sequence::new();
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .times(1)
+            .optional()
+            .in_sequence(&mut seq)
+            .return_const(42u32);
+        mock.expect_bar()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(0u32);
+        mock.foo(1);
+        mock.bar();
+    }
+
+    /// Once skipped, an optional expectation may not be called
+    #[test]
+    #[should_panic(expected = "Method sequence violation")]
+    fn called_after_skipped() {
+        let mut seq = Sequence::new();
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .times(1)
+            .optional()
+            .in_sequence(&mut seq)
+            .return_const(42u32);
+        mock.expect_bar()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(0u32);
+        mock.bar();
+        mock.foo(1);
+    }
+
+    /// Non-optional expectations still may not be skipped
+    #[test]
+    #[should_panic(expected = "Method sequence violation")]
+    fn required_not_skipped() {
+        let mut seq = Sequence::new();
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(42u32);
+        mock.expect_bar()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(0u32);
+        mock.bar();
+    }
+}
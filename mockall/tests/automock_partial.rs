@@ -0,0 +1,74 @@
+// vim: tw=80
+//! The methods attribute mocks only some of a trait's methods.  The rest panic,
+//! or use their default implementations.
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock(methods(get, put))]
+pub trait Store {
+    fn get(&self, key: u32) -> Option<u32>;
+    fn put(&mut self, key: u32, value: u32);
+    fn delete(&mut self, key: u32) -> bool;
+    fn len(&self) -> usize {
+        42
+    }
+}
+
+pub struct Cache {}
+
+#[automock(methods(lookup))]
+impl Cache {
+    pub fn lookup(&self, key: u32) -> u32 {
+        key
+    }
+    pub fn flush(&self) {}
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    let payload = panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err();
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => payload.downcast::<&str>().unwrap().to_string()
+    }
+}
+
+#[test]
+fn listed() {
+    let mut mock = MockStore::new();
+    mock.expect_get()
+        .with(predicate::eq(1))
+        .return_const(Some(2));
+    mock.expect_put()
+        .with(predicate::eq(3), predicate::eq(4))
+        .times(1)
+        .return_const(());
+    assert_eq!(Some(2), mock.get(1));
+    mock.put(3, 4);
+    mock.checkpoint();
+}
+
+#[test]
+fn unlisted() {
+    let mut mock = MockStore::new();
+    let msg = panic_msg(|| {mock.delete(1);});
+    assert_eq!(msg, "not implemented: not mocked: delete");
+}
+
+/// Unlisted methods with a default implementation just use it
+#[test]
+fn unlisted_provided() {
+    let mock = MockStore::new();
+    assert_eq!(42, mock.len());
+}
+
+#[test]
+fn inherent() {
+    let mut mock = MockCache::new();
+    mock.expect_lookup().return_const(5u32);
+    assert_eq!(5, mock.lookup(1));
+    let msg = panic_msg(|| mock.flush());
+    assert_eq!(msg, "not implemented: not mocked: flush");
+}
@@ -0,0 +1,51 @@
+// vim: tw=80
+//! Constructors that return `Self` behind a smart pointer, or a trait object
+#![deny(warnings)]
+
+use mockall::*;
+use std::{rc::Rc, sync::Arc};
+
+#[automock]
+pub trait Factory {
+    fn boxed() -> Box<Self> where Self: Sized;
+    fn counted() -> Rc<Self> where Self: Sized;
+    fn shared() -> Arc<dyn Factory> where Self: Sized;
+    fn id(&self) -> u32;
+}
+
+#[test]
+fn boxed() {
+    let ctx = MockFactory::boxed_context();
+    ctx.expect().returning(|| {
+        let mut mock = MockFactory::new();
+        mock.expect_id().return_const(1u32);
+        Box::new(mock)
+    });
+    let factory: Box<MockFactory> = MockFactory::boxed();
+    assert_eq!(1, factory.id());
+}
+
+#[test]
+fn counted() {
+    let ctx = MockFactory::counted_context();
+    ctx.expect().returning(|| {
+        let mut mock = MockFactory::new();
+        mock.expect_id().return_const(2u32);
+        Rc::new(mock)
+    });
+    let factory: Rc<MockFactory> = MockFactory::counted();
+    assert_eq!(2, factory.id());
+}
+
+/// An `Arc<MockFactory>` can be coerced to the `Arc<dyn Factory>` return type
+#[test]
+fn trait_object() {
+    let mut mock = MockFactory::new();
+    mock.expect_id().return_const(3u32);
+    let preconfigured = Arc::new(mock);
+    let ctx = MockFactory::shared_context();
+    ctx.expect()
+        .return_once(move || preconfigured);
+    let factory: Arc<dyn Factory> = MockFactory::shared();
+    assert_eq!(3, factory.id());
+}
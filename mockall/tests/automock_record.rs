@@ -0,0 +1,123 @@
+// vim: tw=80
+//! A mock can record every call to its methods, for snapshot testing
+#![deny(warnings)]
+
+use mockall::*;
+
+pub struct NotDebug;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32, name: &str) -> u32;
+    fn bar(&mut self, nd: NotDebug) -> Option<String>;
+    fn baz(&self) -> &str;
+    fn generic<T: 'static>(&self, t: T);
+    fn sfoo(x: u32) -> u32;
+}
+
+fn relaxed() -> MockFoo {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().return_const(0u32);
+    mock.expect_bar().return_const(None);
+    mock.expect_baz().return_const("baz".to_owned());
+    mock.expect_generic::<i16>().return_const(());
+    mock
+}
+
+/// Snapshot a three-call interaction
+#[test]
+fn snapshot() {
+    let mut mock = relaxed();
+    let recording = mock.record();
+    mock.foo(1, "one");
+    mock.bar(NotDebug);
+    mock.baz();
+    assert_eq!(recording.to_string(), "\
+MockFoo::foo(1, \"one\") -> 0
+MockFoo::bar(?) -> None
+MockFoo::baz() -> \"baz\"");
+    assert_eq!(recording.to_json(), r#"[
+  {"method": "MockFoo::foo", "args": ["1", "\"one\""], "returned": "0"},
+  {"method": "MockFoo::bar", "args": ["?"], "returned": "None"},
+  {"method": "MockFoo::baz", "args": [], "returned": "\"baz\""}
+]"#);
+}
+
+#[test]
+fn calls() {
+    let mut mock = relaxed();
+    let recording = mock.record();
+    mock.foo(5, "five");
+    let calls = recording.calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].method(), "MockFoo::foo");
+    assert_eq!(calls[0].args(), ["5", "\"five\""]);
+    assert_eq!(calls[0].returned(), Some("0"));
+}
+
+/// Nothing is recorded until record is called
+#[test]
+fn opt_in() {
+    let mut mock = relaxed();
+    mock.foo(1, "one");
+    let recording = mock.record();
+    assert_eq!(recording.to_json(), "[]");
+    assert!(recording.calls().is_empty());
+}
+
+/// Calling record again starts a new recording
+#[test]
+fn restart() {
+    let mut mock = relaxed();
+    let first = mock.record();
+    mock.foo(1, "one");
+    let second = mock.record();
+    mock.foo(2, "two");
+    assert_eq!(first.calls().len(), 1);
+    assert_eq!(second.to_string(), "MockFoo::foo(2, \"two\") -> 0");
+}
+
+/// Generic arguments aren't known to implement Debug
+#[test]
+fn generic() {
+    let mut mock = relaxed();
+    let recording = mock.record();
+    mock.generic(3i16);
+    assert_eq!(recording.to_string(), "MockFoo::generic(?) -> ()");
+}
+
+/// A call that panics is recorded without a return value
+#[test]
+fn panicked() {
+    let mut mock = MockFoo::new();
+    let recording = mock.record();
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        mock.foo(1, "one");
+    })).unwrap_err();
+    assert_eq!(recording.to_string(), "MockFoo::foo(1, \"one\") panicked");
+    assert!(recording.to_json().contains("\"returned\": null"));
+}
+
+/// Static methods aren't recorded
+#[test]
+fn static_method() {
+    let mut mock = relaxed();
+    let recording = mock.record();
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect().return_const(0u32);
+    MockFoo::sfoo(1);
+    assert!(recording.calls().is_empty());
+}
+
+/// Unmatched calls to a fallible mock return defaults, which are recorded
+#[test]
+fn fallible() {
+    let _guard = mockall::fallible();
+    let mut mock = MockFoo::new();
+    let recording = mock.record();
+    mock.foo(1, "one");
+    mock.bar(NotDebug);
+    assert_eq!(recording.to_string(), "\
+MockFoo::foo(1, \"one\") -> 0
+MockFoo::bar(?) -> None");
+}
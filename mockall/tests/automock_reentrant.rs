@@ -0,0 +1,81 @@
+// vim: tw=80
+//! A return function may call other methods of the same mock object, or even
+//! the same method, without deadlocking.
+#![deny(warnings)]
+
+use mockall::*;
+use std::sync::{Arc, Mutex, Weak};
+
+#[automock]
+pub trait Foo {
+    fn outer(&self, x: u32) -> u32;
+    fn inner(&self, x: u32) -> u32;
+    fn generic<T: Into<u32> + 'static>(&self, t: T) -> u32;
+}
+
+/// A slot for a reference back to the mock object, to be filled in once it's
+/// fully configured.
+type Slot = Arc<Mutex<Weak<MockFoo>>>;
+
+fn upgrade(slot: &Slot) -> Arc<MockFoo> {
+    slot.lock().unwrap().upgrade().unwrap()
+}
+
+#[test]
+fn calls_another_method() {
+    let slot = Slot::default();
+    let slot2 = slot.clone();
+    let mut mock = MockFoo::new();
+    mock.expect_outer()
+        .returning(move |x| upgrade(&slot2).inner(x) + 1);
+    mock.expect_inner().returning(|x| x * 2);
+    let mock = Arc::new(mock);
+    *slot.lock().unwrap() = Arc::downgrade(&mock);
+    assert_eq!(7, mock.outer(3));
+}
+
+#[test]
+fn calls_generic_method() {
+    let slot = Slot::default();
+    let slot2 = slot.clone();
+    let mut mock = MockFoo::new();
+    mock.expect_outer()
+        .returning(move |x| upgrade(&slot2).generic(x as u8) + 1);
+    mock.expect_generic::<u8>().returning(|t| u32::from(t) * 2);
+    let mock = Arc::new(mock);
+    *slot.lock().unwrap() = Arc::downgrade(&mock);
+    assert_eq!(7, mock.outer(3));
+}
+
+/// A method may call itself, so long as the nested call matches a different
+/// expectation.
+#[test]
+fn calls_same_method() {
+    let slot = Slot::default();
+    let slot2 = slot.clone();
+    let mut mock = MockFoo::new();
+    mock.expect_outer()
+        .with(predicate::eq(0))
+        .return_const(0u32);
+    mock.expect_outer()
+        .with(predicate::gt(0))
+        .times(1)
+        .returning(move |x| upgrade(&slot2).outer(x - 1) + 10);
+    let mock = Arc::new(mock);
+    *slot.lock().unwrap() = Arc::downgrade(&mock);
+    assert_eq!(10, mock.outer(1));
+}
+
+/// But an expectation can't call itself from its own return function
+#[test]
+#[should_panic(expected = "MockFoo::outer: Expectation(<anything>) called from its own return function")]
+fn calls_same_expectation() {
+    let slot = Slot::default();
+    let slot2 = slot.clone();
+    let mut mock = MockFoo::new();
+    mock.expect_outer()
+        .returning(move |x| if x > 0 { upgrade(&slot2).outer(x - 1) } else { 0 });
+    let mock = Arc::new(mock);
+    *slot.lock().unwrap() = Arc::downgrade(&mock);
+    mock.outer(1);
+}
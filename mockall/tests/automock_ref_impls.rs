@@ -0,0 +1,84 @@
+// vim: tw=80
+//! The ref_impls attribute implements the mocked trait for references to the
+//! mock struct, too
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock(ref_impls)]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar<T: 'static>(&self, t: T) -> T;
+    fn build() -> u32;
+}
+
+#[automock(ref_impls)]
+pub trait Counter {
+    fn get(&self) -> u32;
+    fn incr(&mut self, by: u32);
+}
+
+#[automock(ref_impls; type Item=u32;)]
+pub trait Container {
+    type Item;
+    fn item(&self) -> Self::Item;
+}
+
+fn use_foo<F: Foo>(f: F) -> u32 {
+    f.foo(1) + f.bar(2u32)
+}
+
+fn use_counter<C: Counter>(mut c: C) -> u32 {
+    c.incr(5);
+    c.get()
+}
+
+fn use_container<C: Container>(c: C) -> C::Item {
+    c.item()
+}
+
+#[test]
+fn shared_ref() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(2).return_const(10u32);
+    mock.expect_bar::<u32>().returning(|t| t);
+    assert_eq!(12, use_foo(&mock));
+    // The mock can still be used afterwards
+    assert_eq!(10, mock.foo(3));
+    mock.checkpoint();
+}
+
+#[test]
+fn mut_ref() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().return_const(10u32);
+    mock.expect_bar::<u32>().returning(|t| t);
+    assert_eq!(12, use_foo(&mut mock));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::build_context();
+    ctx.expect().return_const(42u32);
+    assert_eq!(42, <&MockFoo as Foo>::build());
+}
+
+/// A trait with `&mut self` methods can only be implemented for `&mut MockX`
+#[test]
+fn mut_receiver() {
+    let mut mock = MockCounter::new();
+    mock.expect_incr()
+        .with(predicate::eq(5))
+        .times(1)
+        .return_const(());
+    mock.expect_get().return_const(5u32);
+    assert_eq!(5, use_counter(&mut mock));
+    mock.checkpoint();
+}
+
+#[test]
+fn associated_type() {
+    let mut mock = MockContainer::new();
+    mock.expect_item().return_const(7u32);
+    assert_eq!(7, use_container(&mock));
+}
@@ -0,0 +1,59 @@
+// vim: tw=80
+//! A call that matches no expectation explains why each candidate rejected it
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn get(&self, key: u32) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn predicate_and_exhausted() {
+    let mut mock = MockFoo::new();
+    mock.expect_get()
+        .with(predicate::eq(5))
+        .return_const(1u32);
+    mock.expect_get()
+        .times(3)
+        .return_const(2u32);
+    for _ in 0..3 {
+        assert_eq!(2, mock.get(7));
+    }
+    let msg = panic_msg(|| {mock.get(7);});
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{}", msg);
+    assert_eq!(lines[0], "MockFoo::get: No matching expectation found.  \
+        2 expectations, none matched:");
+    assert!(lines[1].starts_with("#1 Expectation(var == 5) (set at "),
+        "{}", msg);
+    assert!(lines[1].ends_with(
+        ": rejected by the `with` predicates: `var == 5` failed"), "{}", msg);
+    assert!(lines[2].starts_with("#2 Expectation(<anything>) (set at "),
+        "{}", msg);
+    assert!(lines[2].ends_with(": already called 3/3 times"), "{}", msg);
+}
+
+#[test]
+fn exhausted_range() {
+    let mut mock = MockFoo::new();
+    mock.expect_get()
+        .times(1..3)
+        .return_const(1u32);
+    mock.expect_get()
+        .with(predicate::eq(5))
+        .return_const(2u32);
+    mock.get(1);
+    mock.get(1);
+    let msg = panic_msg(|| {mock.get(1);});
+    assert!(msg.contains(": already called 2/2 times\n#2 "), "{}", msg);
+}
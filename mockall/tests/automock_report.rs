@@ -0,0 +1,144 @@
+// vim: tw=80
+//! A mock object can summarize the state of all of its expectations, and a
+//! failed checkpoint includes that summary
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar<T: 'static>(&self, t: T);
+    fn baz() -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+/// Strip the locations from a report, so it can be compared exactly
+fn strip_locations(s: &str) -> String {
+    s.lines()
+        .map(|l| match l.find(" (set at ") {
+            Some(b) => {
+                let e = b + l[b..].find(')').unwrap();
+                format!("{}{}", &l[..b], &l[e + 1..])
+            },
+            None => l.to_owned()
+        }).collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn empty() {
+    let mock = MockFoo::new();
+    assert_eq!("", mock.report());
+}
+
+#[test]
+fn report() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1))
+        .times(2)
+        .return_const(0u32);
+    mock.expect_foo()
+        .times(1..3)
+        .return_const(0u32);
+    mock.expect_foo()
+        .times(..=1)
+        .return_const(0u32);
+    mock.expect_foo()
+        .times(1..)
+        .return_const(0u32);
+    mock.expect_foo()
+        .never()
+        .return_const(0u32);
+    mock.expect_bar::<u32>()
+        .times(1)
+        .return_const(());
+    mock.foo(1);
+    mock.foo(2);
+    assert_eq!(strip_locations(&mock.report()), "\
+        MockFoo::foo: Expectation(var == 1): exactly 2 times, \
+        called 1 time, unsatisfied\n\
+        MockFoo::foo: Expectation(<anything>): 1 to 2 times, \
+        called 1 time, satisfied\n\
+        MockFoo::foo: Expectation(<anything>): at most 1 time, \
+        called 0 times, satisfied\n\
+        MockFoo::foo: Expectation(<anything>): at least 1 time, \
+        called 0 times, unsatisfied\n\
+        MockFoo::foo: Expectation(<anything>): never, \
+        called 0 times, satisfied\n\
+        MockFoo::bar::<u32>: Expectation(<anything>): exactly 1 time, \
+        called 0 times, unsatisfied");
+    // Reporting doesn't clear the expectations
+    mock.foo(1);
+    for _ in 0..3 {
+        mock.foo(3);
+    }
+    mock.bar(0u32);
+    mock.checkpoint();
+}
+
+#[test]
+fn report_location() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().return_const(0u32);
+    let line = line!() - 1;
+    let report = mock.report();
+    assert!(report.contains(&format!("(set at {}:{}:", file!(), line)),
+        "{}", report);
+}
+
+#[test]
+fn failed_checkpoint() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1))
+        .times(1)
+        .return_const(0u32);
+    mock.expect_foo()
+        .with(predicate::eq(2))
+        .times(1)
+        .return_const(0u32);
+    mock.foo(2);
+    let msg = panic_msg(|| mock.checkpoint());
+    assert_eq!(strip_locations(&msg), "\
+        MockFoo::foo: Expectation(var == 1) called fewer than 1 times\n\
+        All expectations:\n\
+        MockFoo::foo: Expectation(var == 1): exactly 1 time, \
+        called 0 times, unsatisfied\n\
+        MockFoo::foo: Expectation(var == 2): exactly 1 time, \
+        called 1 time, satisfied");
+}
+
+#[test]
+fn successful_checkpoint() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(0u32);
+    mock.foo(2);
+    mock.checkpoint();
+}
+
+#[test]
+fn failed_checkpoint_all() {
+    let ctx = MockFoo::baz_context();
+    ctx.expect().times(1).return_const(0u32);
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1).return_const(0u32);
+    let msg = panic_msg(|| mock.checkpoint_all());
+    assert_eq!(strip_locations(&msg), "\
+        2 expectations were not satisfied:\n\
+        - MockFoo::foo: Expectation(<anything>) called fewer than 1 times\n\
+        - MockFoo::baz: Expectation(<anything>) called fewer than 1 times\n\
+        All expectations:\n\
+        MockFoo::foo: Expectation(<anything>): exactly 1 time, \
+        called 0 times, unsatisfied\n\
+        MockFoo::baz: Expectation(<anything>): exactly 1 time, \
+        called 0 times, unsatisfied");
+}
@@ -0,0 +1,115 @@
+// vim: tw=80
+//! Expectations of static methods and mocked functions that a panicking test
+//! left behind can be cleared without verifying them
+#![deny(warnings)]
+
+use mockall::*;
+use std::{
+    panic,
+    sync::{Mutex, MutexGuard, PoisonError}
+};
+
+#[automock]
+pub trait Foo {
+    fn foo(x: u32) -> u32;
+    fn bar<T: 'static>(t: T) -> u32;
+}
+
+#[automock]
+pub mod m {
+    pub fn baz() -> u32 {
+        unimplemented!()
+    }
+}
+
+static MTX: Mutex<()> = Mutex::new(());
+
+/// `reset` clears every test's expectations, so these tests mustn't run
+/// concurrently.
+fn serialize() -> MutexGuard<'static, ()> {
+    MTX.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Set an expectation, then panic before it's satisfied
+fn leak<F: FnOnce() + panic::UnwindSafe>(f: F) {
+    panic::catch_unwind(f).unwrap_err();
+}
+
+#[test]
+fn reset_statics() {
+    let _m = serialize();
+    leak(|| {
+        let ctx = MockFoo::foo_context();
+        ctx.expect().times(2).return_const(1u32);
+        MockFoo::foo(1);
+        panic!("Failed mid-test");
+    });
+    MockFoo::reset_statics();
+    let ctx = MockFoo::foo_context();
+    ctx.expect().return_const(2u32);
+    assert_eq!(2, MockFoo::foo(1));
+    ctx.checkpoint();
+}
+
+/// A call that its expectation didn't allow panics while the method's
+/// expectations are locked.  That mustn't stop the method from being reused.
+#[test]
+fn poisoned() {
+    let _m = serialize();
+    leak(|| {
+        let ctx = MockFoo::foo_context();
+        ctx.expect().times(1).return_const(1u32);
+        MockFoo::foo(1);
+        MockFoo::foo(1);
+    });
+    MockFoo::reset_statics();
+    let ctx = MockFoo::foo_context();
+    ctx.expect().times(1).return_const(3u32);
+    assert_eq!(3, MockFoo::foo(1));
+    ctx.checkpoint();
+}
+
+#[test]
+fn generic_method() {
+    let _m = serialize();
+    leak(|| {
+        let ctx = MockFoo::bar_context();
+        ctx.expect::<u16>().times(1).return_const(1u32);
+        panic!("Failed mid-test");
+    });
+    MockFoo::reset_statics();
+    let ctx = MockFoo::bar_context();
+    ctx.expect::<u16>().return_const(4u32);
+    assert_eq!(4, MockFoo::bar(0u16));
+    ctx.checkpoint();
+}
+
+#[test]
+fn module() {
+    let _m = serialize();
+    leak(|| {
+        let ctx = mock_m::baz_context();
+        ctx.expect().times(1).return_const(1u32);
+        panic!("Failed mid-test");
+    });
+    mock_m::reset();
+    let ctx = mock_m::baz_context();
+    ctx.expect().return_const(5u32);
+    assert_eq!(5, mock_m::baz());
+    mock_m::checkpoint();
+}
+
+#[test]
+fn global() {
+    let _m = serialize();
+    leak(|| {
+        let foo_ctx = MockFoo::foo_context();
+        foo_ctx.expect().times(1).return_const(1u32);
+        let baz_ctx = mock_m::baz_context();
+        baz_ctx.expect().times(1).return_const(1u32);
+        panic!("Failed mid-test");
+    });
+    reset();
+    MockFoo::checkpoint_statics();
+    mock_m::checkpoint();
+}
@@ -0,0 +1,79 @@
+// vim: tw=80
+//! Methods that return an `Option` can use the `return_some` and `return_none`
+//! shortcuts.
+
+use mockall::*;
+
+#[automock]
+trait Foo {
+    fn peek(&self) -> Option<u32>;
+    fn get<T: 'static>(&self, t: T) -> Option<String>;
+    fn speek() -> Option<u32>;
+    fn sget<T: 'static>(t: T) -> Option<String>;
+}
+
+#[test]
+fn return_none() {
+    let mut mock = MockFoo::new();
+    mock.expect_peek()
+        .return_none();
+    assert_eq!(None, mock.peek());
+}
+
+#[test]
+fn return_some() {
+    let mut mock = MockFoo::new();
+    mock.expect_peek()
+        .return_some(7);
+    assert_eq!(Some(7), mock.peek());
+    assert_eq!(Some(7), mock.peek());
+}
+
+/// A `return_some` expectation limited by `times` can be followed by a
+/// fallback `return_none` expectation.
+#[test]
+fn return_some_then_none() {
+    let mut mock = MockFoo::new();
+    mock.expect_peek()
+        .times(1)
+        .return_some(7);
+    mock.expect_peek()
+        .return_none();
+    assert_eq!(Some(7), mock.peek());
+    assert_eq!(None, mock.peek());
+    assert_eq!(None, mock.peek());
+}
+
+#[test]
+fn generic_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_get::<u8>()
+        .return_some("u8".to_owned());
+    mock.expect_get::<i8>()
+        .return_none();
+    assert_eq!(Some("u8".to_owned()), mock.get(0u8));
+    assert_eq!(None, mock.get(0i8));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::speek_context();
+    ctx.expect()
+        .times(1)
+        .return_some(7);
+    ctx.expect()
+        .return_none();
+    assert_eq!(Some(7), MockFoo::speek());
+    assert_eq!(None, MockFoo::speek());
+}
+
+#[test]
+fn generic_static_method() {
+    let ctx = MockFoo::sget_context();
+    ctx.expect::<u8>()
+        .return_some("u8".to_owned());
+    ctx.expect::<i8>()
+        .return_none();
+    assert_eq!(Some("u8".to_owned()), MockFoo::sget(0u8));
+    assert_eq!(None, MockFoo::sget(0i8));
+}
@@ -0,0 +1,76 @@
+// vim: tw=80
+//! Methods that return a `Result` can use the `return_ok`, `return_err`, and
+//! `return_ok_default` shortcuts.
+
+use mockall::*;
+
+pub type MyResult<T> = std::result::Result<T, String>;
+
+#[automock]
+trait Foo {
+    fn foo(&self, x: u32) -> Result<u32, String>;
+    fn bar(&self) -> MyResult<Vec<u32>>;
+    fn baz<T: 'static>(&self, t: T) -> Result<u32, i16>;
+    fn sfoo(x: u32) -> Result<u32, String>;
+    fn sbaz<T: 'static>(t: T) -> Result<u32, i16>;
+}
+
+#[test]
+fn return_ok() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .return_ok(42);
+    assert_eq!(Ok(42), mock.foo(5));
+    assert_eq!(Ok(42), mock.foo(6));
+}
+
+#[test]
+fn return_err() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .return_err("oops".to_owned());
+    assert_eq!(Err("oops".to_owned()), mock.foo(5));
+}
+
+#[test]
+fn return_ok_default() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar()
+        .return_ok_default();
+    assert_eq!(Ok(Vec::new()), mock.bar());
+}
+
+#[test]
+fn generic_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_baz::<u8>()
+        .return_ok(1);
+    mock.expect_baz::<i8>()
+        .return_err(-1);
+    assert_eq!(Ok(1), mock.baz(0u8));
+    assert_eq!(Err(-1), mock.baz(0i8));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect()
+        .with(predicate::eq(1))
+        .return_ok(2);
+    ctx.expect()
+        .with(predicate::eq(3))
+        .return_err("three".to_owned());
+    assert_eq!(Ok(2), MockFoo::sfoo(1));
+    assert_eq!(Err("three".to_owned()), MockFoo::sfoo(3));
+}
+
+#[test]
+fn generic_static_method() {
+    let ctx = MockFoo::sbaz_context();
+    ctx.expect::<u8>()
+        .return_ok_default();
+    ctx.expect::<i8>()
+        .return_err(-1);
+    assert_eq!(Ok(0), MockFoo::sbaz(0u8));
+    assert_eq!(Err(-1), MockFoo::sbaz(0i8));
+}
@@ -0,0 +1,63 @@
+// vim: tw=80
+//! Mocked functions that return `'static` references can return data computed
+//! at runtime with `return_static`
+#![deny(warnings)]
+
+use mockall::*;
+use std::cell::Cell;
+
+#[automock]
+pub mod m {
+    use std::cell::Cell;
+
+    pub fn version() -> &'static str {
+        unimplemented!()
+    }
+    pub fn bytes(_x: u32) -> &'static [u8] {
+        unimplemented!()
+    }
+    pub fn cell() -> &'static Cell<u32> {
+        unimplemented!()
+    }
+}
+
+#[automock]
+pub trait Foo {
+    fn name() -> &'static str;
+}
+
+#[test]
+fn string() {
+    let ctx = mock_m::version_context();
+    ctx.expect().return_static(format!("{}.{}", 1, 2));
+    assert_eq!("1.2", mock_m::version());
+}
+
+#[test]
+fn slice() {
+    let ctx = mock_m::bytes_context();
+    ctx.expect()
+        .with(predicate::eq(3))
+        .return_static(vec![3u8; 3]);
+    assert_eq!(&[3u8, 3, 3], mock_m::bytes(3));
+}
+
+/// The reference stays valid after the expectation is gone
+#[test]
+fn outlives_expectation() {
+    let ctx = MockFoo::name_context();
+    ctx.expect().return_static(String::from("foo"));
+    let name = MockFoo::name();
+    ctx.checkpoint();
+    drop(ctx);
+    assert_eq!("foo", name);
+}
+
+/// `return_static` requires a `Sync` referent.  Functions that return other
+/// `'static` references can still be mocked, without it.
+#[test]
+fn not_sync() {
+    let ctx = mock_m::cell_context();
+    ctx.expect().returning_st(|| Box::leak(Box::new(Cell::new(5))));
+    assert_eq!(5, mock_m::cell().get());
+}
@@ -0,0 +1,80 @@
+// vim: tw=80
+//! Builder-style methods that consume and return `Self` can return the mock
+//! object itself
+
+use mockall::*;
+
+#[derive(Debug, PartialEq)]
+pub struct Widget {
+    name: String
+}
+
+#[automock]
+pub trait Builder {
+    fn name(self, n: &str) -> Self;
+    fn width(self, w: u32) -> Self;
+    fn build(self) -> Widget;
+}
+
+#[test]
+fn chain() {
+    let mut mock = MockBuilder::new();
+    mock.expect_name()
+        .with(predicate::eq("foo"))
+        .times(1)
+        .returning_self();
+    mock.expect_width()
+        .times(2)
+        .returning_self();
+    mock.expect_build()
+        .return_once(|| Widget{name: "foo".to_owned()});
+    let widget = mock.name("foo")
+        .width(1)
+        .width(2)
+        .build();
+    assert_eq!(Widget{name: "foo".to_owned()}, widget);
+}
+
+#[test]
+#[should_panic(expected =
+    "MockBuilder::width: Expectation(<anything>) called more than 1 times")]
+fn too_many_calls() {
+    let mut mock = MockBuilder::new();
+    mock.expect_width()
+        .times(1)
+        .returning_self();
+    mock.width(1).width(2);
+}
+
+#[test]
+fn returning() {
+    let mut mock = MockBuilder::new();
+    mock.expect_name()
+        .returning(|_| {
+            let mut other = MockBuilder::new();
+            other.expect_build()
+                .return_once(|| Widget{name: "bar".to_owned()});
+            other
+        });
+    assert_eq!("bar", mock.name("bar").build().name);
+}
+
+pub struct Generic {}
+
+#[automock]
+impl Generic {
+    pub fn set<T: 'static>(self, _t: T) -> Self {
+        Generic{}
+    }
+}
+
+#[test]
+fn generic_method() {
+    let mut mock = MockGeneric::new();
+    mock.expect_set::<u32>()
+        .returning_self();
+    mock.expect_set::<i16>()
+        .times(1)
+        .returning_self();
+    let _mock = mock.set(1u32).set(2i16);
+}
@@ -0,0 +1,106 @@
+// vim: tw=80
+//! An expectation may return a different value on each successive call with
+//! `returning_seq` or `returning_fn_seq`.
+
+use mockall::*;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct NonClone(u32);
+
+#[automock]
+trait Foo {
+    fn foo(&self, x: u32) -> Result<u32, String>;
+    fn bar(&self) -> NonClone;
+    fn baz(&self, x: &u32) -> u32;
+    fn sfoo(x: u32) -> u32;
+    fn sbar<T: 'static>(t: T) -> u32;
+}
+
+#[test]
+fn returning_seq() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(4))
+        .returning_seq(vec![Err("Timeout".to_owned()), Ok(42)]);
+    assert_eq!(Err("Timeout".to_owned()), mock.foo(4));
+    assert_eq!(Ok(42), mock.foo(4));
+}
+
+#[test]
+#[should_panic(expected =
+    "MockFoo::foo: Expectation(<anything>) called more than 2 times")]
+fn returning_seq_too_many() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .returning_seq(vec![Ok(1), Ok(2)]);
+    mock.foo(0).unwrap();
+    mock.foo(0).unwrap();
+    mock.foo(0).unwrap();
+}
+
+#[test]
+#[should_panic(expected =
+    "MockFoo::foo: Expectation(<anything>) called fewer than 2 times")]
+fn returning_seq_too_few() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .returning_seq(vec![Ok(1), Ok(2)]);
+    mock.foo(0).unwrap();
+    mock.checkpoint();
+}
+
+/// If the user later loosens the call count, running out of values is still an
+/// error.
+#[test]
+#[should_panic(expected =
+    "MockFoo::foo: Expectation(<anything>) called more times than the number of supplied return values")]
+fn returning_seq_exhausted() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .returning_seq(vec![Ok(1)])
+        .times(..);
+    mock.foo(0).unwrap();
+    mock.foo(0).unwrap();
+}
+
+#[test]
+fn returning_fn_seq() {
+    let mut mock = MockFoo::new();
+    let r0 = NonClone(0);
+    let r1 = NonClone(1);
+    mock.expect_bar()
+        .returning_fn_seq(vec![
+            Box::new(move || r0),
+            Box::new(move || r1)
+        ]);
+    assert_eq!(NonClone(0), mock.bar());
+    assert_eq!(NonClone(1), mock.bar());
+}
+
+#[test]
+fn returning_fn_seq_reference_arguments() {
+    let mut mock = MockFoo::new();
+    mock.expect_baz()
+        .returning_fn_seq(vec![Box::new(|x| *x), Box::new(|x| *x + 1)]);
+    assert_eq!(5, mock.baz(&5));
+    assert_eq!(6, mock.baz(&5));
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect()
+        .returning_seq(vec![1u32, 2, 3]);
+    assert_eq!(1, MockFoo::sfoo(0));
+    assert_eq!(2, MockFoo::sfoo(0));
+    assert_eq!(3, MockFoo::sfoo(0));
+}
+
+#[test]
+fn generic_static_method() {
+    let ctx = MockFoo::sbar_context();
+    ctx.expect::<u8>()
+        .returning_fn_seq(vec![Box::new(|_| 1), Box::new(|_| 2)]);
+    assert_eq!(1, MockFoo::sbar(0u8));
+    assert_eq!(2, MockFoo::sbar(0u8));
+}
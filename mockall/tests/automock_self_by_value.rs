@@ -28,3 +28,47 @@ fn mutable() {
         .returning(|| ());
     mock.bar();
 }
+
+/// Consumes the mock, returning parts that aren't Clone
+#[automock]
+trait Split {
+    fn into_parts(self) -> (Vec<u8>, String);
+    fn len(&self) -> usize;
+}
+
+#[test]
+fn return_once() {
+    let mut mock = MockSplit::new();
+    mock.expect_len()
+        .return_const(3usize);
+    mock.expect_into_parts()
+        .times(1)
+        .return_once(|| (vec![1, 2, 3], String::from("abc")));
+    assert_eq!(3, mock.len());
+    assert_eq!((vec![1, 2, 3], String::from("abc")), mock.into_parts());
+}
+
+/// Expectations can be set while the mock is built, before it is consumed
+#[test]
+fn builder() {
+    fn build(parts: (Vec<u8>, String)) -> MockSplit {
+        let mut mock = MockSplit::new();
+        mock.expect_into_parts()
+            .times(1)
+            .return_once(move || parts);
+        mock
+    }
+    let mock = build((vec![4], String::from("d")));
+    assert_eq!((vec![4], String::from("d")), mock.into_parts());
+}
+
+/// Dropping the mock without consuming it still verifies its expectations
+#[test]
+#[should_panic(expected =
+    "MockSplit::into_parts: Expectation(<anything>) called fewer than 1 times")]
+fn dropped_without_consuming() {
+    let mut mock = MockSplit::new();
+    mock.expect_into_parts()
+        .times(1)
+        .return_once(|| (Vec::new(), String::new()));
+}
@@ -0,0 +1,58 @@
+// vim: tw=80
+//! Semantic attributes like `#[must_use]` and `#[inline]` are copied to the
+//! mock methods without being misapplied to anything else.
+#![deny(unused_attributes)]
+
+use mockall::*;
+
+pub struct A{}
+#[automock]
+impl A {
+    #[must_use]
+    #[inline]
+    pub fn foo(&self, _x: i32) -> i32 {0}
+    #[cold]
+    #[allow(clippy::needless_return)]
+    pub fn bar(&self) -> i32 {return 0}
+    #[deprecated(since = "0.1.0", note = "Use foo instead")]
+    pub fn baz(&self) -> i32 {0}
+}
+
+#[automock]
+trait T {
+    #[must_use]
+    fn foo(&self) -> u32;
+}
+
+#[test]
+fn must_use() {
+    let mut mock = MockA::new();
+    mock.expect_foo()
+        .returning(|x| x);
+    assert_eq!(4, mock.foo(4));
+}
+
+#[test]
+fn cold() {
+    let mut mock = MockA::new();
+    mock.expect_bar()
+        .return_const(5);
+    assert_eq!(5, mock.bar());
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated() {
+    let mut mock = MockA::new();
+    mock.expect_baz()
+        .return_const(6);
+    assert_eq!(6, mock.baz());
+}
+
+#[test]
+fn trait_method() {
+    let mut mock = MockT::new();
+    mock.expect_foo()
+        .return_const(7u32);
+    assert_eq!(7, mock.foo());
+}
@@ -0,0 +1,91 @@
+// vim: tw=80
+//! Clones of one Sequence can order calls made by mocks on different threads
+#![deny(warnings)]
+
+use mockall::*;
+use std::{sync::mpsc, thread};
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32);
+}
+
+/// Mocks on two threads must take turns
+#[test]
+fn interleave() {
+    let seq = Sequence::new();
+    let mut mock0 = MockFoo::new();
+    let mut mock1 = MockFoo::new();
+    for x in 0..4 {
+        let mock = if x % 2 == 0 { &mut mock0 } else { &mut mock1 };
+        mock.expect_foo()
+            .with(predicate::eq(x))
+            .times(1)
+            .in_sequence(&seq)
+            .return_const(());
+    }
+    let (tx0, rx0) = mpsc::channel();
+    let (tx1, rx1) = mpsc::channel();
+    let t0 = thread::spawn(move || {
+        mock0.foo(0);
+        tx0.send(()).unwrap();
+        rx1.recv().unwrap();
+        mock0.foo(2);
+        tx0.send(()).unwrap();
+    });
+    let t1 = thread::spawn(move || {
+        rx0.recv().unwrap();
+        mock1.foo(1);
+        tx1.send(()).unwrap();
+        rx0.recv().unwrap();
+        mock1.foo(3);
+    });
+    t0.join().unwrap();
+    t1.join().unwrap();
+}
+
+/// Each thread may add its own expectations with its own clone
+#[test]
+fn configure_on_threads() {
+    let seq = Sequence::new();
+    let seq1 = seq.clone();
+    let mock0 = thread::spawn(move || {
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .times(1)
+            .in_sequence(&seq1)
+            .return_const(());
+        mock
+    }).join().unwrap();
+    let seq2 = seq.clone();
+    let mock1 = thread::spawn(move || {
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .times(1)
+            .in_sequence(&seq2)
+            .return_const(());
+        mock
+    }).join().unwrap();
+    drop(seq);
+    thread::spawn(move || mock0.foo(0)).join().unwrap();
+    thread::spawn(move || mock1.foo(1)).join().unwrap();
+}
+
+#[test]
+fn violation() {
+    let seq = Sequence::new();
+    let mut mock0 = MockFoo::new();
+    mock0.expect_foo()
+        .times(1)
+        .in_sequence(&seq)
+        .return_const(());
+    let mut mock1 = MockFoo::new();
+    mock1.expect_foo()
+        .times(1)
+        .in_sequence(&seq.clone())
+        .return_const(());
+    let r = thread::spawn(move || mock1.foo(1)).join();
+    let msg = *r.unwrap_err().downcast::<&str>().unwrap();
+    assert_eq!(msg, "Method sequence violation");
+    mock0.foo(0);
+}
@@ -0,0 +1,106 @@
+// vim: tw=80
+//! An ExpectationRef doesn't borrow the mock, so a test may hold several and
+//! reconfigure their expectations later
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar<T: 'static>(&self, t: T) -> u32;
+    fn baz(&self) -> &u32;
+}
+
+pub struct Bean {}
+
+#[automock]
+impl Bean {
+    pub fn bean(&mut self) -> &mut u32 {
+        unimplemented!()
+    }
+}
+
+struct Fixture {
+    mock: MockFoo,
+    one: ExpectationRef<__mock_Foo_Foo::foo::Expectation>,
+    two: ExpectationRef<__mock_Foo_Foo::foo::Expectation>,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let mut mock = MockFoo::new();
+        let one = mock.expect_foo()
+            .with(predicate::eq(1))
+            .return_const(10u32)
+            .stable_ref();
+        let two = mock.expect_foo()
+            .with(predicate::eq(2))
+            .return_const(20u32)
+            .stable_ref();
+        Fixture{mock, one, two}
+    }
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    let payload = panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err();
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => payload.downcast::<&str>().unwrap().to_string()
+    }
+}
+
+#[test]
+fn two_at_once() {
+    let mut f = Fixture::new();
+    f.one.get(&mut f.mock).return_const(11u32);
+    f.two.get(&mut f.mock).times(1);
+    f.one.get(&mut f.mock).times(2);
+    assert_eq!(11, f.mock.foo(1));
+    assert_eq!(20, f.mock.foo(2));
+    assert_eq!(11, f.mock.foo(1));
+    f.mock.checkpoint();
+}
+
+#[test]
+fn generic_method() {
+    let mut mock = MockFoo::new();
+    let u = mock.expect_bar::<u32>().return_const(1u32).stable_ref();
+    let i = mock.expect_bar::<i16>().return_const(2u32).stable_ref();
+    u.get(&mut mock).return_const(3u32);
+    i.get(&mut mock).return_const(4u32);
+    assert_eq!(3, mock.bar(0u32));
+    assert_eq!(4, mock.bar(0i16));
+}
+
+#[test]
+fn reference() {
+    let mut mock = MockFoo::new();
+    let r = mock.expect_baz().return_const(5u32).stable_ref();
+    r.get(&mut mock).return_const(6u32);
+    assert_eq!(6, *mock.baz());
+
+    let mut mock = MockBean::new();
+    let r = mock.expect_bean().return_var(7u32).stable_ref();
+    r.get(&mut mock).return_var(8u32);
+    assert_eq!(8, *mock.bean());
+}
+
+#[test]
+fn after_checkpoint() {
+    let mut f = Fixture::new();
+    f.mock.checkpoint();
+    let msg = panic_msg(|| {f.one.get(&mut f.mock);});
+    assert!(msg.starts_with("No such expectation"), "{}", msg);
+}
+
+#[test]
+fn other_mock() {
+    let f = Fixture::new();
+    let mut other = MockFoo::new();
+    other.expect_foo().return_const(0u32);
+    let msg = panic_msg(|| {f.one.get(&mut other);});
+    assert!(msg.starts_with("No such expectation"), "{}", msg);
+}
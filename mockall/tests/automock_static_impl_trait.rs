@@ -0,0 +1,60 @@
+// vim: tw=80
+//! Static factory methods and module functions returning `impl Trait`
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Connection {
+    fn send(&self, x: u32) -> u32;
+}
+
+pub struct Pool {}
+#[automock]
+#[allow(dead_code)]
+impl Pool {
+    pub fn build() -> impl Connection { MockConnection::new() }
+}
+
+mock! {
+    pub Builder {
+        fn build(port: u16) -> impl Connection;
+    }
+}
+
+#[automock]
+#[allow(dead_code)]
+pub mod factory {
+    use super::*;
+    pub fn build() -> impl Connection { MockConnection::new() }
+}
+
+fn connection(x: u32) -> Box<dyn Connection> {
+    let mut conn = MockConnection::new();
+    conn.expect_send().returning(move |y| x + y);
+    Box::new(conn)
+}
+
+#[test]
+fn inherent() {
+    let ctx = MockPool::build_context();
+    ctx.expect().returning(|| connection(1));
+    let conn: Box<dyn Connection> = MockPool::build();
+    assert_eq!(5, conn.send(4));
+}
+
+#[test]
+fn mock_macro() {
+    let ctx = MockBuilder::build_context();
+    ctx.expect()
+        .with(predicate::eq(80))
+        .returning(|port| connection(u32::from(port)));
+    assert_eq!(84, MockBuilder::build(80).send(4));
+}
+
+#[test]
+fn module() {
+    let ctx = mock_factory::build_context();
+    ctx.expect().returning(|| connection(2));
+    assert_eq!(6, mock_factory::build().send(4));
+}
@@ -0,0 +1,98 @@
+// vim: tw=80
+//! String predicates work for both owned and borrowed string arguments, with
+//! both owned and borrowed constants.
+#![deny(warnings)]
+
+use mockall::*;
+use mockall::predicate::*;
+
+#[automock]
+pub trait Foo {
+    fn owned(&self, s: String) -> u32;
+    fn borrowed(&self, s: &str) -> u32;
+}
+
+#[test]
+fn eq_owned_arg_borrowed_constant() {
+    let mut mock = MockFoo::new();
+    mock.expect_owned()
+        .with(eq("foo"))
+        .return_const(1u32);
+    assert_eq!(1, mock.owned(String::from("foo")));
+}
+
+#[test]
+fn eq_borrowed_arg_owned_constant() {
+    let mut mock = MockFoo::new();
+    mock.expect_borrowed()
+        .with(eq(String::from("foo")))
+        .return_const(1u32);
+    assert_eq!(1, mock.borrowed("foo"));
+}
+
+#[test]
+fn ne() {
+    let mut mock = MockFoo::new();
+    mock.expect_owned()
+        .with(predicate::ne("foo"))
+        .return_const(1u32);
+    mock.expect_borrowed()
+        .with(predicate::ne(String::from("foo")))
+        .return_const(2u32);
+    assert_eq!(1, mock.owned(String::from("bar")));
+    assert_eq!(2, mock.borrowed("bar"));
+}
+
+#[test]
+#[should_panic(expected = "Expectation(var == \"foo\")")]
+fn eq_mismatch() {
+    let mut mock = MockFoo::new();
+    mock.expect_owned()
+        .with(eq("foo"))
+        .return_const(1u32);
+    mock.owned(String::from("bar"));
+}
+
+#[test]
+fn str_helpers_owned_arg() {
+    let mut mock = MockFoo::new();
+    mock.expect_owned()
+        .with(str::starts_with("abc"))
+        .return_const(1u32);
+    mock.expect_owned()
+        .with(str::contains("x").count(2))
+        .return_const(2u32);
+    mock.expect_owned()
+        .with(str::ends_with("z").or(str::is_empty()))
+        .return_const(3u32);
+    assert_eq!(1, mock.owned(String::from("abcdef")));
+    assert_eq!(2, mock.owned(String::from("xyx")));
+    assert_eq!(3, mock.owned(String::new()));
+}
+
+#[test]
+fn str_helpers_borrowed_arg() {
+    let mut mock = MockFoo::new();
+    mock.expect_borrowed()
+        .with(str::starts_with("abc"))
+        .return_const(1u32);
+    mock.expect_borrowed()
+        .with(str::contains("x"))
+        .return_const(2u32);
+    assert_eq!(1, mock.borrowed("abcdef"));
+    assert_eq!(2, mock.borrowed("xyz"));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn matches_regex() {
+    let mut mock = MockFoo::new();
+    mock.expect_owned()
+        .with(str::matches_regex(r"^\d+$"))
+        .return_const(1u32);
+    mock.expect_borrowed()
+        .with(str::matches_regex(r"^\d+$"))
+        .return_const(2u32);
+    assert_eq!(1, mock.owned(String::from("123")));
+    assert_eq!(2, mock.borrowed("456"));
+}
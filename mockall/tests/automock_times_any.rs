@@ -0,0 +1,89 @@
+// vim: tw=80
+//! An expectation that may be called any number of times, either explicitly
+//! with `times_any` or by default.
+
+use mockall::*;
+
+#[automock]
+trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar(x: u32) -> u32;
+}
+
+/// With neither `times` nor `times_any`, an expectation may be called any
+/// number of times, including zero.
+#[test]
+fn default_never_called() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .return_const(0u32);
+    mock.checkpoint();
+}
+
+#[test]
+fn default_called_many_times() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .return_const(0u32);
+    for i in 0..100 {
+        mock.foo(i);
+    }
+    mock.checkpoint();
+}
+
+#[test]
+fn never_called() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times_any()
+        .return_const(0u32);
+    mock.checkpoint();
+}
+
+#[test]
+fn called_many_times() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times_any()
+        .return_const(0u32);
+    for i in 0..100 {
+        mock.foo(i);
+    }
+    mock.checkpoint();
+}
+
+/// `times_any` overrides an earlier call count
+#[test]
+fn overrides_times() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(2)
+        .times_any()
+        .return_const(0u32);
+    mock.foo(0);
+    mock.checkpoint();
+}
+
+/// Matchers and return values are still honored
+#[test]
+#[should_panic(expected = "MockFoo::foo: No matching expectation found")]
+fn honors_matcher() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(4))
+        .times_any()
+        .returning(|x| x + 1);
+    assert_eq!(5, mock.foo(4));
+    mock.foo(5);
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockFoo::bar_context();
+    ctx.expect()
+        .times_any()
+        .returning(|x| x + 1);
+    assert_eq!(5, MockFoo::bar(4));
+    assert_eq!(6, MockFoo::bar(5));
+    ctx.checkpoint();
+}
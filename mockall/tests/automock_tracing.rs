@@ -0,0 +1,108 @@
+// vim: tw=80
+//! With the tracing feature, every call emits an event, within the span of the
+//! expectation that it matched, if any
+#![cfg(feature = "tracing")]
+#![deny(warnings)]
+
+use mockall::*;
+use std::{
+    fmt,
+    sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}}
+};
+use tracing::{
+    Event,
+    Metadata,
+    Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record}
+};
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+}
+
+/// Formats an event's or span's fields, like `mock="MockFoo" matched=true`
+#[derive(Default)]
+struct Fields(Vec<String>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+/// Captures events, prefixed by the fields of their enclosing span
+#[derive(Clone, Default)]
+struct Capture {
+    next_id: Arc<AtomicU64>,
+    spans: Arc<Mutex<Vec<String>>>,
+    current: Arc<Mutex<Vec<u64>>>,
+    events: Arc<Mutex<Vec<String>>>
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        self.spans.lock().unwrap().push(format!("{}{{{}}}",
+            attrs.metadata().target(), fields.0.join(" ")));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let span = self.current.lock().unwrap().last()
+            .map(|id| self.spans.lock().unwrap()[*id as usize - 1].clone());
+        let line = format!("{}{{{}}}", event.metadata().target(),
+            fields.0.join(" "));
+        self.events.lock().unwrap().push(match span {
+            Some(span) => format!("{}: {}", span, line),
+            None => line
+        });
+    }
+
+    fn enter(&self, id: &Id) {
+        self.current.lock().unwrap().push(id.into_u64());
+    }
+
+    fn exit(&self, _: &Id) {
+        self.current.lock().unwrap().pop();
+    }
+}
+
+#[test]
+fn events() {
+    let capture = Capture::default();
+    tracing::subscriber::with_default(capture.clone(), || {
+        let mut mock = MockFoo::new();
+        mock.expect_foo()
+            .with(predicate::eq(1))
+            .return_const(42u32);
+        mock.foo(1);
+        let _guard = mockall::fallible();
+        mock.foo(2);
+    });
+    let spans = capture.spans.lock().unwrap();
+    assert_eq!(spans.len(), 1);
+    assert!(spans[0].starts_with(
+        "mockall::expect{mock=\"MockFoo\" method=\"foo\" location=mockall/tests/"),
+        "{}", spans[0]);
+    let events = capture.events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].starts_with(&format!("{}: mockall::call{{\
+        mock=\"MockFoo\" method=\"foo\" matched=true \
+        expectation=Expectation(var == 1) (set at ", spans[0])),
+        "{}", events[0]);
+    assert_eq!(events[1],
+        "mockall::call{mock=\"MockFoo\" method=\"foo\" matched=false}");
+}
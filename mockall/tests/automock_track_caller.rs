@@ -0,0 +1,160 @@
+// vim: tw=80
+//! A mock method's panics report the location of the call to it, rather than
+//! a location within Mockall
+#![deny(warnings)]
+
+use mockall::*;
+use std::{
+    cell::RefCell,
+    panic,
+    sync::Once
+};
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar(&self) -> &u32;
+    fn baz() -> u32;
+    #[track_caller]
+    fn bean(&self) -> u32;
+    fn gen<T: 'static>(&self, t: T) -> u32;
+}
+
+#[automock]
+pub mod m {
+    pub fn qux(_x: u32) -> u32 {
+        unimplemented!()
+    }
+}
+
+thread_local! {
+    /// Where the last panic happened, if this thread is expecting one
+    static LOCATION: RefCell<Option<Option<(String, u32)>>> =
+        const { RefCell::new(None) };
+}
+
+/// Run `f`, which must panic, and return the location that the panic reported
+fn panic_location<F: FnOnce()>(f: F) -> (String, u32) {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let expected = LOCATION.with(|l| {
+                let mut l = l.borrow_mut();
+                let loc = info.location().unwrap();
+                if l.is_some() {
+                    *l = Some(Some((loc.file().to_owned(), loc.line())));
+                }
+                l.is_some()
+            });
+            if !expected {
+                default_hook(info);
+            }
+        }));
+    });
+    LOCATION.with(|l| *l.borrow_mut() = Some(None));
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_err();
+    LOCATION.with(|l| l.borrow_mut().take()).unwrap().unwrap()
+}
+
+#[test]
+fn no_matching_expectation() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(4))
+        .return_const(0u32);
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock.foo(5);});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+#[test]
+fn called_too_many_times() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(1)
+        .return_const(0u32);
+    mock.foo(5);
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock.foo(5);});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+#[test]
+fn no_return_value() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().times(1);
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock.foo(5);});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+#[test]
+fn reference() {
+    let mock = MockFoo::new();
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock.bar();});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+#[test]
+fn static_method() {
+    let _ctx = MockFoo::baz_context();
+    let line = line!() + 1;
+    let loc = panic_location(|| {MockFoo::baz();});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+/// Including those of expectations for any generic parameters
+#[test]
+fn any_generic_parameters() {
+    let mut mock = MockFoo::new();
+    mock.expect_gen_any()
+        .times(1)
+        .return_const(0u32);
+    mock.gen(5u16);
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock.gen(5u16);});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+/// The original method's own `#[track_caller]` isn't repeated
+#[test]
+fn already_track_caller() {
+    let mock = MockFoo::new();
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock.bean();});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+#[test]
+fn module_function() {
+    let ctx = mock_m::qux_context();
+    ctx.expect()
+        .with(predicate::eq(2))
+        .return_const(0u32);
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock_m::qux(1);});
+    assert_eq!((file!().to_owned(), line), loc);
+}
+
+#[test]
+fn sequence_violation() {
+    let mut seq = Sequence::new();
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1))
+        .times(1)
+        .return_const(0u32)
+        .in_sequence(&mut seq);
+    mock.expect_foo()
+        .with(predicate::eq(2))
+        .times(1)
+        .return_const(0u32)
+        .in_sequence(&mut seq);
+    let line = line!() + 1;
+    let loc = panic_location(|| {mock.foo(2);});
+    assert_eq!((file!().to_owned(), line), loc);
+    // Satisfy the first expectation, too
+    mock.foo(1);
+}
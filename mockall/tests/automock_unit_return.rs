@@ -0,0 +1,74 @@
+// vim: tw=80
+//! Expectations of methods that return nothing needn't set a return value
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Writer {
+    fn flush(&mut self);
+    fn write(&self, x: u32) -> ();
+    fn write_generic<T: 'static>(&self, t: T);
+    fn reset();
+}
+
+#[test]
+fn no_args() {
+    let mut mock = MockWriter::new();
+    mock.expect_flush();
+    mock.flush();
+}
+
+#[test]
+fn times_and_matchers() {
+    let mut mock = MockWriter::new();
+    mock.expect_write()
+        .with(predicate::eq(1))
+        .times(2);
+    mock.write(1);
+    mock.write(1);
+    mock.checkpoint();
+}
+
+#[test]
+#[should_panic(expected = "MockWriter::write: No matching expectation found")]
+fn matcher_rejects() {
+    let mut mock = MockWriter::new();
+    mock.expect_write().with(predicate::eq(1));
+    mock.write(2);
+}
+
+#[test]
+fn sequence() {
+    let mut seq = Sequence::new();
+    let mut mock = MockWriter::new();
+    mock.expect_write().times(1).in_sequence(&mut seq);
+    mock.expect_flush().times(1).in_sequence(&mut seq);
+    mock.write(1);
+    mock.flush();
+}
+
+#[test]
+fn generic() {
+    let mut mock = MockWriter::new();
+    mock.expect_write_generic::<u16>().times(1);
+    mock.write_generic(1u16);
+}
+
+#[test]
+fn static_method() {
+    let ctx = MockWriter::reset_context();
+    ctx.expect().times(1);
+    MockWriter::reset();
+    ctx.checkpoint();
+}
+
+/// A return function may still be set
+#[test]
+fn returning() {
+    let mut mock = MockWriter::new();
+    mock.expect_write()
+        .times(1)
+        .returning(|x| assert_eq!(x, 5));
+    mock.write(5);
+}
@@ -0,0 +1,61 @@
+// vim: tw=80
+//! Traits and structs with some unsafe methods.  The mock methods are unsafe,
+//! but setting their expectations is not.
+
+use mockall::*;
+
+#[automock]
+trait Mem {
+    fn size(&self) -> usize;
+    unsafe fn poke(&self, addr: usize, value: u8);
+    unsafe fn peek(&self, addr: usize) -> u8;
+    unsafe fn reset(addr: usize) -> bool;
+}
+
+struct Device {}
+
+#[automock]
+#[allow(unused)]
+impl Device {
+    pub unsafe fn raw(&self, reg: u32) -> u32 {
+        reg
+    }
+}
+
+#[test]
+fn safe_method() {
+    let mut mock = MockMem::new();
+    mock.expect_size()
+        .return_const(1024usize);
+    assert_eq!(1024, mock.size());
+}
+
+#[test]
+fn unsafe_methods() {
+    let mut mock = MockMem::new();
+    mock.expect_poke()
+        .withf(|addr, value| *addr == 4 && *value == 5)
+        .return_const(());
+    mock.expect_peek()
+        .returning(|addr| addr as u8 + 1);
+    unsafe {
+        mock.poke(4, 5);
+        assert_eq!(4, mock.peek(3));
+    }
+}
+
+#[test]
+fn unsafe_static_method() {
+    let ctx = MockMem::reset_context();
+    ctx.expect()
+        .return_const(true);
+    assert!(unsafe { MockMem::reset(0) });
+}
+
+#[test]
+fn unsafe_inherent_method() {
+    let mut mock = MockDevice::new();
+    mock.expect_raw()
+        .returning(|reg| reg * 2);
+    assert_eq!(8, unsafe { mock.raw(4) });
+}
@@ -0,0 +1,113 @@
+// vim: tw=80
+//! verify_all checks the expectations of every registered mock on the current
+//! thread
+#![deny(warnings)]
+
+use mockall::*;
+use std::{panic, thread};
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, x: u32) -> u32;
+    fn bar<T: 'static>(&self, t: T);
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn ok() {
+    let mut mock = MockFoo::new_registered();
+    mock.expect_foo()
+        .times(1)
+        .return_const(0u32);
+    mock.foo(1);
+    verify_all();
+}
+
+/// All failures of all registered mocks are reported together
+#[test]
+fn aggregate() {
+    let mut mock1 = MockFoo::new_registered();
+    mock1.expect_foo()
+        .with(predicate::eq(1))
+        .times(1)
+        .return_const(0u32);
+    let mut mock2 = MockFoo::new_registered();
+    mock2.expect_bar::<u16>()
+        .times(2)
+        .return_const(());
+    mock2.bar(0u16);
+    let msg = panic_msg(verify_all);
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "2 expectations were not satisfied:", "{}", msg);
+    assert!(lines[1].starts_with(
+        "- MockFoo::foo: Expectation(var == 1) called fewer than 1 times (set at "),
+        "{}", msg);
+    assert!(lines[2].starts_with(
+        "- MockFoo::bar: Expectation(<anything>) called fewer than 2 times"),
+        "{}", msg);
+    assert_eq!(lines[3], "All expectations:");
+    // Failures aren't reported again, neither by verify_all nor on drop
+    verify_all();
+}
+
+/// Unregistered mocks aren't verified
+#[test]
+fn opt_in() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(1)
+        .return_const(0u32);
+    verify_all();
+    mock.foo(1);
+}
+
+/// Registration doesn't keep mocks' expectations alive
+#[test]
+fn weak() {
+    {
+        let mut mock = MockFoo::new_registered();
+        mock.expect_foo()
+            .return_const(0u32);
+    }
+    let mut mock = MockFoo::new_registered();
+    mock.expect_foo()
+        .times(1)
+        .return_const(0u32);
+    let msg = panic_msg(verify_all);
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{}", msg);
+    assert!(lines[2].ends_with(": exactly 1 time, called 0 times, unsatisfied"),
+        "{}", msg);
+}
+
+/// Each thread has its own registry
+#[test]
+fn per_thread() {
+    let mut mock = MockFoo::new_registered();
+    mock.expect_foo()
+        .times(1)
+        .return_const(0u32);
+    thread::spawn(verify_all).join().unwrap();
+    mock.foo(1);
+}
+
+/// Reporting an expectation doesn't change what calls it accepts
+#[test]
+#[should_panic(expected = "called more than 1 times")]
+fn reported_keeps_times() {
+    let mut mock = MockFoo::new_registered();
+    mock.expect_foo()
+        .times(1)
+        .return_const(0u32);
+    panic_msg(verify_all);
+    mock.foo(1);
+    assert!(mock.report().ends_with(": exactly 1 time, called 1 time, satisfied"),
+        "{}", mock.report());
+    mock.foo(2);
+}
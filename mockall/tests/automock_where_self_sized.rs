@@ -0,0 +1,38 @@
+// vim: tw=80
+//! Trait methods bounded by `where Self: Sized`
+#![deny(warnings)]
+
+use mockall::*;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self) -> u32;
+    fn required(self) -> u32 where Self: Sized;
+    fn twice<F: Fn(u32) -> u32>(self, f: F) -> Twice<Self, F> where Self: Sized {
+        Twice(self, f)
+    }
+}
+
+pub struct Twice<T, F>(T, F);
+
+impl<T: Foo, F: Fn(u32) -> u32> Twice<T, F> {
+    pub fn get(&self) -> u32 {
+        (self.1)((self.1)(self.0.foo()))
+    }
+}
+
+/// A required method with the bound gets mocked normally
+#[test]
+fn required() {
+    let mut mock = MockFoo::new();
+    mock.expect_required().return_const(42u32);
+    assert_eq!(42, mock.required());
+}
+
+/// A provided method with the bound isn't mocked, so its default body is used
+#[test]
+fn provided() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo().return_const(3u32);
+    assert_eq!(12, mock.twice(|x| x * 2).get());
+}
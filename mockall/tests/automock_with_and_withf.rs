@@ -0,0 +1,108 @@
+// vim: tw=80
+//! An expectation may use both `with` and `withf`.  A call must satisfy both.
+#![deny(warnings)]
+
+use mockall::*;
+use std::panic;
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, topic: u32, payload: &str) -> u32;
+    fn bar<T: 'static>(&self, t: T) -> u32;
+}
+
+fn panic_msg<F: FnOnce()>(f: F) -> String {
+    *panic::catch_unwind(panic::AssertUnwindSafe(f))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap()
+}
+
+#[test]
+fn pass() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1), predicate::always())
+        .withf(|_, payload| payload.len() < 4)
+        .return_const(42u32);
+    assert_eq!(42, mock.foo(1, "abc"));
+}
+
+/// The order of `with` and `withf` doesn't matter
+#[test]
+fn withf_first() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .withf(|_, payload| payload.len() < 4)
+        .with(predicate::eq(1), predicate::always())
+        .return_const(42u32);
+    assert_eq!(42, mock.foo(1, "abc"));
+}
+
+#[test]
+fn pred_fails() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1), predicate::always())
+        .withf(|_, payload| payload.len() < 4)
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.foo(2, "abc");});
+    let lines = msg.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2, "{}", msg);
+    assert!(lines[1].starts_with(
+        "#1 Expectation(var == 1, true, <function>) (set at "), "{}", msg);
+    assert!(lines[1].ends_with(
+        ": rejected by the `with` predicates: `var == 1` failed"),
+        "{}", msg);
+}
+
+#[test]
+fn func_fails() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1), predicate::always())
+        .withf(|_, payload| payload.len() < 4)
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.foo(1, "abcdef");});
+    assert!(msg.ends_with(": rejected by the `withf` function"), "{}", msg);
+}
+
+/// Predicates are evaluated first, so a call that fails both is blamed on them
+#[test]
+fn both_fail() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1), predicate::always())
+        .withf(|_, payload| payload.len() < 4)
+        .return_const(42u32);
+    let msg = panic_msg(|| {mock.foo(2, "abcdef");});
+    assert!(msg.ends_with(
+        ": rejected by the `with` predicates: `var == 1` failed"), "{}", msg);
+}
+
+/// A second `withf` replaces the first, but keeps the predicates
+#[test]
+fn withf_replaces_withf() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(predicate::eq(1), predicate::always())
+        .withf(|_, _| false)
+        .withf(|_, _| true)
+        .return_const(42u32);
+    assert_eq!(42, mock.foo(1, "abc"));
+    let msg = panic_msg(|| {mock.foo(2, "abc");});
+    assert!(msg.ends_with(
+        ": rejected by the `with` predicates: `var == 1` failed"), "{}", msg);
+}
+
+#[test]
+fn generic() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar::<u32>()
+        .with(predicate::gt(1))
+        .withf_st(|t| t % 2 == 0)
+        .return_const(42u32);
+    assert_eq!(42, mock.bar(4u32));
+    let msg = panic_msg(|| {mock.bar(3u32);});
+    assert!(msg.ends_with(": rejected by the `withf` function"), "{}", msg);
+}
@@ -0,0 +1,39 @@
+// vim: tw=80
+//! Constructing a mock object and setting its expectations with a closure
+
+use mockall::*;
+
+#[automock]
+pub trait Db {
+    fn get(&self, key: u32) -> Option<u32>;
+}
+
+#[automock]
+pub trait Container<T: 'static> {
+    fn first(&self) -> T;
+}
+
+struct Service {
+    db: MockDb
+}
+
+#[test]
+fn struct_literal() {
+    let service = Service {
+        db: MockDb::with_expectations(|m| {
+            m.expect_get()
+                .with(predicate::eq(1))
+                .return_const(Some(2));
+        })
+    };
+    assert_eq!(Some(2), service.db.get(1));
+}
+
+#[test]
+fn generic() {
+    let mock = MockContainer::<u32>::with_expectations(|m| {
+        m.expect_first()
+            .return_const(42u32);
+    });
+    assert_eq!(42, mock.first());
+}
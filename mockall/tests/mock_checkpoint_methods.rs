@@ -0,0 +1,136 @@
+// vim: tw=80
+//! Each mocked method gets its own checkpoint method, which verifies and clears
+//! only that method's expectations.
+
+use mockall::*;
+
+trait Bar {
+    fn bar(&self) -> u32;
+}
+
+mock! {
+    Foo {
+        fn foo(&self) -> u32;
+        fn baz(&self) -> u32;
+        fn sfoo() -> u32;
+    }
+    trait Bar {
+        fn bar(&self) -> u32;
+    }
+}
+
+mock! {
+    Gen<T: 'static> {
+        fn gfoo(&self, t: T) -> u32;
+        fn gsfoo(t: T) -> u32;
+    }
+}
+
+#[test]
+fn ok() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(1)
+        .return_const(1u32);
+    mock.foo();
+    mock.checkpoint_foo();
+}
+
+/// Other methods' expectations are left alone
+#[test]
+fn other_methods_untouched() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .return_const(1u32);
+    mock.expect_baz()
+        .times(1)
+        .return_const(2u32);
+    mock.checkpoint_foo();
+    assert_eq!(2, mock.baz());
+}
+
+#[test]
+#[should_panic(expected =
+    "MockFoo::foo: Expectation(<anything>) called fewer than 1 times")]
+fn not_yet_satisfied() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .times(1)
+        .return_const(1u32);
+    mock.expect_baz()
+        .times(1)
+        .return_const(2u32);
+    mock.baz();
+    mock.checkpoint_foo();
+}
+
+#[test]
+#[should_panic(expected = "MockFoo::foo: No matching expectation found")]
+fn removes_old_expectations() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .return_const(1u32);
+    mock.checkpoint_foo();
+    mock.foo();
+}
+
+#[test]
+fn trait_method() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar()
+        .times(1)
+        .return_const(1u32);
+    assert_eq!(1, mock.bar());
+    mock.checkpoint_bar();
+}
+
+#[test]
+#[should_panic(expected =
+    "MockFoo::bar: Expectation(<anything>) called fewer than 1 times")]
+fn trait_method_not_yet_satisfied() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar()
+        .times(1)
+        .return_const(1u32);
+    mock.checkpoint_bar();
+}
+
+/// The aggregate checkpoint still covers trait methods
+#[test]
+#[should_panic(expected =
+    "MockFoo::bar: Expectation(<anything>) called fewer than 1 times")]
+fn aggregate_checkpoint() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar()
+        .times(1)
+        .return_const(1u32);
+    mock.checkpoint();
+}
+
+#[test]
+#[should_panic(expected =
+    "MockFoo::sfoo: Expectation(<anything>) called fewer than 1 times")]
+fn static_method() {
+    let ctx = MockFoo::sfoo_context();
+    ctx.expect()
+        .times(1)
+        .return_const(1u32);
+    MockFoo::checkpoint_sfoo();
+}
+
+#[test]
+fn generic_struct() {
+    let mut mock = MockGen::<u8>::new();
+    mock.expect_gfoo()
+        .times(1)
+        .return_const(1u32);
+    mock.gfoo(0);
+    mock.checkpoint_gfoo();
+
+    let ctx = MockGen::<u8>::gsfoo_context();
+    ctx.expect()
+        .times(1)
+        .return_const(2u32);
+    MockGen::<u8>::gsfoo(0);
+    MockGen::<u8>::checkpoint_gsfoo();
+}
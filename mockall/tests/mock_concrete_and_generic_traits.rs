@@ -0,0 +1,56 @@
+// vim: tw=80
+//! A generic struct that implements both a concrete trait and a trait that's
+//! generic over the struct's own parameters
+#![deny(warnings)]
+
+use mockall::*;
+use std::fmt::Debug;
+
+pub trait Named {
+    fn name(&self) -> impl Debug + Send;
+}
+
+pub trait Store<K> {
+    fn get(&self, k: K) -> u32;
+}
+
+pub trait Lookup<K, V> {
+    fn lookup(&self, k: K) -> V;
+}
+
+mock! {
+    pub Foo<K: 'static, V: 'static> {
+        fn size(&self) -> usize;
+    }
+    trait Named {
+        fn name(&self) -> impl Debug + Send;
+    }
+    trait Store<K> {
+        fn get(&self, k: K) -> u32;
+    }
+    trait Lookup<K, V> {
+        fn lookup(&self, k: K) -> V;
+    }
+}
+
+#[test]
+fn returning() {
+    let mut mock = MockFoo::<u32, i16>::new();
+    mock.expect_size().return_const(0usize);
+    mock.expect_name().returning(|| Box::new("foo"));
+    mock.expect_get().returning(|k| k + 1);
+    mock.expect_lookup().returning(|k| -(k as i16));
+    assert_eq!(0, mock.size());
+    assert_eq!("\"foo\"", format!("{:?}", mock.name()));
+    assert_eq!(5, mock.get(4));
+    assert_eq!(-4, mock.lookup(4));
+}
+
+#[test]
+#[should_panic(expected = "called fewer than 1 times")]
+fn checkpoint() {
+    let mut mock = MockFoo::<u32, i16>::new();
+    mock.expect_get().times(1).return_const(0u32);
+    mock.expect_name().returning(|| Box::new("foo"));
+    mock.checkpoint();
+}
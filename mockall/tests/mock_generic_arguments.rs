@@ -51,7 +51,8 @@ mod with {
     }
 
     #[test]
-    #[should_panic(expected = "MockFoo::foo: No matching expectation found")]
+    #[should_panic(expected =
+        "MockFoo::foo::<i32>: no expectation registered for this")]
     fn wrong_generic_type() {
         let mut mock = MockFoo::new();
         mock.expect_foo::<i16>()
@@ -21,3 +21,27 @@ fn returning() {
         .returning(|g| g.t);
     assert_eq!(42u32, mock.bar(G{t: 42}));
 }
+
+trait Serialize {}
+impl Serialize for u32 {}
+
+mock! {
+    Store {
+        fn store<K: Clone, V>(&self, k: K, v: V)
+            where K: Send + 'static, V: Serialize + 'static;
+    }
+}
+
+/// The where clause is merged with the inline bounds
+fn expect_store<K, V>(mock: &mut MockStore)
+    where K: Clone + Send + 'static, V: Serialize + 'static
+{
+    mock.expect_store::<K, V>().times(1).return_const(());
+}
+
+#[test]
+fn two_predicates() {
+    let mut mock = MockStore::new();
+    expect_store::<u8, u32>(&mut mock);
+    mock.store(1u8, 2u32);
+}
@@ -14,9 +14,7 @@ mock! {
     }
 }
 
-lazy_static! {
-    static ref FOO_MTX: Mutex<()> = Mutex::new(());
-}
+static FOO_MTX: Mutex<()> = Mutex::new(());
 
 // Checkpointing the mock object should not checkpoint static methods too
 #[test]
@@ -32,10 +30,11 @@ fn checkpoint() {
     MockFoo::foo(42u32, 69i16);
 }
 
-// It should also be possible to checkpoint just the context object
+// It should also be possible to checkpoint just the context object.  The
+// message names the struct's generic parameters as well as the method's.
 #[test]
 #[should_panic(expected =
-    "MockFoo::foo2: Expectation(<anything>) called fewer than 1 times")]
+    "MockFoo::foo2::<u32, i16>: Expectation(<anything>) called fewer than 1 times")]
 fn ctx_checkpoint() {
     let ctx = MockFoo::<u32>::foo2_context();
     ctx.expect::<i16>()
@@ -47,7 +46,8 @@ fn ctx_checkpoint() {
 
 // Expectations should be cleared when a context object drops
 #[test]
-#[should_panic(expected = "MockFoo::foo3: No matching expectation found")]
+#[should_panic(expected =
+    "MockFoo::foo3::<i32, i32>: no expectation registered for this")]
 fn ctx_hygiene() {
     {
         let ctx0 = MockFoo::<u32>::foo3_context();
@@ -0,0 +1,75 @@
+// vim: tw=80
+//! Mocked methods whose names collide with Mockall's generated helper methods
+//! keep their names.  The helpers get a `mockall_` prefix instead.
+#![deny(warnings)]
+
+use mockall::*;
+
+pub trait Validator {
+    fn checkpoint(&mut self) -> bool;
+    fn default() -> u32;
+}
+
+mock! {
+    pub Foo {
+        fn foo(&self) -> u32;
+        fn expect_foo(&self) -> u32;
+        fn bar() -> u32;
+        fn bar_context(&self) -> u32;
+        fn checkpoint_foo(&self) -> u32;
+    }
+    trait Validator {
+        fn checkpoint(&mut self) -> bool;
+        fn default() -> u32;
+    }
+}
+
+#[test]
+fn expect() {
+    let mut mock = MockFoo::new();
+    mock.mockall_expect_foo().return_const(1u32);
+    mock.expect_expect_foo().return_const(2u32);
+    assert_eq!(1, mock.foo());
+    assert_eq!(2, mock.expect_foo());
+}
+
+#[test]
+fn context() {
+    let mut mock = MockFoo::new();
+    mock.expect_bar_context().return_const(3u32);
+    assert_eq!(3, mock.bar_context());
+
+    let ctx = MockFoo::mockall_bar_context();
+    ctx.expect().return_const(4u32);
+    assert_eq!(4, MockFoo::bar());
+}
+
+#[test]
+fn checkpoints() {
+    let mut mock = MockFoo::new();
+    mock.expect_checkpoint().return_const(true);
+    mock.expect_checkpoint_foo().return_const(5u32);
+    assert!(mock.checkpoint());
+    assert_eq!(5, mock.checkpoint_foo());
+    mock.mockall_expect_foo().times(1).return_const(1u32);
+    mock.foo();
+    mock.mockall_checkpoint_foo();
+    mock.mockall_checkpoint();
+}
+
+#[test]
+#[should_panic(expected = "called fewer than 1 times")]
+fn checkpoint_validates() {
+    let mut mock = MockFoo::new();
+    mock.mockall_expect_foo().times(1).return_const(1u32);
+    mock.mockall_checkpoint();
+}
+
+/// The trait's `default` method doesn't interfere with `Default::default`
+#[test]
+fn default() {
+    let ctx = MockFoo::default_context();
+    ctx.expect().return_const(6u32);
+    assert_eq!(6, <MockFoo as Validator>::default());
+    let _mock: MockFoo = Default::default();
+}
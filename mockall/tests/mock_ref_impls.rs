@@ -0,0 +1,52 @@
+// vim: tw=80
+//! mock! can implement the mocked traits for references to a generic mock
+//! struct, too
+#![deny(warnings)]
+
+use mockall::*;
+
+pub trait Getter<T> {
+    fn get(&self) -> T;
+}
+
+pub trait Setter<T> {
+    fn set(&mut self, t: T);
+}
+
+mock! {
+    #[ref_impls]
+    pub Foo<T: Clone + 'static> {}
+    trait Getter<T> {
+        fn get(&self) -> T;
+    }
+    trait Setter<T> {
+        fn set(&mut self, t: T);
+    }
+}
+
+fn get<G: Getter<u32>>(g: G) -> u32 {
+    g.get()
+}
+
+fn set<S: Setter<u32>>(mut s: S, t: u32) {
+    s.set(t)
+}
+
+#[test]
+fn shared_ref() {
+    let mut mock = MockFoo::<u32>::new();
+    mock.expect_get().return_const(5u32);
+    assert_eq!(5, get(&mock));
+    assert_eq!(5, get(&mut mock));
+}
+
+#[test]
+fn mut_ref() {
+    let mut mock = MockFoo::<u32>::new();
+    mock.expect_set()
+        .with(predicate::eq(6))
+        .times(1)
+        .return_const(());
+    set(&mut mock, 6);
+    mock.checkpoint();
+}
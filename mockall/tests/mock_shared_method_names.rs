@@ -0,0 +1,73 @@
+// vim: tw=80
+//! When two mocked traits have methods of the same name, their expectation
+//! methods are qualified by the trait's name.
+#![deny(warnings)]
+
+use mockall::*;
+
+pub trait Read {
+    fn read(&mut self, x: u32) -> u32;
+    fn open() -> u32;
+}
+
+pub trait Validate {
+    fn read(&self) -> bool;
+    fn open() -> u32;
+}
+
+mock! {
+    pub Foo {
+        fn open(&self) -> u32;
+    }
+    trait Read {
+        fn read(&mut self, x: u32) -> u32;
+        fn open() -> u32;
+    }
+    trait Validate {
+        fn read(&self) -> bool;
+        fn open() -> u32;
+    }
+}
+
+#[test]
+fn qualified() {
+    let mut mock = MockFoo::new();
+    mock.expect_Read_read().returning(|x| x + 1);
+    mock.expect_Validate_read().return_const(true);
+    assert_eq!(5, Read::read(&mut mock, 4));
+    assert!(Validate::read(&mock));
+}
+
+/// An inherent method keeps the short form, while trait methods of the same
+/// name are qualified.
+#[test]
+fn inherent() {
+    let mut mock = MockFoo::new();
+    mock.expect_open().return_const(1u32);
+    assert_eq!(1, mock.open());
+
+    let read_ctx = MockFoo::Read_open_context();
+    read_ctx.expect().return_const(2u32);
+    let validate_ctx = MockFoo::Validate_open_context();
+    validate_ctx.expect().return_const(3u32);
+    assert_eq!(2, <MockFoo as Read>::open());
+    assert_eq!(3, <MockFoo as Validate>::open());
+}
+
+#[test]
+#[should_panic(expected = "called fewer than 1 times")]
+fn checkpoint_first() {
+    let mut mock = MockFoo::new();
+    mock.expect_Read_read().times(1).return_const(0u32);
+    mock.expect_Validate_read().return_const(true);
+    mock.checkpoint();
+}
+
+#[test]
+#[should_panic(expected = "called fewer than 1 times")]
+fn checkpoint_second() {
+    let mut mock = MockFoo::new();
+    mock.expect_Read_read().return_const(0u32);
+    mock.expect_Validate_read().times(1).return_const(true);
+    mock.checkpoint();
+}
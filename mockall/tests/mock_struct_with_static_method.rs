@@ -12,9 +12,7 @@ mock!{
     }
 }
 
-lazy_static! {
-    static ref BAR_MTX: Mutex<()> = Mutex::new(());
-}
+static BAR_MTX: Mutex<()> = Mutex::new(());
 
 // Checkpointing the mock object should not checkpoint static methods
 #[test]
@@ -101,3 +99,12 @@ fn two_matches() {
     assert_eq!(101, MockFoo::bar(69));
     assert_eq!(99, MockFoo::bar(42));
 }
+
+// Mockall's re-export of lazy_static is deprecated, but still works.
+#[test]
+fn lazy_static_compat() {
+    lazy_static! {
+        static ref X: Mutex<u32> = Mutex::new(42);
+    }
+    assert_eq!(42, *X.lock().unwrap());
+}
@@ -0,0 +1,36 @@
+// vim: tw=80
+//! Everything needed to build a matcher comes from `mockall::predicate`
+#![deny(warnings)]
+
+use mockall::{automock, predicate::*};
+
+#[automock]
+pub trait Foo {
+    fn foo(&self, name: &str, x: u32, y: f64) -> u32;
+}
+
+#[test]
+fn combinators() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(str::starts_with("foo").trim(),
+              gt(1).and(lt(5)).and(ne(3)),
+              float::is_close(0.3))
+        .return_const(1u32);
+    mock.expect_foo()
+        .with(str::contains("bar").not(), always(), never().or(always()))
+        .return_const(2u32);
+    assert_eq!(1, mock.foo(" food ", 2, 0.1 + 0.2));
+    assert_eq!(2, mock.foo("food", 3, 0.3));
+}
+
+#[test]
+fn boxed() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(str::ends_with("oo"),
+              function(|x: &u32| *x % 2 == 0).boxed(),
+              ge(0.0).and(le(1.0)).boxed())
+        .return_const(1u32);
+    assert_eq!(1, mock.foo("foo", 4, 0.5));
+}
@@ -0,0 +1,71 @@
+// vim: tw=80
+//! Mock objects exported for use by other crates.
+//!
+//! This crate only exists to test that Mockall's mock objects can be defined
+//! in one crate and used in another.  Its integration tests are the consumers.
+#![deny(missing_docs, warnings)]
+
+use mockall::*;
+
+/// A trait with several kinds of method
+#[automock]
+pub trait Foo {
+    /// A method with a `'static` return type
+    fn foo(&self, x: u32) -> u32;
+    /// A method returning a reference
+    fn bar(&self) -> &u32;
+    /// A method returning a mutable reference
+    fn baz(&mut self) -> &mut u32;
+    /// A generic method
+    fn generic<T: 'static>(&self, t: T) -> u32;
+    /// A static method
+    fn bang(x: u32) -> u32;
+}
+
+/// A generic trait
+#[automock]
+pub trait Generic<T: 'static> {
+    /// A method using the trait's generic parameter
+    fn get(&self) -> T;
+}
+
+/// A struct with an inherent impl
+pub struct Thing {}
+#[automock]
+#[allow(dead_code)]
+impl Thing {
+    /// A method
+    pub fn thing(&self, x: i16) -> i16 { x }
+    /// A static method
+    pub fn make() -> u32 { 0 }
+}
+
+/// A crate-private struct.  Its mock is crate-private too.
+#[allow(dead_code)]
+pub(crate) struct Private {}
+#[automock]
+#[allow(dead_code)]
+impl Private {
+    pub fn private(&self) -> u32 { 0 }
+}
+
+mock! {
+    pub Multi {
+        fn multi(&self) -> u32;
+    }
+    trait Foo {
+        fn foo(&self, x: u32) -> u32;
+        fn bar(&self) -> &u32;
+        fn baz(&mut self) -> &mut u32;
+        fn generic<T: 'static>(&self, t: T) -> u32;
+        fn bang(x: u32) -> u32;
+    }
+}
+
+/// A module of free functions
+#[automock]
+#[allow(dead_code)]
+pub mod ops {
+    /// A free function
+    pub fn op(x: u32) -> u32 { x }
+}
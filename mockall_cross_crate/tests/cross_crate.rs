@@ -0,0 +1,63 @@
+// vim: tw=80
+//! Set expectations on mock objects that were defined in another crate
+#![deny(warnings)]
+
+use mockall::predicate::*;
+use mockall_cross_crate::*;
+
+#[test]
+fn trait_methods() {
+    let mut mock = MockFoo::new();
+    mock.expect_foo()
+        .with(eq(4))
+        .returning(|x| x + 1);
+    mock.expect_bar().return_const(6u32);
+    mock.expect_baz().return_var(7u32);
+    mock.expect_generic::<i8>().return_const(8u32);
+    assert_eq!(5, mock.foo(4));
+    assert_eq!(6, *mock.bar());
+    assert_eq!(7, *mock.baz());
+    assert_eq!(8, mock.generic(0i8));
+    mock.checkpoint();
+}
+
+#[test]
+fn trait_static_method() {
+    let ctx = MockFoo::bang_context();
+    ctx.expect().returning(|x| x * 2);
+    assert_eq!(8, MockFoo::bang(4));
+}
+
+#[test]
+fn generic_trait() {
+    let mut mock = MockGeneric::<u64>::new();
+    mock.expect_get().return_const(9u64);
+    assert_eq!(9, mock.get());
+}
+
+#[test]
+fn struct_methods() {
+    let mut mock = MockThing::new();
+    mock.expect_thing().returning(|x| -x);
+    assert_eq!(-3, mock.thing(3));
+
+    let ctx = MockThing::make_context();
+    ctx.expect().return_const(10u32);
+    assert_eq!(10, MockThing::make());
+}
+
+#[test]
+fn mock_macro() {
+    let mut mock = MockMulti::new();
+    mock.expect_multi().return_const(11u32);
+    mock.expect_foo().return_const(12u32);
+    assert_eq!(11, mock.multi());
+    assert_eq!(12, mock.foo(0));
+}
+
+#[test]
+fn module() {
+    let ctx = mock_ops::op_context();
+    ctx.expect().return_const(13u32);
+    assert_eq!(13, mock_ops::op(0));
+}
@@ -1,25 +1,89 @@
 // vim: tw=80
 use super::*;
-use quote::ToTokens;
+use quote::{ToTokens, quote_spanned};
 use std::{
     collections::HashMap,
-    env
+    env,
+    fs,
+    path
 };
 use syn::parse::{Parse, ParseStream};
 
+mod kw {
+    syn::custom_keyword!(derive);
+    syn::custom_keyword!(inner);
+    syn::custom_keyword!(methods);
+    syn::custom_keyword!(path);
+    syn::custom_keyword!(ref_impls);
+}
+
 /// A single automock attribute
 // This enum is very short-lived, so it's fine not to box it.
 #[allow(clippy::large_enum_variant)]
 enum Attr {
+    Derive(Punctuated<Path, Token![,]>),
+    Inner(Type),
+    Methods(Punctuated<Ident, Token![,]>),
     Mod(ItemMod),
+    Path(LitStr),
+    RefImpls,
+    Trait(Ident),
     Type(TraitItemType),
 }
 
 impl Parse for Attr {
     fn parse(input: ParseStream) -> parse::Result<Self> {
         let lookahead = input.lookahead1();
-        if lookahead.peek(Token![mod]) {
+        if lookahead.peek(kw::derive) {
+            input.parse::<kw::derive>()?;
+            let content;
+            parenthesized!(content in input);
+            let paths = content.parse_terminated(Path::parse_mod_style)?;
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            Ok(Attr::Derive(paths))
+        } else if lookahead.peek(kw::inner) {
+            input.parse::<kw::inner>()?;
+            input.parse::<Token![=]>()?;
+            let ty: Type = input.parse()?;
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            Ok(Attr::Inner(ty))
+        } else if lookahead.peek(kw::methods) {
+            input.parse::<kw::methods>()?;
+            let content;
+            parenthesized!(content in input);
+            let idents = content.parse_terminated(Ident::parse)?;
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            Ok(Attr::Methods(idents))
+        } else if lookahead.peek(Token![mod]) {
             input.parse().map(Attr::Mod)
+        } else if lookahead.peek(kw::path) {
+            input.parse::<kw::path>()?;
+            input.parse::<Token![=]>()?;
+            let path: LitStr = input.parse()?;
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            Ok(Attr::Path(path))
+        } else if lookahead.peek(kw::ref_impls) {
+            input.parse::<kw::ref_impls>()?;
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            Ok(Attr::RefImpls)
+        } else if lookahead.peek(Token![trait]) {
+            input.parse::<Token![trait]>()?;
+            input.parse::<Token![=]>()?;
+            let ident: Ident = input.parse()?;
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            Ok(Attr::Trait(ident))
         } else if lookahead.peek(Token![type]) {
             input.parse().map(Attr::Type)
         } else {
@@ -32,20 +96,122 @@ impl Parse for Attr {
 #[derive(Debug, Default)]
 struct Attrs {
     attrs: HashMap<Ident, Type>,
-    modname: Option<Ident>
+    /// The trait bound of each associated type that has exactly one, besides
+    /// auto traits.  Used to qualify projections like `Self::Conn::Error`.
+    bounds: HashMap<Ident, Path>,
+    /// Leave projections through associated types alone for now, because
+    /// their bounds aren't known yet.
+    defer_projections: bool,
+    /// Extra derive macros for the mock struct
+    derives: Vec<Path>,
+    /// A real type to forward calls to, when they match no expectation
+    inner: Option<Type>,
+    /// Only mock these methods.  The rest will panic if called.
+    methods: Option<Vec<Ident>>,
+    modname: Option<Ident>,
+    /// Source file of an out-of-line module, relative to the crate root
+    path: Option<LitStr>,
+    /// Also implement the mocked traits for references to the mock struct
+    ref_impls: bool,
+    /// Name of a trait to extract from an inherent impl block
+    trait_: Option<Ident>
 }
 
 impl Attrs {
     fn get_path(&self, path: &Path) -> Option<Type> {
-        if path.leading_colon.is_none() & (path.segments.len() == 2) {
-            if path.segments.first().unwrap().ident == "Self" {
-                let ident = &path.segments.last().unwrap().ident;
-                self.attrs.get(ident).cloned()
-            } else {
-                None
+        if path.leading_colon.is_some() || path.segments.len() < 2 ||
+            path.segments[0].ident != "Self"
+        {
+            return None;
+        }
+        let ident = &path.segments[1].ident;
+        let ty = self.attrs.get(ident)?;
+        match path.segments.len() {
+            2 => Some(ty.clone()),
+            _ if self.defer_projections => None,
+            3 => Some(self.project(ident, ty, &path.segments[2])),
+            _ => {
+                compile_error(path.span(),
+                    "Mockall can only project through one associated type.  Use fully qualified syntax instead, like `<<Self::A as Foo>::B as Bar>::C`.");
+                Some(ty.clone())
             }
+        }
+    }
+
+    /// Project through the substitute for associated type `ident`, like
+    /// `Self::Conn::Error`.  Qualify it with the associated type's bound if
+    /// possible, because otherwise it's ambiguous for most types.
+    fn project(&self, ident: &Ident, ty: &Type, seg: &PathSegment) -> Type {
+        if let Some(bound) = self.bounds.get(ident) {
+            let mut bound = TypeParamBound::Trait(TraitBound {
+                paren_token: None,
+                modifier: TraitBoundModifier::None,
+                lifetimes: None,
+                path: bound.clone()
+            });
+            self.substitute_type_param_bound(&mut bound);
+            parse_quote!(<#ty as #bound>::#seg)
+        } else if let Type::Path(TypePath{qself: None, path}) = ty {
+            let mut path = path.clone();
+            path.segments.push(seg.clone());
+            Type::Path(TypePath{qself: None, path})
         } else {
-            None
+            compile_error(ty.span(),
+                "Can't project through this associated type without knowing its trait.  Use fully qualified syntax instead, like `<Self::A as Foo>::B`.");
+            ty.clone()
+        }
+    }
+
+    /// Learn the bounds of the trait's associated types, and use them to
+    /// finish substituting the associated types defined in terms of others.
+    fn learn_bounds(&mut self, item: &ItemTrait) {
+        for ti in item.items.iter() {
+            if let TraitItem::Type(tity) = ti {
+                let mut paths = tity.bounds.iter().filter_map(|b| match b {
+                    TypeParamBound::Trait(tb)
+                        if tb.modifier == TraitBoundModifier::None &&
+                            !is_auto_trait(&tb.path) => Some(&tb.path),
+                    _ => None
+                });
+                if let (Some(path), None) = (paths.next(), paths.next()) {
+                    // A qualified path can't constrain the trait's associated
+                    // types, like `<T as Deref<Target = u32>>::Target`
+                    let mut path = path.clone();
+                    let seg = path.segments.last_mut().unwrap();
+                    if let PathArguments::AngleBracketed(abga) =
+                        &mut seg.arguments
+                    {
+                        abga.args = abga.args.iter()
+                            .filter(|arg| !matches!(arg,
+                                GenericArgument::Binding(_) |
+                                GenericArgument::Constraint(_)))
+                            .cloned()
+                            .collect();
+                        if abga.args.is_empty() {
+                            seg.arguments = PathArguments::None;
+                        }
+                    }
+                    self.bounds.insert(tity.ident.clone(), path);
+                }
+            }
+        }
+        self.resolve_nested(false);
+    }
+
+    /// An associated type may be defined in terms of another one, like
+    /// `type Conn = Pool<Self::Inner>;`.  Substitute those, too.  Each pass
+    /// resolves one more level of indirection.
+    fn resolve_nested(&mut self, defer_projections: bool) {
+        for _ in 0..self.attrs.len() {
+            let prev = Attrs{
+                attrs: self.attrs.clone(),
+                bounds: self.bounds.clone(),
+                defer_projections,
+                ..Default::default()
+            };
+            for ty in self.attrs.values_mut() {
+                prev.substitute_type(ty);
+            }
         }
     }
 
@@ -106,17 +272,23 @@ impl Attrs {
                 }
             }
             Type::Path(path) => {
-                if let Some(ref qself) = path.qself {
-                    let qp = if let Type::Path(p) = qself.ty.as_ref() {
-                        &p.path
-                    } else {
-                        panic!("QSelf's type isn't a path?")
+                if let Some(qself) = path.qself.as_mut() {
+                    let is_self = match qself.ty.as_ref() {
+                        Type::Path(p) => p.qself.is_none() &&
+                            p.path.is_ident("Self"),
+                        _ => false
                     };
-                    let qident = &qp.segments.first().unwrap().ident;
-                    if qself.position != 1
-                        || qp.segments.len() != 1
-                        || path.path.segments.len() != 2
-                        || qident != "Self" {
+                    if !is_self {
+                        // A projection through some other type, possibly one
+                        // of this trait's own associated types, like
+                        // `<Self::Conn as Deref>::Target`
+                        self.substitute_type(qself.ty.as_mut());
+                        for seg in path.path.segments.iter_mut() {
+                            self.substitute_path_segment(seg);
+                        }
+                        return;
+                    }
+                    if qself.position != 1 || path.path.segments.len() != 2 {
                         compile_error(path.span(),
                             "QSelf is a work in progress");
                     }
@@ -181,6 +353,48 @@ impl Attrs {
         }
     }
 
+    /// Check that each substituted associated type satisfies the trait's
+    /// bounds on it.  Otherwise an unsatisfied bound would be reported deep
+    /// within the generated code, rather than at the attribute.  Types and
+    /// bounds that refer to the trait's generic parameters or to `Self` can't
+    /// be checked here, so they're skipped.
+    fn check_bounds(&self, item: &ItemTrait) -> TokenStream {
+        let mut idents = item.generics.params.iter().map(|p| match p {
+            GenericParam::Type(tp) => tp.ident.clone(),
+            GenericParam::Lifetime(ltd) => ltd.lifetime.ident.clone(),
+            GenericParam::Const(cp) => cp.ident.clone()
+        }).collect::<Vec<_>>();
+        idents.push(format_ident!("Self"));
+        let mut output = TokenStream::new();
+        for trait_item in item.items.iter() {
+            let (tity, ty) = match trait_item {
+                TraitItem::Type(tity) => match self.attrs.get(&tity.ident) {
+                    Some(ty) if !mentions(ty.to_token_stream(), &idents) =>
+                        (tity, ty),
+                    _ => continue
+                },
+                _ => continue
+            };
+            let bounds = tity.bounds.iter()
+                .cloned()
+                .map(|mut bound| {
+                    self.substitute_type_param_bound(&mut bound);
+                    bound
+                }).filter(|bound| !mentions(bound.to_token_stream(), &idents))
+                .collect::<Vec<_>>();
+            if bounds.is_empty() {
+                continue;
+            }
+            quote_spanned!(ty.span() =>
+                const _: fn() = || {
+                    fn assert_bounds<MockallT: #(#bounds)+*>() {}
+                    assert_bounds::<#ty>();
+                };
+            ).to_tokens(&mut output);
+        }
+        output
+    }
+
     fn substitute_trait(&self, item: &ItemTrait) -> ItemTrait {
         let mut output = item.clone();
         for trait_item in output.items.iter_mut() {
@@ -220,10 +434,32 @@ impl Attrs {
 impl Parse for Attrs {
     fn parse(input: ParseStream) -> parse::Result<Self> {
         let mut attrs = HashMap::new();
+        let mut derives = Vec::new();
+        let mut inner = None;
+        let mut methods = None;
         let mut modname = None;
+        let mut path = None;
+        let mut ref_impls = false;
+        let mut trait_ = None;
         while !input.is_empty() {
             let attr: Attr = input.parse()?;
             match attr {
+                Attr::Derive(paths) => {
+                    for p in paths.into_iter() {
+                        if p.segments.last().unwrap().ident == "Default" {
+                            compile_error(p.span(),
+                                "Mock structs always implement Default.  Remove it from the derive list.");
+                        } else {
+                            derives.push(p);
+                        }
+                    }
+                },
+                Attr::Inner(ty) => {
+                    inner = Some(ty);
+                },
+                Attr::Methods(idents) => {
+                    methods = Some(idents.into_iter().collect());
+                },
                 Attr::Mod(item_mod) => {
                     if let Some((br, _)) = item_mod.content {
                         compile_error(br.span,
@@ -231,6 +467,15 @@ impl Parse for Attrs {
                     }
                     modname = Some(item_mod.ident.clone());
                 },
+                Attr::Path(p) => {
+                    path = Some(p);
+                },
+                Attr::RefImpls => {
+                    ref_impls = true;
+                },
+                Attr::Trait(ident) => {
+                    trait_ = Some(ident);
+                },
                 Attr::Type(trait_item_type) => {
                     let ident = trait_item_type.ident.clone();
                     if let Some((_, ty)) = trait_item_type.default {
@@ -242,7 +487,12 @@ impl Parse for Attrs {
                 }
             }
         }
-        Ok(Attrs{attrs, modname})
+        let mut attrs = Attrs{attrs, bounds: HashMap::new(),
+            defer_projections: false, derives, inner, methods, modname, path,
+            ref_impls, trait_};
+        // Projections must wait until the trait's bounds are known
+        attrs.resolve_nested(true);
+        Ok(attrs)
     }
 }
 
@@ -323,6 +573,15 @@ fn find_ident_from_path(path: &Path) -> (Ident, PathArguments) {
 fn mock_foreign(attrs: Attrs, foreign_mod: ItemForeignMod) -> TokenStream {
     let mut body = TokenStream::new();
     let mut cp_body = TokenStream::new();
+    let mut reset_body = TokenStream::new();
+    if let Some(methods) = &attrs.methods {
+        compile_error(methods.first().map_or(Span::call_site(), Ident::span),
+            "The methods attribute may only be used with traits and impl blocks");
+    }
+    if let Some(inner) = &attrs.inner {
+        compile_error(inner.span(),
+            "The inner attribute may only be used with traits and impl blocks");
+    }
     let modname = attrs.modname.expect(concat!(
         "module name is required when mocking foreign functions,",
         " like `#[automock(mod mock_ffi)]`"
@@ -332,12 +591,21 @@ fn mock_foreign(attrs: Attrs, foreign_mod: ItemForeignMod) -> TokenStream {
         match item {
             ForeignItem::Fn(f) => {
                 let mod_ident = format_ident!("__{}", &f.sig.ident);
+                // The function may only exist for some configurations
+                let cfgs = f.attrs.iter()
+                    .filter(|attr| attr_is(attr, &["cfg"]))
+                    .collect::<Vec<_>>();
                 quote!(
-                    let __mockall_timeses = #mod_ident::EXPECTATIONS.lock()
-                        .unwrap()
-                        .checkpoint()
-                        .collect::<Vec<_>>();
+                    #(#cfgs)*
+                    {
+                        let mut __mockall_e =
+                            ::mockall::lock_static(&#mod_ident::EXPECTATIONS);
+                        __mockall_cps.report(__mockall_e.report());
+                        __mockall_cps.add(__mockall_e.checkpoint());
+                    }
                 ).to_tokens(&mut cp_body);
+                quote!(#(#cfgs)* #mod_ident::reset();)
+                    .to_tokens(&mut reset_body);
                 mock_foreign_function(&modname, f).to_tokens(&mut body);
             },
             ForeignItem::Static(s) => {
@@ -352,8 +620,22 @@ fn mock_foreign(attrs: Attrs, foreign_mod: ItemForeignMod) -> TokenStream {
         }
     }
 
-    quote!(pub fn checkpoint() { #cp_body }).to_tokens(&mut body);
-    quote!(pub mod #modname { #body })
+    let cp_mut = cps_mut(&cp_body);
+    quote!(pub fn checkpoint() {
+        let #cp_mut __mockall_cps = ::mockall::Checkpoints::default();
+        #cp_body
+        __mockall_cps.finish();
+    }).to_tokens(&mut body);
+    quote!(pub fn reset() { #reset_body }).to_tokens(&mut body);
+    // If the whole extern block is only for some configurations, then so is
+    // its mock.
+    let cfgs = foreign_mod.attrs.iter()
+        .filter(|attr| attr_is(attr, &["cfg"]));
+    quote!(
+        #(#cfgs)*
+        #[allow(missing_docs)]
+        pub mod #modname { #body }
+    )
 }
 
 /// Mock a foreign function the same way we mock static trait methods: with a
@@ -363,25 +645,24 @@ fn mock_foreign_function(modname: &Ident, f: ForeignItemFn) -> TokenStream {
     // unsafe too, to prevent "warning: unused unsafe" messages.
     let mut sig = f.sig.clone();
     sig.unsafety = Some(Token![unsafe](f.sig.span()));
-    mock_function(modname, &f.vis, &sig)
+    mock_function(modname, &f.attrs, &f.vis, &sig)
 }
 
-fn mock_function(modname: &Ident, vis: &Visibility, sig: &Signature)
-    -> TokenStream
+fn mock_function(modname: &Ident, attrs: &[Attribute], vis: &Visibility,
+                 sig: &Signature) -> TokenStream
 {
+    let attrs = attrs.iter()
+        .filter(|attr| attr_is(attr, SEMANTIC_ATTRS))
+        .cloned()
+        .collect::<Vec<_>>();
+    let meth_attrs = format_attrs(&attrs, AttrTarget::Method);
+    let helper_attrs = format_attrs(&attrs, AttrTarget::Helper);
+    let other_attrs = format_attrs(&attrs, AttrTarget::Other);
     let asyncness = &sig.asyncness;
-    let constness = &sig.constness;
     let fn_token = &sig.fn_token;
     let generics = &sig.generics;
     let ident = &sig.ident;
     let unsafety = &sig.unsafety;
-    let output = match &sig.output{
-        ReturnType::Default => quote!(-> ()),
-        _ => {
-            let decl_output = &sig.output;
-            quote!(#decl_output)
-        }
-    };
     let mut args = Vec::new();
 
     if sig.variadic.is_some() {
@@ -395,6 +676,12 @@ fn mock_function(modname: &Ident, vis: &Visibility, sig: &Signature)
     mock_sig.ident = mod_ident.clone();
     let meth_types = method_types(&mock_sig, None);
     let inputs = &meth_types.inputs;
+    // Any impl Trait return type will be boxed
+    let output = match &meth_types.output {
+        _ if returns_never(&sig.output) => quote!(-> !),
+        ReturnType::Default => quote!(-> ()),
+        decl_output => quote!(#decl_output)
+    };
 
     for p in inputs.iter() {
         match p {
@@ -417,28 +704,54 @@ fn mock_function(modname: &Ident, vis: &Visibility, sig: &Signature)
     g.params.push(GenericParam::Lifetime(ltd.clone()));
 
     let mut out = TokenStream::new();
-    Expectation::new(&TokenStream::new(), &inputs, &expect_obj, None, generics,
-        &ident, &mod_ident, None, &sig.output, &expect_vis, 1)
+    Expectation::new(&other_attrs, &inputs, &expect_obj, None, generics,
+        &ident, &mod_ident, None, &meth_types.output, &expect_vis, 1)
+        .in_module(modname)
         .to_tokens(&mut out);
-    let no_match_msg = format!("{}::{}: No matching expectation found",
-        modname, ident);
+    // Generic functions' expectations are keyed by their type parameters
+    let expectations = &meth_types.expectations;
+    let (tbf_g, _, _) = split_lifetimes(generics.clone(), &inputs, &sig.output);
+    let (_, tbf_tg, _) = tbf_g.split_for_impl();
+    let call_turbofish = tbf_tg.as_turbofish();
+    let modname = modname.to_string();
+    let fname = ident.to_string();
+    let no_match = match &meth_types.output {
+        ReturnType::Default => gen_no_match(&modname, &fname, quote!(())),
+        ReturnType::Type(_, ty) => gen_no_match(&modname, &fname, quote!(#ty))
+    };
+    let never_returned = if returns_never(&sig.output) {
+        quote!(; ::mockall::never_returned(#modname, #fname))
+    } else {
+        TokenStream::new()
+    };
+    let track_caller = gen_track_caller(sig, &attrs);
+    // The mock function can't be const, even if the original is, because it
+    // must lock its expectations.
+    let const_note = gen_const_note(sig);
     quote!(
-        #meth_vis #constness #unsafety #asyncness
+        #meth_attrs #const_note #track_caller #meth_vis #unsafety #asyncness
         #fn_token #ident #generics (#inputs) #output {
-            {
-                let __mockall_guard = #mod_ident::EXPECTATIONS
-                    .lock().unwrap();
+            match {
+                let __mockall_guard =
+                    ::mockall::lock_static(&#mod_ident::EXPECTATIONS);
                 /*
                  * TODO: catch panics, then gracefully release the mutex so it
                  * won't be poisoned.  This requires bounding any generic
                  * parameters with UnwindSafe
                  */
                 /* std::panic::catch_unwind(|| */
-                __mockall_guard.call(#(#args),*)
+                // Release the lock before running the return function, in
+                // case it calls another mocked function.
+                #expectations::call_unlocked#call_turbofish(
+                    __mockall_guard, #(#args),*)
                 /*)*/
-            }.expect(#no_match_msg)
+            } {
+                Some(__mockall_o) => __mockall_o,
+                None => #no_match
+            }
+            #never_returned
         }
-        #meth_vis fn #context_ident() -> #mod_ident::Context
+        #helper_attrs #meth_vis fn #context_ident() -> #mod_ident::Context
         {
             #mod_ident::Context::default()
         }
@@ -446,27 +759,130 @@ fn mock_function(modname: &Ident, vis: &Visibility, sig: &Signature)
     out
 }
 
-/// Implement a struct's methods on its mock struct.  Only works if the struct
-/// has a single impl block
-fn mock_impl(item_impl: ItemImpl) -> TokenStream {
-    let name = match *item_impl.self_ty {
+/// The error message for an impl block whose self type can't be mocked
+fn unsupported_self_type(ty: &Type) -> &'static str {
+    match ty {
+        Type::Array(_) =>
+            "#automock does not support mocking impl blocks for arrays",
+        Type::BareFn(_) =>
+            "#automock does not support mocking impl blocks for function pointers",
+        Type::Never(_) =>
+            "#automock does not support mocking impl blocks for the never type",
+        Type::Ptr(_) =>
+            "#automock does not support mocking impl blocks for raw pointers",
+        Type::Slice(_) =>
+            "#automock does not support mocking impl blocks for slices",
+        Type::TraitObject(_) =>
+            "#automock does not support mocking impl blocks for trait objects",
+        Type::Tuple(_) =>
+            "#automock does not support mocking impl blocks for tuples",
+        _ => "mockall_derive only supports mocking traits, structs, and enums"
+    }
+}
+
+/// Generate a trait from an inherent impl block's methods, and implement it for
+/// both the original type and its mock by delegating to their inherent methods.
+fn extract_trait(trait_ident: &Ident, item_impl: &ItemImpl, name: &Ident)
+    -> TokenStream
+{
+    let (ig, tg, wc) = item_impl.generics.split_for_impl();
+    let self_ty = &item_impl.self_ty;
+    let mock_ident = gen_mock_ident(name);
+    let mut sigs = Vec::new();
+    let mut calls = Vec::new();
+    for item in item_impl.items.iter() {
+        let meth = if let ImplItem::Method(meth) = item {
+            meth
+        } else {
+            continue;
+        };
+        let mut sig = meth.sig.clone();
+        if let Some(asyncness) = &sig.asyncness {
+            compile_error(asyncness.span(),
+                "Mockall cannot extract a trait from async methods");
+            return TokenStream::new();
+        }
+        if let ReturnType::Type(_, ty) = &sig.output {
+            if let Type::ImplTrait(_) = **ty {
+                compile_error(ty.span(),
+                    "Mockall cannot extract a trait from methods that return impl Trait");
+                return TokenStream::new();
+            }
+        }
+        // Trait methods without bodies may not have mutable bindings
+        sig.constness = None;
+        sig.inputs = demutify(&sig.inputs);
+        let ident = &sig.ident;
+        let args = sig.inputs.iter().map(|arg| match arg {
+            FnArg::Receiver(_) => quote!(self),
+            FnArg::Typed(pt) => {
+                let pat = &pt.pat;
+                quote!(#pat)
+            }
+        }).collect::<Vec<_>>();
+        calls.push(quote!(#ident(#(#args),*)));
+        sigs.push(sig);
+    }
+    quote!(
+        pub trait #trait_ident #ig #wc {
+            #(#sigs;)*
+        }
+        impl #ig #trait_ident #tg for #self_ty #wc {
+            #(#sigs { <#self_ty>::#calls })*
+        }
+        impl #ig #trait_ident #tg for #mock_ident #tg #wc {
+            #(#sigs { <#mock_ident #tg>::#calls })*
+        }
+    )
+}
+
+/// Implement a struct's or enum's methods on its mock struct.  Only works if
+/// the type has a single impl block
+fn mock_impl(mut attrs: Attrs, item_impl: ItemImpl) -> TokenStream {
+    let (name, ref_impl) = match &*item_impl.self_ty {
         Type::Path(type_path) => {
-            find_ident_from_path(&type_path.path).0
+            (find_ident_from_path(&type_path.path).0, false)
+        },
+        Type::Reference(TypeReference{lifetime, mutability, elem, ..}) => {
+            let lifetime = lifetime.as_ref().filter(|lt| lt.ident != "_");
+            if let Some(lt) = lifetime {
+                compile_error(lt.span(),
+                    "#automock does not yet support named lifetimes on reference self types.  Use an elided lifetime instead.");
+                return TokenStream::new();
+            }
+            if let Some(m) = mutability {
+                compile_error(m.span(),
+                    "#automock does not support implementing traits for mutable references");
+                return TokenStream::new();
+            }
+            if let Type::Path(type_path) = &**elem {
+                (find_ident_from_path(&type_path.path).0, true)
+            } else {
+                compile_error(elem.span(), unsupported_self_type(elem));
+                return TokenStream::new();
+            }
         },
         x => {
-            compile_error(x.span(),
-                "mockall_derive only supports mocking traits and structs");
+            compile_error(x.span(), unsupported_self_type(x));
             return TokenStream::new();
         }
     };
+    let extracted = match (&attrs.trait_, &item_impl.trait_) {
+        (Some(t), None) => extract_trait(t, &item_impl, &name),
+        (Some(t), Some(_)) => {
+            compile_error(t.span(),
+                "The trait attribute may only be used with inherent impls");
+            TokenStream::new()
+        },
+        (None, _) => TokenStream::new()
+    };
     let mut methods = Vec::new();
+    let mut consts = Vec::new();
     let mut titys = Vec::new();
-    let mut attrs = Attrs::default();
     for item in item_impl.items.iter() {
         match item {
-            ImplItem::Const(_) => {
-                // const items can easily be added by the user in a separate
-                // impl block
+            ImplItem::Const(c) => {
+                consts.push(c.clone());
             },
             ImplItem::Method(meth) => {
                 methods.push(meth.clone());
@@ -499,6 +915,17 @@ fn mock_impl(item_impl: ItemImpl) -> TokenStream {
         for ty in titys.into_iter() {
             items.push(TraitItem::Type(ty));
         }
+        for c in consts.drain(..) {
+            items.push(TraitItem::Const(TraitItemConst {
+                attrs: c.attrs,
+                const_token: c.const_token,
+                ident: c.ident,
+                colon_token: c.colon_token,
+                ty: c.ty,
+                default: Some((c.eq_token, c.expr)),
+                semi_token: c.semi_token
+            }));
+        }
         for meth in methods.into_iter() {
             let tim = TraitItemMethod {
                 attrs: Vec::new(),
@@ -522,35 +949,82 @@ fn mock_impl(item_impl: ItemImpl) -> TokenStream {
             brace_token: token::Brace::default(),
             items
         };
+        attrs.learn_bounds(&trait_);
         let concretized_trait = attrs.substitute_trait(&trait_);
         (Vec::new(), vec![concretized_trait])
     } else {
         assert!(titys.is_empty());
         (methods, Vec::new())
     };
+    let all_methods = item_impl.items.iter().filter_map(|item| match item {
+        ImplItem::Method(meth) => Some(meth.sig.ident.clone()),
+        _ => None
+    }).collect::<Vec<_>>();
+    let unmocked = match unmocked_methods(&attrs, &all_methods) {
+        Ok(unmocked) => unmocked,
+        Err(err) => return err.to_compile_error()
+    };
     let mock = Mock {
         vis,
         name,
         generics: item_impl.generics.clone(),
         methods,
-        traits
+        consts,
+        traits,
+        derives: attrs.derives,
+        ref_impl,
+        ref_impls: attrs.ref_impls,
+        unmocked,
+        inner: attrs.inner
     };
-    mock.gen()
+    let mut ts = mock.gen();
+    extracted.to_tokens(&mut ts);
+    ts
 }
 
 /// Generate mock functions for an entire module
-fn mock_module(mod_: ItemMod) -> TokenStream {
+fn mock_module(attrs: Attrs, mod_: ItemMod) -> TokenStream {
     let mut body = TokenStream::new();
     let mut cp_body = TokenStream::new();
+    let mut reset_body = TokenStream::new();
     let modname = format_ident!("mock_{}", mod_.ident);
+    let mod_span = mod_.span();
+    if let Some(methods) = &attrs.methods {
+        compile_error(methods.first().map_or(Span::call_site(), Ident::span),
+            "The methods attribute may only be used with traits and impl blocks");
+    }
+    if let Some(inner) = &attrs.inner {
+        compile_error(inner.span(),
+            "The inner attribute may only be used with traits and impl blocks");
+    }
 
-    let items = if let Some((_, items)) = mod_.content {
-        items
-    } else {
-        compile_error(mod_.span(),
-        "automock can only mock inline modules, not modules from another file");
-        Vec::new()
+    let items = match (mod_.content, &attrs.path) {
+        (Some((_, items)), None) => items,
+        (Some(_), Some(p)) => {
+            compile_error(p.span(),
+                "The path attribute may only be used with out-of-line modules");
+            Vec::new()
+        },
+        (None, Some(p)) => {
+            let (file, items) = read_module_file(p);
+            // Make Cargo rebuild the mock when the file changes
+            quote!(const _: &[u8] = include_bytes!(#file);)
+                .to_tokens(&mut body);
+            items
+        },
+        (None, None) => {
+            compile_error(mod_span,
+                "automock can only mock inline modules, not modules from another file.  Either make the module inline, or specify its source file like #[automock(path = \"src/foo.rs\")]");
+            Vec::new()
+        }
     };
+    let fns = items.iter().filter_map(|item| {
+        if let Item::Fn(f) = item {
+            Some(f.sig.ident.clone())
+        } else {
+            None
+        }
+    }).collect::<Vec<_>>();
     for item in items.iter() {
         match item {
             Item::ExternCrate(_) | Item::Impl(_) =>
@@ -558,26 +1032,44 @@ fn mock_module(mod_: ItemMod) -> TokenStream {
                 // Ignore
             },
             Item::Static(is) => {
+                // Strip symbol-exporting attributes, or the mock static would
+                // clash with the original at link time.
+                let mut is = is.clone();
+                is.attrs.retain(|attr| !attr_is(attr, SYMBOL_ATTRS));
                 is.to_tokens(&mut body)
             },
             Item::Const(ic) => ic.to_tokens(&mut body),
             Item::Fn(f) => {
                 let mod_ident = format_ident!("__{}", &f.sig.ident);
+                // The function may only exist for some configurations
+                let cfgs = f.attrs.iter()
+                    .filter(|attr| attr_is(attr, &["cfg"]))
+                    .collect::<Vec<_>>();
                 quote!(
-                    let __mockall_timeses = #mod_ident::EXPECTATIONS.lock()
-                        .unwrap()
-                        .checkpoint()
-                        .collect::<Vec<_>>();
+                    #(#cfgs)*
+                    {
+                        let mut __mockall_e =
+                            ::mockall::lock_static(&#mod_ident::EXPECTATIONS);
+                        __mockall_cps.report(__mockall_e.report());
+                        __mockall_cps.add(__mockall_e.checkpoint());
+                    }
                 ).to_tokens(&mut cp_body);
+                quote!(#(#cfgs)* #mod_ident::reset();)
+                    .to_tokens(&mut reset_body);
                 mock_native_function(&modname, &f).to_tokens(&mut body);
             },
-            Item::Mod(_) | Item::ForeignMod(_)
-                | Item::Struct(_) | Item::Enum(_)
-                | Item::Union(_) | Item::Trait(_) =>
+            Item::Mod(_) | Item::ForeignMod(_) | Item::Trait(_) =>
             {
                 compile_error(item.span(),
                     "Mockall does not yet support deriving nested mocks");
             },
+            Item::Struct(is) => {
+                // Copy data types verbatim, so the mock functions' signatures
+                // can still refer to them.
+                is.to_tokens(&mut body)
+            },
+            Item::Enum(ie) => ie.to_tokens(&mut body),
+            Item::Union(iu) => iu.to_tokens(&mut body),
             Item::Type(ty) => {
                 // Copy verbatim
                 ty.to_tokens(&mut body)
@@ -587,34 +1079,306 @@ fn mock_module(mod_: ItemMod) -> TokenStream {
                 ta.to_tokens(&mut body)
             },
             Item::Use(u) => {
-                // Copy verbatim
-                u.to_tokens(&mut body)
+                // Copy verbatim, so paths keep resolving and re-exports of
+                // mocked functions point at the mocks.
+                u.to_tokens(&mut body);
+                // A renamed re-export of a mocked function needs its context
+                // method renamed too.
+                let mut renames = Vec::new();
+                use_renames(&u.tree, &fns, &mut renames);
+                if !renames.is_empty() {
+                    let attrs = &u.attrs;
+                    let vis = &u.vis;
+                    let contexts = renames.iter().map(|(name, rename)| {
+                        let ctx = format_ident!("{}_context", name);
+                        let rctx = format_ident!("{}_context", rename);
+                        quote!(#ctx as #rctx)
+                    });
+                    quote!(#(#attrs)* #vis use self::{#(#contexts),*};)
+                        .to_tokens(&mut body);
+                }
             },
             _ => compile_error(item.span(), "Unsupported item"),
         }
     }
 
-    quote!(pub fn checkpoint() { #cp_body }).to_tokens(&mut body);
-    quote!(pub mod #modname { #body })
+    let cp_mut = cps_mut(&cp_body);
+    quote!(pub fn checkpoint() {
+        let #cp_mut __mockall_cps = ::mockall::Checkpoints::default();
+        #cp_body
+        __mockall_cps.finish();
+    }).to_tokens(&mut body);
+    quote!(pub fn reset() { #reset_body }).to_tokens(&mut body);
+    quote!(
+        #[allow(missing_docs)]
+        pub mod #modname { #body }
+    )
+}
+
+/// Read and parse the source file of an out-of-line module.  Relative paths
+/// are relative to the crate root, just like for `include!`.  Returns the
+/// absolute path of the file, and its items.
+fn read_module_file(p: &LitStr) -> (String, Vec<Item>) {
+    let mut path = path::PathBuf::from(
+        env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    path.push(p.value());
+    let file = path.to_string_lossy().into_owned();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            compile_error(p.span(), "Cannot read the module's source file");
+            return (file, Vec::new());
+        }
+    };
+    match syn::parse_file(&contents) {
+        Ok(f) => (file, f.items),
+        Err(_) => {
+            compile_error(p.span(), "Cannot parse the module's source file");
+            (file, Vec::new())
+        }
+    }
+}
+
+/// Find every `use` rename of one of the module's own functions, like
+/// `pub use self::foo as bar;`
+fn use_renames(tree: &UseTree, fns: &[Ident], renames: &mut Vec<(Ident, Ident)>)
+{
+    match tree {
+        UseTree::Path(path) if path.ident == "self" => {
+            use_renames(&path.tree, fns, renames)
+        },
+        UseTree::Rename(rename) if fns.contains(&rename.ident) => {
+            renames.push((rename.ident.clone(), rename.rename.clone()))
+        },
+        UseTree::Group(group) => {
+            for tree in group.items.iter() {
+                use_renames(tree, fns, renames)
+            }
+        },
+        _ => ()
+    }
 }
 
 /// Mock a function the same way we mock static trait methods: with a
 /// global Expectations object
 fn mock_native_function(modname: &Ident, f: &ItemFn) -> TokenStream {
-    mock_function(modname, &f.vis, &f.sig)
+    mock_function(modname, &f.attrs, &f.vis, &f.sig)
 }
 
 /// Generate a mock struct that implements a trait
-fn mock_trait(attrs: Attrs, item: ItemTrait) -> TokenStream {
-    let trait_ = attrs.substitute_trait(&item);
+fn mock_trait(mut attrs: Attrs, item: ItemTrait) -> TokenStream {
+    attrs.learn_bounds(&item);
+    let mut trait_ = attrs.substitute_trait(&item);
+    strip_sized_provided_methods(&mut trait_);
+    let all_methods = item.items.iter().filter_map(|ti| match ti {
+        TraitItem::Method(m) => Some(m.sig.ident.clone()),
+        _ => None
+    }).collect::<Vec<_>>();
+    let mut unmocked = match unmocked_methods(&attrs, &all_methods) {
+        Ok(unmocked) => unmocked,
+        Err(err) => return err.to_compile_error()
+    };
+    // Unmocked methods with a default implementation just use it.
+    trait_.items.retain(|ti| match ti {
+        TraitItem::Method(m) if m.default.is_some() => {
+            !unmocked.contains(&m.sig.ident)
+        },
+        _ => true
+    });
+    unmocked.retain(|ident| trait_.items.iter().any(|ti| match ti {
+        TraitItem::Method(m) => m.sig.ident == *ident,
+        _ => false
+    }));
+    let bound_checks = attrs.check_bounds(&item);
+    let object_check = check_object_safe(&item, &trait_);
     let mock = Mock {
         vis: item.vis.clone(),
         name: item.ident.clone(),
-        generics: item.generics.clone(),
+        generics: strip_self_bounds(&item.generics),
         methods: Vec::new(),
-        traits: vec![trait_]
+        consts: Vec::new(),
+        traits: vec![trait_],
+        derives: attrs.derives,
+        ref_impl: false,
+        ref_impls: attrs.ref_impls,
+        unmocked,
+        inner: attrs.inner
+    };
+    let mut output = mock.gen();
+    bound_checks.to_tokens(&mut output);
+    object_check.to_tokens(&mut output);
+    output
+}
+
+/// Check the methods attribute against the methods that actually exist, and
+/// return the ones that should not be mocked.
+fn unmocked_methods(attrs: &Attrs, all: &[Ident])
+    -> Result<Vec<Ident>>
+{
+    let listed = match &attrs.methods {
+        Some(listed) => listed,
+        None => return Ok(Vec::new())
+    };
+    if let Some(unknown) = listed.iter().find(|ident| !all.contains(ident)) {
+        let valid = all.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let msg = format!("No method named `{}` to mock.  Valid methods are: {}",
+                          unknown, valid);
+        return Err(Error::new(unknown.span(), msg));
+    }
+    Ok(all.iter()
+        .filter(|ident| !listed.contains(ident))
+        .cloned()
+        .collect())
+}
+
+/// Does this token stream mention any of these identifiers?
+fn mentions(ts: TokenStream, idents: &[Ident]) -> bool {
+    ts.into_iter().any(|tt| match tt {
+        proc_macro2::TokenTree::Ident(i) => idents.contains(&i),
+        proc_macro2::TokenTree::Group(g) => mentions(g.stream(), idents),
+        _ => false
+    })
+}
+
+/// Is this one of the auto traits, like `Send`?  They have no items of their
+/// own.
+fn is_auto_trait(path: &Path) -> bool {
+    path.segments.last().map_or(false, |seg| {
+        seg.arguments.is_empty() &&
+            ["Send", "Sync", "Unpin"].iter().any(|m| seg.ident == m)
+    })
+}
+
+/// Could this trait be used as a trait object?  Only answers yes when sure,
+/// because the answer may depend on supertraits that aren't visible here.
+fn is_object_safe(item: &ItemTrait) -> bool {
+    let is_marker = |bound: &TypeParamBound| match bound {
+        TypeParamBound::Lifetime(_) => true,
+        TypeParamBound::Trait(tb) => {
+            tb.modifier == TraitBoundModifier::None && is_auto_trait(&tb.path)
+        }
+    };
+    let self_bounds = item.generics.where_clause.iter()
+        .flat_map(|wc| wc.predicates.iter())
+        .filter_map(|pred| match pred {
+            WherePredicate::Type(pt) if is_self(&pt.bounded_ty) =>
+                Some(pt.bounds.iter()),
+            _ => None
+        }).flatten();
+    item.supertraits.iter().chain(self_bounds).all(is_marker) &&
+        item.items.iter().all(|ti| match ti {
+            TraitItem::Method(m) => is_dispatchable(m) ||
+                m.sig.generics.where_clause.as_ref()
+                    .map_or(false, |wc| wc.predicates.iter().any(is_self_sized)),
+            TraitItem::Type(tity) => tity.generics.params.is_empty(),
+            _ => false
+        })
+}
+
+/// Can this method be called through a trait object?
+fn is_dispatchable(m: &TraitItemMethod) -> bool {
+    /// Does this type use `Self`, other than to name an associated type, or
+    /// `impl Trait`?
+    fn uses_self_or_impl(ts: TokenStream) -> bool {
+        let mut tokens = ts.into_iter().peekable();
+        while let Some(tt) = tokens.next() {
+            match tt {
+                proc_macro2::TokenTree::Ident(i) if i == "Self" => {
+                    let projected = match tokens.peek() {
+                        Some(proc_macro2::TokenTree::Punct(p)) =>
+                            p.as_char() == ':',
+                        Some(proc_macro2::TokenTree::Ident(i)) => i == "as",
+                        _ => false
+                    };
+                    if !projected {
+                        return true;
+                    }
+                },
+                proc_macro2::TokenTree::Ident(i) if i == "impl" => {
+                    return true;
+                },
+                proc_macro2::TokenTree::Group(g)
+                    if uses_self_or_impl(g.stream()) =>
+                {
+                    return true;
+                },
+                _ => ()
+            }
+        }
+        false
+    }
+
+    let sig = &m.sig;
+    let has_receiver = match sig.inputs.first() {
+        Some(FnArg::Receiver(_)) => true,
+        Some(FnArg::Typed(pt)) => match pt.pat.as_ref() {
+            Pat::Ident(pi) => pi.ident == "self",
+            _ => false
+        },
+        None => false
+    };
+    let output_ok = match &sig.output {
+        ReturnType::Default => true,
+        ReturnType::Type(_, ty) => !uses_self_or_impl(ty.to_token_stream())
+    };
+    has_receiver && output_ok && sig.asyncness.is_none() &&
+        sig.generics.type_params().next().is_none() &&
+        sig.generics.const_params().next().is_none() &&
+        sig.inputs.iter().skip(1).all(|arg| match arg {
+            FnArg::Typed(pt) => !uses_self_or_impl(pt.ty.to_token_stream()),
+            FnArg::Receiver(_) => false
+        })
+}
+
+/// If the trait is object safe, check that its mock can be used as a trait
+/// object, too.  `item` is the original trait and `trait_` the one with its
+/// associated types filled in.
+fn check_object_safe(item: &ItemTrait, trait_: &ItemTrait) -> TokenStream {
+    if !is_object_safe(item) {
+        return TokenStream::new();
+    }
+    let mock_ident = gen_mock_ident(&item.ident);
+    let trait_ident = &item.ident;
+    let generics = strip_self_bounds(&item.generics);
+    let (_, tg, _) = generics.split_for_impl();
+    // Name the borrow's lifetime, in case the trait has lifetime parameters
+    // of its own.
+    let lt = Lifetime::new("'__mockall_obj", Span::call_site());
+    let mut fn_generics = generics.clone();
+    fn_generics.params.insert(0, GenericParam::Lifetime(LifetimeDef::new(
+        lt.clone())));
+    let (ig, _, wc) = fn_generics.split_for_impl();
+    let mut args = generics.params.iter().map(|p| match p {
+        GenericParam::Type(tp) => tp.ident.to_token_stream(),
+        GenericParam::Lifetime(ltd) => ltd.lifetime.to_token_stream(),
+        GenericParam::Const(cp) => cp.ident.to_token_stream()
+    }).collect::<Vec<_>>();
+    for ti in trait_.items.iter() {
+        if let TraitItem::Type(TraitItemType{ident, default: Some((_, ty)), ..})
+            = ti
+        {
+            args.push(quote!(#ident = #ty));
+        }
+    }
+    let targs = if args.is_empty() {
+        TokenStream::new()
+    } else {
+        quote!(<#(#args),*>)
     };
-    mock.gen()
+    quote!(
+        const _: fn() = || {
+            #[allow(dead_code)]
+            fn assert_object_safe #ig (mock: &#lt #mock_ident #tg)
+                -> &#lt (dyn #trait_ident #targs + #lt)
+                #wc
+            {
+                mock
+            }
+        };
+    )
 }
 
 pub(crate)
@@ -632,10 +1396,24 @@ fn do_automock(attr_stream: TokenStream, input: TokenStream) -> TokenStream
             return err.to_compile_error();
         }
     };
+    // The mock's name, for debugging output
+    let name = match &item {
+        Item::Impl(ItemImpl{self_ty, ..}) => match &**self_ty {
+            Type::Path(tp) =>
+                gen_mock_ident(&find_ident_from_path(&tp.path).0).to_string(),
+            _ => String::from("Mock")
+        },
+        Item::ForeignMod(_) => attrs.modname.as_ref()
+            .map(Ident::to_string)
+            .unwrap_or_default(),
+        Item::Mod(item_mod) => format!("mock_{}", item_mod.ident),
+        Item::Trait(item_trait) => gen_mock_ident(&item_trait.ident).to_string(),
+        _ => String::new()
+    };
     let ts = match item {
-        Item::Impl(item_impl) => mock_impl(item_impl),
+        Item::Impl(item_impl) => mock_impl(attrs, item_impl),
         Item::ForeignMod(foreign_mod) => mock_foreign(attrs, foreign_mod),
-        Item::Mod(item_mod) => mock_module(item_mod),
+        Item::Mod(item_mod) => mock_module(attrs, item_mod),
         Item::Trait(item_trait) => mock_trait(attrs, item_trait),
         _ => {
             compile_error(item.span(),
@@ -643,9 +1421,7 @@ fn do_automock(attr_stream: TokenStream, input: TokenStream) -> TokenStream
             TokenStream::new()
         }
     };
-    if env::var("MOCKALL_DEBUG").is_ok() {
-        println!("{}", ts);
-    }
+    debug_dump(&name, &ts);
     ts
 }
 
@@ -673,6 +1449,100 @@ mod t {
                               quote!(u32));
     }
 
+    /// A projection through one of the trait's own associated types
+    #[test]
+    fn qself_projection() {
+        check_substitute_type(quote!(type Conn = PgConn;),
+                              quote!(<Self::Conn as Deref>::Target),
+                              quote!(<PgConn as Deref>::Target));
+        check_substitute_type(quote!(type T = u32;),
+                              quote!(<Vec<Self::T> as IntoIterator>::Item),
+                              quote!(<Vec<u32> as IntoIterator>::Item));
+    }
+
+    /// Associated types nested within generic arguments, at any depth
+    #[test]
+    fn generic_arguments() {
+        let attrs = quote!(type T = u32; type E = String;);
+        check_substitute_type(attrs.clone(),
+                              quote!(Vec<Self::T>),
+                              quote!(Vec<u32>));
+        check_substitute_type(attrs.clone(),
+                              quote!(Option<Self::T>),
+                              quote!(Option<u32>));
+        check_substitute_type(attrs.clone(),
+                              quote!(Result<Self::T, Self::E>),
+                              quote!(Result<u32, String>));
+        check_substitute_type(attrs.clone(),
+                              quote!(HashMap<String, Self::T>),
+                              quote!(HashMap<String, u32>));
+        check_substitute_type(attrs.clone(),
+                              quote!(Vec<Option<Self::T>>),
+                              quote!(Vec<Option<u32>>));
+        check_substitute_type(attrs.clone(),
+                              quote!(std::vec::Vec<<Self as Foo>::T>),
+                              quote!(std::vec::Vec<u32>));
+        check_substitute_type(attrs,
+                              quote!(Box<dyn Iterator<Item = Self::T>>),
+                              quote!(Box<dyn Iterator<Item = u32>>));
+    }
+
+    fn check_projection(attrs: TokenStream, item: TokenStream,
+        expected: TokenStream)
+    {
+        let mut attrs: super::Attrs = parse2(attrs).unwrap();
+        attrs.learn_bounds(&parse2(item).unwrap());
+        let mut ty: Type = parse2(quote!(Self::Error)).unwrap();
+        attrs.substitute_type(&mut ty);
+        assert_eq!(ty, parse2::<Type>(expected).unwrap());
+    }
+
+    /// A projection through a substituted associated type is qualified by
+    /// that associated type's bound
+    #[test]
+    fn projection_chain() {
+        check_projection(
+            quote!(type Conn = PgConn; type Error = Self::Conn::Error;),
+            quote!(trait Foo { type Conn: HasError + Send; type Error; }),
+            quote!(<PgConn as HasError>::Error));
+        check_projection(
+            quote!(type Conn = Vec<u8>; type Error = Self::Conn::Error;),
+            quote!(trait Foo { type Conn: TryFrom<u32, Error = E>; type Error; }),
+            quote!(<Vec<u8> as TryFrom<u32> >::Error));
+    }
+
+    /// Without a unique bound, the projection is left unqualified.  That's
+    /// fine for the trait's type parameters.
+    #[test]
+    fn projection_chain_unbounded() {
+        check_projection(
+            quote!(type Conn = T; type Error = Self::Conn::Error;),
+            quote!(trait Foo<T: HasError> { type Conn; type Error; }),
+            quote!(T::Error));
+        check_projection(
+            quote!(type Conn = T; type Error = Self::Conn::Error;),
+            quote!(trait Foo<T: HasError> { type Conn: A + B; type Error; }),
+            quote!(T::Error));
+    }
+
+    #[test]
+    #[should_panic(expected = "can only project through one associated type")]
+    fn projection_chain_too_long() {
+        check_projection(
+            quote!(type Conn = PgConn; type Error = Self::Conn::Error::Kind;),
+            quote!(trait Foo { type Conn: HasError; type Error; }),
+            quote!(u32));
+    }
+
+    /// Associated types defined in terms of other associated types
+    #[test]
+    fn nested_substitution() {
+        check_substitute_type(
+            quote!(type A = Vec<Self::B>; type B = Box<Self::C>; type C = u32;),
+            quote!(Self::A),
+            quote!(Vec<Box<u32> >));
+    }
+
     #[test]
     fn method_visibility() {
         let code = r#"
@@ -698,6 +1568,65 @@ mod t {
         assert!(output.contains("pub ( in super :: x ) fn expect_bean"));
     }
 
+    /// A method's where clause applies to both the mock method and its
+    /// expect_ method
+    #[test]
+    fn method_where_clause() {
+        let code = r#"
+        trait Foo {
+            fn store<K: Clone, V>(&self, k: K, v: V)
+                where K: Send + 'static, V: Serialize + 'static;
+        }"#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        let wc = "where K : Send + 'static , V : Serialize + 'static";
+        assert!(output.contains(&format!(
+            "fn store < K : Clone , V > (& self , k : K , v : V) {}", wc)),
+            "{}", output);
+        assert!(output.contains(&format!(
+            "fn expect_store < K : Clone , V > (& mut self) -> & mut \
+            __mock_Foo_Foo :: store :: Expectation < K , V > {}", wc)),
+            "{}", output);
+    }
+
+    /// A module's generic functions look up their expectations by their
+    /// generic parameters
+    #[test]
+    fn module_generic_function() {
+        let code = r#"
+        mod foo {
+            pub fn parse<T: FromStr + 'static>(s: &str) -> Option<T> {
+                unimplemented!()
+            }
+        }"#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.contains("EXPECTATIONS : :: std :: sync :: Mutex < \
+            GenericExpectations >"), "{}", output);
+        assert!(output.contains("__parse :: GenericExpectations :: \
+            call_unlocked :: < T > (__mockall_guard , s)"), "{}", output);
+    }
+
+    /// Naming a nonexistent method in the methods attribute lists the real
+    /// ones
+    #[test]
+    fn methods_unknown() {
+        let code = r#"
+        pub trait Store {
+            fn get(&self, key: u32) -> u32;
+            fn put(&mut self, key: u32, value: u32);
+        }"#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let attrs_ts = proc_macro2::TokenStream::from_str("methods(get, del)")
+            .unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.contains("compile_error"), "{}", output);
+        assert!(output.contains("No method named `del` to mock.  \
+            Valid methods are: get, put"), "{}", output);
+    }
+
     #[test]
     #[should_panic(expected = "can only mock inline modules")]
     fn external_module() {
@@ -717,4 +1646,353 @@ mod t {
         let output = do_automock(attrs_ts, ts).to_string();
         assert!(output.contains("pub ( super ) struct MockFoo"));
     }
+
+    /// Only semantic attributes should be copied from module functions
+    #[test]
+    fn module_function_attrs() {
+        let code = r#"
+        mod foo {
+            #[must_use]
+            #[inline]
+            #[deprecated(since = "0.1.0", note = "Use bar instead")]
+            #[no_mangle]
+            pub fn foo() -> u32 { 42 }
+        }"#;
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: File = parse2(do_automock(attrs_ts, ts)).unwrap();
+        let items = match &output.items[0] {
+            Item::Mod(m) => &m.content.as_ref().unwrap().1,
+            _ => panic!("Expected a module")
+        };
+        let attrs = |name: &str| items.iter()
+            .filter_map(|item| match item {
+                Item::Fn(f) if f.sig.ident == name => Some(&f.attrs),
+                Item::Mod(m) if m.ident == name => Some(&m.attrs),
+                _ => None
+            }).flat_map(|attrs| attrs.iter())
+            .map(|a| a.path.get_ident().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(attrs("foo"),
+                   ["must_use", "inline", "deprecated", "track_caller"]);
+        assert_eq!(attrs("foo_context"), ["deprecated"]);
+        // Only the generated allow(missing_docs) and doc(hidden)
+        assert_eq!(attrs("__foo"), ["allow", "doc"]);
+    }
+
+    /// Module functions returning impl Trait get boxed, like methods do
+    #[test]
+    fn module_function_impl_trait() {
+        let code = r#"
+        mod foo {
+            pub fn foo() -> impl Debug { 42 }
+        }"#;
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.contains("fn foo () -> Box < dyn Debug >"));
+        assert!(!output.contains("impl Debug"));
+    }
+
+    #[test]
+    #[should_panic(expected = "automock can only mock inline modules")]
+    fn out_of_line_module() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str("mod foo;").unwrap();
+        do_automock(attrs_ts, ts);
+    }
+
+    #[test]
+    fn module_with_cfgs() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(r#"mod foo {
+            #[cfg(unix)] pub fn open(path: &Path) -> RawFd { 0 }
+            #[cfg(windows)] pub fn open(path: &Path) -> RawHandle { 0 }
+        }"#).unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        for cfg in &["# [cfg (unix)]", "# [cfg (windows)]"] {
+            assert!(output.contains(&format!(
+                "{} # [allow (missing_docs)] # [doc (hidden)] pub mod __open", cfg)));
+            assert!(output.contains(&format!(
+                "{} # [track_caller] pub fn open (", cfg)));
+            assert!(output.contains(&format!("{} pub fn open_context", cfg)));
+            assert!(output.contains(&format!(
+                "{} {{ let mut __mockall_e = :: mockall :: lock_static \
+                (& __open :: EXPECTATIONS)", cfg)));
+        }
+    }
+
+    #[test]
+    fn foreign_with_cfgs() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("mod mock_ffi;")
+            .unwrap();
+        let ts = proc_macro2::TokenStream::from_str(r#"
+            #[cfg(target_os = "linux")]
+            extern "C" {
+                fn epoll_create1(flags: c_int) -> c_int;
+                #[cfg(feature = "foo")] fn foo(x: u32) -> u32;
+            }"#).unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.starts_with(
+            "# [cfg (target_os = \"linux\")] # [allow (missing_docs)] pub mod mock_ffi"));
+        let cfg = "# [cfg (feature = \"foo\")]";
+        assert!(output.contains(&format!(
+            "{} # [allow (missing_docs)] # [doc (hidden)] pub mod __foo", cfg)));
+        assert!(output.contains(&format!(
+            "{} # [track_caller] pub (in super) unsafe fn foo (", cfg)));
+        assert!(output.contains(&format!(
+            "{} {{ let mut __mockall_e = :: mockall :: lock_static \
+            (& __foo :: EXPECTATIONS)", cfg)));
+        assert!(output.contains(
+            "# [allow (missing_docs)] # [doc (hidden)] pub mod __epoll_create1"));
+    }
+
+    #[test]
+    fn out_of_line_module_with_path() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("mockall_out_of_line_{}.rs", std::process::id()));
+        std::fs::write(&path, "pub fn bar(x: u32) -> u32 { x }").unwrap();
+        let attrs = format!("path = {:?}", path.to_str().unwrap());
+        let attrs_ts = proc_macro2::TokenStream::from_str(&attrs).unwrap();
+        let ts = proc_macro2::TokenStream::from_str("mod foo;").unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(output.contains("mod mock_foo"));
+        assert!(output.contains("fn bar_context"));
+        assert!(output.contains("include_bytes"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot read the module's source file")]
+    fn out_of_line_module_with_missing_path() {
+        let attrs_ts = proc_macro2::TokenStream::from_str(
+            "path = \"/nonexistent/foo.rs\"").unwrap();
+        let ts = proc_macro2::TokenStream::from_str("mod foo;").unwrap();
+        do_automock(attrs_ts, ts);
+    }
+
+    #[test]
+    fn impl_for_reference() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(
+            "impl Foo for &Bar { fn foo(&self) -> u32 { 42 } }").unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.contains("struct MockBar"));
+        assert!(output.contains("impl Foo for & MockBar"));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not support implementing traits for mutable references")]
+    fn impl_for_mut_reference() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(
+            "impl Foo for &mut Bar { fn foo(&self) -> u32 { 42 } }").unwrap();
+        do_automock(attrs_ts, ts);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not support mocking impl blocks for tuples")]
+    fn impl_for_tuple() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(
+            "impl Foo for (u32, u32) { fn foo(&self) -> u32 { 42 } }").unwrap();
+        do_automock(attrs_ts, ts);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not support mocking impl blocks for arrays")]
+    fn impl_for_array() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(
+            "impl Foo for [u32; 4] { fn foo(&self) -> u32 { 42 } }").unwrap();
+        do_automock(attrs_ts, ts);
+    }
+
+    #[test]
+    fn extract_trait() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("trait = FooApi")
+            .unwrap();
+        let ts = proc_macro2::TokenStream::from_str(
+            "impl Foo { fn foo(&self, mut x: u32) -> u32 { x } }").unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.contains(
+            "pub trait FooApi { fn foo (& self , x : u32) -> u32 ; }"));
+        assert!(output.contains("impl FooApi for Foo { fn foo (& self , x : u32) -> u32 { < Foo > :: foo (self , x) } }"));
+        assert!(output.contains("impl FooApi for MockFoo { fn foo (& self , x : u32) -> u32 { < MockFoo > :: foo (self , x) } }"));
+    }
+
+    #[test]
+    #[should_panic(expected = "may only be used with inherent impls")]
+    fn extract_trait_from_trait_impl() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("trait = FooApi")
+            .unwrap();
+        let ts = proc_macro2::TokenStream::from_str(
+            "impl Bar for Foo { fn foo(&self) -> u32 { 42 } }").unwrap();
+        do_automock(attrs_ts, ts);
+    }
+
+    /// Provided methods bounded by `Self: Sized` aren't mocked, but required
+    /// ones and other provided ones are.
+    #[test]
+    fn where_self_sized() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(r#"trait Foo {
+            fn required(self) -> u32 where Self: Sized;
+            fn provided(&self) -> u32 { 0 }
+            fn sized(self) -> Wrapper<Self> where Self: Sized { Wrapper(self) }
+            fn sized2<T>(self) -> T where T: Default, Self: std::marker::Sized {
+                T::default()
+            }
+        }"#).unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.contains("fn expect_required"));
+        assert!(output.contains("fn expect_provided"));
+        assert!(!output.contains("sized"), "{}", output);
+    }
+
+    /// must_use takes effect on the trait and its methods, which automock
+    /// emits unchanged.  The mock's implementations of them can't have it, and
+    /// the expect_* methods only have their own.
+    #[test]
+    fn trait_must_use() {
+        let attrs_ts = proc_macro2::TokenStream::from_str("").unwrap();
+        let ts = proc_macro2::TokenStream::from_str(r#"
+            #[must_use]
+            trait Foo {
+                #[must_use = "foo is pure"]
+                fn foo(&self) -> u32;
+            }"#).unwrap();
+        let output: File = parse2(do_automock(attrs_ts, ts)).unwrap();
+        let methods = output.items.iter()
+            .filter_map(|item| match item {
+                Item::Impl(i) => Some(i),
+                _ => None
+            }).flat_map(|i| i.items.iter())
+            .filter_map(|item| match item {
+                ImplItem::Method(m) => Some(m),
+                _ => None
+            }).collect::<Vec<_>>();
+        let must_uses = |name: &str| methods.iter()
+            .filter(|m| m.sig.ident == name)
+            .flat_map(|m| m.attrs.iter())
+            .filter(|a| a.path.is_ident("must_use"))
+            .map(|a| a.tokens.to_string())
+            .collect::<Vec<_>>();
+        assert!(must_uses("foo").is_empty());
+        assert!(!must_uses("expect_foo").iter()
+                .any(|m| m.contains("foo is pure")));
+    }
+
+    #[test]
+    fn derive() {
+        let attrs_ts = proc_macro2::TokenStream::from_str(
+            "derive(Clone, foo::Marker)").unwrap();
+        let ts = proc_macro2::TokenStream::from_str("trait Foo {}").unwrap();
+        let output = do_automock(attrs_ts, ts).to_string();
+        assert!(output.contains(
+            "# [derive (Clone , foo :: Marker)] struct MockFoo"));
+        assert!(output.contains(
+            "# [derive (Clone , foo :: Marker)] struct MockFoo_Foo"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Mock structs always implement Default")]
+    fn derive_default() {
+        let attrs_ts = proc_macro2::TokenStream::from_str(
+            "derive(Debug, std::default::Default)").unwrap();
+        let ts = proc_macro2::TokenStream::from_str("trait Foo {}").unwrap();
+        do_automock(attrs_ts, ts);
+    }
+
+    fn check_bounds(attrs: &str, item: &str) -> String {
+        let attrs: super::Attrs = parse2(
+            proc_macro2::TokenStream::from_str(attrs).unwrap()).unwrap();
+        let item: ItemTrait = syn::parse_str(item).unwrap();
+        attrs.check_bounds(&item).to_string()
+    }
+
+    #[test]
+    fn check_bounds_substituted() {
+        let checks = check_bounds("type T = u32; type U = Vec<u32>;",
+            "trait Foo { type T: Send + ?Sized; type U: AsRef<[Self::T]>; }");
+        let expected = quote!(
+            const _: fn() = || {
+                fn assert_bounds<MockallT: Send + ?Sized>() {}
+                assert_bounds::<u32>();
+            };
+            const _: fn() = || {
+                fn assert_bounds<MockallT: AsRef<[u32]> >() {}
+                assert_bounds::<Vec<u32> >();
+            };
+        ).to_string();
+        assert_eq!(expected, checks);
+    }
+
+    /// Types and bounds that use the trait's generic parameters or `Self`
+    /// can't be checked outside of the trait impl
+    #[test]
+    fn check_bounds_skipped() {
+        let checks = check_bounds("type T = u32; type U = Vec<V>; type W = u8;",
+            "trait Foo<V> { type T: From<V>; type U: Default; type W; }");
+        assert_eq!("", checks);
+    }
+
+    fn object_safe(item: &str) -> bool {
+        super::is_object_safe(&syn::parse_str(item).unwrap())
+    }
+
+    #[test]
+    fn object_safe_yes() {
+        assert!(object_safe("trait Foo { fn foo(&self, x: u32) -> u32; }"));
+        assert!(object_safe("trait Foo<T>: Send + Sync + 'static {
+            type Item;
+            fn foo(&mut self, t: T) -> Self::Item;
+            fn bar<'a>(&'a self) -> &'a <Self as Foo<T>>::Item;
+        }"));
+        assert!(object_safe("trait Foo where Self: Send {
+            fn new() -> Self where Self: Sized;
+            fn foo<T>(&self, t: T) where Self: Sized;
+            fn bar(self);
+        }"));
+    }
+
+    #[test]
+    fn check_object_safe() {
+        let attrs: super::Attrs = parse2(
+            proc_macro2::TokenStream::from_str("type Item = u32;").unwrap())
+            .unwrap();
+        let item: ItemTrait = syn::parse_str("trait Foo<'a, T> where Self: Send {
+            type Item;
+            fn foo(&self, t: &'a T) -> Self::Item;
+        }").unwrap();
+        let trait_ = attrs.substitute_trait(&item);
+        let expected = quote!(
+            const _: fn() = || {
+                #[allow(dead_code)]
+                fn assert_object_safe<'__mockall_obj, 'a, T>(
+                    mock: &'__mockall_obj MockFoo<'a, T>)
+                    -> &'__mockall_obj (dyn Foo<'a, T, Item = u32>
+                                        + '__mockall_obj)
+                {
+                    mock
+                }
+            };
+        ).to_string();
+        assert_eq!(expected,
+                   super::check_object_safe(&item, &trait_).to_string());
+    }
+
+    #[test]
+    fn object_safe_no() {
+        assert!(!object_safe("trait Foo: Sized { fn foo(&self); }"));
+        assert!(!object_safe("trait Foo: Clone { fn foo(&self); }"));
+        assert!(!object_safe("trait Foo where Self: Sized { fn foo(&self); }"));
+        assert!(!object_safe("trait Foo { const X: u32 = 1; }"));
+        assert!(!object_safe("trait Foo { fn new() -> u32; }"));
+        assert!(!object_safe("trait Foo { fn foo<T>(&self, t: T); }"));
+        assert!(!object_safe("trait Foo { fn foo(&self, x: impl Debug); }"));
+        assert!(!object_safe("trait Foo { fn foo(&self) -> Self; }"));
+        assert!(!object_safe("trait Foo { fn foo(&self, x: &Self); }"));
+        assert!(!object_safe("trait Foo { fn foo(&self) -> Option<Self>; }"));
+    }
 }
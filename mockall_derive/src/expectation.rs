@@ -3,6 +3,32 @@
 use super::*;
 use quote::ToTokens;
 
+/// Give a trait object argument like `&dyn Foo` an explicit lifetime bound,
+/// like `&(dyn Foo + 'l)`, so the expectation can work for any such lifetime.
+/// Returns the new lifetime and the bounded trait object type.
+fn bound_dyn(ty: &mut Type, i: usize) -> Option<(Lifetime, Type)> {
+    if let Type::Reference(tr) = ty {
+        if let Type::TraitObject(tto) = tr.elem.as_ref() {
+            let bounded = tto.bounds.iter()
+                .any(|b| matches!(b, TypeParamBound::Lifetime(_)));
+            if !bounded {
+                let lt = Lifetime::new(&format!("'__mockall_dyn{}", i),
+                                       Span::call_site());
+                let mut tto = tto.clone();
+                tto.bounds.push(TypeParamBound::Lifetime(lt.clone()));
+                // Parenthesize it, because "&dyn Foo + 'l" is ambiguous
+                let dty = Type::Paren(TypeParen {
+                    paren_token: token::Paren::default(),
+                    elem: Box::new(Type::TraitObject(tto))
+                });
+                *tr.elem = dty.clone();
+                return Some((lt, dty));
+            }
+        }
+    }
+    None
+}
+
 /// Convert a special reference type like "&str" into a reference to its owned
 /// type like "&String".
 fn destrify(ty: &mut Type) {
@@ -67,6 +93,11 @@ struct Common<'a> {
     rlifetimes: Generics,
     /// Is this for a static method or free function?
     is_static: bool,
+    /// Does the method consume `self` and return `Self`?
+    returns_self: bool,
+    /// Can the method have expectations for any instantiation of its generic
+    /// parameters?
+    dyn_expectable: bool,
     /// Expressions that create the predicate arguments from the call arguments
     predexprs: Vec<TokenStream>,
     /// Types used for Predicates.  Will be almost the same as args, but every
@@ -80,6 +111,8 @@ struct Common<'a> {
     output: Type,
     /// Identifier of the parent structure, if any
     parent_ident: Option<&'a Ident>,
+    /// Identifier of the mock module, for a function of a mocked module
+    parent_mod: Option<&'a Ident>,
     /// Visibility of the expectation
     /// TODO: supersuperfy it here rather than in the caller
     vis: Visibility
@@ -96,10 +129,25 @@ impl<'a> Common<'a> {
         let predty = &self.predty;
         let lg = &self.alifetimes;
         let hrtb = self.hrtb();
+        // Global expectations have no mock object to look them up in
+        let stable_ref = if self.is_static {
+            TokenStream::new()
+        } else {
+            quote!(
+                /// Get an
+                /// [`ExpectationRef`](../../../mockall/struct.ExpectationRef.html)
+                /// to this expectation.  It doesn't borrow the mock object, so
+                /// it can be used to reconfigure this expectation after
+                /// setting others.
+                #v fn stable_ref(&self) -> ::mockall::ExpectationRef<Self> {
+                    ::mockall::ExpectationRef::new(self.common.id)
+                }
+            )
+        };
         quote!(
             /// Add this expectation to a
             /// [`Sequence`](../../../mockall/struct.Sequence.html).
-            #v fn in_sequence(&mut self, __mockall_seq: &mut ::mockall::Sequence)
+            #v fn in_sequence(&mut self, __mockall_seq: &::mockall::Sequence)
                 -> &mut Self
             {
                 self.common.in_sequence(__mockall_seq);
@@ -150,16 +198,64 @@ impl<'a> Common<'a> {
                 self
             }
 
-            /// Allow this expectation to be called any number of times
+            /// Allow this expectation to be called any number of times,
+            /// including zero.  Such an expectation is always satisfied at
+            /// checkpoint time.
             ///
-            /// This behavior is the default, but the method is provided in case the
-            /// default behavior changes.
-            #[deprecated(since = "0.3.0", note = "Use times instead")]
+            /// This is the default behavior when neither `times_any` nor
+            /// [`times`](#method.times) is used.  It is equivalent to
+            /// `.times(..)`.
             #v fn times_any(&mut self) -> &mut Self {
                 self.common.times(..);
                 self
             }
 
+            /// Exempt this expectation from its minimum call count.  It will
+            /// be satisfied at checkpoint time even if it was never called,
+            /// but its maximum call count still applies.  In a
+            /// [`Sequence`](../../../mockall/struct.Sequence.html), it may be
+            /// skipped.
+            #v fn optional(&mut self) -> &mut Self {
+                self.common.optional();
+                self
+            }
+
+            /// Get a handle to this expectation, so other expectations can be
+            /// ordered after it with their [`after`](#method.after) methods.
+            #v fn handle(&mut self) -> ::mockall::ExpectationHandle {
+                self.common.handle()
+            }
+
+            /// Register this expectation for `mockall::verify_all`.
+            #[doc(hidden)]
+            #v fn register(&mut self) {
+                self.common.register();
+            }
+
+            #stable_ref
+
+            /// Require this expectation to happen after another one.  It will
+            /// panic if called before the other has been satisfied, and called
+            /// at least once.  See
+            /// [`ExpectationHandle`](../../../mockall/struct.ExpectationHandle.html).
+            #v fn after(&mut self, __mockall_h: &::mockall::ExpectationHandle)
+                -> &mut Self
+            {
+                self.common.after(__mockall_h);
+                self
+            }
+
+            /// Enroll this expectation in a
+            /// [`CallBudget`](../../../mockall/struct.CallBudget.html).  Each
+            /// call will add the given weight to the budget's count.  An
+            /// expectation may be enrolled in several budgets.
+            #v fn in_budget(&mut self, __mockall_b: &::mockall::CallBudget,
+                            __mockall_weight: usize) -> &mut Self
+            {
+                self.common.in_budget(__mockall_b, __mockall_weight);
+                self
+            }
+
             /// Allow this expectation to be called any number of times within a
             /// given range
             #[deprecated(since = "0.3.0", note = "Use times instead")]
@@ -173,8 +269,11 @@ impl<'a> Common<'a> {
             /// Set matching crieteria for this Expectation.
             ///
             /// The matching predicate can be anything implemening the
-            /// [`Predicate`](../../../mockall/trait.Predicate.html) trait.  Only
-            /// one matcher can be set per `Expectation` at a time.
+            /// [`Matcher`](../../../mockall/trait.Matcher.html) trait, which
+            /// includes every
+            /// [`Predicate`](../../../mockall/trait.Predicate.html).  Only
+            /// one set of predicates can be set per `Expectation` at a time,
+            /// though it may be combined with [`withf`](#method.withf).
             #v fn with<#with_generics>(&mut self, #with_args) -> &mut Self
             {
                 self.common.with(#(#argnames, )*);
@@ -207,9 +306,13 @@ impl<'a> Common<'a> {
 
     /// Common methods of the Expectations structs
     fn expectations_methods(&self) -> TokenStream {
+        let argnames = &self.argnames;
         let generics = merge_generics(&self.egenerics, &self.rlifetimes);
         let (ig, tg, wc) = generics.split_for_impl();
+        let lg = &self.alifetimes;
+        let predty = &self.predty;
         let v = &self.vis;
+        let (trace_mock, trace_meth) = self.trace_names();
         quote!(
             /// A collection of [`Expectation`](struct.Expectations.html)
             /// objects.  Users will rarely if ever use this struct directly.
@@ -217,23 +320,111 @@ impl<'a> Common<'a> {
             #v struct Expectations #ig ( Vec<Expectation #tg>) #wc;
 
             impl #ig Expectations #tg #wc {
-                /// Verify that all current expectations are satisfied and clear
-                /// them.
-                #v fn checkpoint(&mut self) -> std::vec::Drain<Expectation #tg>
+                /// Clear all current expectations, returning a description of
+                /// each one that wasn't satisfied, in the order they were
+                /// created.
+                #v fn checkpoint(&mut self) -> Vec<String>
                 {
                     self.0.drain(..)
+                        .flat_map(|mut __mockall_e|
+                                  __mockall_e.common.unsatisfied())
+                        .collect()
+                }
+
+                /// Clear all current expectations, without verifying them.
+                #v fn clear(&mut self) {
+                    self.checkpoint();
+                }
+
+                /// The number of current expectations.
+                #v fn len(&self) -> usize {
+                    self.0.len()
+                }
+
+                /// Are there no current expectations?
+                #v fn is_empty(&self) -> bool {
+                    self.0.is_empty()
+                }
+
+                /// Summarize the state of every expectation, one per line, in
+                /// the order they were created.
+                #v fn report(&self) -> Vec<String> {
+                    self.0.iter()
+                        .map(|__mockall_e| __mockall_e.common.report())
+                        .collect()
                 }
 
                 /// Create a new expectation for this method.
+                #[track_caller]
                 #v fn expect(&mut self) -> &mut Expectation #tg
                 {
-                    self.0.push(Expectation::default());
+                    let mut __mockall_e = Expectation::default();
+                    __mockall_e.common.location =
+                        ::mockall::SetLocation::caller();
+                    __mockall_e.common.span = ::mockall::TraceSpan::new(
+                        #trace_mock, #trace_meth, __mockall_e.common.location);
+                    if ::mockall::installing_fixture() {
+                        __mockall_e.common.fixture = true;
+                        ::mockall::fixture_member(__mockall_e.common.handle());
+                    }
+                    self.0.push(__mockall_e);
                     let __mockall_l = self.0.len();
                     &mut self.0[__mockall_l - 1]
                 }
 
-                #v fn new() -> Self {
-                    Self::default()
+                /// Find the current expectation with the given id.
+                #v fn find(&mut self, __mockall_id: usize)
+                    -> Option<&mut Expectation #tg>
+                {
+                    self.0.iter_mut()
+                        .find(|__mockall_e| __mockall_e.common.id == __mockall_id)
+                }
+
+                /// Choose the expectation to handle a call.  Expectations are
+                /// checked in FIFO order, but those set by a `Fixture` are
+                /// only candidates if none of the test's own match.
+                #v fn select #lg (&self, #( #argnames: &#predty, )*)
+                    -> Option<usize>
+                {
+                    let __mockall_n = self.0.len();
+                    let __mockall_find = |__mockall_fixture: bool|
+                        self.0.iter().position(|__mockall_e|
+                            __mockall_e.common.fixture == __mockall_fixture &&
+                            __mockall_e.matches(#(#argnames, )*) &&
+                            (!__mockall_e.is_done() || __mockall_n == 1));
+                    __mockall_find(false).or_else(|| {
+                        if !self.0.iter().any(|__mockall_e|
+                                              __mockall_e.common.fixture)
+                        {
+                            return None;
+                        }
+                        // The test's own expectations shadow the fixture's,
+                        // even when they're done.
+                        self.0.iter().position(|__mockall_e|
+                            !__mockall_e.common.fixture &&
+                            __mockall_e.matches(#(#argnames, )*)
+                        ).or_else(|| __mockall_find(true))
+                    })
+                }
+
+                /// Would a call with these arguments match any current
+                /// expectation?
+                #v fn has_match #lg (&self, #( #argnames: &#predty, )*)
+                    -> bool
+                {
+                    self.select(#(#argnames, )*).is_some()
+                }
+
+                /// Record that a call rejected all of these expectations.
+                #v fn reject #lg (&self, #( #argnames: &#predty, )*) {
+                    ::mockall::reject(self.0.iter()
+                        .map(|__mockall_e|
+                             __mockall_e.common.describe(#(#argnames, )*))
+                        .collect());
+                }
+
+                #v const fn new() -> Self {
+                    Expectations(Vec::new())
                 }
             }
             impl #ig Default for Expectations #tg #wc
@@ -251,6 +442,7 @@ impl<'a> Common<'a> {
         if !self.is_generic() {
             return TokenStream::new();
         }
+        let ident_str = self.ident_str();
         quote!(
             /// A collection of [`Expectation`](struct.Expectations.html)
             /// objects for a generic method.  Users will rarely if ever use
@@ -258,26 +450,113 @@ impl<'a> Common<'a> {
             #[doc(hidden)]
             #[derive(Default)]
             #v struct GenericExpectations{
-                store: std::collections::hash_map::HashMap<::mockall::Key,
-                               Box<dyn ::mockall::AnyExpectations>>
+                store: std::collections::BTreeMap<::mockall::Key,
+                               Box<dyn ::mockall::AnyExpectations>>,
+                any: ::mockall::DynExpectations
             }
             impl GenericExpectations {
-                /// Verify that all current expectations are satisfied and clear
-                /// them.  This applies to all sets of generic parameters!
-                #v fn checkpoint(&mut self) ->
-                    std::collections::hash_map::Drain<::mockall::Key,
-                               Box<dyn ::mockall::AnyExpectations>>
+                /// Clear all current expectations, returning a description of
+                /// each one that wasn't satisfied.  This applies to all sets of
+                /// generic parameters!  Each description names its set.
+                #v fn checkpoint(&mut self) -> Vec<String>
                 {
-                    self.store.drain()
+                    mem::take(&mut self.store).into_iter()
+                        .flat_map(|(__mockall_k, mut __mockall_e)|
+                            __mockall_k.annotate(#ident_str,
+                                                 __mockall_e.checkpoint()))
+                        .chain(self.any.checkpoint())
+                        .collect()
+                }
+
+                /// Clear all current expectations, without verifying them.
+                /// This applies to all sets of generic parameters!
+                #v fn clear(&mut self) {
+                    self.checkpoint();
+                }
+
+                /// The number of current expectations, for all sets of
+                /// generic parameters.
+                #v fn len(&self) -> usize {
+                    self.store.values()
+                        .map(|__mockall_e| __mockall_e.len())
+                        .sum::<usize>() + self.any.len()
                 }
 
-                #v fn new() -> Self {
-                    Self::default()
+                /// Are there no current expectations, for any set of generic
+                /// parameters?
+                #v fn is_empty(&self) -> bool {
+                    self.len() == 0
+                }
+
+                /// Summarize the state of every expectation, one per line.
+                /// This applies to all sets of generic parameters!
+                #v fn report(&self) -> Vec<String> {
+                    self.store.iter()
+                        .flat_map(|(__mockall_k, __mockall_e)|
+                            __mockall_k.annotate(#ident_str,
+                                                 __mockall_e.report()))
+                        .chain(self.any.report())
+                        .collect()
+                }
+
+                #v const fn new() -> Self {
+                    GenericExpectations {
+                        store: std::collections::BTreeMap::new(),
+                        any: ::mockall::DynExpectations::new()
+                    }
+                }
+            }
+        )
+    }
+
+    /// GenericExpectations' `has_match` method.  `wc` must be the where clause
+    /// used to downcast to `Expectations`.  `any` says whether to fall back to
+    /// the expectations for any generic parameters.
+    fn generic_has_match(&self, wc: &TokenStream, any: bool) -> TokenStream {
+        let argnames = &self.argnames;
+        let predty = &self.predty;
+        let mg = merge_generics(&self.alifetimes, &self.egenerics);
+        let (ig, _, _) = mg.split_for_impl();
+        let (_, tg, _) = self.egenerics.split_for_impl();
+        let key_ty = self.key_ty();
+        let v = &self.vis;
+        let fallback = if any {
+            quote!(self.any.has_match(
+                &[#(#argnames as &dyn ::std::any::Any, )*]))
+        } else {
+            quote!(false)
+        };
+        quote!(
+            /// Would a call with these arguments match any current
+            /// expectation?
+            #v fn has_match #ig (&self, #( #argnames: &#predty, )*) -> bool
+                #wc
+            {
+                match self.store.get(&::mockall::Key::new::<#key_ty>()) {
+                    Some(__mockall_e) => {
+                        __mockall_e.downcast_ref::<Expectations #tg>()
+                        .unwrap()
+                        .has_match(#(#argnames, )*)
+                    },
+                    None => #fallback
                 }
             }
         )
     }
 
+    /// The Expectation's where clause, plus the `Send + Sync` bound on the
+    /// output that reference expectations need for downcasting.
+    fn send_sync_wc(&self) -> WhereClause {
+        let output = &self.output;
+        let mut wc = self.egenerics.where_clause.clone()
+            .unwrap_or_else(|| WhereClause {
+                where_token: <Token![where]>::default(),
+                predicates: Punctuated::new()
+            });
+        wc.predicates.push(parse2(quote!(#output: Send + Sync)).unwrap());
+        wc
+    }
+
     fn hrtb(&self) -> TokenStream {
         if self.alifetimes.params.is_empty() {
             TokenStream::default()
@@ -287,8 +566,43 @@ impl<'a> Common<'a> {
         }
     }
 
+    /// The mock struct's name and the method's, for tracing.  Functions of
+    /// mocked modules have no mock struct.
+    fn trace_names(&self) -> (String, String) {
+        let mock = self.parent_ident.map(|pi| pi.to_string())
+            .unwrap_or_default();
+        (mock, self.meth_ident.to_string())
+    }
+
+    /// The type of the key to GenericExpectations: a tuple of the generic
+    /// parameters, so each one's name can be recovered.
+    fn key_ty(&self) -> TokenStream {
+        let fn_params = &self.fn_params;
+        if fn_params.is_empty() {
+            quote!(())
+        } else {
+            quote!((#fn_params,))
+        }
+    }
+
+    /// If this is a global expectation, of a function or static method, that
+    /// returns a `'static` shared reference, then the type it refers to.
+    /// Does this static method construct a mock of its parent struct?
+    fn returns_mock(&self) -> bool {
+        self.is_static && self.parent_ident
+            .map_or(false, |pi| crate::is_mock_type(&self.output, pi))
+    }
+
+    fn static_pointee(&self) -> Option<&Type> {
+        match &self.output {
+            Type::Reference(tr) if self.is_static && tr.mutability.is_none() =>
+                Some(&tr.elem),
+            _ => None
+        }
+    }
+
     fn ident_str(&self) -> String {
-        if let Some(pi) = self.parent_ident {
+        if let Some(pi) = self.parent_ident.or(self.parent_mod) {
             format!("{}::{}", pi, self.meth_ident)
         } else {
             format!("{}", self.meth_ident)
@@ -340,11 +654,43 @@ impl<'a> Expectation<'a> {
         dispatch!(self, generic_expectations_methods)
     }
 
+    /// The generic parameters and arguments of the `with` method, which takes
+    /// one matcher per argument of the mocked method, positionally.  The
+    /// arguments have the mocked method's argument names.
+    pub(crate) fn with_signature(&self) -> (TokenStream, TokenStream) {
+        let hrtb = self.common().hrtb();
+        let with_generics_idents = (0..self.common().predty.len())
+            .map(|i| format_ident!("MockallMatcher{}", i))
+            .collect::<Vec<_>>();
+        let with_generics = TokenStream::from_iter(
+            with_generics_idents.iter().zip(self.common().predty.iter())
+            .map(|(id, mt)|
+                quote!(#id: #hrtb ::mockall::Matcher<#mt> + Send + 'static, )
+            )
+        );
+        let with_args = TokenStream::from_iter(
+            self.common().argnames.iter().zip(with_generics_idents.iter())
+            .map(|(argname, id)| quote!(#argname: #id, ))
+        );
+        (with_generics, with_args)
+    }
+
+    /// Do the argument matchers need a higher-ranked trait bound?
+    pub(crate) fn has_hrtb(&self) -> bool {
+        !self.common().alifetimes.params.is_empty()
+    }
+
+    /// Names of the mocked method's arguments, not including the receiver
+    pub(crate) fn argnames(&self) -> &[Pat] {
+        &self.common().argnames
+    }
+
     pub(crate) fn gen(&self) -> TokenStream {
         let argnames = &self.common().argnames;
         let attrs = &self.common().attrs;
         let ident = &self.common().mod_ident;
         let ident_str = self.common().ident_str();
+        let (trace_mock, trace_meth) = self.common().trace_names();
         let extra_uses = self.extra_uses();
         let fn_params = &self.common().fn_params;
         let predty = &self.common().predty;
@@ -354,32 +700,20 @@ impl<'a> Expectation<'a> {
         let lg = &self.common().alifetimes;
         let preds = TokenStream::from_iter(
             self.common().predty.iter().map(|t|
-                quote!(Box<dyn #hrtb ::mockall::Predicate<#t> + Send>,)
+                quote!(Box<dyn #hrtb ::mockall::Matcher<#t> + Send>,)
             )
         );
         let pred_matches = TokenStream::from_iter(
             argnames.iter().enumerate()
             .map(|(i, argname)| {
                 let idx = syn::Index::from(i);
-                quote!(__mockall_pred.#idx.eval(#argname),)
+                quote!(__mockall_pred.#idx.matches(#argname),)
             })
         );
         let refpredty = TokenStream::from_iter(
             self.common().predty.iter().map(|mt| quote!(&#mt,))
         );
-        let with_generics_idents = (0..self.common().predty.len())
-            .map(|i| format_ident!("MockallMatcher{}", i))
-            .collect::<Vec<_>>();
-        let with_generics = TokenStream::from_iter(
-            with_generics_idents.iter().zip(self.common().predty.iter())
-            .map(|(id, mt)|
-                quote!(#id: #hrtb ::mockall::Predicate<#mt> + Send + 'static, )
-            )
-        );
-        let with_args = TokenStream::from_iter(
-            argnames.iter().zip(with_generics_idents.iter())
-            .map(|(argname, id)| quote!(#argname: #id, ))
-        );
+        let (with_generics, with_args) = self.with_signature();
         let boxed_withargs = TokenStream::from_iter(
             argnames.iter().map(|aa| quote!(Box::new(#aa), ))
         );
@@ -392,6 +726,11 @@ impl<'a> Expectation<'a> {
                 }
                 acc
             });
+        let predfunc_braces = if braces.is_empty() {
+            String::from("{}")
+        } else {
+            format!("{}, {{}}", braces)
+        };
         let indices = (0..argnames.len())
             .map(|i| {
                 let idx = syn::Index::from(i);
@@ -404,6 +743,8 @@ impl<'a> Expectation<'a> {
                 // Version of Matcher::Func for closures that aren't Send
                 FuncST(::mockall::Fragile<Box<dyn #hrtb Fn(#refpredty) -> bool>>),
                 Pred(Box<(#preds)>),
+                // Both per-argument predicates and a Func or FuncST
+                PredFunc(Box<(#preds)>, Box<Matcher #tg>),
                 // Prevent "unused type parameter" errors
                 // Surprisingly, PhantomData<Fn(generics)> is Send even if
                 // generics are not, unlike PhantomData<generics>
@@ -422,9 +763,39 @@ impl<'a> Expectation<'a> {
                             [#pred_matches]
                             .iter()
                             .all(|__mockall_x| *__mockall_x),
+                        Matcher::PredFunc(__mockall_pred, __mockall_f) =>
+                            [#pred_matches]
+                            .iter()
+                            .all(|__mockall_x| *__mockall_x) &&
+                            __mockall_f.matches(#(#argnames, )*),
                         _ => unreachable!()
                     }
                 }
+
+                /// Which part of the matcher, if any, rejects these
+                /// arguments, and why.  Per-argument predicates are evaluated
+                /// first.
+                #[allow(unused_variables)]
+                fn rejected_by #lg (&self, #( #argnames: &#predty, )*)
+                    -> Option<String>
+                {
+                    let __mockall_pred_why = match self {
+                        Matcher::Pred(__mockall_pred) |
+                        Matcher::PredFunc(__mockall_pred, _) => {
+                            None::<String>
+                            #(.or_else(|| __mockall_pred.#indices
+                                .describe_mismatch(#argnames)))*
+                        },
+                        _ => None
+                    };
+                    if let Some(__mockall_why) = __mockall_pred_why {
+                        Some(format!("the `with` predicates: {}", __mockall_why))
+                    } else if !self.matches(#(#argnames, )*) {
+                        Some(String::from("the `withf` function"))
+                    } else {
+                        None
+                    }
+                }
             }
 
             impl #ig Default for Matcher #tg #wc {
@@ -444,7 +815,11 @@ impl<'a> Expectation<'a> {
                         Matcher::FuncST(_) => write!(__mockall_fmt, "<single threaded function>"),
                         Matcher::Pred(__mockall_p) => {
                             write!(__mockall_fmt, #braces,
-                                #(__mockall_p.#indices,)*)
+                                #(__mockall_p.#indices.describe(),)*)
+                        }
+                        Matcher::PredFunc(__mockall_p, __mockall_f) => {
+                            write!(__mockall_fmt, #predfunc_braces,
+                                #(__mockall_p.#indices.describe(),)* __mockall_f)
                         }
                         _ => unreachable!(),
                     }
@@ -453,46 +828,107 @@ impl<'a> Expectation<'a> {
 
             /// Holds the stuff that is independent of the output type
             struct Common #ig #wc {
-                matcher: Mutex<Matcher #tg>,
+                matcher: Arc<Mutex<Matcher #tg>>,
                 seq_handle: Option<::mockall::SeqHandle>,
-                times: ::mockall::Times
+                budgets: Vec<::mockall::BudgetHandle>,
+                handle: Option<::mockall::ExpectationHandle>,
+                /// Identifies this expectation to an `ExpectationRef`
+                id: usize,
+                /// Was this expectation set by a `Fixture`?
+                fixture: bool,
+                span: ::mockall::TraceSpan,
+                registration: Option<Arc<::mockall::Registration>>,
+                times: ::mockall::Times,
+                location: ::mockall::SetLocation
             }
 
             impl #ig std::default::Default for Common #tg #wc
             {
                 fn default() -> Self {
                     Common {
-                        matcher: Mutex::new(Matcher::default()),
+                        matcher: Arc::new(Mutex::new(Matcher::default())),
                         seq_handle: None,
-                        times: ::mockall::Times::default()
+                        budgets: Vec::new(),
+                        handle: None,
+                        id: ::mockall::next_expectation_id(),
+                        fixture: false,
+                        span: ::mockall::TraceSpan::default(),
+                        registration: None,
+                        times: ::mockall::Times::default(),
+                        location: ::mockall::SetLocation::default()
                     }
                 }
             }
 
             impl #ig Common #tg #wc {
+                // Panics report the location of the call to the mock method,
+                // so each function on the way here tracks its caller, and
+                // panics outside of closures.
+                #[track_caller]
                 fn call(&self) {
-                    self.times.call()
-                        .unwrap_or_else(|m| {
+                    self.span.call(#trace_mock, #trace_meth, || format!(
+                        "Expectation({}){}", self.matcher.lock().unwrap(),
+                        self.location));
+                    if let Err(m) = self.times.call() {
+                        let desc = format!("{}", self.matcher.lock().unwrap());
+                        panic!("{}: Expectation({}) {}{}", #ident_str, desc,
+                            m, self.location);
+                    }
+                    for __mockall_b in self.budgets.iter() {
+                        if let Err(m) = __mockall_b.call() {
                             let desc = format!("{}",
                                                self.matcher.lock().unwrap());
-                            panic!("{}: Expectation({}) {}", #ident_str, desc,
-                                m);
-                        });
+                            panic!("{}: Expectation({}){}: {}", #ident_str,
+                                desc, self.location, m);
+                        }
+                    }
+                    if let Some(__mockall_h) = &self.handle {
+                        if let Err(m) = __mockall_h.verify() {
+                            let desc = format!("{}",
+                                               self.matcher.lock().unwrap());
+                            panic!("{}: Expectation({}){}: {}", #ident_str,
+                                desc, self.location, m);
+                        }
+                        if self.times.is_satisfied() {
+                            __mockall_h.satisfy();
+                        }
+                    }
                     self.verify_sequence();
-                    if self.times.is_satisfied() {
+                    // Sequenced expectations have exact call counts, so this
+                    // is when they're satisfied, even if optional.
+                    if self.times.is_done() {
                         self.satisfy_sequence()
                     }
                 }
 
-                fn in_sequence(&mut self, __mockall_seq: &mut ::mockall::Sequence)
+                fn in_sequence(&mut self, __mockall_seq: &::mockall::Sequence)
                     -> &mut Self
                 {
                     assert!(self.times.is_exact(),
                         "Only Expectations with an exact call count have sequences");
-                    self.seq_handle = Some(__mockall_seq.next_handle());
+                    let __mockall_handle = __mockall_seq.next_handle();
+                    if self.times.is_optional() {
+                        __mockall_handle.optional();
+                    }
+                    self.seq_handle = Some(__mockall_handle);
                     self
                 }
 
+                /// Describe this expectation for the error message of a call
+                /// that it rejected, and say why it rejected it.
+                fn describe #lg (&self, #( #argnames: &#predty, )*) -> String {
+                    let __mockall_m = self.matcher.lock().unwrap();
+                    let __mockall_why = match __mockall_m
+                        .rejected_by(#(#argnames, )*)
+                    {
+                        Some(__mockall_layer) =>
+                            format!("rejected by {}", __mockall_layer),
+                        None => self.times.exhausted()
+                    };
+                    format!("Expectation({}){}: {}", __mockall_m,
+                            self.location, __mockall_why)
+                }
+
                 fn is_done(&self) -> bool {
                     self.times.is_done()
                 }
@@ -506,6 +942,7 @@ impl<'a> Expectation<'a> {
                     self.times.never();
                 }
 
+                #[track_caller]
                 fn satisfy_sequence(&self) {
                     if let Some(__mockall_handle) = &self.seq_handle {
                         __mockall_handle.satisfy()
@@ -520,47 +957,134 @@ impl<'a> Expectation<'a> {
                     self.times.times(__mockall_r)
                 }
 
+                fn handle(&mut self) -> ::mockall::ExpectationHandle {
+                    let __mockall_name = format!("{}{}", #ident_str,
+                                                 self.location);
+                    self.handle.get_or_insert_with(||
+                        ::mockall::ExpectationHandle::new(__mockall_name)
+                    ).clone()
+                }
+
+                fn after(&mut self, __mockall_h: &::mockall::ExpectationHandle) {
+                    self.handle().after(__mockall_h);
+                }
+
+                fn in_budget(&mut self, __mockall_b: &::mockall::CallBudget,
+                             __mockall_weight: usize)
+                {
+                    self.budgets.push(__mockall_b.handle(__mockall_weight));
+                }
+
+                fn optional(&mut self) {
+                    self.times.optional();
+                    if let Some(__mockall_handle) = &self.seq_handle {
+                        __mockall_handle.optional();
+                    }
+                }
+
                 fn with<#with_generics>(&mut self, #with_args)
                 {
                     let mut __mockall_guard = self.matcher.lock().unwrap();
-                    mem::replace(__mockall_guard.deref_mut(),
-                        Matcher::Pred(Box::new((#boxed_withargs))));
+                    let __mockall_p: Box<(#preds)> = Box::new((#boxed_withargs));
+                    *__mockall_guard = match mem::take(&mut *__mockall_guard) {
+                        Matcher::PredFunc(_, __mockall_f) =>
+                            Matcher::PredFunc(__mockall_p, __mockall_f),
+                        __mockall_f @ Matcher::Func(_) |
+                        __mockall_f @ Matcher::FuncST(_) =>
+                            Matcher::PredFunc(__mockall_p, Box::new(__mockall_f)),
+                        _ => Matcher::Pred(__mockall_p)
+                    };
+                    drop(__mockall_guard);
+                    self.describe_registration();
+                }
+
+                /// Register this expectation for `mockall::verify_all`.
+                fn register(&mut self) {
+                    self.registration = Some(::mockall::Registration::new(
+                        #ident_str, self.location, self.times.share()));
+                    self.describe_registration();
+                }
+
+                fn describe_registration(&self) {
+                    if let Some(__mockall_r) = &self.registration {
+                        __mockall_r.describe(
+                            format!("{}", self.matcher.lock().unwrap()));
+                    }
+                }
+
+                /// Set the function matcher, keeping any per-argument
+                /// predicates.
+                fn set_func(&mut self, __mockall_f: Matcher #tg) {
+                    let mut __mockall_guard = self.matcher.lock().unwrap();
+                    *__mockall_guard = match mem::take(&mut *__mockall_guard) {
+                        Matcher::Pred(__mockall_p) |
+                        Matcher::PredFunc(__mockall_p, _) =>
+                            Matcher::PredFunc(__mockall_p, Box::new(__mockall_f)),
+                        _ => __mockall_f
+                    };
+                    drop(__mockall_guard);
+                    self.describe_registration();
                 }
 
                 fn withf<MockallF>(&mut self, __mockall_f: MockallF)
                     where MockallF: #hrtb Fn(#refpredty)
                                     -> bool + Send + 'static
                 {
-                    let mut __mockall_guard = self.matcher.lock().unwrap();
-                    mem::replace(__mockall_guard.deref_mut(),
-                                 Matcher::Func(Box::new(__mockall_f)));
+                    self.set_func(Matcher::Func(Box::new(__mockall_f)));
                 }
 
                 fn withf_st<MockallF>(&mut self, __mockall_f: MockallF)
                     where MockallF: #hrtb Fn(#refpredty)
                                     -> bool + 'static
                 {
-                    let mut __mockall_guard = self.matcher.lock().unwrap();
-                    mem::replace(__mockall_guard.deref_mut(),
-                                 Matcher::FuncST(::mockall::Fragile::new(Box::new(__mockall_f))));
+                    self.set_func(Matcher::FuncST(
+                        ::mockall::Fragile::new(Box::new(__mockall_f))));
                 }
 
+                #[track_caller]
                 fn verify_sequence(&self) {
                     if let Some(__mockall_handle) = &self.seq_handle {
                         __mockall_handle.verify()
                     }
                 }
+
+                /// Summarize this expectation's state, like its call count
+                /// constraint and how many times it's been called.
+                fn report(&self) -> String {
+                    format!("{}: Expectation({}){}: {}", #ident_str,
+                            self.matcher.lock().unwrap(), self.location,
+                            self.times.report())
+                }
+
+                /// If this expectation or any of its call budgets haven't been
+                /// called often enough, then describe the problems, and forget
+                /// about them so that they won't be reported again on drop.
+                /// Skip any that `verify_all` already reported.
+                fn unsatisfied(&mut self) -> Vec<String> {
+                    let mut __mockall_msgs = Vec::new();
+                    let __mockall_reported = self.registration.as_ref()
+                        .map_or(false, |__mockall_r| __mockall_r.is_reported());
+                    if !__mockall_reported && !self.times.is_satisfied() {
+                        let desc = format!("{}", self.matcher.lock().unwrap());
+                        __mockall_msgs.push(format!(
+                            "{}: Expectation({}) called fewer than {} times{}",
+                            #ident_str,
+                            desc,
+                            self.times.minimum(),
+                            self.location));
+                        self.times.times(..);
+                    }
+                    __mockall_msgs.extend(self.budgets.iter()
+                        .filter_map(::mockall::BudgetHandle::unsatisfied));
+                    __mockall_msgs
+                }
             }
 
             impl #ig Drop for Common #tg #wc {
                 fn drop(&mut self) {
-                    if !::std::thread::panicking() && !self.times.is_satisfied()
-                    {
-                        let desc = format!("{}", self.matcher.lock().unwrap());
-                        panic!("{}: Expectation({}) called fewer than {} times",
-                               #ident_str,
-                               desc,
-                               self.times.minimum());
+                    if !::std::thread::panicking() {
+                        ::mockall::Checkpoints::from(self.unsatisfied())
+                            .finish();
                     }
                 }
             }
@@ -573,8 +1097,11 @@ impl<'a> Expectation<'a> {
         let gem_ts1 = self.common().generic_expectations_methods();
         let gem_ts2 = self.generic_expectations_methods();
         let sm_ts = self.static_method_methods(&with_generics, &with_args);
+        let doc_hidden = doc_hidden();
         quote!(
             #attrs
+            #[allow(missing_docs)]
+            #doc_hidden
             pub mod #ident {
                 #extra_uses
                 use super::*;   // Import types from the calling environment
@@ -582,7 +1109,7 @@ impl<'a> Expectation<'a> {
                 use ::std::{
                     mem,
                     ops::{DerefMut, Range},
-                    sync::Mutex
+                    sync::{Arc, Mutex}
                 };
                 #rfunc_ts
                 #matcher_ts
@@ -630,11 +1157,18 @@ impl<'a> Expectation<'a> {
         let mut is_static = true;
         let mut predexprs = Vec::new();
         let mut predty = Vec::new();
+        let mut dyn_lifetimes = Vec::new();
         for fa in args.iter() {
             if let FnArg::Typed(pt) = fa {
                 let argname = (*pt.pat).clone();
-                let aty = supersuperfy(&pt.ty, levels);
-                if let Type::Reference(ref tr) = aty {
+                let mut aty = supersuperfy(&pt.ty, levels);
+                if let Some((lt, dty)) = bound_dyn(&mut aty, dyn_lifetimes.len())
+                {
+                    predexprs.push(quote!(#argname));
+                    predty.push(dty);
+                    dyn_lifetimes.push(GenericParam::Lifetime(
+                            LifetimeDef::new(lt)));
+                } else if let Type::Reference(ref tr) = aty {
                     predexprs.push(quote!(#argname));
                     predty.push((*tr.elem).clone());
                 } else {
@@ -653,8 +1187,13 @@ impl<'a> Expectation<'a> {
         } else {
             meth_generics.clone()
         };
-        let (mut egenerics, alifetimes, rlifetimes) = split_lifetimes(generics,
-                                                                      args, rt);
+        let (mut egenerics, mut alifetimes, rlifetimes) =
+            split_lifetimes(generics, args, rt);
+        if !dyn_lifetimes.is_empty() {
+            let mut lv = alifetimes.params.into_iter().collect::<Vec<_>>();
+            lv.extend(dyn_lifetimes);
+            alifetimes = lifetimes_to_generics(lv);
+        }
         for p in egenerics.params.iter_mut() {
             if let GenericParam::Type(tp) = p {
                 let static_bound = Lifetime::new("'static", Span::call_site());
@@ -709,12 +1248,15 @@ impl<'a> Expectation<'a> {
             alifetimes,
             rlifetimes,
             is_static,
+            returns_self: crate::returns_self(args, rt),
+            dyn_expectable: crate::is_dyn_expectable(args, rt, meth_generics),
             predexprs,
             predty,
             meth_ident,
             mod_ident,
             output,
             parent_ident,
+            parent_mod: None,
             vis: vis.clone()
         };
         if ref_mut_expectation {
@@ -726,6 +1268,18 @@ impl<'a> Expectation<'a> {
         }
     }
 
+    /// Name the expectation's function after the mock module that it belongs
+    /// to, in its error messages.
+    pub(crate) fn in_module(mut self, modname: &'a Ident) -> Self {
+        let common = match &mut self {
+            Expectation::Ref(e) => &mut e.common,
+            Expectation::RefMut(e) => &mut e.common,
+            Expectation::Static(e) => &mut e.common,
+        };
+        common.parent_mod = Some(modname);
+        self
+    }
+
     fn rfunc(&self) -> TokenStream { dispatch!(self, rfunc) }
 
     fn static_method_methods(&self, with_generics: &TokenStream,
@@ -766,27 +1320,124 @@ impl<'a> StaticExpectation<'a> {
         let lg = &self.common.alifetimes;
         let output = &self.common.output;
         let v = &self.common.vis;
+        let return_static = match self.common.static_pointee() {
+            Some(pointee) => quote!(
+                /// Return a `'static` reference to a value computed at
+                /// runtime, like a `String` for a function that returns
+                /// `&'static str`.  The value is kept until the process exits.
+                ///
+                /// Only usable by functions and static methods that return a
+                /// `'static` reference.
+                #[allow(unused_variables)]
+                #v fn return_static<MockallT>(&mut self, __mockall_t: MockallT)
+                    -> &mut Self
+                    where MockallT: ::mockall::IntoStaticRef<#pointee>,
+                          // Equivalent to `#pointee: Sync`, but higher-ranked
+                          // so it isn't an error when it's false.
+                          for<'__mockall_a> &'__mockall_a #pointee: Send
+                {
+                    let __mockall_r = __mockall_t.into_static_ref();
+                    self.returning(move |#(#argnames, )*| __mockall_r.get())
+                }
+            ),
+            None => TokenStream::new()
+        };
+        let (finish_self, returning_self) = if self.common.returns_self {
+            (quote!(
+                /// Run the return function, unless the expectation returns
+                /// the mock object itself.  In that case, return `None`.
+                #[track_caller]
+                #v fn finish_self #lg (self, #(#argnames: #argty, )* )
+                    -> Option<#output>
+                {
+                    let returns_self = self.rfunc.lock_for_call()
+                        .map_or(false, |__mockall_guard| {
+                            if let Rfunc::ReturnSelf = *__mockall_guard {
+                                true
+                            } else {
+                                false
+                            }
+                        });
+                    if returns_self {
+                        None
+                    } else {
+                        Some(self.finish(#(#argnames, )*))
+                    }
+                }
+            ), quote!(
+                /// Return the mock object itself, along with all of its
+                /// expectations.  This is useful for builder-style methods,
+                /// which consume `self` and return `Self`.
+                #v fn returning_self(&mut self) -> &mut Self {
+                    {
+                        let mut __mockall_guard = self.rfunc.lock().unwrap();
+                        *__mockall_guard = Rfunc::ReturnSelf;
+                    }
+                    self
+                }
+            ))
+        } else {
+            (TokenStream::new(), TokenStream::new())
+        };
         quote!(
             /// Expectation type for methods that return a `'static` type.
             /// This is the type returned by the `expect_*` methods.
             #v struct Expectation #ig #wc {
                 common: Common #common_tg,
-                rfunc: Mutex<Rfunc #tg>,
+                rfunc: Arc<::mockall::CallMutex<Rfunc #tg>>,
+            }
+
+            /// A call to an [`Expectation`] that has been matched, but whose
+            /// return function hasn't run yet.  It doesn't borrow the
+            /// `Expectation`, so any locks on that may be released first.
+            #[doc(hidden)]
+            #v struct PendingCall #ig #wc {
+                matcher: Arc<Mutex<Matcher #common_tg>>,
+                rfunc: Arc<::mockall::CallMutex<Rfunc #tg>>,
+                location: ::mockall::SetLocation,
+            }
+
+            impl #ig PendingCall #tg #wc {
+                /// Run the return function
+                #[track_caller]
+                #v fn finish #lg (self, #(#argnames: #argty, )* ) -> #output
+                {
+                    let desc = || format!("{}", self.matcher.lock().unwrap());
+                    let mut __mockall_guard = match self.rfunc.lock_for_call() {
+                        Some(__mockall_guard) => __mockall_guard,
+                        None => panic!("{}: Expectation({}) called from its own return function{}",
+                                       #ident_str, desc(), self.location)
+                    };
+                    match __mockall_guard.call_mut(#(#argnames, )*) {
+                        Ok(__mockall_o) => __mockall_o,
+                        Err(message) => panic!("{}: Expectation({}) {}{}",
+                            #ident_str, desc(), message, self.location)
+                    }
+                }
+
+                #finish_self
             }
 
             impl #ig Expectation #tg #wc {
                 /// Call this [`Expectation`] as if it were the real method.
                 #[doc(hidden)]
+                #[track_caller]
                 #v fn call #lg (&self, #(#argnames: #argty, )* ) -> #output
                 {
+                    self.start_call().finish(#(#argnames, )*)
+                }
+
+                /// Record a call to this [`Expectation`], but don't run its
+                /// return function yet.
+                #[doc(hidden)]
+                #[track_caller]
+                #v fn start_call(&self) -> PendingCall #tg {
                     self.common.call();
-                    self.rfunc.lock().unwrap().call_mut(#(#argnames, )*)
-                        .unwrap_or_else(|message| {
-                            let desc = format!("{}",
-                                self.common.matcher.lock().unwrap());
-                            panic!("{}: Expectation({}) {}", #ident_str, desc,
-                                   message);
-                        })
+                    PendingCall {
+                        matcher: self.common.matcher.clone(),
+                        rfunc: self.rfunc.clone(),
+                        location: self.common.location
+                    }
                 }
 
                 /// Return a constant value from the `Expectation`
@@ -806,6 +1457,101 @@ impl<'a> StaticExpectation<'a> {
                     self.returning(move |#(#argnames, )*| __mockall_c.clone().into())
                 }
 
+                #return_static
+
+                /// Panic with the given payload, like `panic!` does with its
+                /// message.  The call still counts toward the expectation's
+                /// call count.  This is the only way a method that returns `!`
+                /// can behave.
+                #[allow(unused_variables)]
+                #v fn panics_with<MockallP>(&mut self, __mockall_p: MockallP)
+                    -> &mut Self
+                    where MockallP: Clone + Send + 'static
+                {
+                    self.returning(move |#(#argnames, )*|
+                        ::std::panic::panic_any(__mockall_p.clone()))
+                }
+
+                /// Return a constant `Ok` value from the `Expectation`.
+                ///
+                /// Only usable when the method returns a `Result`.  The value
+                /// must be `Clone`.
+                #[allow(unused_variables)]
+                #v fn return_ok<MockallT>(&mut self, __mockall_t: MockallT)
+                    -> &mut Self
+                    where #output: ::mockall::ReturnOk<MockallT>,
+                          MockallT: Clone + Send + 'static
+                {
+                    self.returning(move |#(#argnames, )*|
+                        <#output as ::mockall::ReturnOk<MockallT>>::from_ok(
+                            __mockall_t.clone()
+                        )
+                    )
+                }
+
+                /// Return `Ok` of the default value from the `Expectation`.
+                ///
+                /// Only usable when the method returns a `Result` whose `Ok`
+                /// type implements `Default`.
+                #[allow(unused_variables)]
+                #v fn return_ok_default<MockallT>(&mut self) -> &mut Self
+                    where #output: ::mockall::ReturnOk<MockallT>,
+                          MockallT: Default + 'static
+                {
+                    self.returning(move |#(#argnames, )*|
+                        <#output as ::mockall::ReturnOk<MockallT>>::from_ok(
+                            MockallT::default()
+                        )
+                    )
+                }
+
+                /// Return a constant `Err` value from the `Expectation`.
+                ///
+                /// Only usable when the method returns a `Result`.  The error
+                /// must be `Clone`.
+                #[allow(unused_variables)]
+                #v fn return_err<MockallE>(&mut self, __mockall_e: MockallE)
+                    -> &mut Self
+                    where #output: ::mockall::ReturnErr<MockallE>,
+                          MockallE: Clone + Send + 'static
+                {
+                    self.returning(move |#(#argnames, )*|
+                        <#output as ::mockall::ReturnErr<MockallE>>::from_err(
+                            __mockall_e.clone()
+                        )
+                    )
+                }
+
+                /// Return `Some` constant value from the `Expectation`.
+                ///
+                /// Only usable when the method returns an `Option`.  The value
+                /// must be `Clone`.
+                #[allow(unused_variables)]
+                #v fn return_some<MockallT>(&mut self, __mockall_t: MockallT)
+                    -> &mut Self
+                    where #output: ::mockall::ReturnOption<MockallT>,
+                          MockallT: Clone + Send + 'static
+                {
+                    self.returning(move |#(#argnames, )*|
+                        <#output as ::mockall::ReturnOption<MockallT>>::from_some(
+                            __mockall_t.clone()
+                        )
+                    )
+                }
+
+                /// Return `None` from the `Expectation`.
+                ///
+                /// Only usable when the method returns an `Option`.
+                #[allow(unused_variables)]
+                #v fn return_none<MockallT>(&mut self) -> &mut Self
+                    where #output: ::mockall::ReturnOption<MockallT>,
+                          MockallT: 'static
+                {
+                    self.returning(move |#(#argnames, )*|
+                        <#output as ::mockall::ReturnOption<MockallT>>::none()
+                    )
+                }
+
                 /// Supply an `FnOnce` closure that will provide the return
                 /// value for this Expectation.  This is useful for return types
                 /// that aren't `Clone`.  It will be an error to call this
@@ -817,8 +1563,7 @@ impl<'a> StaticExpectation<'a> {
                 {
                     {
                         let mut __mockall_guard = self.rfunc.lock().unwrap();
-                        mem::replace(__mockall_guard.deref_mut(),
-                            Rfunc::Once(Box::new(__mockall_f)));
+                        *__mockall_guard = Rfunc::Once(Box::new(__mockall_f));
                     }
                     self
                 }
@@ -838,8 +1583,8 @@ impl<'a> StaticExpectation<'a> {
                 {
                     {
                         let mut __mockall_guard = self.rfunc.lock().unwrap();
-                        mem::replace(__mockall_guard.deref_mut(), Rfunc::OnceST(
-                            ::mockall::Fragile::new(Box::new(__mockall_f))));
+                        *__mockall_guard = Rfunc::OnceST(
+                            ::mockall::Fragile::new(Box::new(__mockall_f)));
                     }
                     self
                 }
@@ -854,8 +1599,57 @@ impl<'a> StaticExpectation<'a> {
                 {
                     {
                         let mut __mockall_guard = self.rfunc.lock().unwrap();
-                        mem::replace(__mockall_guard.deref_mut(),
-                            Rfunc::Mut(Box::new(__mockall_f)));
+                        *__mockall_guard = Rfunc::Mut(Box::new(__mockall_f));
+                    }
+                    self
+                }
+
+                /// Supply a sequence of return values for this `Expectation`.
+                ///
+                /// Each value will be returned exactly once, in order.  The
+                /// `Expectation` must be called exactly as many times as there
+                /// are values.  Unlike setting multiple `Expectation`s, this
+                /// version uses a single matcher for every call.  As with
+                /// [`return_const`](#method.return_const), you will usually
+                /// need to specify the values' type explicitly.
+                #[allow(unused_variables)]
+                #v fn returning_seq<MockallOutput>(&mut self,
+                    __mockall_values: Vec<MockallOutput>) -> &mut Self
+                    where MockallOutput: Into<#output> + Send + 'static
+                {
+                    let __mockall_q = __mockall_values.into_iter()
+                        .map(|__mockall_v| {
+                            let __mockall_f: Box<dyn #hrtb FnOnce(#(#argty, )*)
+                                -> #output + Send> =
+                                Box::new(move |#(#argnames, )*|
+                                    __mockall_v.into());
+                            __mockall_f
+                        }).collect::<::std::collections::VecDeque<_>>();
+                    self.common.times(__mockall_q.len());
+                    {
+                        let mut __mockall_guard = self.rfunc.lock().unwrap();
+                        *__mockall_guard.deref_mut() = Rfunc::Seq(__mockall_q);
+                    }
+                    self
+                }
+
+                /// Supply a sequence of `FnOnce` closures that will provide the
+                /// return values for this `Expectation`.
+                ///
+                /// Each closure will be called exactly once, in order.  This is
+                /// useful for return types that aren't `Clone`.  The
+                /// `Expectation` must be called exactly as many times as there
+                /// are closures.
+                #v fn returning_fn_seq(&mut self,
+                    __mockall_fs: Vec<Box<dyn #hrtb FnOnce(#(#argty, )*)
+                                          -> #output + Send>>)
+                    -> &mut Self
+                {
+                    self.common.times(__mockall_fs.len());
+                    {
+                        let mut __mockall_guard = self.rfunc.lock().unwrap();
+                        *__mockall_guard.deref_mut() =
+                            Rfunc::Seq(__mockall_fs.into());
                     }
                     self
                 }
@@ -873,13 +1667,14 @@ impl<'a> StaticExpectation<'a> {
                 {
                     {
                         let mut __mockall_guard = self.rfunc.lock().unwrap();
-                        mem::replace(__mockall_guard.deref_mut(), Rfunc::MutST(
-                            ::mockall::Fragile::new(Box::new(__mockall_f)))
-                        );
+                        *__mockall_guard = Rfunc::MutST(
+                            ::mockall::Fragile::new(Box::new(__mockall_f)));
                     }
                     self
                 }
 
+                #returning_self
+
                 #em_ts
             }
             impl #ig Default for Expectation #tg #wc
@@ -887,7 +1682,8 @@ impl<'a> StaticExpectation<'a> {
                 fn default() -> Self {
                     Expectation {
                         common: Common::default(),
-                        rfunc: Mutex::new(Rfunc::default())
+                        rfunc: Arc::new(::mockall::CallMutex::new(
+                                Rfunc::default()))
                     }
                 }
             }
@@ -903,24 +1699,99 @@ impl<'a> StaticExpectation<'a> {
         let lg = &self.common.alifetimes;
         let output = &self.common.output;
         let predexprs = &self.common.predexprs;
+        let predty = &self.common.predty;
         let v = &self.common.vis;
+        let ident_str = self.common.ident_str();
+        let call_unlocked = if self.common.is_static {
+            quote!(
+                /// Like `call`, but release the lock on these expectations
+                /// before running the return function.  That way the return
+                /// function may call other static methods, or this one.
+                #[track_caller]
+                #v fn call_unlocked #lg (
+                    __mockall_guard: MutexGuard<'_, Self>,
+                    #(#argnames: #argty, )*
+                ) -> Option<#output>
+                {
+                    let __mockall_p = __mockall_guard
+                        .start_call(#(#predexprs, )*);
+                    drop(__mockall_guard);
+                    match __mockall_p {
+                        Some(__mockall_p) =>
+                            Some(__mockall_p.finish(#(#argnames, )*)),
+                        None => None
+                    }
+                }
+            )
+        } else {
+            TokenStream::new()
+        };
+        let call_self = if self.common.returns_self {
+            quote!(
+                /// Like `call`, but return `Some(None)` if the matching
+                /// expectation returns the mock object itself.
+                #[track_caller]
+                #v fn call_self #lg (&self, #(#argnames: #argty, )* )
+                    -> Option<Option<#output>>
+                {
+                    match self.start_call(#(#predexprs, )*) {
+                        Some(__mockall_p) =>
+                            Some(__mockall_p.finish_self(#(#argnames, )*)),
+                        None => None
+                    }
+                }
+            )
+        } else {
+            TokenStream::new()
+        };
         quote!(
             impl #ig Expectations #tg #wc {
                 /// Simulate calling the real method.  Every current expectation
                 /// will be checked in FIFO order and the first one with
                 /// matching arguments will be used.
+                #[track_caller]
                 #v fn call #lg (&self, #(#argnames: #argty, )* )
                     -> Option<#output>
                 {
-                    self.0.iter()
-                        .find(|__mockall_e|
-                              __mockall_e.matches(#(#predexprs, )*) &&
-                              (!__mockall_e.is_done() || self.0.len() == 1))
-                        .map(move |__mockall_e|
-                             __mockall_e.call(#(#argnames, )*)
-                        )
+                    match self.start_call(#(#predexprs, )*) {
+                        Some(__mockall_p) =>
+                            Some(__mockall_p.finish(#(#argnames, )*)),
+                        None => None
+                    }
                 }
 
+                /// Like `call`, but if no expectation matches, then return a
+                /// [`CallError`](::mockall::CallError) describing the call
+                /// instead of `None`.
+                #[track_caller]
+                #v fn try_call #lg (&self, #(#argnames: #argty, )* )
+                    -> ::std::result::Result<#output, ::mockall::CallError>
+                {
+                    match self.call(#(#argnames, )*) {
+                        Some(__mockall_o) => Ok(__mockall_o),
+                        None => Err(::mockall::CallError::unmatched(#ident_str))
+                    }
+                }
+
+                /// Find the first current expectation with matching arguments,
+                /// and record a call to it, but don't run its return function
+                /// yet.
+                #[track_caller]
+                #v fn start_call #lg (&self, #(#argnames: &#predty, )* )
+                    -> Option<PendingCall #tg>
+                {
+                    match self.select(#(#argnames, )*) {
+                        Some(__mockall_i) => Some(self.0[__mockall_i].start_call()),
+                        None => {
+                            self.reject(#(#argnames, )*);
+                            None
+                        }
+                    }
+                }
+
+                #call_self
+
+                #call_unlocked
             }
         )
     }
@@ -937,35 +1808,196 @@ impl<'a> StaticExpectation<'a> {
         let argnames = &self.common.argnames;
         let argty = &self.common.argty;
         let (ig, tg, wc) = self.common.egenerics.split_for_impl();
+        let key_ty = self.common.key_ty();
+        let ident_str = self.common.ident_str();
         let output = &self.common.output;
         let tbf = tg.as_turbofish();
         let v = &self.common.vis;
-        quote!(
-            impl #ig ::mockall::AnyExpectations for Expectations #tg #wc {}
-            impl GenericExpectations {
+        let predexprs = &self.common.predexprs;
+        let call_unlocked = if self.common.is_static {
+            quote!(
+                /// Like `call`, but release the lock on these expectations
+                /// before running the return function.
+                #[track_caller]
+                #v fn call_unlocked #ig (
+                    __mockall_guard: MutexGuard<'_, Self>,
+                    #(#argnames: #argty, )*
+                ) -> Option<#output> #wc
+                {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    ::mockall::note_lookup(#ident_str, &__mockall_k,
+                                           &__mockall_guard.store);
+                    let __mockall_p = match __mockall_guard.store
+                        .get(&__mockall_k)
+                    {
+                        Some(__mockall_e) => {
+                            __mockall_e.downcast_ref::<Expectations #tg>()
+                            .unwrap()
+                            .start_call(#(#predexprs, )*)
+                        },
+                        None => None
+                    };
+                    drop(__mockall_guard);
+                    match __mockall_p {
+                        Some(__mockall_p) =>
+                            Some(__mockall_p.finish(#(#argnames, )*)),
+                        None => None
+                    }
+                }
+
+                /// Like `checkpoint`, but only for this set of generic
+                /// parameters.
+                #v fn checkpoint_one #ig (&mut self) -> Vec<String> #wc
+                {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    self.store.remove(&__mockall_k)
+                        .map(|mut __mockall_e| __mockall_k.annotate(
+                                #ident_str, __mockall_e.checkpoint()))
+                        .unwrap_or_default()
+                }
+
+                /// Like `report`, but only for this set of generic parameters.
+                #v fn report_one #ig (&self) -> Vec<String> #wc
+                {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    self.store.get(&__mockall_k)
+                        .map(|__mockall_e| __mockall_k.annotate(
+                                #ident_str, __mockall_e.report()))
+                        .unwrap_or_default()
+                }
+
+                /// Like `len`, but only for this set of generic parameters.
+                #v fn len_one #ig (&self) -> usize #wc
+                {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    self.store.get(&__mockall_k)
+                        .map_or(0, |__mockall_e| __mockall_e.len())
+                }
+            )
+        } else {
+            TokenStream::new()
+        };
+        let call_self = if self.common.returns_self {
+            quote!(
+                /// Like `call`, but return `Some(None)` if the matching
+                /// expectation returns the mock object itself.
+                #[track_caller]
+                #v fn call_self #ig (&self, #(#argnames: #argty, )* )
+                    -> Option<Option<#output>> #wc
+                {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    ::mockall::note_lookup(#ident_str, &__mockall_k,
+                                           &self.store);
+                    match self.store.get(&__mockall_k) {
+                        Some(__mockall_e) => {
+                            __mockall_e.downcast_ref::<Expectations #tg>()
+                            .unwrap()
+                            .call_self(#(#argnames, )*)
+                        },
+                        None => None
+                    }
+                }
+            )
+        } else {
+            TokenStream::new()
+        };
+        let has_match = self.common.generic_has_match(
+            &quote!(#wc), self.common.dyn_expectable);
+        let call = if self.common.dyn_expectable {
+            quote!(
+                /// Simulating calling the real method.  If there are no
+                /// expectations for these particular generic parameters, then
+                /// use the ones for any generic parameters.
+                #[track_caller]
+                #v fn call #ig (&self, #(#argnames: #argty, )* )
+                    -> Option<#output> #wc
+                {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    ::mockall::note_lookup(#ident_str, &__mockall_k,
+                                           &self.store);
+                    match self.store.get(&__mockall_k) {
+                        Some(__mockall_e) => {
+                            __mockall_e.downcast_ref::<Expectations #tg>()
+                            .unwrap()
+                            .call(#(#argnames, )*)
+                        },
+                        None => self.any.call::<#output>(
+                            &[#(#predexprs as &dyn ::std::any::Any, )*])
+                    }
+                }
+
+                /// Create a new Expectation for any generic parameters.
+                #[track_caller]
+                #v fn expect_any(&mut self) -> &mut ::mockall::DynExpectation {
+                    self.any.expect(#ident_str)
+                }
+            )
+        } else {
+            quote!(
                 /// Simulating calling the real method.
+                #[track_caller]
                 #v fn call #ig (&self, #(#argnames: #argty, )* )
                     -> Option<#output> #wc
                 {
-                    self.store.get(&::mockall::Key::new::<(#(#argty, )*)>())
-                        .map(|__mockall_e| {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    ::mockall::note_lookup(#ident_str, &__mockall_k,
+                                           &self.store);
+                    match self.store.get(&__mockall_k) {
+                        Some(__mockall_e) => {
                             __mockall_e.downcast_ref::<Expectations #tg>()
                             .unwrap()
                             .call(#(#argnames, )*)
-                        // TODO: use Option::flatten once it stabilizes
-                        // https://github.com/rust-lang/rust/issues/60258
-                        }).and_then(std::convert::identity)
+                        },
+                        None => None
+                    }
+                }
+            )
+        };
+        quote!(
+            impl #ig ::mockall::AnyExpectations for Expectations #tg #wc
+            {
+                fn checkpoint(&mut self) -> Vec<String> {
+                    Expectations::checkpoint(self)
                 }
 
+                fn report(&self) -> Vec<String> {
+                    Expectations::report(self)
+                }
+
+                fn len(&self) -> usize {
+                    Expectations::len(self)
+                }
+            }
+            impl GenericExpectations {
+                #call
+
+                #call_self
+
+                #call_unlocked
+
+                #has_match
+
                 /// Create a new Expectation.
                 #v fn expect #ig (&mut self) -> &mut Expectation #tg #wc
                 {
-                    self.store.entry(::mockall::Key::new::<(#(#argty, )*)>())
+                    self.store.entry(::mockall::Key::new::<#key_ty>())
                         .or_insert_with(|| Box::new(Expectations #tbf::new()))
                         .downcast_mut::<Expectations #tg>()
                         .unwrap()
                         .expect()
                 }
+
+                /// Find the current expectation with the given id.
+                #v fn find #ig (&mut self, __mockall_id: usize)
+                    -> Option<&mut Expectation #tg> #wc
+                {
+                    self.store.get_mut(&::mockall::Key::new::<#key_ty>())
+                        .and_then(|__mockall_e|
+                            __mockall_e.downcast_mut::<Expectations #tg>()
+                            .unwrap()
+                            .find(__mockall_id)
+                        )
+                }
             }
         )
     }
@@ -980,6 +2012,27 @@ impl<'a> StaticExpectation<'a> {
         let argty = &self.common.argty;
         let fn_params = &self.common.fn_params;
         let output = &self.common.output;
+        // Expectations of methods that return nothing don't need a return
+        // value set
+        let default_ret = if crate::is_unit(output) {
+            quote!(Ok(()))
+        } else {
+            quote!({
+                use ::mockall::ReturnDefault;
+                ::mockall::DefaultReturner::<#output>::return_default()
+            })
+        };
+        let (return_self_variant, return_self_arm) = if self.common.returns_self
+        {
+            (quote!(
+                // Return the mock object itself.  Handled by the caller.
+                ReturnSelf,
+            ), quote!(
+                Rfunc::ReturnSelf => unreachable!(),
+            ))
+        } else {
+            (TokenStream::new(), TokenStream::new())
+        };
         quote!(
             enum Rfunc #ig #wc {
                 Default,
@@ -996,6 +2049,11 @@ impl<'a> StaticExpectation<'a> {
                 OnceST(::mockall::Fragile<
                     Box<dyn #hrtb FnOnce(#(#argty, )*) -> #output>>
                 ),
+                // A queue of closures, each of which will be used exactly once
+                Seq(::std::collections::VecDeque<
+                    Box<dyn #hrtb FnOnce(#(#argty, )*) -> #output + Send>>
+                ),
+                #return_self_variant
                 // Prevent "unused type parameter" errors Surprisingly,
                 // PhantomData<Fn(generics)> is Send even if generics are not,
                 // unlike PhantomData<generics>
@@ -1007,11 +2065,7 @@ impl<'a> StaticExpectation<'a> {
                     -> std::result::Result<#output, &'static str>
                 {
                     match self {
-                        Rfunc::Default => {
-                            use ::mockall::ReturnDefault;
-                            ::mockall::DefaultReturner::<#output>
-                                ::return_default()
-                        },
+                        Rfunc::Default => #default_ret,
                         Rfunc::Expired => {
                             Err("called twice, but it returns by move")
                         },
@@ -1037,6 +2091,14 @@ impl<'a> StaticExpectation<'a> {
                                 unreachable!()
                             }
                         },
+                        Rfunc::Seq(__mockall_q) => {
+                            match __mockall_q.pop_front() {
+                                Some(__mockall_f) =>
+                                    Ok(__mockall_f( #(#argnames, )* )),
+                                None => Err("called more times than the number of supplied return values")
+                            }
+                        },
+                        #return_self_arm
                         Rfunc::_Phantom(_) => unreachable!()
                     }
                 }
@@ -1061,29 +2123,54 @@ impl<'a> StaticExpectation<'a> {
         let argnames = &self.common.argnames;
         let argty = &self.common.argty;
         let expect_obj = &self.common.expect_obj;
-        let fn_params = &self.common.fn_params;
+        let pointee = self.common.static_pointee();
         let (_ig, tg, _wc) = self.common.egenerics.split_for_impl();
         let hrtb = self.common.hrtb();
         let output = &self.common.output;
         let predty = &self.common.predty;
-        let tbf = tg.as_turbofish();
         let v = &self.common.vis;
+        let guard_return_static = pointee.map(|pointee| quote!(
+            /// Just like
+            /// [`Expectation::return_static`](struct.Expectation.html#method.return_static)
+            #v fn return_static<MockallT>(&mut self, __mockall_t: MockallT)
+                -> &mut Self
+                where MockallT: ::mockall::IntoStaticRef<#pointee>,
+                      for<'__mockall_a> &'__mockall_a #pointee: Send
+            {
+                self.configure(|__mockall_exp| {
+                    __mockall_exp.return_static(__mockall_t);
+                });
+                self
+            }
+        ));
+        let guard_return_once_mock = if self.common.returns_mock() {
+            quote!(
+                /// Expect exactly one call, which will return `mock`.  The
+                /// test can configure the mock's own expectations first, so
+                /// the code under test will construct one that's ready to use.
+                #[allow(unused_variables)]
+                #v fn return_once_mock<MockallM>(&mut self, mock: MockallM)
+                    -> &mut Self
+                    where MockallM: Into<#output> + Send + 'static
+                {
+                    self.configure(|__mockall_exp| {
+                        __mockall_exp.times(1)
+                            .return_once(move |#(#argnames, )*| mock.into());
+                    });
+                    self
+                }
+            )
+        } else {
+            TokenStream::new()
+        };
 
         let gd = Generics::default();
         let (s_ig, s_tg, s_wc) = self.common.struct_generics.unwrap_or(&gd)
             .split_for_impl();
 
-        // Add a lifetime parameter, needed by MutexGuard
-        let mut meth_generics = self.common.meth_generics.clone();
-        let ltdef = LifetimeDef::new(
-            Lifetime::new("'__mockall_lt", Span::call_site())
-        );
-        meth_generics.params.push(GenericParam::Lifetime(ltdef.clone()));
-        let (meth_ig, _meth_tg, meth_wc) = meth_generics.split_for_impl();
-
-        let mut e_generics = self.common.egenerics.clone();
-        e_generics.params.push(GenericParam::Lifetime(ltdef));
-        let (e_ig, e_tg, e_wc) = e_generics.split_for_impl();
+        let (meth_ig, _meth_tg, meth_wc) =
+            self.common.meth_generics.split_for_impl();
+        let (e_ig, e_tg, e_wc) = self.common.egenerics.split_for_impl();
 
         let ctx_fn_params = match self.common.struct_generics {
             None => Punctuated::new(),
@@ -1092,13 +2179,41 @@ impl<'a> StaticExpectation<'a> {
             )
         };
 
+        // Each instantiation of a generic struct gets its own Context, which
+        // only manages that instantiation's expectations.  But a generic
+        // method's Context manages every instantiation of the method's own
+        // generic parameters.
+        let (checkpoint, report, len) = if self.common.is_generic() &&
+            self.common.meth_generics.type_params().next().is_none()
+        {
+            let tbf = e_tg.as_turbofish();
+            (quote!(checkpoint_one #tbf), quote!(report_one #tbf),
+             quote!(len_one #tbf))
+        } else {
+            (quote!(checkpoint), quote!(report), quote!(len))
+        };
+
         #[cfg(not(feature = "nightly_derive"))]
-        let must_use = quote!(#[must_use =
+        let must_use = if crate::is_unit(&self.common.output) {
+            quote!()
+        } else {
+            quote!(#[must_use =
                 "Must set return value when not using the \"nightly\" feature"
-            ]);
+            ])
+        };
         #[cfg(feature = "nightly_derive")]
         let must_use = quote!();
 
+        let reset_ts = quote!(
+            // Has this method registered its `reset` function yet?
+            #[doc(hidden)]
+            static RESET: ::std::sync::Once = ::std::sync::Once::new();
+            /// Clear this method's expectations, without verifying them.
+            #[doc(hidden)]
+            #v fn reset() {
+                ::mockall::lock_static(&EXPECTATIONS).clear();
+            }
+        );
         let context_ts = quote!(
             /// Manages the context for expectations of static methods.
             ///
@@ -1119,23 +2234,42 @@ impl<'a> StaticExpectation<'a> {
                 /// Verify that all current expectations for this method are
                 /// satisfied and clear them.
                 #v fn checkpoint(&self) {
-                    Self::do_checkpoint()
+                    ::mockall::Checkpoints::from(Self::do_checkpoint())
+                        .finish()
                 }
                 #[doc(hidden)]
-                #v fn do_checkpoint() {
-                    let __mockall_timeses = EXPECTATIONS
-                        .lock()
-                        .unwrap()
-                        .checkpoint()
-                        .collect::<Vec<_>>();
+                #v fn do_checkpoint() -> Vec<String> {
+                    ::mockall::lock_static(&EXPECTATIONS)
+                        .#checkpoint()
+                }
+                #[doc(hidden)]
+                #v fn do_report() -> Vec<String> {
+                    ::mockall::lock_static(&EXPECTATIONS)
+                        .#report()
+                }
+                #[doc(hidden)]
+                #v fn do_len() -> usize {
+                    ::mockall::lock_static(&EXPECTATIONS)
+                        .#len()
+                }
+
+                /// The number of current expectations for this method.
+                #v fn len(&self) -> usize {
+                    Self::do_len()
+                }
+
+                /// Are there no current expectations for this method?
+                #v fn is_empty(&self) -> bool {
+                    Self::do_len() == 0
                 }
 
                 /// Create a new expectation for this method.
                 #must_use
+                #[track_caller]
                 #v fn expect #meth_ig ( &self,) -> ExpectationGuard #e_tg
                     #meth_wc
                 {
-                    ExpectationGuard::new(EXPECTATIONS.lock().unwrap())
+                    ExpectationGuard::new()
                 }
             }
             impl #s_ig Default for Context #s_tg #s_wc {
@@ -1146,34 +2280,29 @@ impl<'a> StaticExpectation<'a> {
             impl #s_ig Drop for Context #s_tg #s_wc {
                 fn drop(&mut self) {
                     if !std::thread::panicking() {
-                        Self::do_checkpoint()
+                        ::mockall::Checkpoints::from(Self::do_checkpoint())
+                            .finish()
                     }
                 }
             }
         );
         if !self.common.is_generic() {
             quote!(
-                ::mockall::lazy_static! {
-                    #v static ref EXPECTATIONS:
-                        ::std::sync::Mutex<#expect_obj> =
-                        ::std::sync::Mutex::new(Expectations::new());
-                }
-                /// Like an [`&Expectation`](struct.Expectation.html) but
-                /// protected by a Mutex guard.  Useful for mocking static
-                /// methods.  Forwards accesses to an `Expectation` object.
-                // We must return the MutexGuard to the caller so he can
-                // configure the expectation.  But we can't bundle both the
-                // guard and the &Expectation into the same structure; the
-                // borrow checker won't let us.  Instead we'll record the
-                // expectation's position within the Expectations vector so we
-                // can proxy its methods.
-                //
-                // ExpectationGuard is only defined for expectations that return
-                // 'static return types.
+                #[doc(hidden)]
+                #v static EXPECTATIONS: ::std::sync::Mutex<#expect_obj> =
+                    ::std::sync::Mutex::new(Expectations::new());
+                #reset_ts
+                /// A reference to an [`Expectation`](struct.Expectation.html)
+                /// of a static method, that forwards accesses to it.
+                ///
+                /// It doesn't hold the lock on the method's expectations, so it
+                /// may be sent to another thread.  Each access locks them
+                /// briefly.
                 #v struct ExpectationGuard #e_ig #e_wc {
-                    guard: MutexGuard<'__mockall_lt, #expect_obj>,
-                    //guard: MutexGuard<'__mockall_lt, Expectations #tg>,
-                    i: usize
+                    id: usize,
+                    _phantom: ::std::marker::PhantomData<
+                        fn() -> Expectation #tg
+                    >,
                 }
 
                 impl #e_ig ExpectationGuard #e_tg #e_wc
@@ -1181,144 +2310,359 @@ impl<'a> StaticExpectation<'a> {
                     /// Just like
                     /// [`Expectation::in_sequence`](struct.Expectation.html#method.in_sequence)
                     #v fn in_sequence(&mut self,
-                        __mockall_seq: &mut ::mockall::Sequence)
-                        -> &mut Expectation #tg
+                        __mockall_seq: &::mockall::Sequence)
+                        -> &mut Self
                     {
-                        self.guard.0[self.i].in_sequence(__mockall_seq)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.in_sequence(__mockall_seq);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::never`](struct.Expectation.html#method.never)
-                    #v fn never(&mut self) -> &mut Expectation #tg {
-                        self.guard.0[self.i].never()
+                    #v fn never(&mut self) -> &mut Self {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.never();
+                        });
+                        self
                     }
 
                     // Should only be called from the mockall_derive generated
                     // code
                     #[doc(hidden)]
-                    #v fn new(mut __mockall_guard: MutexGuard<'__mockall_lt, Expectations #tg>)
-                        -> Self
+                    #[track_caller]
+                    #v fn new() -> Self {
+                        RESET.call_once(|| ::mockall::register_reset(reset));
+                        let __mockall_id = ::mockall::lock_static(&EXPECTATIONS)
+                            .expect()
+                            .common.id;
+                        ExpectationGuard{id: __mockall_id,
+                            _phantom: ::std::marker::PhantomData}
+                    }
+
+                    /// Lock this method's expectations, and apply a function
+                    /// to this one.
+                    fn configure<MockallR>(&self,
+                        __mockall_f: impl FnOnce(&mut Expectation #tg) -> MockallR)
+                        -> MockallR
                     {
-                        __mockall_guard.expect(); // Drop the &Expectation
-                        let __mockall_i = __mockall_guard.0.len() - 1;
-                        ExpectationGuard{guard: __mockall_guard, i: __mockall_i}
+                        let mut __mockall_guard =
+                            ::mockall::lock_static(&EXPECTATIONS);
+                        let __mockall_e = __mockall_guard.find(self.id)
+                            .expect("This expectation was cleared by a checkpoint or reset");
+                        __mockall_f(__mockall_e)
                     }
 
                     /// Just like [`Expectation::once`](struct.Expectation.html#method.once)
-                    #v fn once(&mut self) -> &mut Expectation #tg {
-                        self.guard.0[self.i].once()
+                    #v fn once(&mut self) -> &mut Self {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.once();
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::return_const`](struct.Expectation.html#method.return_const)
                     #v fn return_const<MockallOutput>
                     (&mut self, __mockall_c: MockallOutput)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallOutput: Clone + Into<#output> + Send + 'static
                     {
-                        self.guard.0[self.i].return_const(__mockall_c)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_const(__mockall_c);
+                        });
+                        self
+                    }
+
+                    #guard_return_static
+
+                    #guard_return_once_mock
+
+                    /// Just like
+                    /// [`Expectation::panics_with`](struct.Expectation.html#method.panics_with)
+                    #v fn panics_with<MockallP>(&mut self, __mockall_p: MockallP)
+                        -> &mut Self
+                        where MockallP: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.panics_with(__mockall_p);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_ok`](struct.Expectation.html#method.return_ok)
+                    #v fn return_ok<MockallT>(&mut self, __mockall_t: MockallT)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOk<MockallT>,
+                              MockallT: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_ok(__mockall_t);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_ok_default`](struct.Expectation.html#method.return_ok_default)
+                    #v fn return_ok_default<MockallT>(&mut self)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOk<MockallT>,
+                              MockallT: Default + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_ok_default();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_err`](struct.Expectation.html#method.return_err)
+                    #v fn return_err<MockallE>(&mut self, __mockall_e: MockallE)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnErr<MockallE>,
+                              MockallE: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_err(__mockall_e);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_some`](struct.Expectation.html#method.return_some)
+                    #v fn return_some<MockallT>(&mut self, __mockall_t: MockallT)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOption<MockallT>,
+                              MockallT: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_some(__mockall_t);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_none`](struct.Expectation.html#method.return_none)
+                    #v fn return_none<MockallT>(&mut self)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOption<MockallT>,
+                              MockallT: 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_none();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::returning_seq`](struct.Expectation.html#method.returning_seq)
+                    #v fn returning_seq<MockallOutput>(&mut self,
+                        __mockall_values: Vec<MockallOutput>)
+                        -> &mut Self
+                        where MockallOutput: Into<#output> + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning_seq(__mockall_values);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::returning_fn_seq`](struct.Expectation.html#method.returning_fn_seq)
+                    #v fn returning_fn_seq(&mut self,
+                        __mockall_fs: Vec<Box<dyn #hrtb FnOnce(#(#argty, )*)
+                                              -> #output + Send>>)
+                        -> &mut Self
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning_fn_seq(__mockall_fs);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::returning`](struct.Expectation.html#method.returning)
                     #v fn returning<MockallF>(&mut self, __mockall_f: MockallF)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallF: #hrtb FnMut(#(#argty, )*)
                             -> #output + Send + 'static
                     {
-                        self.guard.0[self.i].returning(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning(__mockall_f);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::return_once`](struct.Expectation.html#method.return_once)
                     #v fn return_once<MockallF>(&mut self, __mockall_f: MockallF)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallF: #hrtb FnOnce(#(#argty, )*)
                                         -> #output + Send + 'static
                     {
-                        self.guard.0[self.i].return_once(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_once(__mockall_f);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::returning_st`](struct.Expectation.html#method.returning_st)
                     #v fn returning_st<MockallF>(&mut self, __mockall_f: MockallF)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallF: #hrtb FnMut(#(#argty, )*)
                                         -> #output + 'static
                     {
-                        self.guard.0[self.i].returning_st(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning_st(__mockall_f);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_once_st`](struct.Expectation.html#method.return_once_st)
+                    #v fn return_once_st<MockallF>(&mut self, __mockall_f: MockallF)
+                        -> &mut Self
+                        where MockallF: #hrtb FnOnce(#(#argty, )*)
+                                        -> #output + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_once_st(__mockall_f);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::times`](struct.Expectation.html#method.times)
                     #v fn times<MockallR>(&mut self, __mockall_r: MockallR)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallR: Into<::mockall::TimesRange>
                     {
-                        self.guard.0[self.i].times(__mockall_r)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.times(__mockall_r);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::times_any`](struct.Expectation.html#method.times_any)
-                    #[deprecated(since = "0.3.0", note = "Use times instead")]
-                    #v fn times_any(&mut self) -> &mut Expectation #tg {
-                        self.guard.0[self.i].times(..)
+                    #v fn times_any(&mut self) -> &mut Self {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.times_any();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::optional`](struct.Expectation.html#method.optional)
+                    #v fn optional(&mut self) -> &mut Self {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.optional();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::in_budget`](struct.Expectation.html#method.in_budget)
+                    #v fn in_budget(&mut self,
+                                    __mockall_b: &::mockall::CallBudget,
+                                    __mockall_weight: usize)
+                        -> &mut Self
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.in_budget(__mockall_b, __mockall_weight);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::handle`](struct.Expectation.html#method.handle)
+                    #v fn handle(&mut self) -> ::mockall::ExpectationHandle {
+                        self.configure(|__mockall_exp| __mockall_exp.handle())
+                    }
+
+                    /// Just like
+                    /// [`Expectation::after`](struct.Expectation.html#method.after)
+                    #v fn after(&mut self,
+                                __mockall_h: &::mockall::ExpectationHandle)
+                        -> &mut Self
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.after(__mockall_h);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::times_range`](struct.Expectation.html#method.times_range)
                     #[deprecated(since = "0.3.0", note = "Use times instead")]
                     #v fn times_range(&mut self, __mockall_range: Range<usize>)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                     {
-                        self.guard.0[self.i].times(__mockall_range)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.times(__mockall_range);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::with`](struct.Expectation.html#method.with)
                     #v fn with<#with_generics> (&mut self, #with_args)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                     {
-                        self.guard.0[self.i].with(#(#argnames, )*)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.with(#(#argnames, )*);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::withf`](struct.Expectation.html#method.withf)
                     #v fn withf<MockallF>(&mut self, __mockall_f: MockallF)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallF: #hrtb Fn(#(&#predty, )*)
                                         -> bool + Send + 'static
                     {
-                        self.guard.0[self.i].withf(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.withf(__mockall_f);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::withf_st`](struct.Expectation.html#method.withf_st)
                     #v fn withf_st<MockallF>(&mut self, __mockall_f: MockallF)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallF: #hrtb Fn(#(&#predty, )*)
                                         -> bool + 'static
                     {
-                        self.guard.0[self.i].withf_st(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.withf_st(__mockall_f);
+                        });
+                        self
                     }
                 }
                 #context_ts
             )
         } else {
             quote!(
-                ::mockall::lazy_static! {
-                    #v static ref EXPECTATIONS:
-                        ::std::sync::Mutex<GenericExpectations> =
-                        ::std::sync::Mutex::new(GenericExpectations::new());
-                }
-                /// Like an
-                /// [`&Expectation`](struct.Expectation.html) but
-                /// protected by a Mutex guard.  Useful for mocking static
-                /// methods.  Forwards accesses to an `Expectation` object.
+                #[doc(hidden)]
+                #v static EXPECTATIONS: ::std::sync::Mutex<GenericExpectations> =
+                    ::std::sync::Mutex::new(GenericExpectations::new());
+                #reset_ts
+                /// A reference to an [`Expectation`](struct.Expectation.html)
+                /// of a static method, that forwards accesses to it.
+                ///
+                /// It doesn't hold the lock on the method's expectations, so it
+                /// may be sent to another thread.  Each access locks them
+                /// briefly.
                 #v struct ExpectationGuard #e_ig #e_wc{
-                    guard: MutexGuard<'__mockall_lt, GenericExpectations>,
-                    i: usize,
-                    _phantom: ::std::marker::PhantomData<(#fn_params)>,
+                    id: usize,
+                    _phantom: ::std::marker::PhantomData<
+                        fn() -> Expectation #tg
+                    >,
                 }
 
                 impl #e_ig ExpectationGuard #e_tg #e_wc
@@ -1326,191 +2670,335 @@ impl<'a> StaticExpectation<'a> {
                     /// Just like
                     /// [`Expectation::in_sequence`](struct.Expectation.html#method.in_sequence)
                     #v fn in_sequence(&mut self,
-                        __mockall_seq: &mut ::mockall::Sequence)
-                        -> &mut Expectation #tg
+                        __mockall_seq: &::mockall::Sequence)
+                        -> &mut Self
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .in_sequence(__mockall_seq)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.in_sequence(__mockall_seq);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::never`](struct.Expectation.html#method.never)
-                    #v fn never(&mut self) -> &mut Expectation #tg {
-                            self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .never()
+                    #v fn never(&mut self) -> &mut Self {
+                            self.configure(|__mockall_exp| {
+                                __mockall_exp.never();
+                            });
+                            self
                     }
 
                     #[doc(hidden)]
-                    #v fn new(mut guard: MutexGuard<'__mockall_lt, GenericExpectations>)
-                        -> Self
-                    {
-                        let __mockall_ee: &mut Expectations #tg =
-                            guard.store.entry(
-                                ::mockall::Key::new::<(#(#argty, )*)>()
-                            ).or_insert_with(||
-                                Box::new(Expectations #tbf ::new()))
-                            .downcast_mut()
-                            .unwrap();
-                        __mockall_ee.expect();    // Drop the &Expectation
-                        let __mockall_i = __mockall_ee.0.len() - 1;
-                        ExpectationGuard{guard, i: __mockall_i,
+                    #[track_caller]
+                    #v fn new() -> Self {
+                        RESET.call_once(|| ::mockall::register_reset(reset));
+                        let mut __mockall_guard =
+                            ::mockall::lock_static(&EXPECTATIONS);
+                        let __mockall_e: &mut Expectation #tg =
+                            __mockall_guard.expect();
+                        ExpectationGuard{id: __mockall_e.common.id,
                             _phantom: ::std::marker::PhantomData}
                     }
 
+                    /// Lock this method's expectations, and apply a function
+                    /// to this one.
+                    fn configure<MockallR>(&self,
+                        __mockall_f: impl FnOnce(&mut Expectation #tg) -> MockallR)
+                        -> MockallR
+                    {
+                        let mut __mockall_guard =
+                            ::mockall::lock_static(&EXPECTATIONS);
+                        let __mockall_e: &mut Expectation #tg = __mockall_guard
+                            .find(self.id)
+                            .expect("This expectation was cleared by a checkpoint or reset");
+                        __mockall_f(__mockall_e)
+                    }
+
                     /// Just like
                     /// [`Expectation::once`](struct.Expectation.html#method.once)
-                    #v fn once(&mut self) -> &mut Expectation #tg {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .once()
+                    #v fn once(&mut self) -> &mut Self {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.once();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_ok`](struct.Expectation.html#method.return_ok)
+                    #v fn return_ok<MockallT>(&mut self, __mockall_t: MockallT)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOk<MockallT>,
+                              MockallT: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_ok(__mockall_t);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_ok_default`](struct.Expectation.html#method.return_ok_default)
+                    #v fn return_ok_default<MockallT>(&mut self)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOk<MockallT>,
+                              MockallT: Default + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_ok_default();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_err`](struct.Expectation.html#method.return_err)
+                    #v fn return_err<MockallE>(&mut self, __mockall_e: MockallE)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnErr<MockallE>,
+                              MockallE: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_err(__mockall_e);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_some`](struct.Expectation.html#method.return_some)
+                    #v fn return_some<MockallT>(&mut self, __mockall_t: MockallT)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOption<MockallT>,
+                              MockallT: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_some(__mockall_t);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_none`](struct.Expectation.html#method.return_none)
+                    #v fn return_none<MockallT>(&mut self)
+                        -> &mut Self
+                        where #output: ::mockall::ReturnOption<MockallT>,
+                              MockallT: 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_none();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::returning_seq`](struct.Expectation.html#method.returning_seq)
+                    #v fn returning_seq<MockallOutput>(&mut self,
+                        __mockall_values: Vec<MockallOutput>)
+                        -> &mut Self
+                        where MockallOutput: Into<#output> + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning_seq(__mockall_values);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::returning_fn_seq`](struct.Expectation.html#method.returning_fn_seq)
+                    #v fn returning_fn_seq(&mut self,
+                        __mockall_fs: Vec<Box<dyn #hrtb FnOnce(#(#argty, )*)
+                                              -> #output + Send>>)
+                        -> &mut Self
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning_fn_seq(__mockall_fs);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::returning`](struct.Expectation.html#method.returning)
                     #v fn returning<MockallF>(&mut self, __mockall_f: MockallF)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallF: FnMut(#(#argty, )*)
                             -> #output + Send + 'static
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .returning(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning(__mockall_f);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::return_once`](struct.Expectation.html#method.return_once)
                     #v fn return_once<MockallF>(&mut self,
-                        __mockall_f: MockallF) -> &mut Expectation #tg
+                        __mockall_f: MockallF) -> &mut Self
                         where MockallF: FnOnce(#(#argty, )*)
                             -> #output + Send + 'static
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .return_once(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_once(__mockall_f);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_const`](struct.Expectation.html#method.return_const)
+                    #v fn return_const<MockallOutput>
+                    (&mut self, __mockall_c: MockallOutput)
+                        -> &mut Self
+                        where MockallOutput: Clone + Into<#output> + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_const(__mockall_c);
+                        });
+                        self
+                    }
+
+                    #guard_return_static
+
+                    #guard_return_once_mock
+
+                    /// Just like
+                    /// [`Expectation::panics_with`](struct.Expectation.html#method.panics_with)
+                    #v fn panics_with<MockallP>(&mut self, __mockall_p: MockallP)
+                        -> &mut Self
+                        where MockallP: Clone + Send + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.panics_with(__mockall_p);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::returning_st`](struct.Expectation.html#method.returning_st)
                     #v fn returning_st<MockallF>(&mut self,
-                        __mockall_f: MockallF) -> &mut Expectation #tg
+                        __mockall_f: MockallF) -> &mut Self
                         where MockallF: FnMut(#(#argty, )*) -> #output + 'static
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .returning_st(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.returning_st(__mockall_f);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::return_once_st`](struct.Expectation.html#method.return_once_st)
+                    #v fn return_once_st<MockallF>(&mut self,
+                        __mockall_f: MockallF) -> &mut Self
+                        where MockallF: FnOnce(#(#argty, )*) -> #output + 'static
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.return_once_st(__mockall_f);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::times`](struct.Expectation.html#method.times)
                     #v fn times<MockallR>(&mut self, __mockall_r: MockallR)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                         where MockallR: Into<::mockall::TimesRange>
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .times(__mockall_r)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.times(__mockall_r);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::times_any`](struct.Expectation.html#method.times_any)
-                    #[deprecated(since = "0.3.0", note = "Use times instead")]
-                    #v fn times_any(&mut self) -> &mut Expectation #tg {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .times(..)
+                    #v fn times_any(&mut self) -> &mut Self {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.times_any();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::optional`](struct.Expectation.html#method.optional)
+                    #v fn optional(&mut self) -> &mut Self {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.optional();
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::in_budget`](struct.Expectation.html#method.in_budget)
+                    #v fn in_budget(&mut self,
+                                    __mockall_b: &::mockall::CallBudget,
+                                    __mockall_weight: usize)
+                        -> &mut Self
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.in_budget(__mockall_b, __mockall_weight);
+                        });
+                        self
+                    }
+
+                    /// Just like
+                    /// [`Expectation::handle`](struct.Expectation.html#method.handle)
+                    #v fn handle(&mut self) -> ::mockall::ExpectationHandle {
+                        self.configure(|__mockall_exp| __mockall_exp.handle())
+                    }
+
+                    /// Just like
+                    /// [`Expectation::after`](struct.Expectation.html#method.after)
+                    #v fn after(&mut self,
+                                __mockall_h: &::mockall::ExpectationHandle)
+                        -> &mut Self
+                    {
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.after(__mockall_h);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::times_range`](struct.Expectation.html#method.times_range)
                     #[deprecated(since = "0.3.0", note = "Use times instead")]
                     #v fn times_range(&mut self, __mockall_range: Range<usize>)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .times(__mockall_range)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.times(__mockall_range);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::with`](struct.Expectation.html#method.with)
                     #v fn with<#with_generics> (&mut self, #with_args)
-                        -> &mut Expectation #tg
+                        -> &mut Self
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .with(#(#argnames, )*)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.with(#(#argnames, )*);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::withf`](struct.Expectation.html#method.withf)
-                    #v fn withf<MockallF>(&mut self, __mockall_f: MockallF) -> &mut Expectation #tg
+                    #v fn withf<MockallF>(&mut self, __mockall_f: MockallF) -> &mut Self
                         where MockallF: #hrtb Fn(#(&#predty, )*)
                                         -> bool + Send + 'static
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .withf(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.withf(__mockall_f);
+                        });
+                        self
                     }
 
                     /// Just like
                     /// [`Expectation::withf_st`](struct.Expectation.html#method.withf_st)
-                    #v fn withf_st<MockallF>(&mut self, __mockall_f: MockallF) -> &mut Expectation #tg
+                    #v fn withf_st<MockallF>(&mut self, __mockall_f: MockallF) -> &mut Self
                         where MockallF: #hrtb Fn(#(&#predty, )*)
                                         -> bool + 'static
                     {
-                        self.guard.store.get_mut(
-                                &::mockall::Key::new::<(#(#argty, )*)>()
-                            ).unwrap()
-                            .downcast_mut::<Expectations #tg>()
-                            .unwrap()
-                            .0[self.i]
-                            .withf_st(__mockall_f)
+                        self.configure(|__mockall_exp| {
+                            __mockall_exp.withf_st(__mockall_f);
+                        });
+                        self
                     }
                 }
                 #context_ts
@@ -1528,7 +3016,7 @@ impl<'a> RefExpectation<'a> {
     fn common(&self) -> &Common {&self.common}
     fn expectation(&self, em_ts: TokenStream) -> TokenStream {
         let ident_str = self.common().ident_str();
-        let (ig, tg, _wc) = self.common.egenerics.split_for_impl();
+        let (ig, tg, wc) = self.common.egenerics.split_for_impl();
         let lg = &self.common.alifetimes;
         let output = &self.common.output;
         let v = &self.common.vis;
@@ -1536,34 +3024,38 @@ impl<'a> RefExpectation<'a> {
             /// Expectation type for methods taking a `&self` argument and
             /// returning immutable references.  This is the type returned by
             /// the `expect_*` methods.
-            #v struct Expectation #ig {
+            #v struct Expectation #ig #wc {
                 common: Common #tg,
                 rfunc: Rfunc #tg,
             }
 
-            impl #ig Expectation #tg {
+            impl #ig Expectation #tg #wc {
+                #[track_caller]
                 #v fn call #lg (&self) -> &#output {
                     self.common.call();
-                    self.rfunc.call().unwrap_or_else(|m| {
-                        let desc = format!("{}",
-                                           self.common.matcher.lock().unwrap());
-                        panic!("{}: Expectation({}) {}", #ident_str, desc,
-                            m);
-                    })
+                    match self.rfunc.call() {
+                        Ok(__mockall_o) => __mockall_o,
+                        Err(m) => {
+                            let desc = format!("{}",
+                                self.common.matcher.lock().unwrap());
+                            panic!("{}: Expectation({}) {}{}", #ident_str, desc,
+                                m, self.common.location);
+                        }
+                    }
                 }
 
                 /// Return a reference to a constant value from the `Expectation`
                 #v fn return_const(&mut self, __mockall_o: #output)
                     -> &mut Self
                 {
-                    mem::replace(&mut self.rfunc, Rfunc::Const(__mockall_o));
+                    self.rfunc = Rfunc::Const(__mockall_o);
                     self
                 }
 
                 #em_ts
             }
 
-            impl #ig Default for Expectation #tg
+            impl #ig Default for Expectation #tg #wc
             {
                 fn default() -> Self {
                     Expectation {
@@ -1578,26 +3070,41 @@ impl<'a> RefExpectation<'a> {
     fn expectations_methods(&self) -> TokenStream {
         let argnames = &self.common.argnames;
         let argty = &self.common.argty;
-        let (ig, tg, _wc) = self.common.egenerics.split_for_impl();
+        let (ig, tg, wc) = self.common.egenerics.split_for_impl();
         let lg = &self.common.alifetimes;
         let output = &self.common.output;
         let predexprs = &self.common.predexprs;
         let v = &self.common.vis;
+        let ident_str = self.common.ident_str();
         quote!(
-            impl #ig Expectations #tg {
+            impl #ig Expectations #tg #wc {
                 /// Simulate calling the real method.  Every current expectation
                 /// will be checked in FIFO order and the first one with
                 /// matching arguments will be used.
+                #[track_caller]
                 #v fn call #lg (&self, #(#argnames: #argty,)* )
                     -> Option<&#output>
                 {
-                    self.0.iter()
-                        .find(|__mockall_e|
-                              __mockall_e.matches(#(#predexprs, )*) &&
-                              (!__mockall_e.is_done() || self.0.len() == 1))
-                        .map(move |__mockall_e|
-                             __mockall_e.call()
-                        )
+                    match self.select(#(#predexprs, )*) {
+                        Some(__mockall_i) => Some(self.0[__mockall_i].call()),
+                        None => {
+                            self.reject(#(#predexprs, )*);
+                            None
+                        }
+                    }
+                }
+
+                /// Like `call`, but if no expectation matches, then return a
+                /// [`CallError`](::mockall::CallError) describing the call
+                /// instead of `None`.
+                #[track_caller]
+                #v fn try_call #lg (&self, #(#argnames: #argty,)* )
+                    -> ::std::result::Result<&#output, ::mockall::CallError>
+                {
+                    match self.call(#(#argnames, )*) {
+                        Some(__mockall_o) => Ok(__mockall_o),
+                        None => Err(::mockall::CallError::unmatched(#ident_str))
+                    }
                 }
             }
         )
@@ -1608,45 +3115,78 @@ impl<'a> RefExpectation<'a> {
     fn generic_expectations_methods(&self) -> TokenStream {
         let argnames = &self.common.argnames;
         let argty = &self.common.argty;
-        let (ig, tg, wc) = self.common.egenerics.split_for_impl();
+        let (ig, tg, _) = self.common.egenerics.split_for_impl();
+        let wc = self.common.send_sync_wc();
         let output = &self.common.output;
+        let key_ty = self.common.key_ty();
+        let ident_str = self.common.ident_str();
         let tbf = tg.as_turbofish();
         let v = &self.common.vis;
+        let has_match = self.common.generic_has_match(&quote!(#wc), false);
 
         quote!(
             // The Senc + Sync are required for downcast, since Expectation
             // stores an Option<#output>
-            impl #ig ::mockall::AnyExpectations for Expectations #tg
-                    where #output: Send + Sync
-            {}
+            impl #ig ::mockall::AnyExpectations for Expectations #tg #wc
+            {
+                fn checkpoint(&mut self) -> Vec<String> {
+                    Expectations::checkpoint(self)
+                }
+
+                fn report(&self) -> Vec<String> {
+                    Expectations::report(self)
+                }
+
+                fn len(&self) -> usize {
+                    Expectations::len(self)
+                }
+            }
             impl GenericExpectations {
                 /// Simulating calling the real method.
+                #[track_caller]
                 #v fn call #ig (&self, #(#argnames: #argty,)*)
                     -> Option<&#output>
+                    #wc
                 {
-                    self.store.get(&::mockall::Key::new::<(#(#argty, )*)>())
-                        .map(|__mockall_e| {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    ::mockall::note_lookup(#ident_str, &__mockall_k,
+                                           &self.store);
+                    match self.store.get(&__mockall_k) {
+                        Some(__mockall_e) => {
                             __mockall_e.downcast_ref::<Expectations #tg>()
                             .unwrap()
                             .call(#(#argnames, )*)
-                        // TODO: use Option::flatten once it stabilizes
-                        // https://github.com/rust-lang/rust/issues/60258
-                        }).and_then(std::convert::identity)
+                        },
+                        None => None
+                    }
                 }
 
+                #has_match
+
                 /// Create a new Expectation.
                 #v fn expect #ig (&mut self)
                     -> &mut Expectation #tg
                     #wc
-                    where #output: Send + Sync
                 {
-                    self.store.entry(::mockall::Key::new::<(#(#argty, )*)>())
+                    self.store.entry(::mockall::Key::new::<#key_ty>())
                         .or_insert_with(||
                             Box::new(Expectations #tbf ::new())
                         ).downcast_mut::<Expectations #tg>()
                         .unwrap()
                         .expect()
                 }
+
+                /// Find the current expectation with the given id.
+                #v fn find #ig (&mut self, __mockall_id: usize)
+                    -> Option<&mut Expectation #tg> #wc
+                {
+                    self.store.get_mut(&::mockall::Key::new::<#key_ty>())
+                        .and_then(|__mockall_e|
+                            __mockall_e.downcast_mut::<Expectations #tg>()
+                            .unwrap()
+                            .find(__mockall_id)
+                        )
+                }
             }
         )
     }
@@ -1726,7 +3266,7 @@ impl<'a> RefMutExpectation<'a> {
         let argnames = &self.common.argnames;
         let argty = &self.common.argty;
         let ident_str = self.common().ident_str();
-        let (ig, tg, _wc) = self.common.egenerics.split_for_impl();
+        let (ig, tg, wc) = self.common.egenerics.split_for_impl();
         let lg = &self.common.alifetimes;
         let output = &self.common.output;
         let v = &self.common.vis;
@@ -1734,23 +3274,26 @@ impl<'a> RefMutExpectation<'a> {
             /// Expectation type for methods taking a `&mut self` argument and
             /// returning references.  This is the type returned by the
             /// `expect_*` methods.
-            #v struct Expectation #ig {
+            #v struct Expectation #ig #wc {
                 common: Common #tg,
                 rfunc: Rfunc #tg
             }
 
-            impl #ig Expectation #tg {
+            impl #ig Expectation #tg #wc {
                 /// Simulating calling the real method for this expectation
+                #[track_caller]
                 #v fn call_mut #lg (&mut self, #(#argnames: #argty, )*)
                     -> &mut #output
                 {
                     self.common.call();
                     let desc = format!("{}",
                         self.common.matcher.lock().unwrap());
-                    self.rfunc.call_mut(#(#argnames, )*).unwrap_or_else(|m| {
-                            panic!("{}: Expectation({}) {}", #ident_str, desc,
-                                   m);
-                    })
+                    let location = self.common.location;
+                    match self.rfunc.call_mut(#(#argnames, )*) {
+                        Ok(__mockall_o) => __mockall_o,
+                        Err(m) => panic!("{}: Expectation({}) {}{}", #ident_str,
+                                         desc, m, location)
+                    }
                 }
 
                 /// Convenience method that can be used to supply a return value
@@ -1758,7 +3301,7 @@ impl<'a> RefMutExpectation<'a> {
                 /// reference.
                 #v fn return_var(&mut self, __mockall_o: #output) -> &mut Self
                 {
-                    mem::replace(&mut self.rfunc, Rfunc::Var(__mockall_o));
+                    self.rfunc = Rfunc::Var(__mockall_o);
                     self
                 }
 
@@ -1769,8 +3312,7 @@ impl<'a> RefMutExpectation<'a> {
                     -> &mut Self
                     where MockallF: FnMut(#(#argty, )*) -> #output + Send + Sync + 'static
                 {
-                    mem::replace(&mut self.rfunc,
-                                 Rfunc::Mut(Box::new(__mockall_f), None));
+                    self.rfunc = Rfunc::Mut(Box::new(__mockall_f), None);
                     self
                 }
 
@@ -1780,15 +3322,14 @@ impl<'a> RefMutExpectation<'a> {
                     -> &mut Self
                     where MockallF: FnMut(#(#argty, )*) -> #output + 'static
                 {
-                    mem::replace(&mut self.rfunc, Rfunc::MutST(
-                        ::mockall::Fragile::new(Box::new(__mockall_f)), None)
-                    );
+                    self.rfunc = Rfunc::MutST(
+                        ::mockall::Fragile::new(Box::new(__mockall_f)), None);
                     self
                 }
 
                 #em_ts
             }
-            impl #ig Default for Expectation #tg
+            impl #ig Default for Expectation #tg #wc
             {
                 fn default() -> Self {
                     Expectation {
@@ -1803,35 +3344,62 @@ impl<'a> RefMutExpectation<'a> {
     fn expectations_methods(&self) -> TokenStream {
         let argnames = &self.common.argnames;
         let argty = &self.common.argty;
-        let (ig, tg, _wc) = self.common.egenerics.split_for_impl();
+        let (ig, tg, wc) = self.common.egenerics.split_for_impl();
+        let ss_wc = self.common.send_sync_wc();
         let lg = &self.common.alifetimes;
         let output = &self.common.output;
         let predexprs = &self.common.predexprs;
         let v = &self.common.vis;
+        let ident_str = self.common.ident_str();
         quote!(
-            impl #ig Expectations #tg {
+            impl #ig Expectations #tg #wc {
                 /// Simulate calling the real method.  Every current expectation
                 /// will be checked in FIFO order and the first one with
                 /// matching arguments will be used.
+                #[track_caller]
                 #v fn call_mut #lg (&mut self, #(#argnames: #argty, )* )
                     -> Option<&mut #output>
                 {
-                    let __mockall_n = self.0.len();
-                    self.0.iter_mut()
-                        .find(|__mockall_e|
-                              __mockall_e.matches(#(#predexprs, )*) &&
-                              (!__mockall_e.is_done() || __mockall_n == 1))
-                        .map(move |__mockall_e|
-                             __mockall_e.call_mut(#(#argnames, )*)
-                        )
+                    match self.select(#(#predexprs, )*) {
+                        Some(__mockall_i) =>
+                            Some(self.0[__mockall_i].call_mut(#(#argnames, )*)),
+                        None => {
+                            self.reject(#(#predexprs, )*);
+                            None
+                        }
+                    }
+                }
+
+                /// Like `call_mut`, but if no expectation matches, then return
+                /// a [`CallError`](::mockall::CallError) describing the call
+                /// instead of `None`.
+                #[track_caller]
+                #v fn try_call_mut #lg (&mut self, #(#argnames: #argty, )* )
+                    -> ::std::result::Result<&mut #output, ::mockall::CallError>
+                {
+                    match self.call_mut(#(#argnames, )*) {
+                        Some(__mockall_o) => Ok(__mockall_o),
+                        None => Err(::mockall::CallError::unmatched(#ident_str))
+                    }
                 }
             }
             // The Senc + Sync are required for downcast, since Expectation
             // stores an Option<#output>
             impl #ig
-                ::mockall::AnyExpectations for Expectations #tg
-                where #output: Send + Sync
-            {}
+                ::mockall::AnyExpectations for Expectations #tg #ss_wc
+            {
+                fn checkpoint(&mut self) -> Vec<String> {
+                    Expectations::checkpoint(self)
+                }
+
+                fn report(&self) -> Vec<String> {
+                    Expectations::report(self)
+                }
+
+                fn len(&self) -> usize {
+                    Expectations::len(self)
+                }
+            }
         )
     }
     fn extra_uses(&self) -> TokenStream { TokenStream::new() }
@@ -1839,38 +3407,60 @@ impl<'a> RefMutExpectation<'a> {
     fn generic_expectations_methods(&self) -> TokenStream {
         let argnames = &self.common.argnames;
         let argty = &self.common.argty;
-        let (ig, tg, wc) = self.common.egenerics.split_for_impl();
+        let (ig, tg, _) = self.common.egenerics.split_for_impl();
+        let wc = self.common.send_sync_wc();
         let output = &self.common.output;
+        let key_ty = self.common.key_ty();
+        let ident_str = self.common.ident_str();
         let tbf = tg.as_turbofish();
         let v = &self.common.vis;
+        let has_match = self.common.generic_has_match(&quote!(#wc), false);
 
         quote!(
             impl GenericExpectations {
                 /// Simulating calling the real method.
+                #[track_caller]
                 #v fn call_mut #ig (&mut self, #(#argnames: #argty, )* )
                     -> Option<&mut #output>
+                    #wc
                 {
-                    self.store.get_mut(&::mockall::Key::new::<(#(#argty, )*)>())
-                        .map(|__mockall_e| {
+                    let __mockall_k = ::mockall::Key::new::<#key_ty>();
+                    ::mockall::note_lookup(#ident_str, &__mockall_k,
+                                           &self.store);
+                    match self.store.get_mut(&__mockall_k) {
+                        Some(__mockall_e) => {
                             __mockall_e.downcast_mut::<Expectations #tg>()
                             .unwrap()
                             .call_mut(#(#argnames, )*)
-                        // TODO: use Option::flatten once it stabilizes
-                        // https://github.com/rust-lang/rust/issues/60258
-                        }).and_then(std::convert::identity)
+                        },
+                        None => None
+                    }
                 }
 
+                #has_match
+
                 /// Create a new Expectation.
                 #v fn expect #ig (&mut self) -> &mut Expectation #tg #wc
-                    where #output: Send + Sync
                 {
-                    self.store.entry(::mockall::Key::new::<(#(#argty, )*)>())
+                    self.store.entry(::mockall::Key::new::<#key_ty>())
                         .or_insert_with(||
                             Box::new(Expectations #tbf ::new())
                         ).downcast_mut::<Expectations #tg>()
                         .unwrap()
                         .expect()
                 }
+
+                /// Find the current expectation with the given id.
+                #v fn find #ig (&mut self, __mockall_id: usize)
+                    -> Option<&mut Expectation #tg> #wc
+                {
+                    self.store.get_mut(&::mockall::Key::new::<#key_ty>())
+                        .and_then(|__mockall_e|
+                            __mockall_e.downcast_mut::<Expectations #tg>()
+                            .unwrap()
+                            .find(__mockall_id)
+                        )
+                }
             }
         )
     }
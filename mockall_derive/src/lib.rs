@@ -10,14 +10,19 @@ extern crate proc_macro;
 
 use cfg_if::cfg_if;
 use proc_macro2::{Span, TokenStream};
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
 use std::{
     collections::{HashMap, HashSet},
+    env,
+    fs,
     iter::FromIterator,
-    mem
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Mutex, PoisonError}
 };
 use syn::{
     *,
+    parse::Parser,
     punctuated::Punctuated,
     spanned::Spanned
 };
@@ -32,6 +37,8 @@ use crate::expectation::Expectation;
 #[derive(Debug)]
 struct MethodTypes {
     is_static: bool,
+    /// Does the method consume `self` and return `Self`?
+    returns_self: bool,
     /// Is the Expectation type generic?  This can be true even if the method is
     /// not generic.
     is_expectation_generic: bool,
@@ -75,8 +82,35 @@ cfg_if! {
     }
 }
 
+/// Can an "impl Trait" argument with these bounds be turned into a boxed trait
+/// object?  Only if it has at most one trait other than the auto traits, and
+/// that trait isn't one of the standard library's that can't be made into an
+/// object.  Other traits can't be checked here.
+fn is_object_safe(bounds: &Punctuated<TypeParamBound, Token![+]>) -> bool {
+    const AUTO: &[&str] = &["Send", "Sync", "Unpin", "UnwindSafe",
+                            "RefUnwindSafe"];
+    const UNSAFE: &[&str] = &["Clone", "Copy", "Default", "Eq", "Extend",
+        "From", "FromIterator", "FromStr", "Hash", "Into", "IntoIterator",
+        "Ord", "PartialEq", "PartialOrd", "Sized", "TryFrom", "TryInto"];
+    let mut traits = 0;
+    for b in bounds.iter() {
+        if let TypeParamBound::Trait(tb) = b {
+            let ident = &tb.path.segments.last().unwrap().ident;
+            if AUTO.iter().any(|s| ident == *s) {
+                continue;
+            }
+            if UNSAFE.iter().any(|s| ident == *s) {
+                return false;
+            }
+            traits += 1;
+        }
+    }
+    traits <= 1
+}
+
 // If there are any closures in the argument list, turn them into boxed
-// functions
+// functions.  Likewise, turn any "impl Trait" arguments into boxed trait
+// objects.
 fn declosurefy(gen: &Generics, args: &Punctuated<FnArg, Token![,]>) -> 
     (Generics, Punctuated<FnArg, Token![,]>, Punctuated<TokenStream, Token![,]>)
 {
@@ -117,6 +151,32 @@ fn declosurefy(gen: &Generics, args: &Punctuated<FnArg, Token![,]>) ->
         }
     }
 
+    // Then the "impl Trait" arguments.  Unless their bounds already include a
+    // lifetime, their trait objects get a new lifetime parameter, so the
+    // arguments needn't be 'static.
+    let impl_lt: Lifetime = parse2(quote!('__mockall_impl)).unwrap();
+    let mut needs_impl_lt = false;
+    for arg in args.iter() {
+        if let FnArg::Typed(pt) = arg {
+            if let Type::ImplTrait(tit) = &*pt.ty {
+                let bounds = &tit.bounds;
+                if !is_object_safe(bounds) {
+                    compile_error(pt.ty.span(),
+                        "Mockall can only mock impl Trait arguments whose bounds can form a trait object, like `impl Read` or `impl Iterator<Item=u32> + Send`.  Take a concrete type or a generic parameter instead.");
+                }
+                let has_lt = bounds.iter()
+                    .any(|b| matches!(b, TypeParamBound::Lifetime(_)));
+                let newty: Type = if has_lt {
+                    parse2(quote!(Box<dyn #bounds>)).unwrap()
+                } else {
+                    needs_impl_lt = true;
+                    parse2(quote!(Box<dyn #bounds + #impl_lt>)).unwrap()
+                };
+                hm.insert((*pt.ty).clone(), newty);
+            }
+        }
+    }
+
     // Then remove those types from both the Generics' params and where clause
     let should_remove = |ident: &Ident| {
             let ty: Type = parse2(quote!(#ident)).unwrap();
@@ -149,9 +209,13 @@ fn declosurefy(gen: &Generics, args: &Punctuated<FnArg, Token![,]>) ->
             }
         }).cloned());
     }
+    let mut params = params;
+    if needs_impl_lt {
+        params.insert(0, GenericParam::Lifetime(LifetimeDef::new(impl_lt)));
+    }
     let outg = Generics {
-        lt_token: if params.is_empty() { None } else { gen.lt_token.clone() },
-        gt_token: if params.is_empty() { None } else { gen.gt_token.clone() },
+        lt_token: if params.is_empty() { None } else { Some(<Token![<]>::default()) },
+        gt_token: if params.is_empty() { None } else { Some(<Token![>]>::default()) },
         params,
         where_clause: wc2
     };
@@ -274,8 +338,8 @@ fn deselfify(literal_type: &mut Type, actual: &Ident, generics: &Generics) {
             }
         }
         Type::Path(type_path) => {
-            if let Some(ref _qself) = type_path.qself {
-                compile_error(type_path.span(), "QSelf is TODO");
+            if let Some(qself) = type_path.qself.as_mut() {
+                deselfify(qself.ty.as_mut(), actual, generics);
             }
             let p = &mut type_path.path;
             for seg in p.segments.iter_mut() {
@@ -496,8 +560,7 @@ fn supersuperfy(original: &Type, levels: i32) -> Type {
             },
             Type::BareFn(bfn) => {
                 if let ReturnType::Type(_, ref mut bt) = bfn.output {
-                    let new_bt = Box::new(supersuperfy(bt.as_ref(), levels));
-                    mem::replace(bt, new_bt);
+                    **bt = supersuperfy(bt.as_ref(), levels);
                 }
                 for input in bfn.inputs.iter_mut() {
                     input.ty = supersuperfy(&input.ty, levels);
@@ -509,8 +572,8 @@ fn supersuperfy(original: &Type, levels: i32) -> Type {
                 }
             }
             Type::Path(type_path) => {
-                if let Some(ref _qself) = type_path.qself {
-                    compile_error(type_path.span(), "QSelf is TODO");
+                if let Some(qself) = type_path.qself.as_mut() {
+                    *qself.ty = supersuperfy(qself.ty.as_ref(), levels);
                 }
                 supersuperfy_path(&mut type_path.path, levels)
             },
@@ -545,11 +608,310 @@ fn supersuperfy(original: &Type, levels: i32) -> Type {
     output
 }
 
+/// Which of the items generated for a mocked method an attribute of the
+/// original method is destined for
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum AttrTarget {
+    /// The mock method itself
+    Method,
+    /// The mock method, when it implements a trait's method
+    TraitMethod,
+    /// Methods that configure the mock method, like `expect_foo`
+    Helper,
+    /// Everything else, like the method's expectation module
+    Other
+}
+
+/// How an attribute of a mocked method gets copied to the items generated from
+/// it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum AttrClass {
+    /// Doc comments.  They would only cause warnings on the generated items.
+    Doc,
+    /// Lint levels and `cfg`, which must apply to every generated item
+    Scoped,
+    /// Attributes that are only valid on functions, like `inline`
+    FnOnly,
+    /// `must_use`, which isn't allowed on a trait impl's methods
+    MustUse,
+    /// `deprecated`, which should warn users about the methods they call, but
+    /// not about the generated code's own calls
+    Deprecated,
+    /// `cfg_attr`, whose attributes are classified in turn
+    CfgAttr,
+    /// Anything else, which is only known to be valid on the mock method
+    Unknown
+}
+
+impl AttrClass {
+    fn applies_to(self, target: AttrTarget) -> bool {
+        match self {
+            AttrClass::Doc => false,
+            AttrClass::Scoped | AttrClass::CfgAttr => true,
+            AttrClass::FnOnly | AttrClass::Unknown =>
+                target == AttrTarget::Method ||
+                target == AttrTarget::TraitMethod,
+            AttrClass::MustUse => target == AttrTarget::Method,
+            AttrClass::Deprecated =>
+                target == AttrTarget::Method || target == AttrTarget::Helper
+        }
+    }
+}
+
+/// Lint level attributes.  `expect` isn't among them, because it would fail
+/// on every generated item that doesn't trigger the lint.
+const LINT_ATTRS: &[&str] = &["allow", "deny", "forbid", "warn"];
+
+/// Attributes of a module's or foreign function that will be copied to its
+/// mock
+const SEMANTIC_ATTRS: &[&str] = &["allow", "cfg", "cfg_attr", "cold", "deny",
+                                  "deprecated", "forbid", "inline", "must_use",
+                                  "track_caller", "warn"];
+
+/// Attributes that export an item's symbol under a fixed name
+const SYMBOL_ATTRS: &[&str] = &["export_name", "no_mangle"];
+
+fn attr_is(attr: &Attribute, names: &[&str]) -> bool {
+    attr.path.get_ident()
+        .map(|i| names.iter().any(|n| i == n))
+        .unwrap_or(false)
+}
+
+fn classify_attr(attr: &Attribute) -> AttrClass {
+    if attr_is(attr, &["doc"]) {
+        AttrClass::Doc
+    } else if attr_is(attr, LINT_ATTRS) || attr_is(attr, &["cfg"]) {
+        AttrClass::Scoped
+    } else if attr_is(attr, &["cold", "inline", "track_caller"]) {
+        AttrClass::FnOnly
+    } else if attr_is(attr, &["must_use"]) {
+        AttrClass::MustUse
+    } else if attr_is(attr, &["deprecated"]) {
+        AttrClass::Deprecated
+    } else if attr_is(attr, &["cfg_attr"]) {
+        AttrClass::CfgAttr
+    } else {
+        AttrClass::Unknown
+    }
+}
+
+/// Translate a `cfg_attr` for one of the items generated from a method, by
+/// keeping only the attributes that apply to it.  Returns the translated
+/// attribute's contents, or `None` if none apply.
+fn translate_cfg_attr(attr: &Attribute, target: AttrTarget)
+    -> Option<TokenStream>
+{
+    let path = &attr.path;
+    let tokens = &attr.tokens;
+    let content = match tokens.clone().into_iter().next() {
+        Some(proc_macro2::TokenTree::Group(g)) => g.stream(),
+        _ => return Some(quote!(#path #tokens))
+    };
+    // Commas within an attribute's arguments are nested in a Group, so the
+    // top-level ones separate the predicate from each attribute.
+    let mut parts = vec![TokenStream::new()];
+    for tt in content {
+        match &tt {
+            proc_macro2::TokenTree::Punct(p) if p.as_char() == ',' =>
+                parts.push(TokenStream::new()),
+            _ => parts.last_mut().unwrap().extend(Some(tt))
+        }
+    }
+    let predicate = parts.remove(0);
+    let mut kept = Vec::new();
+    for part in parts.into_iter().filter(|part| !part.is_empty()) {
+        let inner = match Attribute::parse_outer.parse2(quote!(#[#part])) {
+            Ok(mut attrs) if attrs.len() == 1 => attrs.remove(0),
+            _ => return Some(quote!(#path #tokens))
+        };
+        let class = classify_attr(&inner);
+        if class == AttrClass::CfgAttr {
+            kept.extend(translate_cfg_attr(&inner, target));
+        } else if class.applies_to(target) {
+            kept.push(part);
+        }
+    }
+    if kept.is_empty() {
+        None
+    } else {
+        Some(quote!(cfg_attr(#predicate, #(#kept),*)))
+    }
+}
+
+/// Format a method's attributes for one of the items generated from it
+fn format_attrs(attrs: &[Attribute], target: AttrTarget) -> TokenStream {
+    let mut out = TokenStream::new();
+    for attr in attrs {
+        let class = classify_attr(attr);
+        if class == AttrClass::CfgAttr {
+            if let Some(meta) = translate_cfg_attr(attr, target) {
+                out.extend(quote!(#[#meta]));
+            }
+        } else if class.applies_to(target) {
+            quote::ToTokens::to_tokens(attr, &mut out);
+        }
+    }
+    out
+}
+
+/// If the `MOCKALL_DEBUG` environment variable is set, print a mock's generated
+/// code, and save it to `mockall/<crate>/<name>.rs` in the target directory.
+fn debug_dump(name: &str, ts: &TokenStream) {
+    if env::var("MOCKALL_DEBUG").is_err() {
+        return;
+    }
+    println!("{}", ts);
+    let dir = debug_dir(env::var_os("CARGO_TARGET_DIR").map(PathBuf::from),
+                        env::var_os("OUT_DIR").map(PathBuf::from),
+                        env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from));
+    let krate = env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    dump_to(&dir.join(krate), name, ts);
+}
+
+/// Find the directory to save generated code in: `mockall` within the target
+/// directory.  That's `CARGO_TARGET_DIR`, if it's set.  Otherwise, if the
+/// crate has a build script, it's found from that script's `OUT_DIR`, like
+/// `target/debug/build/foo-0123456789abcdef/out`.  Otherwise, it's assumed to
+/// be `target` within the workspace's root, which is the closest directory to
+/// the crate's manifest that has a `Cargo.lock`.
+fn debug_dir(target_dir: Option<PathBuf>, out_dir: Option<PathBuf>,
+             manifest_dir: Option<PathBuf>) -> PathBuf
+{
+    let target_dir = target_dir
+        .or_else(|| out_dir.and_then(|d| {
+            d.ancestors().nth(4).map(PathBuf::from)
+        })).unwrap_or_else(|| {
+            let manifest_dir = manifest_dir.unwrap_or_default();
+            manifest_dir.ancestors()
+                .find(|d| d.join("Cargo.lock").is_file())
+                .unwrap_or(&manifest_dir)
+                .join("target")
+        });
+    target_dir.join("mockall")
+}
+
+/// Save a mock's generated code to `<name>.rs` in `dir`, formatted by rustfmt
+/// if it's available.  If another mock of the same name was already saved
+/// there by this compilation, like one in a different module, then the file
+/// name gets a suffix, like `<name>-2.rs`.  Returns the file's path.
+fn dump_to(dir: &std::path::Path, name: &str, ts: &TokenStream)
+    -> Option<PathBuf>
+{
+    static DUMPED: Mutex<Option<HashMap<PathBuf, usize>>> = Mutex::new(None);
+
+    let n = {
+        let mut dumped = DUMPED.lock().unwrap_or_else(PoisonError::into_inner);
+        let n = dumped.get_or_insert_with(HashMap::new)
+            .entry(dir.join(name))
+            .or_insert(0);
+        *n += 1;
+        *n
+    };
+    let path = if n == 1 {
+        dir.join(format!("{}.rs", name))
+    } else {
+        dir.join(format!("{}-{}.rs", name, n))
+    };
+    // This is only a debugging aid, so it mustn't fail the build
+    if fs::create_dir_all(dir).is_err() ||
+        fs::write(&path, ts.to_string()).is_err()
+    {
+        return None;
+    }
+    let rustfmt = env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+    let _ = Command::new(rustfmt)
+        .args(["--edition", "2018"])
+        .arg(&path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    Some(path)
+}
+
 /// Generate a mock identifier from the regular one: eg "Foo" => "MockFoo"
 fn gen_mock_ident(ident: &Ident) -> Ident {
     format_ident!("Mock{}", ident)
 }
 
+/// Generate an identifier for the mock struct's builder: eg "Foo" =>
+/// "MockFooBuilder"
+fn gen_builder_ident(ident: &Ident) -> Ident {
+    format_ident!("Mock{}Builder", ident)
+}
+
+/// The mutability of a generated checkpoint method's `__mockall_cps`
+/// accumulator.  It only needs to be mutable if anything gets added to it.
+fn cps_mut(cp_body: &TokenStream) -> Option<Token![mut]> {
+    if cp_body.is_empty() {
+        None
+    } else {
+        Some(Token![mut](Span::call_site()))
+    }
+}
+
+/// Generate `#[track_caller]` for a mock method, so that its panics report
+/// the location of the call instead of somewhere in the generated code.  It
+/// isn't allowed on functions with a non-Rust ABI, has no effect on async
+/// functions, and mustn't be repeated if the original method already has it.
+fn gen_track_caller(sig: &Signature, attrs: &[Attribute]) -> TokenStream {
+    let rust_abi = sig.abi.as_ref()
+        .map(|abi| abi.name.as_ref().map(|n| n.value() == "Rust")
+             .unwrap_or(false))
+        .unwrap_or(true);
+    if !rust_abi || sig.asyncness.is_some() ||
+        attrs.iter().any(|attr| attr_is(attr, &["track_caller"]))
+    {
+        TokenStream::new()
+    } else {
+        quote!(#[track_caller])
+    }
+}
+
+/// Generate a doc note for the mock of a `const fn`, explaining why the mock
+/// isn't `const`.  It's spanned at the original `const` keyword.
+fn gen_const_note(sig: &Signature) -> TokenStream {
+    match &sig.constness {
+        Some(c) => quote_spanned!(c.span() =>
+            #[doc = ""]
+            #[doc = "Note: the original function is `const`, but its mock \
+                isn't, because it must lock its expectations.  It can't be \
+                called in a constant expression."]
+        ),
+        None => TokenStream::new()
+    }
+}
+
+/// Generate the handler for a call to `mock::method` that matched no
+/// expectation.  `ty` is the type that the call must produce.  If the call is
+/// fallible, it will produce that type's default value, if it has one.
+fn gen_no_match(mock: &str, method: &str, ty: TokenStream) -> TokenStream {
+    quote!(::mockall::no_match(#mock, #method, || {
+        #[allow(unused_imports)]
+        use ::mockall::{FallbackDefault as _, FallbackNone as _};
+        (&&::mockall::Fallback::<#ty>(::std::marker::PhantomData)).fallback()
+    }))
+}
+
+/// Generate an expression that formats the value that `expr` refers to with
+/// its `Debug` implementation, if it has one, or as a placeholder otherwise.
+fn gen_debug_string(expr: TokenStream) -> TokenStream {
+    quote!({
+        #[allow(unused_imports)]
+        use ::mockall::{ViaDebug as _, ViaNothing as _};
+        (&&::mockall::ArgPrinter(#expr)).debug_string()
+    })
+}
+
+/// Hide generated scaffolding, which users never need to name, from the docs.
+/// It stays visible when documenting the generated API itself.
+fn doc_hidden() -> TokenStream {
+    #[cfg(all(not(test), feature = "extra-docs"))]
+    let attr = TokenStream::new();
+    #[cfg(any(test, not(feature = "extra-docs")))]
+    let attr = quote!(#[doc(hidden)]);
+    attr
+}
+
 /// Generate an identifier for the mock struct's private module: eg "Foo" =>
 /// "__mock_Foo"
 fn gen_mod_ident(struct_: &Ident, trait_: Option<&Ident>) -> Ident {
@@ -705,14 +1067,17 @@ fn split_lifetimes(
             GenericParam::Lifetime(ltd) if rlts.contains(&ltd.lifetime) &&
                                            alts.contains(&ltd.lifetime) =>
             {
-                unimplemented!("Methods that return references to their arguments are TODO")
+                compile_error(ltd.span(),
+                    "Mockall does not yet support methods whose return values borrow from their arguments");
+                continue;
             },
             GenericParam::Lifetime(ltd) if alts.contains(&ltd.lifetime) =>
                 alv.push(p),
             GenericParam::Lifetime(ltd) if rlts.contains(&ltd.lifetime) =>
                 rlv.push(p),
-            GenericParam::Lifetime(_) =>
-                panic!("An unused lifetime parameter?"),
+            // A lifetime that this method doesn't use, most likely one of its
+            // trait's lifetime parameters.  The method doesn't need it.
+            GenericParam::Lifetime(_) => (),
             _ => tv.push(p)
         }
     }
@@ -871,12 +1236,200 @@ fn method_types(sig: &Signature, generics: Option<&Generics>) -> MethodTypes {
     let expectation: Type = parse2(expect_ts).unwrap();
     let mut output = sig.output.clone();
     deimplify(&mut output);
+    if returns_never(&output) {
+        output = ReturnType::Default;
+    }
+
+    let returns_self = returns_self(&sig.inputs, &sig.output);
 
-    MethodTypes{is_static, is_expectation_generic, expectation,
+    MethodTypes{is_static, returns_self, is_expectation_generic, expectation,
                 expectation_generics, expectation_inputs, expectations, call,
                 expect_obj, call_exprs, inputs, output}
 }
 
+/// Remove a trait's provided methods that only exist for `Sized` implementors,
+/// like `Iterator::map`.  Those can't always be mocked, and don't need to be,
+/// because their default implementations work fine for the mock struct.
+fn strip_sized_provided_methods(item: &mut ItemTrait) {
+    item.items.retain(|ti| match ti {
+        TraitItem::Method(m) if m.default.is_some() => {
+            !m.sig.generics.where_clause.as_ref()
+                .map_or(false, |wc| wc.predicates.iter().any(is_self_sized))
+        },
+        _ => true
+    });
+}
+
+/// Is this where clause predicate `Self: Sized`?
+fn is_self_sized(pred: &WherePredicate) -> bool {
+    if let WherePredicate::Type(pt) = pred {
+        is_self(&pt.bounded_ty) && pt.bounds.iter().any(|b| match b {
+            TypeParamBound::Trait(tb) => {
+                tb.modifier == TraitBoundModifier::None &&
+                    tb.path.segments.last().map_or(false, |s| s.ident == "Sized")
+            },
+            _ => false
+        })
+    } else {
+        false
+    }
+}
+
+/// Remove the where clause predicates that bound `Self`, like
+/// `where Self: Send + Sync`.  On a trait they act like supertraits, but on the
+/// mock's generated structs they would bound those structs instead.
+fn strip_self_bounds(generics: &Generics) -> Generics {
+    let mut generics = generics.clone();
+    if let Some(wc) = generics.where_clause.as_mut() {
+        wc.predicates = wc.predicates.iter()
+            .filter(|pred| match pred {
+                WherePredicate::Type(pt) => !is_self(&pt.bounded_ty),
+                _ => true
+            }).cloned()
+            .collect();
+        if wc.predicates.is_empty() {
+            generics.where_clause = None;
+        }
+    }
+    generics
+}
+
+/// Is this type `Self`?
+fn is_self(ty: &Type) -> bool {
+    match ty {
+        Type::Path(tp) => tp.qself.is_none() && tp.path.is_ident("Self"),
+        _ => false
+    }
+}
+
+/// Is this the unit type, `()`?
+fn is_unit(ty: &Type) -> bool {
+    match ty {
+        Type::Paren(tp) => is_unit(&tp.elem),
+        Type::Tuple(tt) => tt.elems.is_empty(),
+        _ => false
+    }
+}
+
+/// Does a function with this return type return nothing, or `()`?  Then its
+/// expectations needn't set a return value.
+fn returns_unit(rt: &ReturnType) -> bool {
+    match rt {
+        ReturnType::Default => true,
+        ReturnType::Type(_, ty) => is_unit(ty)
+    }
+}
+
+/// Does a function with this return type never return, like `-> !`?  Then
+/// its expectations return `()` instead, and the mock panics afterwards.
+fn returns_never(rt: &ReturnType) -> bool {
+    match rt {
+        ReturnType::Default => false,
+        ReturnType::Type(_, ty) => matches!(**ty, Type::Never(_))
+    }
+}
+
+/// Is this the type of the mock struct named `mock`, or `Self`?  Then a static
+/// method that returns it is a constructor, whose expectations can return a
+/// preconfigured mock.
+fn is_mock_type(ty: &Type, mock: &Ident) -> bool {
+    match ty {
+        Type::Path(tp) if tp.qself.is_none() =>
+            tp.path.is_ident("Self") ||
+            tp.path.segments.last().map_or(false, |seg| seg.ident == *mock),
+        _ => false
+    }
+}
+
+/// Can this generic method have `DynExpectation`s, which see its arguments as
+/// `&dyn Any` and return a `Box<dyn Any>`?  Only if it takes `self` by
+/// reference, and its arguments (after stripping one level of shared
+/// reference) and return type are all `Sized` and `'static`.
+fn is_dyn_expectable(inputs: &Punctuated<FnArg, Token![,]>,
+                     output: &ReturnType,
+                     generics: &Generics) -> bool
+{
+    /// Is this type obviously `'static` and `Sized`?
+    fn is_plain(ty: &Type) -> bool {
+        match ty {
+            Type::Array(ta) => is_plain(&ta.elem),
+            Type::Group(tg) => is_plain(&tg.elem),
+            Type::Paren(tp) => is_plain(&tp.elem),
+            Type::Tuple(tt) => tt.elems.iter().all(is_plain),
+            Type::Path(tp) => {
+                tp.qself.is_none() &&
+                !tp.path.is_ident("str") &&
+                tp.path.segments.first().map_or(false, |s| s.ident != "Self") &&
+                tp.path.segments.iter().all(|seg| match &seg.arguments {
+                    PathArguments::None => true,
+                    PathArguments::AngleBracketed(abga) => {
+                        abga.args.iter().all(|ga| match ga {
+                            GenericArgument::Type(t) => is_plain(t),
+                            GenericArgument::Binding(b) => is_plain(&b.ty),
+                            GenericArgument::Lifetime(lt) =>
+                                lt.ident == "static",
+                            GenericArgument::Const(_) => true,
+                            GenericArgument::Constraint(_) => false
+                        })
+                    },
+                    PathArguments::Parenthesized(_) => false
+                })
+            },
+            _ => false
+        }
+    }
+
+    let maybe_sized = |b: &TypeParamBound| match b {
+        TypeParamBound::Trait(tb) => tb.modifier != TraitBoundModifier::None,
+        _ => false
+    };
+    let unsized_params = generics.type_params()
+        .any(|tp| tp.bounds.iter().any(maybe_sized)) ||
+        generics.where_clause.as_ref().map_or(false, |wc| {
+            wc.predicates.iter().any(|wp| match wp {
+                WherePredicate::Type(pt) => pt.bounds.iter().any(maybe_sized),
+                _ => false
+            })
+        });
+    let mut by_ref = false;
+    let args_ok = inputs.iter().all(|fa| match fa {
+        FnArg::Receiver(r) => {
+            by_ref = r.reference.is_some();
+            true
+        },
+        FnArg::Typed(pt) => match pt.ty.as_ref() {
+            Type::Reference(tr) if tr.mutability.is_none() => is_plain(&tr.elem),
+            ty => is_plain(ty)
+        }
+    });
+    let output_ok = match output {
+        ReturnType::Default => true,
+        ReturnType::Type(_, ty) => is_plain(ty)
+    };
+    generics.type_params().next().is_some() && !unsized_params && by_ref &&
+        args_ok && output_ok
+}
+
+/// Does this method consume `self` and return `Self`, like a builder's methods
+/// usually do?
+fn returns_self(inputs: &Punctuated<FnArg, Token![,]>, output: &ReturnType)
+    -> bool
+{
+    let by_value = inputs.iter().any(|fa| {
+        if let FnArg::Receiver(r) = fa {
+            r.reference.is_none()
+        } else {
+            false
+        }
+    });
+    if let ReturnType::Type(_, ty) = output {
+        if let Type::Path(tp) = ty.as_ref() {
+            return by_value && tp.qself.is_none() && tp.path.is_ident("Self");
+        }
+    }
+    false
+}
+
 fn staticize(generics: &Generics) -> Generics {
     let mut ret = generics.clone();
     for lt in ret.lifetimes_mut() {
@@ -904,6 +1457,66 @@ pub fn automock(attrs: proc_macro::TokenStream, input: proc_macro::TokenStream)
 mod t {
     use super::*;
 
+    mod debug_dump {
+        use super::super::*;
+        use std::str::FromStr;
+
+        /// A new, empty directory for one test
+        fn tempdir(name: &str) -> PathBuf {
+            let dir = env::temp_dir().join(format!("mockall-{}-{}", name,
+                                                   std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            dir
+        }
+
+        #[test]
+        fn target_dir() {
+            let dir = debug_dir(Some(PathBuf::from("/t")),
+                                Some(PathBuf::from("/o/debug/build/x-1/out")),
+                                Some(PathBuf::from("/m")));
+            assert_eq!(PathBuf::from("/t/mockall"), dir);
+        }
+
+        #[test]
+        fn out_dir() {
+            let dir = debug_dir(None,
+                                Some(PathBuf::from("/o/debug/build/x-1/out")),
+                                Some(PathBuf::from("/m")));
+            assert_eq!(PathBuf::from("/o/mockall"), dir);
+        }
+
+        /// A workspace member's target directory is in the workspace's root
+        #[test]
+        fn workspace() {
+            let root = tempdir("debug-dir-workspace");
+            let member = root.join("member");
+            fs::create_dir_all(&member).unwrap();
+            fs::write(root.join("Cargo.lock"), "").unwrap();
+            let dir = debug_dir(None, None, Some(member));
+            fs::remove_dir_all(&root).unwrap();
+            assert_eq!(root.join("target").join("mockall"), dir);
+        }
+
+        #[test]
+        fn dump() {
+            let dir = tempdir("debug-dump");
+            let ts = TokenStream::from_str("struct MockFoo {}").unwrap();
+            let path = dump_to(&dir, "MockFoo", &ts).unwrap();
+            assert_eq!(dir.join("MockFoo.rs"), path);
+            let contents = fs::read_to_string(&path).unwrap();
+            let file: File = parse2(TokenStream::from_str(&contents).unwrap())
+                .unwrap();
+            assert!(file.items.iter().any(|item| matches!(item,
+                Item::Struct(s) if s.ident == "MockFoo")));
+            // Another mock of the same name doesn't overwrite it
+            let path2 = dump_to(&dir, "MockFoo", &ts).unwrap();
+            assert_eq!(dir.join("MockFoo-2.rs"), path2);
+            assert!(path.is_file());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
     #[test]
     fn merge_generics() {
         let mut g1: Generics = parse2(quote!(<T: 'static, V: Copy> )).unwrap();
@@ -928,6 +1541,122 @@ mod t {
                    quote!(#gm #gm_wc).to_string());
     }
 
+    /// Lifetimes that the method doesn't use, like its trait's, are dropped
+    #[test]
+    fn split_lifetimes() {
+        let g: Generics = parse2(quote!(<'a, 'b, 'c, T>)).unwrap();
+        let sig: Signature = parse2(quote!(
+            fn foo(&self, x: &'a str, t: T) -> Ast<'b>
+        )).unwrap();
+        let (tg, alg, rlg) = super::split_lifetimes(g, &sig.inputs,
+                                                    &sig.output);
+        assert_eq!(quote!(#tg).to_string(), quote!(<T>).to_string());
+        assert_eq!(quote!(#alg).to_string(), quote!(<'a>).to_string());
+        assert_eq!(quote!(#rlg).to_string(), quote!(<'b>).to_string());
+    }
+
+mod attrs {
+    use super::*;
+
+    fn attr(ts: TokenStream) -> Attribute {
+        Attribute::parse_outer.parse2(ts).unwrap().remove(0)
+    }
+
+    fn format(ts: TokenStream, target: AttrTarget) -> String {
+        format_attrs(&[attr(ts)], target).to_string()
+    }
+
+    #[test]
+    fn classify() {
+        let table = [
+            (quote!(#[doc = "foo"]), AttrClass::Doc),
+            (quote!(#[allow(clippy::too_many_arguments)]), AttrClass::Scoped),
+            (quote!(#[deny(unused)]), AttrClass::Scoped),
+            (quote!(#[forbid(unsafe_code)]), AttrClass::Scoped),
+            (quote!(#[warn(missing_docs)]), AttrClass::Scoped),
+            (quote!(#[cfg(feature = "x")]), AttrClass::Scoped),
+            (quote!(#[cold]), AttrClass::FnOnly),
+            (quote!(#[inline(always)]), AttrClass::FnOnly),
+            (quote!(#[track_caller]), AttrClass::FnOnly),
+            (quote!(#[must_use]), AttrClass::MustUse),
+            (quote!(#[deprecated(note = "foo")]), AttrClass::Deprecated),
+            (quote!(#[cfg_attr(feature = "x", allow(unused))]),
+                AttrClass::CfgAttr),
+            (quote!(#[expect(unused)]), AttrClass::Unknown),
+            (quote!(#[tracing::instrument]), AttrClass::Unknown),
+        ];
+        for (ts, class) in table.iter() {
+            assert_eq!(classify_attr(&attr(ts.clone())), *class, "{}", ts);
+        }
+    }
+
+    #[test]
+    fn targets() {
+        use AttrTarget::*;
+        let all = [Method, TraitMethod, Helper, Other];
+        let table = [
+            (AttrClass::Doc, vec![]),
+            (AttrClass::Scoped, all.to_vec()),
+            (AttrClass::FnOnly, vec![Method, TraitMethod]),
+            (AttrClass::MustUse, vec![Method]),
+            (AttrClass::Deprecated, vec![Method, Helper]),
+            (AttrClass::Unknown, vec![Method, TraitMethod]),
+        ];
+        for (class, targets) in table.iter() {
+            for target in all.iter() {
+                assert_eq!(class.applies_to(*target), targets.contains(target),
+                    "{:?} {:?}", class, target);
+            }
+        }
+    }
+
+    /// cfg_attr keeps only the attributes that apply to each target
+    #[test]
+    fn cfg_attr() {
+        let ts = quote!(#[cfg_attr(feature = "x", inline, allow(unused))]);
+        assert_eq!(format(ts.clone(), AttrTarget::Method),
+            quote!(#[cfg_attr(feature = "x", inline, allow(unused))])
+            .to_string());
+        assert_eq!(format(ts, AttrTarget::Other),
+            quote!(#[cfg_attr(feature = "x", allow(unused))]).to_string());
+
+        let ts = quote!(#[cfg_attr(feature = "x", must_use)]);
+        assert_eq!(format(ts, AttrTarget::TraitMethod), "");
+    }
+
+    #[test]
+    fn nested_cfg_attr() {
+        let ts = quote!(#[cfg_attr(unix, cfg_attr(feature = "x", cold),
+                                   deprecated)]);
+        assert_eq!(format(ts.clone(), AttrTarget::TraitMethod),
+            quote!(#[cfg_attr(unix, cfg_attr(feature = "x", cold))])
+            .to_string());
+        assert_eq!(format(ts.clone(), AttrTarget::Helper),
+            quote!(#[cfg_attr(unix, deprecated)]).to_string());
+        assert_eq!(format(ts, AttrTarget::Other), "");
+    }
+
+    #[test]
+    fn track_caller() {
+        let table = [
+            (quote!(fn foo(&self)), vec![], true),
+            (quote!(fn foo(&self)), vec![quote!(#[track_caller])], false),
+            (quote!(fn foo(&self)), vec![quote!(#[inline])], true),
+            (quote!(async fn foo(&self)), vec![], false),
+            (quote!(extern "C" fn foo()), vec![], false),
+            (quote!(extern "Rust" fn foo()), vec![], true),
+        ];
+        for (sig, attrs, expected) in table.iter() {
+            let sig: Signature = parse2(sig.clone()).unwrap();
+            let attrs = attrs.iter()
+                .map(|ts| attr(ts.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(!gen_track_caller(&sig, &attrs).is_empty(), *expected,
+                "{}", quote!(#sig));
+        }
+    }
+}
+
 // Tests for the method_types function.  But there are no assertions for the
 // call_exprs field, because TokenStream doesn't implement Eq or anything close
 // to it.
@@ -985,6 +1714,47 @@ mod method_types{
         assert_eq!(mt.output, parse2(quote!(-> u32)).unwrap());
     }
 
+    // impl Trait args become boxed trait objects, with a lifetime unless they
+    // already have one
+    #[test]
+    fn impl_trait_arg() {
+        let tim: TraitItemMethod = parse2(quote!(
+            fn foo(&self, r: impl Read, s: impl Read + 'static) -> u32;
+        )).unwrap();
+        let mt = method_types(&tim.sig, None);
+        assert!(!mt.is_expectation_generic);
+        assert_eq!(mt.expectation_generics,
+                   parse2(quote!(<'__mockall_impl>)).unwrap());
+        let einputs_vec: Vec<FnArg> = vec![
+            parse2(quote!(&self)).unwrap(),
+            parse2(quote!(r: Box<dyn Read + '__mockall_impl>)).unwrap(),
+            parse2(quote!(s: Box<dyn Read + 'static>)).unwrap()
+        ];
+        let einputs = Punctuated::from_iter(einputs_vec.into_iter());
+        assert_eq!(mt.expectation_inputs, einputs);
+        assert_eq!(mt.expectation,
+                   parse2(quote!(foo::Expectation)).unwrap());
+    }
+
+    // impl Trait args that can't be trait objects are rejected
+    #[test]
+    #[should_panic(expected = "can only mock impl Trait arguments")]
+    fn impl_trait_arg_not_object_safe() {
+        let tim: TraitItemMethod = parse2(quote!(
+            fn name(&self, n: impl Into<String>);
+        )).unwrap();
+        method_types(&tim.sig, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "can only mock impl Trait arguments")]
+    fn impl_trait_arg_two_traits() {
+        let tim: TraitItemMethod = parse2(quote!(
+            fn name(&self, n: impl Read + Debug + Send);
+        )).unwrap();
+        method_types(&tim.sig, None);
+    }
+
     #[test]
     fn generic_method() {
         let tim: TraitItemMethod = parse2(quote!(
@@ -1012,6 +1782,21 @@ mod method_types{
         assert_eq!(mt.output, parse2(quote!(-> O)).unwrap());
     }
 
+    /// The method's bounds and where clause must be kept verbatim, so the
+    /// expect_* method accepts exactly the types that the method does
+    #[test]
+    fn generic_method_with_bounds() {
+        let tim: TraitItemMethod = parse2(quote!(
+            fn send<M: Message + Send + 'static, N>(&self, m: M, n: N)
+                where N: Message + Clone + 'static;
+        )).unwrap();
+        let mt = method_types(&tim.sig, None);
+        assert!(mt.is_expectation_generic);
+        assert_eq!(mt.expectation,
+                   parse2(quote!(send::Expectation<M, N>)).unwrap());
+        assert_eq!(mt.expectation_generics, tim.sig.generics);
+    }
+
     #[test]
     fn generic_method_with_lifetime_parameter() {
         let tim: TraitItemMethod = parse2(quote!(
@@ -1223,6 +2008,27 @@ mod method_types{
         assert!(mt.inputs.is_empty());
         assert_eq!(mt.output, parse2(quote!(-> u32)).unwrap());
     }
+
+    /// A constructor that is itself generic needs GenericExpectations
+    #[test]
+    fn generic_static_constructor() {
+        let tim: TraitItemMethod = parse2(quote!(
+            fn new<C: Config + 'static>(cfg: C) -> Self;
+        )).unwrap();
+        let mt = method_types(&tim.sig, None);
+        assert!(mt.is_static);
+        assert!(mt.is_expectation_generic);
+        assert_eq!(mt.expectation,
+                   parse2(quote!(new::Expectation<C>)).unwrap());
+        assert_eq!(mt.expectation_generics,
+                   parse2(quote!(<C: Config + 'static>)).unwrap());
+        assert_eq!(mt.expectations,
+                   parse2(quote!(new::GenericExpectations)).unwrap());
+        assert_eq!(mt.expect_obj,
+                   parse2(quote!(new::GenericExpectations)).unwrap());
+        assert_eq!(mt.call, "call");
+        assert_eq!(mt.output, parse2(quote!(-> Self)).unwrap());
+    }
 }
 
 }
@@ -1,9 +1,10 @@
 // vim: tw=80
 use super::*;
+use proc_macro2::TokenTree;
 use quote::ToTokens;
 use std::{
     borrow::Borrow,
-    env
+    collections::HashSet
 };
 use syn::parse::{Parse, ParseStream};
 
@@ -13,7 +14,20 @@ pub(crate) struct Mock {
     pub(crate) generics: syn::Generics,
     // The Mock struct's inherent methods.  The blocks will all be empty.
     pub(crate) methods: Vec<syn::ImplItemMethod>,
-    pub(crate) traits: Vec<syn::ItemTrait>
+    // The Mock struct's inherent associated constants, copied verbatim
+    pub(crate) consts: Vec<syn::ImplItemConst>,
+    pub(crate) traits: Vec<syn::ItemTrait>,
+    // Extra derive macros for the Mock struct and its trait sub-structures
+    pub(crate) derives: Vec<syn::Path>,
+    // Implement the traits for `&MockFoo` instead of for `MockFoo`
+    pub(crate) ref_impl: bool,
+    // Also implement the traits for `&MockFoo` and `&mut MockFoo`, where their
+    // methods' receivers permit
+    pub(crate) ref_impls: bool,
+    // Methods that get no expectations.  They panic if called.
+    pub(crate) unmocked: Vec<syn::Ident>,
+    // A real type to forward calls to, when they match no expectation
+    pub(crate) inner: Option<syn::Type>,
 }
 
 impl Mock {
@@ -21,84 +35,248 @@ impl Mock {
         let mut output = TokenStream::new();
         let mut mock_body = TokenStream::new();
         let mut cp_body = TokenStream::new();
+        let mut static_cp_body = TokenStream::new();
+        let mut static_reset_body = TokenStream::new();
+        let mut report_body = TokenStream::new();
+        let mut count_body = TokenStream::new();
+        let mut builder_body = TokenStream::new();
+        let mut store_impls = TokenStream::new();
+        // Each method's contribution to checkpoint_all
+        let mut all_cps = Vec::new();
         let mut has_new = false;
+        let names = self.method_names();
         let mock_struct_name = gen_mock_ident(&self.name);
         let mock_mod_ident = gen_mod_ident(&self.name, None);
         let subs = self.traits.iter().map(|trait_| {
             (trait_.ident.to_string(), self.generics.clone())
         }).collect::<Vec<_>>();
+        let mocked_methods = self.methods.iter()
+            .filter(|meth| !self.unmocked.contains(&meth.sig.ident))
+            .collect::<Vec<_>>();
         // generate the mock structure
         gen_struct(&mock_struct_name, &self.vis, &self.name, &self.generics,
-                   &subs, &self.methods)
+                   &subs, &mocked_methods, &self.derives, true,
+                   self.inner.as_ref())
             .to_tokens(&mut output);
         // generate sub structures
         for trait_ in self.traits.iter() {
-            let mut sub_cp_body = TokenStream::new();
             let sub_mock = format_ident!("{}_{}", &self.name, &trait_.ident);
-            let sub_struct = format_ident!("{}_expectations", &trait_.ident);
             let mod_ident = gen_mod_ident(&self.name, Some(&trait_.ident));
             let methods = trait_.items.iter().filter_map(|item| {
-                if let syn::TraitItem::Method(m) = item {
-                    Some(tim2iim(m, &self.vis))
-                } else {
-                    None
+                match item {
+                    syn::TraitItem::Method(m)
+                        if !self.unmocked.contains(&m.sig.ident) =>
+                    {
+                        Some(tim2iim(m, &self.vis))
+                    },
+                    _ => None
                 }
             }).collect::<Vec<_>>();
             let vis = syn::Visibility::Inherited;
             gen_struct(&mock_struct_name, &vis, &sub_mock, &self.generics, &[],
-                       &methods)
+                       &methods, &self.derives, false, None)
                 .to_tokens(&mut output);
-            let mock_sub_name = gen_mock_ident(&sub_mock);
             for meth in methods {
-                has_new |= meth.borrow().sig.ident == "new";
-                let generics = merge_generics(&self.generics, &trait_.generics);
-                let (_, _, cp) = gen_mock_method(&mock_struct_name,
-                                                 Some(&mod_ident),
-                                                 &meth.attrs[..],
-                                                 &meth.vis, &meth.vis,
-                                                 &meth.borrow().sig, None,
-                                                 &generics);
-                cp.to_tokens(&mut sub_cp_body);
-            }
-            let (ig, tg, wc) = self.generics.split_for_impl();
-            quote!(impl #ig #mock_sub_name #tg #wc {
-                fn checkpoint(&mut self) {
-                    #sub_cp_body
-                }
-            }).to_tokens(&mut output);
-            quote!(self.#sub_struct.checkpoint();).to_tokens(&mut cp_body);
+                // A trait's constructor named "new" takes the place of the
+                // generated one.  But a trait method like `fn new(&self)` is
+                // just an ordinary method.
+                has_new |= meth.sig.ident == "new" &&
+                    is_constructor(&meth.sig, &mock_struct_name);
+                let generics = merge_generics(&self.generics,
+                    &strip_self_bounds(&trait_.generics));
+                let (_, _, cp, scp, rp, _, _, srs, cnt) = gen_mock_method(
+                    &mock_struct_name,
+                    Some(&mod_ident),
+                    &meth.attrs[..],
+                    &meth.vis, &meth.vis,
+                    &meth.borrow().sig,
+                    Some(&trait_.ident),
+                    &generics,
+                    None,
+                    &names);
+                cp.to_tokens(&mut cp_body);
+                scp.to_tokens(&mut static_cp_body);
+                srs.to_tokens(&mut static_reset_body);
+                rp.to_tokens(&mut report_body);
+                cnt.to_tokens(&mut count_body);
+                all_cps.push(cp);
+                all_cps.push(scp);
+            }
         }
         // generate methods on the mock structure itself
         for meth in self.methods.iter() {
+            if self.unmocked.contains(&meth.sig.ident) {
+                gen_unmocked_method(&meth.attrs, AttrTarget::Method,
+                                    &meth.vis, &meth.sig)
+                    .to_tokens(&mut mock_body);
+                continue;
+            }
             has_new |= meth.sig.ident == "new";
-            let (mm, em, cp) = gen_mock_method(&mock_struct_name,
-                                               Some(&mock_mod_ident),
-                                               &meth.attrs[..],
-                                               &meth.vis, &meth.vis,
-                                               &meth.sig, None,
-                                               &self.generics);
+            let inner_path = self.inner.as_ref().map(|ty| quote!(<#ty>));
+            let (mm, em, cp, scp, rp, bm, sm, srs, cnt) = gen_mock_method(
+                &mock_struct_name,
+                Some(&mock_mod_ident),
+                &meth.attrs[..],
+                &meth.vis, &meth.vis,
+                &meth.sig, None,
+                &self.generics,
+                inner_path.as_ref(),
+                &names);
             // For inherent methods, use the same visibility for the mock and
             // expectation method as for the original.
             mm.to_tokens(&mut mock_body);
             em.to_tokens(&mut mock_body);
+            bm.to_tokens(&mut builder_body);
+            sm.to_tokens(&mut store_impls);
             cp.to_tokens(&mut cp_body);
+            scp.to_tokens(&mut static_cp_body);
+            srs.to_tokens(&mut static_reset_body);
+            rp.to_tokens(&mut report_body);
+            cnt.to_tokens(&mut count_body);
+            all_cps.push(cp);
+            all_cps.push(scp);
+        }
+        for c in self.consts.iter() {
+            c.to_tokens(&mut mock_body);
         }
         // generate the mock struct's inherent methods
+        let checkpoint_ident = names.helper(format_ident!("checkpoint"));
         #[cfg(all(not(test),feature = "extra-docs"))]
-        let checkpoint_docs = quote!(
-            #[doc = "Immediately validate all expectations and clear them."]
-        );
+        let checkpoint_docs = {
+            let inner_ds = format!(
+                "Immediately validate all expectations and clear them.{}",
+                renamed_note(&checkpoint_ident));
+            quote!( #[doc = #inner_ds])
+        };
         #[cfg(any(test, not(feature = "extra-docs")))]
         let checkpoint_docs: Option<syn::Attribute> = None;
+        let cp_mut = cps_mut(&cp_body);
         quote!(
             #checkpoint_docs
-            pub fn checkpoint(&mut self) {
+            pub fn #checkpoint_ident(&mut self) {
+                let #cp_mut __mockall_cps = ::mockall::Checkpoints::default();
                 #cp_body
+                __mockall_cps.finish();
+            }
+        ).to_tokens(&mut mock_body);
+        let report_ident = names.helper(format_ident!("report"));
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let report_docs = {
+            let inner_ds = format!(
+                "Summarize the state of every expectation of this object, one per line, without validating or clearing them.{}",
+                renamed_note(&report_ident));
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let report_docs: Option<syn::Attribute> = None;
+        let report_mut = cps_mut(&report_body);
+        quote!(
+            #report_docs
+            pub fn #report_ident(&self) -> String {
+                let #report_mut __mockall_rows = Vec::<String>::new();
+                #report_body
+                __mockall_rows.join("\n")
             }
         ).to_tokens(&mut mock_body);
-        // Add a "new" method if the struct doesn't already have one.  Add it
-        // even if the struct implements a trait that has a new method.  The
-        // trait's new method can still be called as `<MockX as TraitY>::new`
+        let count_ident = names.helper(format_ident!("expectation_count"));
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let count_docs = {
+            let inner_ds = format!(
+                "The number of current expectations of this object, for all of its non-static methods.{}",
+                renamed_note(&count_ident));
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let count_docs: Option<syn::Attribute> = None;
+        let count_mut = cps_mut(&count_body);
+        quote!(
+            #count_docs
+            pub fn #count_ident(&self) -> usize {
+                let #count_mut __mockall_count = 0usize;
+                #count_body
+                __mockall_count
+            }
+        ).to_tokens(&mut mock_body);
+        let record_ident = names.helper(format_ident!("record"));
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let record_docs = {
+            let inner_ds = format!(
+                "Start recording every call to this object's non-static methods, in order, replacing any previous recording.  Returns the new [`Recording`](::mockall::Recording).{}",
+                renamed_note(&record_ident));
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let record_docs: Option<syn::Attribute> = None;
+        quote!(
+            #record_docs
+            pub fn #record_ident(&mut self) -> ::mockall::Recording {
+                let __mockall_rec = ::mockall::Recording::default();
+                self.__mockall_recording = Some(__mockall_rec.clone());
+                __mockall_rec
+            }
+        ).to_tokens(&mut mock_body);
+        let checkpoint_statics_ident = names.checkpoint_helper("statics");
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let checkpoint_statics_docs = {
+            let inner_ds = format!(
+                "Immediately validate all expectations of static methods and clear them.{}",
+                renamed_note(&checkpoint_statics_ident));
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let checkpoint_statics_docs: Option<syn::Attribute> = None;
+        let static_cp_mut = cps_mut(&static_cp_body);
+        quote!(
+            #checkpoint_statics_docs
+            pub fn #checkpoint_statics_ident() {
+                let #static_cp_mut __mockall_cps =
+                    ::mockall::Checkpoints::default();
+                #static_cp_body
+                __mockall_cps.finish();
+            }
+        ).to_tokens(&mut mock_body);
+        let reset_statics_ident = names.helper(format_ident!("reset_statics"));
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let reset_statics_docs = {
+            let inner_ds = format!(
+                "Clear all expectations of static methods, without validating them.{}",
+                renamed_note(&reset_statics_ident));
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let reset_statics_docs: Option<syn::Attribute> = None;
+        quote!(
+            #reset_statics_docs
+            pub fn #reset_statics_ident() {
+                #static_reset_body
+            }
+        ).to_tokens(&mut mock_body);
+        let checkpoint_all_ident = names.checkpoint_helper("all");
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let checkpoint_all_docs = {
+            let inner_ds = format!(
+                "Immediately validate all expectations of both this object and the static methods, and clear them.  Report all failures together.{}",
+                renamed_note(&checkpoint_all_ident));
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let checkpoint_all_docs: Option<syn::Attribute> = None;
+        let all_cps = all_cps.into_iter().collect::<TokenStream>();
+        let all_cp_mut = cps_mut(&all_cps);
+        quote!(
+            #checkpoint_all_docs
+            pub fn #checkpoint_all_ident(&mut self) {
+                let #all_cp_mut __mockall_cps =
+                    ::mockall::Checkpoints::default();
+                #all_cps
+                __mockall_cps.finish();
+            }
+        ).to_tokens(&mut mock_body);
+        // Add a "new" method if the struct doesn't already have one, and none
+        // of its traits has a constructor named "new".  Add it even if a
+        // trait has a non-constructor method named "new".  That method can
+        // still be called with method syntax, or as `<MockX as TraitY>::new`.
+        // Either way, `Default::default()` is always available.
         if !has_new {
             #[cfg(all(not(test),feature = "extra-docs"))]
             let docstr = {
@@ -116,24 +294,167 @@ impl Mock {
             quote!(
                 #docstr
                 pub fn new() -> Self {
-                    Self::default()
+                    <Self as ::std::default::Default>::default()
+                }
+            ).to_tokens(&mut mock_body);
+        }
+        let new_registered_ident = names.helper(
+            format_ident!("new_registered"));
+        quote!(
+            /// Create a new mock object with no expectations, whose
+            /// expectations will be checked by `mockall::verify_all`.
+            pub fn #new_registered_ident() -> Self {
+                let mut __mockall_mock =
+                    <Self as ::std::default::Default>::default();
+                __mockall_mock.__mockall_registered = true;
+                __mockall_mock
+            }
+        ).to_tokens(&mut mock_body);
+        if let Some(inner) = &self.inner {
+            let new_with_inner_ident = names.helper(
+                format_ident!("new_with_inner"));
+            quote!(
+                /// Create a new mock object with no expectations, that
+                /// forwards calls matching no expectation to `inner`.
+                pub fn #new_with_inner_ident(inner: #inner) -> Self {
+                    let mut __mockall_mock =
+                        <Self as ::std::default::Default>::default();
+                    __mockall_mock.__mockall_inner = Some(inner);
+                    __mockall_mock
                 }
             ).to_tokens(&mut mock_body);
         }
+        let with_expectations_ident = names.helper(
+            format_ident!("with_expectations"));
+        quote!(
+            /// Create a new mock object, and set its expectations with a
+            /// closure.
+            pub fn #with_expectations_ident(
+                __mockall_f: impl FnOnce(&mut Self)) -> Self
+            {
+                let mut __mockall_mock =
+                    <Self as ::std::default::Default>::default();
+                __mockall_f(&mut __mockall_mock);
+                __mockall_mock
+            }
+        ).to_tokens(&mut mock_body);
         // generate methods on traits
         let (ig, tg, wc) = self.generics.split_for_impl();
-        quote!(impl #ig #mock_struct_name #tg #wc {#mock_body})
+        quote!(
+            #[allow(missing_docs, non_snake_case)]
+            impl #ig #mock_struct_name #tg #wc {#mock_body}
+        )
             .to_tokens(&mut output);
+        store_impls.to_tokens(&mut output);
         for trait_ in self.traits.iter() {
-            mock_trait_methods(&self.name, &self.generics, &trait_, &self.vis)
+            mock_trait_methods(&self.name, &self.generics, &trait_, &self.vis,
+                               self.ref_impl, &self.unmocked,
+                               self.inner.as_ref(), &names)
                 .to_tokens(&mut output);
+            if self.ref_impls && !self.ref_impl {
+                forward_ref_impls(&self.name, &self.generics, &trait_)
+                    .to_tokens(&mut output);
+            }
         }
+        self.gen_builder(builder_body, !static_cp_body.is_empty(), &names)
+            .to_tokens(&mut output);
         output
     }
+
+    /// Generate the mock struct's builder, which sets expectations as part of
+    /// a single expression.  `body` contains the builder methods for the mock
+    /// struct's inherent methods.
+    fn gen_builder(&self, body: TokenStream, has_statics: bool,
+                   names: &MethodNames) -> TokenStream
+    {
+        let mock_struct_name = gen_mock_ident(&self.name);
+        let builder_name = gen_builder_ident(&self.name);
+        let vis = &self.vis;
+        let (ig, tg, wc) = self.generics.split_for_impl();
+        let builder_ident = names.helper(format_ident!("builder"));
+        let build_ident = names.helper(format_ident!("build"));
+        let bwc_ident = names.helper(format_ident!("build_with_contexts"));
+        let build_msg = format!(
+            "{}: static methods' expectations were set, so use {} instead of {}",
+            builder_name, bwc_ident, build_ident);
+        let build_with_contexts = if has_statics {
+            quote!(
+                /// Create the mock object, along with the `Context` objects
+                /// of any static methods whose expectations were set.  Those
+                /// expectations last until the `Context` objects drop.
+                pub fn #bwc_ident(self)
+                    -> (#mock_struct_name #tg, Vec<Box<dyn ::std::any::Any>>)
+                {
+                    (self.mock, self.contexts)
+                }
+            )
+        } else {
+            TokenStream::new()
+        };
+        quote!(
+            /// Builds a mock object, setting its expectations as part of a
+            /// single expression.
+            #[allow(missing_docs)]
+            #vis struct #builder_name #ig #wc {
+                mock: #mock_struct_name #tg,
+                contexts: Vec<Box<dyn ::std::any::Any>>
+            }
+            #[allow(missing_docs, non_snake_case)]
+            impl #ig #builder_name #tg #wc {
+                #body
+
+                /// Create the mock object
+                pub fn #build_ident(self) -> #mock_struct_name #tg {
+                    assert!(self.contexts.is_empty(), #build_msg);
+                    self.mock
+                }
+
+                #build_with_contexts
+            }
+            #[allow(missing_docs)]
+            impl #ig #mock_struct_name #tg #wc {
+                /// Create a builder, which sets this mock object's
+                /// expectations as part of a single expression.
+                pub fn #builder_ident() -> #builder_name #tg {
+                    #builder_name {
+                        mock: <Self as ::std::default::Default>::default(),
+                        contexts: Vec::new()
+                    }
+                }
+            }
+        )
+    }
+
+    /// Names of all of the mocked methods, both inherent and trait methods
+    fn method_names(&self) -> MethodNames {
+        let inherent = self.methods.iter().map(|meth| meth.sig.ident.to_string());
+        let trait_methods = self.traits.iter()
+            .flat_map(|trait_| trait_.items.iter())
+            .filter_map(|item| match item {
+                syn::TraitItem::Method(m) => Some(m.sig.ident.to_string()),
+                _ => None
+            });
+        let mut names = MethodNames::default();
+        for name in inherent.chain(trait_methods) {
+            if !names.all.insert(name.clone()) {
+                names.shared.insert(name);
+            }
+        }
+        names
+    }
 }
 
 impl Parse for Mock {
     fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut ref_impls = false;
+        for attr in input.call(syn::Attribute::parse_outer)? {
+            if attr.path.is_ident("ref_impls") && attr.tokens.is_empty() {
+                ref_impls = true;
+            } else {
+                return Err(syn::Error::new(attr.span(),
+                    "Unsupported attribute for a mock struct"));
+            }
+        }
         let vis: syn::Visibility = input.parse()?;
         let name: syn::Ident = input.parse()?;
         let mut generics: syn::Generics = input.parse()?;
@@ -157,25 +478,15 @@ impl Parse for Mock {
 
         let mut traits = Vec::new();
         while !input.is_empty() {
-            let trait_: syn::ItemTrait = input.parse()?;
+            let mut trait_: syn::ItemTrait = input.parse()?;
+            strip_sized_provided_methods(&mut trait_);
             traits.push(trait_);
         }
 
-        Ok(Mock{vis, name, generics, methods, traits})
-    }
-}
-
-fn format_attrs(attrs: &[syn::Attribute]) -> TokenStream {
-    let mut out = TokenStream::new();
-    for attr in attrs {
-        if attr.path.get_ident().map(|i| i == "doc").unwrap_or(false) {
-            // Discard doc attributes from the mock object.  They cause a bunch
-            // of warnings.
-            continue;
-        }
-        attr.to_tokens(&mut out);
+        Ok(Mock{vis, name, generics, methods, consts: Vec::new(), traits,
+                derives: Vec::new(), ref_impl: false, ref_impls,
+                unmocked: Vec::new(), inner: None})
     }
-    out
 }
 
 /// Generate a mock method and its expectation method
@@ -194,6 +505,12 @@ fn format_attrs(attrs: &[syn::Attribute]) -> TokenStream {
 ///                     object, if any.
 /// * `generics`:       Generics of the method's parent trait or structure,
 ///                     _not_ the method itself.
+/// * `inner_path`:     Path to the real method's type, like
+///                     `<Foo as Bar>`, if calls that match no expectation
+///                     should be forwarded to the mock struct's inner object.
+/// * `names`:          Names of all of the mock struct's mocked methods, which
+///                     generated helper methods must not reuse.
+#[allow(clippy::too_many_arguments)]
 fn gen_mock_method(mock_struct_name: &syn::Ident,
                    mod_ident: Option<&syn::Ident>,
                    meth_attrs: &[syn::Attribute],
@@ -201,35 +518,74 @@ fn gen_mock_method(mock_struct_name: &syn::Ident,
                    expect_vis: &syn::Visibility,
                    sig: &syn::Signature,
                    sub: Option<&syn::Ident>,
-                   generics: &syn::Generics)
-    -> (TokenStream, TokenStream, TokenStream)
+                   generics: &syn::Generics,
+                   inner_path: Option<&TokenStream>,
+                   names: &MethodNames)
+    -> (TokenStream, TokenStream, TokenStream, TokenStream, TokenStream,
+        TokenStream, TokenStream, TokenStream, TokenStream)
 {
     assert!(sig.variadic.is_none(),
         "MockAll does not yet support variadic functions");
     let mut mock_output = TokenStream::new();
     let mut expect_output = TokenStream::new();
     let mut cp_output = TokenStream::new();
-    let constness = sig.constness;
+    let mut static_cp_output = TokenStream::new();
+    let mut static_reset_output = TokenStream::new();
+    let mut report_output = TokenStream::new();
+    let mut count_output = TokenStream::new();
+    let mut builder_output = TokenStream::new();
+    let mut store_output = TokenStream::new();
     let unsafety = sig.unsafety;
     let asyncness = sig.asyncness;
     let abi = &sig.abi;
     let fn_token = &sig.fn_token;
     let ident = &sig.ident;
+    let stem = names.stem(ident, sub);
     let meth_types = method_types(sig, Some(generics));
     let merged_g = merge_generics(&generics, &meth_types.expectation_generics);
     let inputs = &meth_types.inputs;
     let output = &meth_types.output;
-    let attrs = format_attrs(meth_attrs);
+    // Neither must_use nor deprecated is allowed on a trait impl's methods.
+    // The trait's own attributes will still apply.
+    let attrs = if sub.is_some() {
+        format_attrs(meth_attrs, AttrTarget::TraitMethod)
+    } else {
+        format_attrs(meth_attrs, AttrTarget::Method)
+    };
+    // Generated code that calls a deprecated method, or its expect_* method,
+    // mustn't warn about it.  Only the user's own calls should.
+    let allow_deprecated = if meth_attrs.iter()
+        .any(|attr| attr_is(attr, &["deprecated"]))
+    {
+        quote!(#[allow(deprecated)])
+    } else {
+        TokenStream::new()
+    };
+    let helper_attrs = format_attrs(meth_attrs, AttrTarget::Helper);
+    let other_attrs = format_attrs(meth_attrs, AttrTarget::Other);
 
-    // First the mock method
+    // First the mock method.  It can't be const, even if the original is,
+    // because it must lock its expectations.
     {
-        let (ig, _, wc) = sig.generics.split_for_impl();
-        quote!(#attrs #meth_vis #constness #unsafety #asyncness #abi
-               #fn_token #ident #ig (#inputs) #output #wc)
+        // Print the generics directly, rather than with split_for_impl, so
+        // that any default type parameters are preserved.
+        let g = &sig.generics;
+        let wc = &g.where_clause;
+        let decl_output = if returns_never(&sig.output) {
+            &sig.output
+        } else {
+            output
+        };
+        let track_caller = gen_track_caller(sig, meth_attrs);
+        let const_note = gen_const_note(sig);
+        quote!(#attrs #const_note #allow_deprecated #track_caller #meth_vis
+               #unsafety #asyncness #abi #fn_token #ident #g (#inputs)
+               #decl_output #wc)
             .to_tokens(&mut mock_output);
     }
 
     let expectation = &meth_types.expectation;
+    let expectations = &meth_types.expectations;
     let call = &meth_types.call;
     let call_exprs = &meth_types.call_exprs;
     let mut args = Vec::new();
@@ -250,7 +606,8 @@ fn gen_mock_method(mock_struct_name: &syn::Ident,
         }
     }
 
-    let (ig, _, wc) = meth_types.expectation_generics.split_for_impl();
+    let eg = &meth_types.expectation_generics;
+    let wc = &eg.where_clause;
     let tbf_g = if meth_types.is_static || meth_types.is_expectation_generic {
         // For generic and static methods only, the trait's generic parameters
         // become generic parameters of the method.
@@ -261,96 +618,446 @@ fn gen_mock_method(mock_struct_name: &syn::Ident,
     let (tbf_tg, _, _) = split_lifetimes(tbf_g, &inputs, &sig.output);
     let (_, tg, _) = tbf_tg.split_for_impl();
     let call_turbofish = tg.as_turbofish();
-    let no_match_msg = format!("{}::{}: No matching expectation found",
-        mock_struct_name, ident);
-    if meth_types.is_static {
+    let mock_name = mock_struct_name.to_string();
+    let meth_name = ident.to_string();
+    let output_ty = match output {
+        syn::ReturnType::Default => quote!(()),
+        syn::ReturnType::Type(_, ty) => quote!(#ty)
+    };
+    let no_match = gen_no_match(&mock_name, &meth_name, output_ty.clone());
+    // A fallible call to a method that returns Self returns the mock itself
+    let no_match_self = gen_no_match(&mock_name, &meth_name,
+                                     quote!(Option<#output_ty>));
+    // Match instead of using unwrap_or_else, so the expectation's output may
+    // be coerced to the method's, like &String to &str.
+    let body = if meth_types.is_static {
         quote!({
-            {
-                let __mockall_guard = #mod_ident::#ident::EXPECTATIONS
-                    .lock().unwrap();
+            match {
+                let __mockall_guard = ::mockall::lock_static(
+                    &#mod_ident::#ident::EXPECTATIONS);
                 /*
                  * TODO: catch panics, then gracefully release the mutex so it
                  * won't be poisoned.  This requires bounding any generic
                  * parameters with UnwindSafe
                  */
                 /* std::panic::catch_unwind(|| */
-                __mockall_guard.#call#call_turbofish(#call_exprs)
+                // Release the lock before running the return function, in
+                // case it calls another static method.
+                #mod_ident::#expectations::call_unlocked#call_turbofish(
+                    __mockall_guard, #call_exprs)
                 /*)*/
-            }.expect(#no_match_msg)
+            } {
+                Some(__mockall_o) => __mockall_o,
+                None => #no_match
+            }
             /*}.unwrap()*/
         })
+    } else if meth_types.returns_self {
+        quote!({
+            // The expectation might return the mock object itself, moving its
+            // expectations along with it.
+            let __mockall_o = match #expect_obj_name
+                .call_self#call_turbofish(#call_exprs)
+            {
+                Some(__mockall_o) => __mockall_o,
+                None => #no_match_self
+            };
+            __mockall_o.unwrap_or(self)
+        })
+    } else {
+        quote!({
+            match #expect_obj_name.#call#call_turbofish(#call_exprs) {
+                Some(__mockall_o) => __mockall_o,
+                None => #no_match
+            }
+        })
+    };
+    // Forward calls that match no expectation to the inner object, if there
+    // is one.  Static methods need no object.
+    let body = match inner_path.filter(|_| is_delegable(sig, &meth_types)) {
+        Some(path) => {
+            let preds = meth_types.expectation_inputs.iter()
+                .filter_map(|fa| match fa {
+                    syn::FnArg::Typed(pt) => {
+                        let pat = &pt.pat;
+                        Some(match &*pt.ty {
+                            syn::Type::Reference(_) => quote!(#pat),
+                            _ => quote!(&#pat)
+                        })
+                    },
+                    syn::FnArg::Receiver(_) => None
+                });
+            if meth_types.is_static {
+                quote!({
+                    if !::mockall::lock_static(
+                            &#mod_ident::#ident::EXPECTATIONS)
+                        .has_match#call_turbofish(#(#preds),*)
+                    {
+                        #path::#ident(#(#args),*)
+                    } else #body
+                })
+            } else {
+                let as_ref = match sig.receiver() {
+                    Some(syn::FnArg::Receiver(r)) if r.mutability.is_some() =>
+                        quote!(as_mut),
+                    _ => quote!(as_ref)
+                };
+                quote!({
+                    if self.__mockall_inner.is_some() &&
+                        !#expect_obj_name.has_match#call_turbofish(#(#preds),*)
+                    {
+                        #path::#ident(
+                            self.__mockall_inner.#as_ref().unwrap(),
+                            #(#args),*)
+                    } else #body
+                })
+            }
+        },
+        None => body
+    };
+    // Record calls to non-static methods, if the mock is recording.  The
+    // arguments must be formatted before the call consumes them.
+    let body = if meth_types.is_static {
+        body
     } else {
+        let method_name = format!("{}::{}", mock_struct_name, ident);
+        let arg_strs = args.iter().map(|a| gen_debug_string(quote!(&#a)));
+        let ret_str = gen_debug_string(quote!(&__mockall_r));
+        quote!({
+            let __mockall_rc = self.__mockall_recording.as_ref()
+                .map(|__mockall_rec|
+                    __mockall_rec.start(#method_name, vec![#(#arg_strs),*])
+                );
+            let __mockall_r: #output_ty = #body;
+            if let Some(__mockall_rc) = __mockall_rc {
+                __mockall_rc.finish(#ret_str);
+            }
+            __mockall_r
+        })
+    };
+    if returns_never(&sig.output) {
         quote!({
-            #expect_obj_name.#call#call_turbofish(#call_exprs)
-            .expect(#no_match_msg)
+            #body;
+            ::mockall::never_returned(#mock_name, #meth_name)
         })
+    } else {
+        body
     }.to_tokens(&mut mock_output);
 
     // Then the expectation method
     if meth_types.is_static {
+        let context_ident = names.helper(format_ident!("{}_context", stem));
         #[cfg(all(not(test),feature = "extra-docs"))]
         let docstr = {
-            let inner_ds = format!("Create a [`Context`]({}/{}/struct.Context.html) for mocking the `{}` method",
-                quote!(#mod_ident), ident, ident);
+            let inner_ds = format!("Create a [`Context`]({}/{}/struct.Context.html) for mocking the `{}` method{}",
+                quote!(#mod_ident), ident, ident,
+                renamed_note(&context_ident));
             quote!( #[doc = #inner_ds])
         };
         #[cfg(any(test, not(feature = "extra-docs")))]
         let docstr: Option<syn::Attribute> = None;
-        let context_ident = format_ident!("{}_context", ident);
         let (_, ctx_tg, _) = generics.split_for_impl();
-        quote!(#attrs #docstr #expect_vis fn #context_ident()
+        // A constructor can expect one call, returning a preconfigured mock
+        let returns_mock = match output {
+            syn::ReturnType::Type(_, ty) =>
+                crate::is_mock_type(ty, mock_struct_name),
+            syn::ReturnType::Default => false
+        };
+        let returning = if returns_mock && !meth_types.is_expectation_generic {
+            let returning_ident = names.helper(
+                format_ident!("expect_{}_returning", stem));
+            #[cfg(all(not(test),feature = "extra-docs"))]
+            let docstr = {
+                let inner_ds = format!("Expect exactly one call to the `{}` method, which will return `mock`.  The expectation lasts as long as the returned [`Context`]({}/{}/struct.Context.html){}",
+                    ident, quote!(#mod_ident), ident,
+                    renamed_note(&returning_ident));
+                quote!( #[doc = #inner_ds])
+            };
+            #[cfg(any(test, not(feature = "extra-docs")))]
+            let docstr: Option<syn::Attribute> = None;
+            quote!(#helper_attrs #docstr
+                #expect_vis fn #returning_ident<MockallM>(mock: MockallM)
+                    -> #mod_ident::#ident::Context #ctx_tg
+                    where MockallM: Into<#output_ty> + Send + 'static
+                {
+                    let __mockall_ctx = Self::#context_ident();
+                    __mockall_ctx.expect().return_once_mock(mock);
+                    __mockall_ctx
+                }
+            )
+        } else {
+            TokenStream::new()
+        };
+        quote!(#helper_attrs #docstr #expect_vis fn #context_ident()
                -> #mod_ident::#ident::Context #ctx_tg
             {
                 #mod_ident::#ident::Context::default()
             }
+            #returning
         )
     } else {
+        let expect_ident = names.helper(format_ident!("expect_{}", stem));
         #[cfg(all(not(test),feature = "extra-docs"))]
         let docstr = {
-            let inner_ds = format!("Create an [`Expectation`]({}/{}/struct.Expectation.html) for mocking the `{}` method",
-                quote!(#mod_ident), ident, ident);
+            let inner_ds = format!("Create an [`Expectation`]({}/{}/struct.Expectation.html) for mocking the `{}` method{}",
+                quote!(#mod_ident), ident, ident,
+                renamed_note(&expect_ident));
             quote!( #[doc = #inner_ds])
         };
         #[cfg(any(test, not(feature = "extra-docs")))]
         let docstr: Option<syn::Attribute> = None;
-        let expect_ident = format_ident!("expect_{}", ident);
 
         #[cfg(not(feature = "nightly_derive"))]
-        let must_use = quote!(#[must_use =
+        let must_use = if crate::returns_unit(output) {
+            quote!()
+        } else {
+            quote!(#[must_use =
                 "Must set return value when not using the \"nightly\" feature"
-            ]);
+            ])
+        };
         #[cfg(feature = "nightly_derive")]
         let must_use = quote!();
 
         quote!(
             #must_use
-            #attrs #docstr #expect_vis fn #expect_ident #ig(&mut self)
+            #[track_caller]
+            #helper_attrs #docstr #expect_vis fn #expect_ident #eg(&mut self)
                -> &mut #mod_ident::#expectation
                #wc
             {
-                #expect_obj_name.expect#call_turbofish()
+                let __mockall_registered = self.__mockall_registered;
+                let __mockall_e = #expect_obj_name.expect#call_turbofish();
+                if __mockall_registered {
+                    __mockall_e.register();
+                }
+                __mockall_e
+            }
+        )
+    }.to_tokens(&mut expect_output);
+
+    // Generic methods may also have expectations for any type
+    if !meth_types.is_static && meth_types.is_expectation_generic &&
+        crate::is_dyn_expectable(&meth_types.expectation_inputs, output,
+                                 &meth_types.expectation_generics)
+    {
+        let expect_any_ident = names.helper(
+            format_ident!("expect_{}_any", stem));
+        quote!(
+            /// Create a [`DynExpectation`](::mockall::DynExpectation) that
+            /// applies to every instantiation of the method's generic
+            /// parameters that has no expectations of its own.
+            #[track_caller]
+            #helper_attrs #expect_vis fn #expect_any_ident(&mut self)
+               -> &mut ::mockall::DynExpectation
+            {
+                #expect_obj_name.expect_any()
+            }
+        ).to_tokens(&mut expect_output);
+    }
+
+    // Non-static methods with arguments also get a shorthand for expect_*
+    // followed by with.  But not if it would collide with the expect_*
+    // method of another mocked method, like `foo_with`, nor if the matchers
+    // would need to be valid for all of the method's lifetimes.
+    let with_stem = format!("{}_with", stem);
+    if !meth_types.is_static && !names.all.contains(&with_stem) {
+        let no_attrs = TokenStream::new();
+        let with_exp = Expectation::new(&no_attrs,
+            &meth_types.expectation_inputs, &meth_types.expect_obj,
+            Some(generics), &meth_types.expectation_generics, ident, ident,
+            Some(mock_struct_name), output, expect_vis, 0);
+        let argnames = with_exp.argnames();
+        if !argnames.is_empty() && !with_exp.has_hrtb() {
+            let (with_generics, with_args) = with_exp.with_signature();
+            let expect_ident = names.helper(format_ident!("expect_{}", stem));
+            let expect_with_ident = names.helper(
+                format_ident!("expect_{}", with_stem));
+            // Defaults aren't allowed before the matchers' type parameters
+            let egp = eg.params.iter().cloned().map(|mut gp| {
+                if let syn::GenericParam::Type(tp) = &mut gp {
+                    tp.eq_token = None;
+                    tp.default = None;
+                }
+                gp
+            });
+            #[cfg(not(feature = "nightly_derive"))]
+            let must_use = if crate::returns_unit(output) {
+                quote!()
+            } else {
+                quote!(#[must_use =
+                    "Must set return value when not using the \"nightly\" feature"
+                ])
+            };
+            #[cfg(feature = "nightly_derive")]
+            let must_use = quote!();
+            quote!(
+                /// Create an expectation for mocking the method, with one
+                /// matcher per argument.  Shorthand for `expect_*` followed
+                /// by `with`.
+                #must_use
+                #[track_caller]
+                #helper_attrs #allow_deprecated
+                #expect_vis fn #expect_with_ident
+                    <#(#egp,)* #with_generics>
+                    (&mut self, #with_args)
+                   -> &mut #mod_ident::#expectation
+                   #wc
+                {
+                    self.#expect_ident().with(#(#argnames),*)
+                }
+            ).to_tokens(&mut expect_output);
+        }
+    }
+
+    // Then the method's own checkpoint method
+    let checkpoint_ident = names.helper(format_ident!("checkpoint_{}", stem));
+    if meth_types.is_static {
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let docstr = {
+            let inner_ds = format!("Validate all expectations for the static `{}` method and clear them", ident);
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let docstr: Option<syn::Attribute> = None;
+        let (_, ctx_tg, _) = generics.split_for_impl();
+        quote!(#other_attrs #docstr #expect_vis fn #checkpoint_ident() {
+                ::mockall::Checkpoints::from(
+                    <#mod_ident::#ident::Context #ctx_tg>::do_checkpoint()
+                ).finish()
+            }
+        )
+    } else {
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let docstr = {
+            let inner_ds = format!("Validate all expectations for the `{}` method and clear them", ident);
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let docstr: Option<syn::Attribute> = None;
+        quote!(#other_attrs #docstr #expect_vis fn #checkpoint_ident(&mut self) {
+                ::mockall::Checkpoints::from(#expect_obj_name.checkpoint())
+                    .finish()
+            }
+        )
+    }.to_tokens(&mut expect_output);
+
+    // And one to count its expectations
+    let count_ident = names.helper(
+        format_ident!("expectation_count_{}", stem));
+    if meth_types.is_static {
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let docstr = {
+            let inner_ds = format!("The number of current expectations for the static `{}` method", ident);
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let docstr: Option<syn::Attribute> = None;
+        let (_, ctx_tg, _) = generics.split_for_impl();
+        quote!(#other_attrs #docstr #expect_vis fn #count_ident() -> usize {
+                <#mod_ident::#ident::Context #ctx_tg>::do_len()
+            }
+        )
+    } else {
+        #[cfg(all(not(test),feature = "extra-docs"))]
+        let docstr = {
+            let inner_ds = format!("The number of current expectations for the `{}` method", ident);
+            quote!( #[doc = #inner_ds])
+        };
+        #[cfg(any(test, not(feature = "extra-docs")))]
+        let docstr: Option<syn::Attribute> = None;
+        quote!(#other_attrs #docstr #expect_vis fn #count_ident(&self) -> usize {
+                #expect_obj_name.len()
             }
         )
     }.to_tokens(&mut expect_output);
 
-    // Finally this method's contribution to the checkpoint method
+    // Finally this method's contribution to the checkpoint and report
+    // methods.  Don't checkpoint static methods there.  They get checkpointed
+    // by their context objects, or by checkpoint_statics.  Either way, the
+    // failures get collected so they can all be reported together, along
+    // with the state of every expectation that was checked.
+    if meth_types.is_static {
+        let (_, ctx_tg, _) = generics.split_for_impl();
+        quote!(#other_attrs {
+            __mockall_cps.report(
+                <#mod_ident::#ident::Context #ctx_tg>::do_report());
+            __mockall_cps.add(
+                <#mod_ident::#ident::Context #ctx_tg>::do_checkpoint());
+        }).to_tokens(&mut static_cp_output);
+        quote!(#other_attrs { #mod_ident::#ident::reset(); })
+            .to_tokens(&mut static_reset_output);
+    } else {
+        quote!(#other_attrs {
+            __mockall_cps.report(#expect_obj_name.report());
+            __mockall_cps.add(#expect_obj_name.checkpoint());
+        }).to_tokens(&mut cp_output);
+        quote!(#other_attrs {
+            __mockall_rows.extend(#expect_obj_name.report());
+        }).to_tokens(&mut report_output);
+        quote!(#other_attrs {
+            __mockall_count += #expect_obj_name.len();
+        }).to_tokens(&mut count_output);
+    }
+
+    // And a method for the builder, named just like the mocked method's stem
+    let builder_meth_ident = format_ident!("{}", stem);
     if meth_types.is_static {
-        // Don't checkpoint static methods.  They get checkpointed by their
-        // context objects instead.
-        quote!()
+        let (_, ctx_tg, _) = generics.split_for_impl();
+        let ctx = quote!(#mod_ident::#ident::Context #ctx_tg);
+        quote!(#helper_attrs #expect_vis fn #builder_meth_ident(mut self,
+                __mockall_f: impl FnOnce(&#ctx)) -> Self
+            where #ctx: 'static
+        {
+            let __mockall_ctx = <#ctx>::default();
+            __mockall_f(&__mockall_ctx);
+            self.contexts.push(Box::new(__mockall_ctx));
+            self
+        })
     } else {
-        quote!(#attrs { #expect_obj_name.checkpoint(); })
-    }.to_tokens(&mut cp_output);
+        let expect_ident = names.helper(format_ident!("expect_{}", stem));
+        quote!(#helper_attrs #allow_deprecated #[track_caller]
+            #expect_vis fn #builder_meth_ident #eg(mut self,
+                __mockall_f: impl FnOnce(&mut #mod_ident::#expectation)) -> Self
+            #wc
+        {
+            __mockall_f(self.mock.#expect_ident());
+            self
+        })
+    }.to_tokens(&mut builder_output);
 
-    (mock_output, expect_output, cp_output)
+    // And a way for ExpectationRef to find this method's expectations
+    if !meth_types.is_static {
+        let (ig, _, wc) = merged_g.split_for_impl();
+        let (_, tg, _) = generics.split_for_impl();
+        let expectation_ty = quote!(#mod_ident::#expectation);
+        quote!(
+            #other_attrs
+            impl #ig ::mockall::ExpectationStore<#expectation_ty>
+                for #mock_struct_name #tg #wc
+            {
+                fn expectation_by_id(&mut self, __mockall_id: usize)
+                    -> Option<&mut #expectation_ty>
+                {
+                    #expect_obj_name.find#call_turbofish(__mockall_id)
+                }
+            }
+        ).to_tokens(&mut store_output);
+    }
+
+    (mock_output, expect_output, cp_output, static_cp_output, report_output,
+     builder_output, store_output, static_reset_output, count_output)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn gen_struct<T>(mock_ident: &syn::Ident,
                  vis: &syn::Visibility,
                  ident: &syn::Ident,
                  generics: &syn::Generics,
                  subs: &[(String, syn::Generics)],
-                 methods: &[T]) -> TokenStream
+                 methods: &[T],
+                 derives: &[syn::Path],
+                 top: bool,
+                 inner: Option<&syn::Type>) -> TokenStream
     where T: Borrow<syn::ImplItemMethod>
 {
     let mut output = TokenStream::new();
@@ -370,7 +1077,7 @@ fn gen_struct<T>(mock_ident: &syn::Ident,
             .to_tokens(&mut default_body)
     }
     for meth in methods.iter() {
-        let attrs = format_attrs(&meth.borrow().attrs);
+        let attrs = format_attrs(&meth.borrow().attrs, AttrTarget::Other);
         let method_ident = &meth.borrow().sig.ident;
         let meth_types = method_types(&meth.borrow().sig, Some(generics));
         let expect_obj = &meth_types.expect_obj;
@@ -402,6 +1109,23 @@ fn gen_struct<T>(mock_ident: &syn::Ident,
         }
     }
 
+    // The top-level struct holds the recording of its calls, if any, and
+    // whether its expectations should be registered for verify_all
+    if top {
+        quote!(
+            __mockall_recording: Option<::mockall::Recording>,
+            __mockall_registered: bool,
+        ).to_tokens(&mut body);
+        quote!(
+            __mockall_recording: None,
+            __mockall_registered: false,
+        ).to_tokens(&mut default_body);
+    }
+    if let Some(inner) = inner {
+        quote!(__mockall_inner: Option<#inner>,).to_tokens(&mut body);
+        quote!(__mockall_inner: None,).to_tokens(&mut default_body);
+    }
+
     // Make PhantomData fields, if necessary
     for (count, param) in generics.params.iter().enumerate() {
         let phident = format_ident!("_t{}", count);
@@ -416,8 +1140,10 @@ fn gen_struct<T>(mock_ident: &syn::Ident,
                     .to_tokens(&mut body);
             },
             syn::GenericParam::Type(tp) => {
+                // PhantomData<fn() -> T> is Send and Sync even if T isn't, so
+                // the mock can satisfy Send and Sync supertraits.
                 let ty = &tp.ident;
-                quote!(#phident: ::std::marker::PhantomData<#ty>,)
+                quote!(#phident: ::std::marker::PhantomData<fn() -> #ty>,)
                     .to_tokens(&mut body);
             },
             syn::GenericParam::Const(_) => {
@@ -429,14 +1155,28 @@ fn gen_struct<T>(mock_ident: &syn::Ident,
             .to_tokens(&mut default_body);
     }
     let (ig, tg, wc) = generics.split_for_impl();
+    let derive_attr = if derives.is_empty() {
+        TokenStream::new()
+    } else {
+        quote!(#[derive(#(#derives),*)])
+    };
+    let doc_hidden = doc_hidden();
+    // Only the top-level struct is part of the mock's API
+    let struct_doc_hidden = if top {
+        None
+    } else {
+        Some(quote!(#[doc(hidden)]))
+    };
     quote!(
-        #[allow(non_snake_case)]
+        #[allow(missing_docs, non_snake_case)]
+        #doc_hidden
         pub mod #mod_ident {
             use super::*;
             #mod_body
         }
-        #[allow(non_camel_case_types)]
-        #[allow(non_snake_case)]
+        #[allow(missing_docs, non_camel_case_types, non_snake_case)]
+        #struct_doc_hidden
+        #derive_attr
         #vis struct #ident #ig #wc {
             #body
         }
@@ -462,25 +1202,61 @@ fn gen_struct<T>(mock_ident: &syn::Ident,
 ///                         generics from the Trait
 /// * `item`:               The trait whose methods are being mocked
 /// * `vis`:                Visibility of the struct
+/// * `ref_impl`:           Implement the trait for a reference to the Mock
+///                         struct, rather than for the struct itself
+/// * `unmocked`:           Methods that get no expectations
+/// * `inner`:              Real type to forward unmatched calls to, if any
+/// * `names`:              Names of all of the mock struct's mocked methods
+#[allow(clippy::too_many_arguments)]
 fn mock_trait_methods(struct_ident: &syn::Ident,
                       struct_generics: &syn::Generics,
                       item: &syn::ItemTrait,
-                      vis: &syn::Visibility) -> TokenStream
+                      vis: &syn::Visibility,
+                      ref_impl: bool,
+                      unmocked: &[syn::Ident],
+                      inner: Option<&syn::Type>,
+                      names: &MethodNames) -> TokenStream
 {
     let mut output = TokenStream::new();
     let mut mock_body = TokenStream::new();
     let mut expect_body = TokenStream::new();
+    let mut builder_body = TokenStream::new();
+    let mut store_impls = TokenStream::new();
     let mock_ident = gen_mock_ident(&struct_ident);
 
     for trait_item in item.items.iter() {
         match trait_item {
-            syn::TraitItem::Const(_) => {
-                // Nothing to implement
+            syn::TraitItem::Const(c) => {
+                if let Some((eq_token, expr)) = &c.default {
+                    // Copy the value supplied by the impl block
+                    let attrs = format_attrs(&c.attrs, AttrTarget::Other);
+                    let ident = &c.ident;
+                    let ty = &c.ty;
+                    quote!(#attrs const #ident: #ty #eq_token #expr;)
+                        .to_tokens(&mut mock_body);
+                } else {
+                    compile_error(c.span(),
+                        "Associated constants must have a value to be mocked.");
+                }
+            },
+            syn::TraitItem::Method(meth)
+                if unmocked.contains(&meth.sig.ident) =>
+            {
+                gen_unmocked_method(&meth.attrs, AttrTarget::TraitMethod,
+                                    &syn::Visibility::Inherited, &meth.sig)
+                    .to_tokens(&mut mock_body);
             },
             syn::TraitItem::Method(meth) => {
                 let mod_ident = gen_mod_ident(&struct_ident, Some(&item.ident));
-                let generics = merge_generics(&struct_generics, &item.generics);
-                let (mock_meth, expect_meth, _cp) = gen_mock_method(
+                let generics = merge_generics(&struct_generics,
+                    &strip_self_bounds(&item.generics));
+                let trait_ident = &item.ident;
+                let (_, t_tg, _) = item.generics.split_for_impl();
+                let inner_path = inner
+                    .map(|ty| quote!(<#ty as #trait_ident #t_tg>));
+                let (mock_meth, expect_meth, _cp, _scp, _rp, builder_meth,
+                     store_impl, _srs, _cnt) =
+                    gen_mock_method(
                     &mock_ident,
                     Some(&mod_ident),
                     &meth.attrs[..],
@@ -488,12 +1264,16 @@ fn mock_trait_methods(struct_ident: &syn::Ident,
                     vis,
                     &meth.sig,
                     Some(&item.ident),
-                    &generics
+                    &generics,
+                    inner_path.as_ref(),
+                    names
                 );
                 // trait methods must have inherited visibility.  Expectation
                 // methods should have public, for lack of any clearer option.
                 mock_meth.to_tokens(&mut mock_body);
                 expect_meth.to_tokens(&mut expect_body);
+                builder_meth.to_tokens(&mut builder_body);
+                store_impl.to_tokens(&mut store_impls);
             },
             syn::TraitItem::Type(ty) => {
                 if !ty.generics.params.is_empty() {
@@ -527,20 +1307,316 @@ fn mock_trait_methods(struct_ident: &syn::Ident,
     let ident = &item.ident;
     let (s_ig, s_sg, s_wc) = struct_generics.split_for_impl();
     let (_t_ig, t_tg, _t_wc) = item.generics.split_for_impl();
+    let ref_token = if ref_impl { Some(quote!(&)) } else { None };
     quote!(impl #s_ig #ident #t_tg
-           for #mock_ident #s_sg #s_wc {
+           for #ref_token #mock_ident #s_sg #s_wc {
         #mock_body
     }).to_tokens(&mut output);
 
     // Put all expect methods in a separate impl block.  This is necessary when
     // mocking a trait impl, where we can't add any new methods
-    quote!(impl #s_ig #mock_ident #s_sg #s_wc {
-        #expect_body
-    }).to_tokens(&mut output);
+    quote!(
+        #[allow(missing_docs, non_snake_case)]
+        impl #s_ig #mock_ident #s_sg #s_wc {
+            #expect_body
+        }
+    ).to_tokens(&mut output);
+    let builder_ident = gen_builder_ident(&struct_ident);
+    quote!(
+        #[allow(missing_docs, non_snake_case)]
+        impl #s_ig #builder_ident #s_sg #s_wc {
+            #builder_body
+        }
+    ).to_tokens(&mut output);
+    store_impls.to_tokens(&mut output);
 
     output
 }
 
+/// Does this type or signature name `Self` as a type, other than as a prefix of
+/// an associated type like `Self::Item`?
+fn has_bare_self(ts: TokenStream) -> bool {
+    let mut tts = ts.into_iter().peekable();
+    while let Some(tt) = tts.next() {
+        match tt {
+            TokenTree::Ident(i) if i == "Self" => {
+                match tts.peek() {
+                    Some(TokenTree::Punct(p)) if p.as_char() == ':' => (),
+                    _ => return true
+                }
+            },
+            TokenTree::Group(g) if has_bare_self(g.stream()) => return true,
+            _ => ()
+        }
+    }
+    false
+}
+
+/// Can a call to this method be forwarded to a real object when it matches no
+/// expectation?  Only if the method takes `&self`, `&mut self`, or no receiver
+/// at all, doesn't otherwise mention `Self`, and its arguments need no
+/// conversion to be matched.
+fn is_delegable(sig: &syn::Signature, meth_types: &MethodTypes) -> bool {
+    let receiver_ok = match sig.receiver() {
+        Some(syn::FnArg::Receiver(r)) => r.reference.is_some(),
+        Some(syn::FnArg::Typed(_)) => false,
+        None => true
+    };
+    let args_ok = sig.inputs.iter().zip(meth_types.expectation_inputs.iter())
+        .all(|(orig, exp)| match (orig, exp) {
+            (syn::FnArg::Typed(opt), syn::FnArg::Typed(ept)) =>
+                opt.ty == ept.ty && !has_bare_self(opt.ty.to_token_stream()),
+            (syn::FnArg::Receiver(_), syn::FnArg::Receiver(_)) => true,
+            _ => false
+        });
+    let output_ok = match &sig.output {
+        syn::ReturnType::Default => true,
+        syn::ReturnType::Type(_, ty) =>
+            !matches!(**ty, syn::Type::ImplTrait(_)) &&
+            !has_bare_self(ty.to_token_stream())
+    };
+    receiver_ok && args_ok && output_ok && sig.asyncness.is_none()
+}
+
+/// Generate a method that has no expectations, for partial mocking.  It just
+/// panics.
+fn gen_unmocked_method(attrs: &[syn::Attribute], target: AttrTarget,
+                       vis: &syn::Visibility, sig: &syn::Signature)
+    -> TokenStream
+{
+    let attrs = format_attrs(attrs, target);
+    let msg = format!("not mocked: {}", sig.ident);
+    quote!(
+        #attrs
+        #[allow(unused_variables)]
+        #vis #sig {
+            unimplemented!(#msg)
+        }
+    )
+}
+
+/// Implement a mocked trait for `&MockFoo` and `&mut MockFoo` too, forwarding
+/// every item to `MockFoo`'s own implementation.  `&MockFoo` only gets one if
+/// all of the trait's methods take `&self`, and `&mut MockFoo` only if they all
+/// take `&self` or `&mut self`.  Static methods don't matter either way.
+///
+/// # Parameters
+///
+/// * `struct_ident`:       Name of the structure to mock
+/// * `struct_generics`:    Generic fields of the Mock struct
+/// * `item`:               The trait whose methods are being mocked
+fn forward_ref_impls(struct_ident: &syn::Ident,
+                     struct_generics: &syn::Generics,
+                     item: &syn::ItemTrait) -> TokenStream
+{
+    let mock_ident = gen_mock_ident(&struct_ident);
+    let ident = &item.ident;
+    let (_, s_tg, s_wc) = struct_generics.split_for_impl();
+    let (_, t_tg, _) = item.generics.split_for_impl();
+    let mock_ty = quote!(#mock_ident #s_tg);
+    let trait_ty = quote!(#ident #t_tg);
+    let mut shared_body = TokenStream::new();
+    let mut mut_body = TokenStream::new();
+    let mut shared_ok = true;
+
+    for trait_item in item.items.iter() {
+        match trait_item {
+            syn::TraitItem::Const(c) => {
+                let ident = &c.ident;
+                let ty = &c.ty;
+                let ts = quote!(
+                    const #ident: #ty = <#mock_ty as #trait_ty>::#ident;
+                );
+                ts.to_tokens(&mut shared_body);
+                ts.to_tokens(&mut mut_body);
+            },
+            syn::TraitItem::Type(ty) => {
+                let ident = &ty.ident;
+                let ts = quote!(type #ident = <#mock_ty as #trait_ty>::#ident;);
+                ts.to_tokens(&mut shared_body);
+                ts.to_tokens(&mut mut_body);
+            },
+            syn::TraitItem::Method(meth) => {
+                let mut sig = meth.sig.clone();
+                // A method that mentions Self would refer to a different type
+                // in the forwarding impls, and async methods and impl Trait
+                // return values get transformed when mocked.
+                if sig.asyncness.is_some() ||
+                    has_bare_self(sig.output.to_token_stream()) ||
+                    matches!(&sig.output, syn::ReturnType::Type(_, ty)
+                             if matches!(**ty, syn::Type::ImplTrait(_)))
+                {
+                    return TokenStream::new();
+                }
+                let mut receiver = None;
+                let mut args = Vec::new();
+                for fa in sig.inputs.iter_mut() {
+                    match fa {
+                        syn::FnArg::Receiver(r) if r.reference.is_some() => {
+                            receiver = Some(r.mutability.is_some());
+                        },
+                        syn::FnArg::Typed(pt) => {
+                            if has_bare_self(pt.ty.to_token_stream()) {
+                                return TokenStream::new();
+                            }
+                            if let syn::Pat::Ident(pi) = &mut *pt.pat {
+                                pi.mutability = None;
+                                args.push(pi.ident.clone());
+                            } else {
+                                return TokenStream::new();
+                            }
+                        },
+                        // A by-value receiver can't be forwarded at all
+                        _ => return TokenStream::new()
+                    }
+                }
+                let meth_ident = &sig.ident;
+                let tparams = sig.generics.params.iter()
+                    .filter_map(|p| match p {
+                        syn::GenericParam::Type(tp) => Some(&tp.ident),
+                        syn::GenericParam::Const(cp) => Some(&cp.ident),
+                        syn::GenericParam::Lifetime(_) => None
+                    }).collect::<Vec<_>>();
+                let turbofish = if tparams.is_empty() {
+                    None
+                } else {
+                    Some(quote!(::<#(#tparams),*>))
+                };
+                let unsafety = &sig.unsafety;
+                let forward = |recv: TokenStream| quote!(
+                    #sig {
+                        #unsafety {
+                            <#mock_ty as #trait_ty>::#meth_ident #turbofish(
+                                #recv #(#args),*)
+                        }
+                    }
+                );
+                match receiver {
+                    None => {
+                        forward(quote!()).to_tokens(&mut shared_body);
+                        forward(quote!()).to_tokens(&mut mut_body);
+                    },
+                    Some(false) => {
+                        forward(quote!(&**self,)).to_tokens(&mut shared_body);
+                        forward(quote!(&**self,)).to_tokens(&mut mut_body);
+                    },
+                    Some(true) => {
+                        shared_ok = false;
+                        forward(quote!(&mut **self,)).to_tokens(&mut mut_body);
+                    }
+                }
+            },
+            _ => return TokenStream::new()
+        }
+    }
+
+    let mut output = TokenStream::new();
+    let mut generics = struct_generics.clone();
+    let lt = syn::Lifetime::new("'__mockall_r", Span::call_site());
+    generics.params.insert(0, syn::GenericParam::Lifetime(
+        syn::LifetimeDef::new(lt.clone())));
+    let (ig, _, _) = generics.split_for_impl();
+    let unsafety = &item.unsafety;
+    if shared_ok {
+        quote!(
+            #unsafety impl #ig #trait_ty for &#lt #mock_ty #s_wc {
+                #shared_body
+            }
+        ).to_tokens(&mut output);
+    }
+    quote!(
+        #unsafety impl #ig #trait_ty for &#lt mut #mock_ty #s_wc {
+            #mut_body
+        }
+    ).to_tokens(&mut output);
+    output
+}
+
+/// The names of a mock struct's mocked methods, used for naming the generated
+/// helper methods.
+#[derive(Default)]
+pub(crate) struct MethodNames {
+    /// Every mocked method's name
+    all: HashSet<String>,
+    /// Names used by more than one mocked method, from different traits
+    shared: HashSet<String>,
+}
+
+impl MethodNames {
+    /// Choose the name of a generated helper method, like `checkpoint` or
+    /// `expect_foo`.  If one of the mocked methods already has that name, then
+    /// the mocked method keeps it, and the helper gets a `mockall_` prefix
+    /// instead.
+    fn helper(&self, ident: syn::Ident) -> syn::Ident {
+        if self.all.contains(&ident.to_string()) {
+            format_ident!("mockall_{}", ident)
+        } else {
+            ident
+        }
+    }
+
+    /// Like [`helper`](#method.helper), but for an aggregate checkpoint method
+    /// like `checkpoint_statics`.  That would also collide with the
+    /// checkpoint method of a mocked method named `statics`.
+    fn checkpoint_helper(&self, suffix: &str) -> syn::Ident {
+        if self.all.contains(suffix) {
+            format_ident!("mockall_checkpoint_{}", suffix)
+        } else {
+            self.helper(format_ident!("checkpoint_{}", suffix))
+        }
+    }
+
+    /// The stem of a method's helpers' names, like `foo` in `expect_foo`.  If
+    /// another mocked method has the same name, then a trait method's stem is
+    /// qualified by its trait's name, like `Foo_foo`.  An inherent method
+    /// always gets the short form.
+    fn stem(&self, ident: &syn::Ident, sub: Option<&syn::Ident>) -> String {
+        match sub {
+            Some(trait_) if self.shared.contains(&ident.to_string()) =>
+                format!("{}_{}", trait_, ident),
+            _ => ident.to_string()
+        }
+    }
+}
+
+/// A note for the documentation of a helper method that was renamed by
+/// [`MethodNames::helper`]
+#[cfg(all(not(test),feature = "extra-docs"))]
+fn renamed_note(ident: &syn::Ident) -> String {
+    let name = ident.to_string();
+    match name.strip_prefix("mockall_") {
+        Some(orig) => format!(
+            "\n\nThis method would normally be named `{}`, but that name is \
+            taken by a mocked method.", orig),
+        None => String::new()
+    }
+}
+
+/// Is this method a constructor?  That is, does it lack a receiver and return
+/// `Self` or the mock struct, possibly wrapped in another type like
+/// `Result<Self, E>`?
+fn is_constructor(sig: &syn::Signature, mock_ident: &syn::Ident) -> bool {
+    fn mentions_self(ts: TokenStream, mock_ident: &syn::Ident) -> bool {
+        ts.into_iter().any(|tt| match tt {
+            proc_macro2::TokenTree::Ident(i) =>
+                i == "Self" || i == *mock_ident,
+            proc_macro2::TokenTree::Group(g) =>
+                mentions_self(g.stream(), mock_ident),
+            _ => false
+        })
+    }
+
+    let has_receiver = sig.inputs.iter().any(|arg| match arg {
+        syn::FnArg::Receiver(_) => true,
+        syn::FnArg::Typed(_) => false
+    });
+    match &sig.output {
+        syn::ReturnType::Default => false,
+        syn::ReturnType::Type(_, ty) =>
+            !has_receiver && mentions_self(ty.to_token_stream(), mock_ident)
+    }
+}
+
 fn tim2iim(m: &syn::TraitItemMethod, vis: &syn::Visibility)
     -> syn::ImplItemMethod
 {
@@ -560,16 +1636,16 @@ pub(crate) fn do_mock(input: TokenStream) -> TokenStream {
             return err.to_compile_error();
         }
     };
-    if env::var("MOCKALL_DEBUG").is_ok() {
-        println!("{}", mock.gen());
-    }
-    mock.gen()
+    let ts = mock.gen();
+    debug_dump(&gen_mock_ident(&mock.name).to_string(), &ts);
+    ts
 }
 
 /// Test cases for `mock!{}`.
 #[cfg(test)]
 mod t {
 
+    use quote::ToTokens;
     use std::str::FromStr;
     use super::super::*;
 
@@ -586,4 +1662,543 @@ mod t {
         assert!(!output.contains("pub struct MockFoo"));
     }
 
+    /// Is this impl block for a mock struct's builder?
+    fn is_builder_impl(i: &syn::ItemImpl) -> bool {
+        let ty = &i.self_ty;
+        quote!(#ty).to_string().contains("Builder")
+    }
+
+    /// Every method named `name` in any impl block, except for the builder's
+    fn methods<'a>(file: &'a syn::File, name: &'a str)
+        -> impl Iterator<Item=&'a syn::ImplItemMethod> + 'a
+    {
+        file.items.iter()
+            .filter_map(|item| match item {
+                syn::Item::Impl(i) if !is_builder_impl(i) => Some(i),
+                _ => None
+            }).flat_map(|i| i.items.iter())
+            .filter_map(move |item| match item {
+                syn::ImplItem::Method(m) if m.sig.ident == name => Some(m),
+                _ => None
+            })
+    }
+
+    /// Names of the attributes on every method named `name` in any impl block
+    fn method_attrs(file: &syn::File, name: &str) -> Vec<String> {
+        methods(file, name)
+            .flat_map(|m| m.attrs.iter())
+            .map(|a| a.path.get_ident().unwrap().to_string())
+            .collect()
+    }
+
+    /// Semantic attributes should be copied to the mock method, and where
+    /// appropriate to its helper methods, but not to anything else.
+    #[test]
+    fn semantic_attrs() {
+        let code = r#"
+            Foo {
+                #[must_use]
+                #[inline]
+                #[cold]
+                #[allow(unused)]
+                #[deprecated(since = "0.1.0", note = "Use bar instead")]
+                fn foo(&self) -> u32;
+            }
+            trait Bar {
+                #[must_use]
+                #[allow(unused)]
+                fn bar(&self) -> u32;
+                #[deprecated]
+                fn baz(&self, x: u32) -> u32;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        assert_eq!(method_attrs(&output, "foo"),
+            ["must_use", "inline", "cold", "allow", "deprecated", "allow",
+             "track_caller"]);
+        assert_eq!(method_attrs(&output, "expect_foo"),
+            ["must_use", "track_caller", "allow", "deprecated"]);
+        assert_eq!(method_attrs(&output, "checkpoint_foo"), ["allow"]);
+        // must_use isn't allowed on trait impl methods
+        assert_eq!(method_attrs(&output, "bar"), ["allow", "track_caller"]);
+        assert_eq!(method_attrs(&output, "expect_bar"),
+            ["must_use", "track_caller", "allow"]);
+        // Nor is deprecated.  But setting expectations on the method should
+        // still warn, without the generated callers of expect_baz warning too.
+        assert_eq!(method_attrs(&output, "baz"), ["allow", "track_caller"]);
+        assert_eq!(method_attrs(&output, "expect_baz"),
+            ["must_use", "track_caller", "deprecated"]);
+        assert_eq!(method_attrs(&output, "expect_baz_with"),
+            ["doc", "doc", "doc", "must_use", "track_caller", "deprecated",
+             "allow"]);
+
+        // The expectation module gets neither the function-only attributes nor
+        // deprecated.
+        let expectation_mod = output.items.iter()
+            .filter_map(|item| match item {
+                syn::Item::Mod(m) if m.ident == "__mock_Foo" => Some(m),
+                _ => None
+            }).flat_map(|m| m.content.as_ref().unwrap().1.iter())
+            .filter_map(|item| match item {
+                syn::Item::Mod(m) if m.ident == "foo" => Some(m),
+                _ => None
+            }).next()
+            .unwrap();
+        let attrs = expectation_mod.attrs.iter()
+            .map(|a| a.path.get_ident().unwrap().to_string())
+            .collect::<Vec<_>>();
+        // The user's allow, then the generated allow(missing_docs) and
+        // doc(hidden)
+        assert_eq!(attrs, ["allow", "allow", "doc"]);
+    }
+
+    /// Generated scaffolding should be hidden from the docs, but not the mock
+    /// struct or its methods.
+    #[test]
+    fn doc_hidden() {
+        fn hidden(attrs: &[syn::Attribute]) -> bool {
+            attrs.iter().any(|a| a.path.is_ident("doc") &&
+                             a.tokens.to_string() == "(hidden)")
+        }
+        let code = r#"
+            Foo {}
+            trait Bar {
+                fn bar(&self) -> u32;
+                fn baz() -> u32;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        let mut structs = 0;
+        let mut mods = 0;
+        for item in output.items.iter() {
+            match item {
+                syn::Item::Struct(s) => {
+                    structs += 1;
+                    assert_eq!(hidden(&s.attrs), s.ident != "MockFoo" &&
+                               s.ident != "MockFooBuilder", "{}", s.ident);
+                },
+                syn::Item::Mod(m) => {
+                    mods += 1;
+                    assert!(hidden(&m.attrs), "{}", m.ident);
+                    let content = &m.content.as_ref().unwrap().1;
+                    let statics = content.iter()
+                        .flat_map(|item| match item {
+                            syn::Item::Mod(m) => m.content.as_ref().unwrap()
+                                .1.iter(),
+                            _ => [].iter()
+                        }).filter_map(|item| match item {
+                            syn::Item::Static(s) => Some(s),
+                            _ => None
+                        });
+                    for s in statics {
+                        assert!(hidden(&s.attrs), "{}", s.ident);
+                    }
+                },
+                _ => ()
+            }
+        }
+        assert_eq!(structs, 3);
+        assert_eq!(mods, 2);
+        for name in &["bar", "expect_bar", "baz_context", "checkpoint", "new"] {
+            let meth = methods(&output, name).next().unwrap();
+            assert!(!hidden(&meth.attrs), "{}", name);
+        }
+    }
+
+    /// Default type parameters should be preserved on both the mock method and
+    /// its expectation method.
+    #[test]
+    fn default_type_params() {
+        let code = r#"
+            Foo {}
+            trait Bar {
+                fn convert<T: From<u32> + 'static = u64>(&self, x: u32) -> T;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        for name in &["convert", "expect_convert"] {
+            let meth = methods(&output, name).next().unwrap();
+            let tp = meth.sig.generics.type_params().next().unwrap();
+            let default = tp.default.as_ref().unwrap();
+            assert_eq!(quote!(#default).to_string(), "u64");
+        }
+    }
+
+    /// Unsafe methods should stay unsafe, but their expectation methods
+    /// shouldn't be.
+    #[test]
+    fn unsafe_methods() {
+        let code = r#"
+            Foo {}
+            trait Bar {
+                unsafe fn poke(&self, addr: usize);
+                unsafe fn reset();
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        for name in &["poke", "reset"] {
+            assert!(methods(&output, name).next().unwrap().sig.unsafety
+                    .is_some());
+        }
+        for name in &["expect_poke", "reset_context"] {
+            assert!(methods(&output, name).next().unwrap().sig.unsafety
+                    .is_none());
+        }
+    }
+
+    /// The mock versions of const fns can't be const, and their docs should
+    /// say so.
+    #[test]
+    fn const_fn() {
+        let code = r#"
+            Foo {
+                const fn foo(&self) -> u32;
+                const fn bar() -> u32;
+                fn baz(&self) -> u32;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        let has_note = |meth: &ImplItemMethod| meth.attrs.iter()
+            .any(|a| a.path.is_ident("doc") &&
+                 a.tokens.to_string().contains("is `const`"));
+        for name in &["foo", "bar"] {
+            let meth = methods(&output, name).next().unwrap();
+            assert!(meth.sig.constness.is_none());
+            assert!(has_note(meth));
+        }
+        assert!(!has_note(methods(&output, "baz").next().unwrap()));
+    }
+
+    /// Constructors' expectations should return the mock type wrapped the same
+    /// way as the original return type.
+    #[test]
+    fn wrapped_self_constructors() {
+        let code = r#"
+            Foo {
+                fn open(path: &str) -> io::Result<Self>;
+                fn find(id: u32) -> Option<Self>;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output = do_mock(ts).to_string();
+        assert!(output.contains("io :: Result < MockFoo >"));
+        assert!(output.contains("Option < MockFoo >"));
+    }
+
+    /// Every mock struct gets a `with_expectations` constructor, whatever its
+    /// shape
+    #[test]
+    fn with_expectations() {
+        let expected = concat!(
+            "pub fn with_expectations (__mockall_f : impl FnOnce (& mut Self)) ",
+            "-> Self { ",
+            "let mut __mockall_mock = ",
+            "< Self as :: std :: default :: Default > :: default () ; ",
+            "__mockall_f (& mut __mockall_mock) ; ",
+            "__mockall_mock }");
+        for code in &[
+            "Foo { fn foo(&self); }",
+            "Foo<T: 'static> { fn foo(&self, t: T); }",
+            "Foo {} trait Bar { fn bar(&self); } trait Baz { fn baz(&self); }",
+        ] {
+            let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+            let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+            let meths = methods(&output, "with_expectations")
+                .map(|m| {
+                    let (vis, sig, block) = (&m.vis, &m.sig, &m.block);
+                    quote!(#vis #sig #block).to_string()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(meths, [expected], "{}", code);
+        }
+    }
+
+    /// The self types of every impl of the trait named `name`
+    fn trait_impl_types(file: &syn::File, name: &str) -> Vec<String> {
+        file.items.iter()
+            .filter_map(|item| match item {
+                syn::Item::Impl(syn::ItemImpl{
+                    trait_: Some((_, path, _)), self_ty, ..
+                }) if path.segments.last().unwrap().ident == name =>
+                    Some(self_ty.to_token_stream().to_string()),
+                _ => None
+            }).collect()
+    }
+
+    #[test]
+    fn ref_impls_multiple_traits() {
+        let code = r#"
+            #[ref_impls]
+            Foo {}
+            trait Bar { fn bar(&self) -> u32; fn new() -> Self; }
+            trait Baz { fn baz(&mut self, x: u32); fn get(&self) -> u32; }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        // Bar can't be forwarded, because its constructor returns Self
+        assert_eq!(trait_impl_types(&output, "Bar"), ["MockFoo"]);
+        assert_eq!(trait_impl_types(&output, "Baz"),
+            ["MockFoo", "& '__mockall_r mut MockFoo"]);
+        let baz = output.items.iter()
+            .filter_map(|item| match item {
+                syn::Item::Impl(i) if i.trait_.is_some() &&
+                    i.self_ty.to_token_stream().to_string().starts_with('&')
+                    => Some(i),
+                _ => None
+            }).next()
+            .unwrap();
+        assert_eq!(baz.items.iter().map(|item| item.to_token_stream().to_string())
+            .collect::<Vec<_>>(), [
+            concat!("fn baz (& mut self , x : u32) { { ",
+                "< MockFoo as Baz > :: baz (& mut * * self , x) } }"),
+            concat!("fn get (& self) -> u32 { { ",
+                "< MockFoo as Baz > :: get (& * * self ,) } }")
+        ]);
+    }
+
+    #[test]
+    fn ref_impls_generic() {
+        let code = r#"
+            #[ref_impls]
+            Foo<T: 'static> {}
+            trait Bar<T: 'static> {
+                type Out = u32;
+                fn bar<Q: 'static>(&self, t: T) -> Q;
+                fn build(t: T) -> Self::Out;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output = do_mock(ts).to_string();
+        assert!(output.contains(concat!(
+            "impl < '__mockall_r , T : 'static > Bar < T > ",
+            "for & '__mockall_r MockFoo < T > { ",
+            "type Out = < MockFoo < T > as Bar < T > > :: Out ; ",
+            "fn bar < Q : 'static > (& self , t : T) -> Q { { ",
+            "< MockFoo < T > as Bar < T > > :: bar :: < Q > (& * * self , t) ",
+            "} } ",
+            "fn build (t : T) -> Self :: Out { { ",
+            "< MockFoo < T > as Bar < T > > :: build (t) } } }")), "{}", output);
+        assert!(output.contains(
+            "impl < '__mockall_r , T : 'static > Bar < T > \
+            for & '__mockall_r mut MockFoo < T >"), "{}", output);
+    }
+
+    /// A trait with a by-value receiver can't be implemented for references
+    #[test]
+    fn ref_impls_by_value() {
+        let code = r#"
+            #[ref_impls]
+            Foo {}
+            trait Bar { fn bar(&self); fn into_u32(self) -> u32; }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        assert_eq!(trait_impl_types(&output, "Bar"), ["MockFoo"]);
+    }
+
+    /// Without the attribute, the trait is only implemented for the mock struct
+    #[test]
+    fn no_ref_impls() {
+        let code = "Foo {} trait Bar { fn bar(&self); }";
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        assert_eq!(trait_impl_types(&output, "Bar"), ["MockFoo"]);
+    }
+
+    #[test]
+    fn with_expectations_collision() {
+        let code = "Foo { fn with_expectations(&self); }";
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        assert_eq!(methods(&output, "with_expectations").count(), 1);
+        assert_eq!(methods(&output, "mockall_with_expectations").count(), 1);
+    }
+
+    /// Every method named `name` in the builder's impl blocks
+    fn builder_methods<'a>(file: &'a syn::File, name: &'a str)
+        -> impl Iterator<Item=&'a syn::ImplItemMethod> + 'a
+    {
+        file.items.iter()
+            .filter_map(|item| match item {
+                syn::Item::Impl(i) if is_builder_impl(i) => Some(i),
+                _ => None
+            }).flat_map(|i| i.items.iter())
+            .filter_map(move |item| match item {
+                syn::ImplItem::Method(m) if m.sig.ident == name => Some(m),
+                _ => None
+            })
+    }
+
+    /// The builder gets one method for each mocked method, plus `build`, and
+    /// `build_with_contexts` if there are any static methods.
+    #[test]
+    fn builder() {
+        let code = r#"
+            Foo {
+                fn foo(&self);
+                fn bar() -> u32;
+                fn build(&self);
+            }
+            trait Baz {
+                fn baz(&self);
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        for name in &["foo", "bar", "build", "baz", "mockall_build",
+                      "build_with_contexts"]
+        {
+            assert_eq!(builder_methods(&output, name).count(), 1, "{}", name);
+        }
+        assert_eq!(methods(&output, "builder").count(), 1);
+    }
+
+    #[test]
+    fn builder_without_statics() {
+        let code = r#"
+            Foo {
+                fn foo(&self);
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        assert_eq!(builder_methods(&output, "build").count(), 1);
+        assert_eq!(builder_methods(&output, "build_with_contexts").count(), 0);
+    }
+
+    /// Generated helpers whose names collide with mocked methods get renamed
+    #[test]
+    fn helper_name_collisions() {
+        let code = r#"
+            Foo {
+                fn foo(&self);
+                fn expect_foo(&self);
+                fn bar();
+                fn bar_context(&self);
+            }
+            trait Baz {
+                fn checkpoint(&mut self);
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        for name in &["mockall_expect_foo", "expect_expect_foo",
+                      "mockall_bar_context", "expect_bar_context",
+                      "mockall_checkpoint", "expect_checkpoint"]
+        {
+            assert_eq!(methods(&output, name).count(), 1, "{}", name);
+        }
+        // The user's methods each keep their names
+        for name in &["expect_foo", "bar_context", "checkpoint"] {
+            assert_eq!(methods(&output, name).count(), 1, "{}", name);
+        }
+    }
+
+    #[test]
+    fn aggregate_checkpoints() {
+        let code = r#"
+            Foo {
+                fn foo() -> u32;
+                fn all(&self);
+            }
+            trait Bar {
+                fn statics(&self);
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        for name in &["mockall_checkpoint_statics", "checkpoint_statics",
+                      "mockall_checkpoint_all", "checkpoint_all",
+                      "checkpoint_foo"]
+        {
+            assert_eq!(methods(&output, name).count(), 1, "{}", name);
+        }
+    }
+
+    #[test]
+    fn shared_method_names() {
+        let code = r#"
+            Foo {}
+            trait Read {
+                fn read(&mut self, x: u32) -> u32;
+            }
+            trait Validate {
+                fn read(&self) -> bool;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        for name in &["expect_Read_read", "expect_Validate_read"] {
+            assert_eq!(methods(&output, name).count(), 1, "{}", name);
+        }
+        assert_eq!(methods(&output, "expect_read").count(), 0);
+    }
+
+    #[test]
+    fn unshared_method_names() {
+        let code = r#"
+            Foo {}
+            trait Read {
+                fn read(&mut self, x: u32) -> u32;
+            }
+            trait Validate {
+                fn validate(&self) -> bool;
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        for name in &["expect_read", "expect_validate"] {
+            assert_eq!(methods(&output, name).count(), 1, "{}", name);
+        }
+        assert_eq!(methods(&output, "expect_Read_read").count(), 0);
+    }
+
+    #[test]
+    fn is_constructor() {
+        let mock_ident = format_ident!("MockFoo");
+        let is_constructor = |code: &str| {
+            let sig = syn::parse_str::<syn::TraitItemMethod>(code).unwrap().sig;
+            super::is_constructor(&sig, &mock_ident)
+        };
+        assert!(is_constructor("fn new() -> Self;"));
+        assert!(is_constructor("fn new(t: T) -> MockFoo<T>;"));
+        assert!(is_constructor("fn open(path: &str) -> io::Result<Self>;"));
+        assert!(!is_constructor("fn new(&self) -> Child;"));
+        assert!(!is_constructor("fn new(&self) -> Self;"));
+        assert!(!is_constructor("fn new(x: u32) -> u32;"));
+        assert!(!is_constructor("fn new();"));
+    }
+
+    /// Only generic methods whose arguments and return values can all be
+    /// `dyn Any` get expectations for any type.
+    #[test]
+    fn expect_any() {
+        let code = r#"
+            Foo {
+                fn owned<T: 'static>(&self, t: T, x: &u32) -> Vec<T>;
+                fn nongeneric(&self, x: u32) -> u32;
+                fn unsized_arg<T: 'static>(&self, t: T, s: &str);
+                fn ref_ret<T: 'static>(&self, t: T) -> &u32;
+                fn mut_arg<T: 'static>(&self, t: &mut T);
+                fn maybe_unsized<T: ?Sized + 'static>(&self, t: &T);
+                fn by_value<T: 'static>(self, t: T);
+                fn lifetime<'a, T: 'static>(&self, t: T) -> Cow<'a, u32>;
+                fn stat<T: 'static>(t: T);
+            }
+        "#;
+        let ts = proc_macro2::TokenStream::from_str(code).unwrap();
+        let output: syn::File = syn::parse2(do_mock(ts)).unwrap();
+        assert_eq!(methods(&output, "expect_owned_any").count(), 1);
+        for name in &["nongeneric", "unsized_arg", "ref_ret", "mut_arg",
+                      "maybe_unsized", "by_value", "lifetime", "stat"]
+        {
+            let any_name = format!("expect_{}_any", name);
+            assert_eq!(methods(&output, &any_name).count(), 0, "{}", name);
+        }
+    }
 }